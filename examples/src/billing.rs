@@ -4,7 +4,7 @@ use xai_sdk::Request;
 use xai_sdk::api::management::billing::{
     GetAmountToPayReq, GetBillingInfoReq, GetSpendingLimitsReq, ListPaymentMethodsReq,
 };
-use xai_sdk::billing;
+use xai_sdk::billing::{self, InvoiceLine, Money};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -131,51 +131,48 @@ async fn main() -> Result<()> {
             }
 
             println!(
-                "💵 Effective Spending Limit: ${:.2}",
-                amount_info.effective_spending_limit as f64 / 100.0
+                "💵 Effective Spending Limit: {}",
+                Money::from_cents(amount_info.effective_spending_limit)
             );
             println!(
-                "🎁 Default Credits: ${:.2}",
-                amount_info.default_credits as f64 / 100.0
+                "🎁 Default Credits: {}",
+                Money::from_cents(amount_info.default_credits)
             );
 
             if let Some(invoice) = amount_info.core_invoice {
                 println!("\n📄 Current Invoice:");
                 println!(
-                    "   Amount Before VAT: ${:.2}",
-                    invoice.amount_before_vat as f64 / 100.0
+                    "   Amount Before VAT: {}",
+                    Money::from_cents(invoice.amount_before_vat)
                 );
-                println!("   VAT: ${:.2}", invoice.vat_cost as f64 / 100.0);
+                println!("   VAT: {}", Money::from_cents(invoice.vat_cost));
                 println!(
-                    "   Amount After VAT: ${:.2}",
-                    invoice.amount_after_vat as f64 / 100.0
+                    "   Amount After VAT: {}",
+                    Money::from_cents(invoice.amount_after_vat)
                 );
 
                 if let Some(total) = invoice.total_with_corr {
-                    println!("   Total: ${:.2}", total.val as f64 / 100.0);
+                    println!("   Total: {}", Money::from(total));
                 }
 
                 if let Some(prepaid) = invoice.prepaid_credits {
-                    println!(
-                        "   Prepaid Credits Available: ${:.2}",
-                        prepaid.val as f64 / 100.0
-                    );
+                    println!("   Prepaid Credits Available: {}", Money::from(prepaid));
                 }
 
                 if let Some(used) = invoice.prepaid_credits_used {
-                    println!("   Prepaid Credits Used: ${:.2}", used.val as f64 / 100.0);
+                    println!("   Prepaid Credits Used: {}", Money::from(used));
                 }
 
                 if !invoice.lines.is_empty() {
                     println!("\n   Line Items:");
-                    for line in &invoice.lines {
+                    for line in invoice.lines.into_iter().map(InvoiceLine::from) {
                         println!(
-                            "     - {}: {} {} @ ${:.6} = ${:.2}",
+                            "     - {}: {} {} @ {} = {}",
                             line.description,
-                            line.num_units,
+                            line.quantity,
                             line.unit_type,
-                            line.unit_price as f64 / 1_000_000.0,
-                            line.amount as f64 / 100.0
+                            line.unit_price,
+                            line.amount
                         );
                     }
                 }
@@ -199,27 +196,21 @@ async fn main() -> Result<()> {
                 println!("✅ Spending limits retrieved successfully\n");
 
                 if let Some(hard_auto) = limits.hard_sl_auto {
-                    println!("💵 Hard Limit (Auto): ${:.2}", hard_auto.val as f64 / 100.0);
+                    println!("💵 Hard Limit (Auto): {}", Money::from(hard_auto));
                 }
 
                 if let Some(effective_hard) = limits.effective_hard_sl {
-                    println!(
-                        "💵 Effective Hard Limit: ${:.2}",
-                        effective_hard.val as f64 / 100.0
-                    );
+                    println!("💵 Effective Hard Limit: {}", Money::from(effective_hard));
                 }
 
                 if let Some(soft) = limits.soft_sl {
-                    println!("💵 Soft Limit (User Set): ${:.2}", soft.val as f64 / 100.0);
+                    println!("💵 Soft Limit (User Set): {}", Money::from(soft));
                 } else {
                     println!("💵 Soft Limit: Not set");
                 }
 
                 if let Some(effective) = limits.effective_sl {
-                    println!(
-                        "💵 Effective Limit (Enforced): ${:.2}",
-                        effective.val as f64 / 100.0
-                    );
+                    println!("💵 Effective Limit (Enforced): {}", Money::from(effective));
                 }
             } else {
                 println!("ℹ️  No spending limits information available");
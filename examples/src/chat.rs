@@ -1,11 +1,6 @@
 use anyhow::{Context, Result};
 use std::env;
-use xai_sdk::api::{
-    Content, GetChatCompletionChunk, GetChatCompletionResponse, GetCompletionsRequest, Message,
-    MessageRole, content,
-};
-use xai_sdk::chat;
-use xai_sdk::{Request, Streaming};
+use xai_sdk::prelude::*;
 
 const COMPLETE: &str = "--complete";
 const STREAM: &str = "--stream";
@@ -52,12 +47,7 @@ async fn complete(api_key: &str) -> Result<()> {
     // Create the request
     let prompt = "Quote Hannibal Lectre.";
     let model = "grok-3";
-    let mut cntnt = Content::default();
-    cntnt.content = Some(content::Content::Text(prompt.into()));
-    let mut msg = Message::default();
-    msg.role = MessageRole::RoleUser.into();
-    msg.content = vec![cntnt];
-    let messages = vec![msg];
+    let messages = vec![Message::from((MessageRole::RoleUser, prompt))];
     let request = Request::new(GetCompletionsRequest {
         model: model.to_string(),
         messages,
@@ -104,12 +94,7 @@ async fn stream(api_key: &str) -> Result<()> {
     // Create the request
     let prompt = "What is 35 + 34?";
     let model = "grok-3-mini";
-    let mut cntnt = Content::default();
-    cntnt.content = Some(content::Content::Text(prompt.into()));
-    let mut msg = Message::default();
-    msg.role = MessageRole::RoleUser.into();
-    msg.content = vec![cntnt];
-    let messages = vec![msg];
+    let messages = vec![Message::from((MessageRole::RoleUser, prompt))];
     let request = Request::new(GetCompletionsRequest {
         model: model.to_string(),
         messages,
@@ -145,12 +130,7 @@ async fn assemble(api_key: &str) -> Result<()> {
     // Create the request
     let prompt = "Quote a King 810 song.";
     let model = "grok-3-mini";
-    let mut cntnt = Content::default();
-    cntnt.content = Some(content::Content::Text(prompt.into()));
-    let mut msg = Message::default();
-    msg.role = MessageRole::RoleUser.into();
-    msg.content = vec![cntnt];
-    let messages = vec![msg];
+    let messages = vec![Message::from((MessageRole::RoleUser, prompt))];
     let request = Request::new(GetCompletionsRequest {
         model: model.to_string(),
         messages,
@@ -168,53 +148,50 @@ async fn assemble(api_key: &str) -> Result<()> {
         Ok(response) => {
             let stream: Streaming<GetChatCompletionChunk> = response.into_inner();
 
-            // Process the stream and collect all chunks
+            // Process the stream, collect all chunks and assemble them into a complete response
             let consumer = chat::stream::Consumer::new();
-            let chunks = chat::stream::process(stream, consumer).await?;
+            let (response, chunks) = chat::stream::process_collect(stream, consumer).await?;
 
             println!("📦 Collected {} chunks from stream", chunks.len());
 
-            // Assemble the chunks into a complete response
-            if let Some(response) = chat::stream::assemble(chunks) {
-                println!("\n🎯 Assembled Response:");
-                println!("ID: {}", response.id);
-                println!("Model: {}", response.model);
-                println!("Created: {:?}", response.created);
-                println!("System Fingerprint: {}", response.system_fingerprint);
-
-                if let Some(usage) = &response.usage {
-                    println!("Usage:");
-                    println!("  - Completion tokens: {}", usage.completion_tokens);
-                    println!("  - Reasoning tokens: {}", usage.reasoning_tokens);
-                    println!("  - Prompt tokens: {}", usage.prompt_tokens);
-                    println!("  - Total tokens: {}", usage.total_tokens);
-                }
+            println!("\n🎯 Assembled Response:");
+            println!("ID: {}", response.id);
+            println!("Model: {}", response.model);
+            println!("Created: {:?}", response.created);
+            println!("System Fingerprint: {}", response.system_fingerprint);
+
+            if let Some(usage) = &response.usage {
+                println!("Usage:");
+                println!("  - Completion tokens: {}", usage.completion_tokens);
+                println!("  - Reasoning tokens: {}", usage.reasoning_tokens);
+                println!("  - Prompt tokens: {}", usage.prompt_tokens);
+                println!("  - Total tokens: {}", usage.total_tokens);
+            }
 
-                if !response.citations.is_empty() {
-                    println!("Citations: {:?}", response.citations);
-                }
+            if !response.citations.is_empty() {
+                println!("Citations: {:?}", response.citations);
+            }
 
-                println!("\n📝 Choices:");
-                for (i, choice) in response.outputs.iter().enumerate() {
-                    println!(
-                        "Choice {} (index: {}, finish_reason: {}):",
-                        i, choice.index, choice.finish_reason
-                    );
-
-                    if let Some(message) = &choice.message {
-                        println!("  Role: {}", message.role);
-                        println!("  Content: {}", message.content);
-                        if !message.reasoning_content.is_empty() {
-                            println!("  Reasoning: {}", message.reasoning_content);
-                        }
-                        if !message.tool_calls.is_empty() {
-                            println!("  Tool calls: {:?}", message.tool_calls);
-                        }
-                        println!();
+            println!("\n📝 Choices:");
+            for (i, choice) in response.outputs.iter().enumerate() {
+                println!(
+                    "Choice {} (index: {}, finish_reason: {}):",
+                    i,
+                    choice.index,
+                    choice.finish_reason_enum()
+                );
+
+                if let Some(message) = &choice.message {
+                    println!("  Role: {}", message.role_enum());
+                    println!("  Content: {}", message.content);
+                    if !message.reasoning_content.is_empty() {
+                        println!("  Reasoning: {}", message.reasoning_content);
+                    }
+                    if !message.tool_calls.is_empty() {
+                        println!("  Tool calls: {:?}", message.tool_calls);
                     }
+                    println!();
                 }
-            } else {
-                println!("❌ Failed to assemble response from chunks");
             }
         }
         Err(e) => {
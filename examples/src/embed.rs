@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use std::env;
+use xai_sdk::Request;
+use xai_sdk::embed;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load API key from environment variable
+    let api_key =
+        env::var("XAI_API_KEY").context("XAI_API_KEY environment variable must be set")?;
+
+    // Create authenticated embed client
+    let mut client = embed::client::new(&api_key).await?;
+
+    let model = "v1";
+    let image = "https://upload.wikimedia.org/wikipedia/commons/3/3f/Fronalpstock_big.jpg";
+
+    // Mix text and image inputs; embeddings come back in input order
+    let request = embed::RequestBuilder::new(model)
+        .text("A mountain landscape with a lake")
+        .texts(vec!["A city skyline at night".to_string()])
+        .image_url(image)
+        .try_build()?;
+
+    println!("🚀 Sending embedding request to xAI API...");
+    println!("🤖 Model: {model}");
+    println!();
+
+    match client.embed(Request::new(request)).await {
+        Ok(response) => {
+            let response = response.into_inner();
+            for embedding in &response.embeddings {
+                for vector in &embedding.embeddings {
+                    println!(
+                        "📐 Input {}: {} dimensions, first values {:?}",
+                        embedding.index,
+                        vector.float_array.len(),
+                        &vector.float_array[..vector.float_array.len().min(4)]
+                    );
+                }
+            }
+            if let Some(usage) = &response.usage {
+                println!("\n📊 Usage: {usage:?}");
+            }
+        }
+        Err(e) => {
+            eprintln!("❌ Error calling xAI API: {}", e);
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
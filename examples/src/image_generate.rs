@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::env;
+use xai_sdk::Request;
+use xai_sdk::api::generated_image::Image;
+use xai_sdk::api::{ImageAspectRatio, ImageResolution};
+use xai_sdk::image;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load API key from environment variable
+    let api_key =
+        env::var("XAI_API_KEY").context("XAI_API_KEY environment variable must be set")?;
+
+    // Create authenticated image client
+    let mut client = image::client::new(&api_key).await?;
+
+    let model = "grok-imagine-image";
+
+    // Two wide, high-resolution images returned as URLs
+    let request = image::RequestBuilder::new(model)
+        .prompt("A lighthouse on a rocky coast at dawn, cinematic lighting")
+        .n(2)
+        .aspect_ratio(ImageAspectRatio::ImgAspectRatio169)
+        .resolution(ImageResolution::ImgResolution2k)
+        .try_build()?;
+
+    println!("🚀 Sending image generation request to xAI API...");
+    println!("🤖 Model: {model}");
+    println!();
+
+    match client.generate_image(Request::new(request)).await {
+        Ok(response) => {
+            let response = response.into_inner();
+            for (index, generated) in response.images.iter().enumerate() {
+                match &generated.image {
+                    Some(Image::Url(url)) => println!("🖼️  Image {index}: {url}"),
+                    Some(Image::Base64(data)) => {
+                        println!("🖼️  Image {index}: {} bytes of base64", data.len())
+                    }
+                    None => println!("🖼️  Image {index}: no image returned"),
+                }
+                if !generated.respect_moderation {
+                    println!("   ⚠️  Replaced by a placeholder (moderation)");
+                }
+            }
+            if let Some(usage) = &response.usage {
+                println!("\n📊 Usage: {usage:?}");
+            }
+        }
+        Err(e) => {
+            eprintln!("❌ Error calling xAI API: {}", e);
+            return Err(e.into());
+        }
+    }
+
+    Ok(())
+}
@@ -1,9 +1,7 @@
 use anyhow::{Context, Result};
 use std::env;
 use xai_sdk::Request;
-use xai_sdk::api::{
-    Content, GetChatCompletionResponse, GetCompletionsRequest, Message, MessageRole, content,
-};
+use xai_sdk::api::{GetChatCompletionResponse, GetCompletionsRequest, Message, MessageRole};
 use xai_sdk::{chat, common};
 
 #[tokio::main]
@@ -48,12 +46,7 @@ async fn main() -> Result<()> {
     let mut client = chat::client::with_interceptor(composed).await?;
 
     // Prepare a minimal chat completion request
-    let mut cntnt = Content::default();
-    cntnt.content = Some(content::Content::Text("Say hello briefly.".into()));
-    let mut msg = Message::default();
-    msg.role = MessageRole::RoleUser.into();
-    msg.content = vec![cntnt];
-    let messages = vec![msg];
+    let messages = vec![Message::from((MessageRole::RoleUser, "Say hello briefly."))];
     let request = Request::new(GetCompletionsRequest {
         model: "grok-3-latest".to_string(),
         messages,
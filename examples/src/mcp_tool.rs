@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use std::env;
+use xai_sdk::Request;
+use xai_sdk::api::{Message, MessageRole};
+use xai_sdk::chat;
+use xai_sdk::chat::RequestBuilder;
+use xai_sdk::chat::tools::{self, McpAuth};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load API key from environment variable
+    let api_key =
+        env::var("XAI_API_KEY").context("XAI_API_KEY environment variable must be set")?;
+
+    // MCP server reachable from xAI's servers, and an optional token for it
+    let server_url =
+        env::var("MCP_SERVER_URL").context("MCP_SERVER_URL environment variable must be set")?;
+    let auth = env::var("MCP_TOKEN").ok().map(McpAuth::Bearer);
+
+    // Create authenticated chat client
+    let mut client = chat::client::new(&api_key).await?;
+
+    let prompt = "List the tools you can use and try one of them.";
+    let model = "grok-4-latest";
+
+    // Empty allow list: the model may call every tool the server offers
+    let mcp = tools::mcp(server_url.as_str(), Vec::new(), auth)?;
+
+    let request = RequestBuilder::new(model)
+        .message(Message::from((MessageRole::RoleUser, prompt)))
+        .tools([mcp])
+        .try_build()?;
+
+    println!("🚀 Sending request to xAI API...");
+    println!("🔌 MCP server: {server_url}");
+    println!("🤖 Model: {model}");
+    println!();
+
+    let response = client
+        .get_completion(Request::new(request))
+        .await?
+        .into_inner();
+    for output in &response.outputs {
+        if let Some(message) = &output.message {
+            for call in &message.tool_calls {
+                if let Some(xai_sdk::api::tool_call::Tool::Function(function)) = &call.tool {
+                    println!("🛠️  {}({})", function.name, function.arguments);
+                }
+            }
+            println!("{}", message.content);
+        }
+    }
+
+    Ok(())
+}
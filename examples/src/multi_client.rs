@@ -1,8 +1,7 @@
 use anyhow::{Context, Result};
 use xai_sdk::Request;
 use xai_sdk::api::{
-    Content, GetCompletionsRequest, GetModelRequest, Message, MessageRole, SampleTextRequest,
-    content,
+    GetCompletionsRequest, GetModelRequest, Message, MessageRole, SampleTextRequest,
 };
 use xai_sdk::{chat, common, models, sample};
 
@@ -54,15 +53,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Chat completion
     println!("💬 Chat completion...");
-    let message = Message {
-        role: MessageRole::RoleUser.into(),
-        content: vec![Content {
-            content: Some(content::Content::Text(
-                "Explain Rust ownership in simple terms".to_string(),
-            )),
-        }],
-        ..Default::default()
-    };
+    let message = Message::from((
+        MessageRole::RoleUser,
+        "Explain Rust ownership in simple terms",
+    ));
     let chat_request = Request::new(GetCompletionsRequest {
         model: "grok-3-latest".to_string(),
         messages: vec![message],
@@ -2,6 +2,7 @@ use anyhow::{Context, Result};
 use std::env;
 use xai_sdk::Request;
 use xai_sdk::api::{SampleTextRequest, SampleTextResponse};
+use xai_sdk::common::user;
 use xai_sdk::sample;
 
 /// Demonstrates raw text sampling with various parameters
@@ -29,7 +30,7 @@ async fn demonstrate_text_sampling() -> Result<()> {
         n: Some(1),
         logprobs: true,
         top_logprobs: Some(3),
-        user: "rust-demo".to_string(),
+        user: user::hash_id("rust-demo"),
         ..Default::default()
     });
 
@@ -82,7 +83,7 @@ async fn demonstrate_text_sampling() -> Result<()> {
         top_p: Some(0.8),
         n: Some(1),
         logprobs: false,
-        user: "rust-demo".to_string(),
+        user: user::hash_id("rust-demo"),
         ..Default::default()
     });
 
@@ -134,7 +135,7 @@ async fn demonstrate_text_sampling() -> Result<()> {
         top_p: Some(0.9),
         n: Some(3),
         logprobs: false,
-        user: "rust-demo".to_string(),
+        user: user::hash_id("rust-demo"),
         ..Default::default()
     });
 
@@ -0,0 +1,62 @@
+use anyhow::{Context, Result};
+use std::env;
+use xai_sdk::Request;
+use xai_sdk::api::{Message, MessageRole};
+use xai_sdk::chat;
+use xai_sdk::chat::RequestBuilder;
+use xai_sdk::chat::tools::{self, Timestamp};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Load API key from environment variable
+    let api_key =
+        env::var("XAI_API_KEY").context("XAI_API_KEY environment variable must be set")?;
+
+    // Create authenticated chat client
+    let mut client = chat::client::new(&api_key).await?;
+
+    let prompt = "What did @xai announce in the first week of January 2025?";
+    let model = "grok-4-latest";
+
+    let msg = Message::from((MessageRole::RoleUser, prompt));
+
+    // X search restricted to one account and a date window (validated: from <= to)
+    let x_search = tools::x_search()
+        .allowed_x_handles(["xai"])
+        .date_range(Timestamp::date(2025, 1, 1)?, Timestamp::date(2025, 1, 7)?)
+        .build()?;
+
+    // Web search limited to a few sites, preferring US results
+    let web_search = tools::web_search()
+        .allowed_domains(["x.ai", "techcrunch.com"])
+        .country("US")
+        .build()?;
+
+    let request = RequestBuilder::new(model)
+        .message(msg)
+        .tools([x_search, web_search])
+        .try_build()?;
+
+    println!("🚀 Sending request to xAI API...");
+    println!("📝 Prompt: {prompt}");
+    println!("🤖 Model: {model}");
+    println!();
+
+    let response = client
+        .get_completion(Request::new(request))
+        .await?
+        .into_inner();
+    for output in &response.outputs {
+        if let Some(message) = &output.message {
+            println!("{}", message.content);
+        }
+    }
+    if !response.citations.is_empty() {
+        println!("\n📚 Citations:");
+        for citation in &response.citations {
+            println!("  - {citation}");
+        }
+    }
+
+    Ok(())
+}
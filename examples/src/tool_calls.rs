@@ -4,8 +4,8 @@ use std::env;
 use std::io::{self, Write};
 use std::path::PathBuf;
 use xai_sdk::api::{
-    Content, Function, GetChatCompletionChunk, GetCompletionsRequest, InlineCitation, Message,
-    MessageRole, Tool, ToolCall, ToolCallStatus, ToolCallType, XSearch, content,
+    Function, GetChatCompletionChunk, GetCompletionsRequest, InlineCitation, Message, MessageRole,
+    Tool, ToolCall, ToolCallStatus, ToolCallType, XSearch,
 };
 use xai_sdk::chat;
 use xai_sdk::chat::stream::{Consumer, OutputContext};
@@ -23,12 +23,7 @@ async fn main() -> Result<()> {
     let prompt = "What where the last two tweets from @elonmusk and @tsoding? Write their tweets with sources to 'musk.txt' and 'tsoding.txt'.";
     let model = "grok-4-latest";
 
-    let mut cntnt = Content::default();
-    cntnt.content = Some(content::Content::Text(prompt.into()));
-    let mut msg = Message::default();
-    msg.role = MessageRole::RoleUser.into();
-    msg.content = vec![cntnt];
-    let messages = vec![msg];
+    let messages = vec![Message::from((MessageRole::RoleUser, prompt))];
 
     // Create XSearch tool for searching X/Twitter
     let xsearch = XSearch::default();
@@ -82,8 +77,10 @@ async fn main() -> Result<()> {
                         if !citations.is_empty() {
                             println!("\n📚 {} inline citation(s)", citations.len());
                             for c in &citations {
-                                if let Some(ref data) = c.citation {
-                                    println!("  • [{}] {:?}", c.id, data);
+                                if let Some(url) = c.url() {
+                                    println!("  • [{}] {url}", c.id);
+                                } else if let Some(snippet) = c.snippet() {
+                                    println!("  • [{}] \"{snippet}\"", c.id);
                                 } else {
                                     println!("  • [{}] (no data)", c.id);
                                 }
@@ -176,22 +173,22 @@ fn print_tool_calls(tool_calls: &[ToolCall]) {
     }
     println!("\n🔧 Tool call(s):");
     for tool_call in tool_calls {
-        let tool_type = match ToolCallType::try_from(tool_call.r#type) {
-            Ok(ToolCallType::XSearchTool) => "XSearch (Twitter/X)",
-            Ok(ToolCallType::WebSearchTool) => "WebSearch",
-            Ok(ToolCallType::CodeExecutionTool) => "CodeExecution",
-            Ok(ToolCallType::CollectionsSearchTool) => "CollectionsSearch",
-            Ok(ToolCallType::McpTool) => "MCP",
-            Ok(ToolCallType::AttachmentSearchTool) => "AttachmentSearch",
-            Ok(ToolCallType::ClientSideTool) => "Client-side function",
+        let tool_type = match tool_call.call_type() {
+            ToolCallType::XSearchTool => "XSearch (Twitter/X)",
+            ToolCallType::WebSearchTool => "WebSearch",
+            ToolCallType::CodeExecutionTool => "CodeExecution",
+            ToolCallType::CollectionsSearchTool => "CollectionsSearch",
+            ToolCallType::McpTool => "MCP",
+            ToolCallType::AttachmentSearchTool => "AttachmentSearch",
+            ToolCallType::ClientSideTool => "Client-side function",
             _ => "Unknown",
         };
-        let status = match ToolCallStatus::try_from(tool_call.status) {
-            Ok(ToolCallStatus::InProgress) => "⏳ In progress",
-            Ok(ToolCallStatus::Completed) => "✅ Completed",
-            Ok(ToolCallStatus::Incomplete) => "⚠️ Incomplete",
-            Ok(ToolCallStatus::Failed) => "❌ Failed",
-            _ => "❓ Unknown",
+        let status = match tool_call.call_status() {
+            Some(ToolCallStatus::InProgress) => "⏳ In progress",
+            Some(ToolCallStatus::Completed) => "✅ Completed",
+            Some(ToolCallStatus::Incomplete) => "⚠️ Incomplete",
+            Some(ToolCallStatus::Failed) => "❌ Failed",
+            None => "❓ Unknown",
         };
         println!("  ┌─ {} (id: {}, {})", tool_type, tool_call.id, status);
         if let Some(xai_sdk::api::tool_call::Tool::Function(f)) = &tool_call.tool {
@@ -9,13 +9,8 @@ use anyhow::{Context, Result};
 use std::env;
 use std::time::Duration;
 
-use tokio::time::sleep;
-
 use xai_sdk::Request;
-use xai_sdk::api::{
-    DeferredStatus, GenerateVideoRequest, GetDeferredVideoRequest, VideoAspectRatio,
-    VideoResolution,
-};
+use xai_sdk::api::{DeferredStatus, GenerateVideoRequest, VideoAspectRatio, VideoResolution};
 use xai_sdk::video;
 
 /// Video model alias (see xAI docs / model list for current names).
@@ -23,7 +18,7 @@ const MODEL: &str = "grok-imagine-video";
 
 const DURATION_SECS: i32 = 5;
 const POLL_INTERVAL: Duration = Duration::from_secs(5);
-const MAX_POLLS: u32 = 120;
+const TIMEOUT: Duration = Duration::from_secs(600);
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -51,75 +46,41 @@ async fn main() -> Result<()> {
     let request_id = start.request_id;
     println!("Started video job request_id={request_id}");
 
-    for poll in 1..=MAX_POLLS {
-        sleep(POLL_INTERVAL).await;
-
-        let poll_resp = client
-            .get_deferred_video(Request::new(GetDeferredVideoRequest {
-                request_id: request_id.clone(),
-            }))
-            .await
-            .context("get_deferred_video RPC failed")?
-            .into_inner();
+    let poll_resp = video::deferred::wait(&mut client, &request_id, POLL_INTERVAL, TIMEOUT)
+        .await
+        .context("waiting for the video job failed")?;
 
-        let status = match poll_resp.status {
-            x if x == DeferredStatus::Done as i32 => DeferredStatus::Done,
-            x if x == DeferredStatus::Pending as i32 => DeferredStatus::Pending,
-            x if x == DeferredStatus::Failed as i32 => DeferredStatus::Failed,
-            x if x == DeferredStatus::Expired as i32 => DeferredStatus::Expired,
-            x if x == DeferredStatus::InvalidDeferredStatus as i32 => {
-                DeferredStatus::InvalidDeferredStatus
-            }
-            other => anyhow::bail!("unknown DeferredStatus value {other}"),
-        };
+    match poll_resp.status() {
+        DeferredStatus::Done => {
+            let video_resp = poll_resp
+                .response
+                .context("status DONE but response missing")?;
 
-        match status {
-            DeferredStatus::Pending => {
-                if let Some(ref r) = poll_resp.response {
-                    println!("poll {poll}: pending (progress {}%)", r.progress);
-                } else {
-                    println!("poll {poll}: pending");
-                }
+            if let Some(err) = video_resp.error {
+                anyhow::bail!("video error: {} — {}", err.code, err.message);
             }
-            DeferredStatus::Done => {
-                let video_resp = poll_resp
-                    .response
-                    .context("status DONE but response missing")?;
-
-                if let Some(err) = video_resp.error {
-                    anyhow::bail!("video error: {} — {}", err.code, err.message);
-                }
 
-                let v = video_resp
-                    .video
-                    .context("status DONE but video payload missing")?;
+            let v = video_resp
+                .video
+                .context("status DONE but video payload missing")?;
 
-                println!(
-                    "done: url={} duration_s={} respect_moderation={}",
-                    v.url, v.duration, v.respect_moderation
-                );
-                return Ok(());
-            }
-            DeferredStatus::Failed => {
-                let detail = poll_resp
-                    .response
-                    .and_then(|r| r.error)
-                    .map(|e| format!("{}: {}", e.code, e.message))
-                    .unwrap_or_else(|| "no error details".into());
-                anyhow::bail!("job failed: {detail}");
-            }
-            DeferredStatus::Expired => {
-                anyhow::bail!("job expired before completion");
-            }
-            DeferredStatus::InvalidDeferredStatus => {
-                println!(
-                    "poll {poll}: unexpected invalid status (raw {})",
-                    poll_resp.status
-                );
-            }
+            println!(
+                "done: url={} duration_s={} respect_moderation={}",
+                v.url, v.duration, v.respect_moderation
+            );
+            Ok(())
         }
+        DeferredStatus::Failed => {
+            let detail = poll_resp
+                .response
+                .and_then(|r| r.error)
+                .map(|e| format!("{}: {}", e.code, e.message))
+                .unwrap_or_else(|| "no error details".into());
+            anyhow::bail!("job failed: {detail}");
+        }
+        DeferredStatus::Expired => {
+            anyhow::bail!("job expired before completion");
+        }
+        other => anyhow::bail!("unexpected final status {other:?}"),
     }
-
-    let secs = POLL_INTERVAL.as_secs() * u64::from(MAX_POLLS);
-    anyhow::bail!("timed out after {MAX_POLLS} polls (~{secs}s)");
 }
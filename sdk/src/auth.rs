@@ -2,11 +2,21 @@
 //!
 //! Provides gRPC clients for API key validation, metadata retrieval, and authentication operations.
 
+use crate::export::Status;
+use crate::xai_api::ApiKey;
+use prost_types::Timestamp;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tonic::Code;
+
+pub use credentials::{ApiKeyError, api_key_from_env};
+
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::auth_client::AuthClient as XAuthClient;
 
     pub type AuthClient = XAuthClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -19,9 +29,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<AuthClient, Error>` - Connected client or transport error
+    /// * `Result<AuthClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<AuthClient, Error> {
+    pub async fn new(api_key: &str) -> Result<AuthClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XAuthClient::with_interceptor(channel, auth_intercept);
@@ -29,6 +39,32 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `AuthClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<AuthClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<AuthClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XAuthClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `AuthClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -55,11 +91,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<AuthClient, Error>` - Intercepted client or connection error
+    /// * `Result<AuthClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<AuthClient, Error> {
+    ) -> Result<AuthClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client = XAuthClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
 
@@ -83,3 +119,417 @@ pub mod client {
         XAuthClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Loading the API key from the environment or a credentials file.
+///
+/// [`api_key_from_env`] checks, in order:
+/// 1. The `XAI_API_KEY` environment variable, if set and not blank
+/// 2. The credentials file at [`credentials_path`]: `$XDG_CONFIG_HOME/xai/credentials`, or
+///    `~/.config/xai/credentials` when `XDG_CONFIG_HOME` is unset
+///
+/// The credentials file holds `key = value` lines. Blank lines and lines starting with `#`
+/// are ignored, and values may be wrapped in double quotes. Only `api_key` is read:
+///
+/// ```text
+/// # xAI credentials
+/// api_key = "xai-..."
+/// ```
+///
+/// Restrict the file to its owner (`chmod 600`), as it holds a secret.
+pub mod credentials {
+    use std::fmt;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    /// Environment variable checked first by [`api_key_from_env`].
+    pub const API_KEY_ENV: &str = "XAI_API_KEY";
+
+    /// Error returned when no API key could be loaded.
+    #[derive(Debug)]
+    pub enum ApiKeyError {
+        /// Neither the environment variable nor a credentials file provided a key.
+        NotFound {
+            /// Credentials file that was looked for, if a home directory is known.
+            credentials_path: Option<PathBuf>,
+        },
+        /// The credentials file exists but could not be read.
+        Unreadable {
+            /// Path of the credentials file.
+            path: PathBuf,
+            /// Underlying I/O error.
+            source: io::Error,
+        },
+        /// The credentials file has no non-empty `api_key` entry.
+        MissingEntry {
+            /// Path of the credentials file.
+            path: PathBuf,
+        },
+    }
+
+    impl fmt::Display for ApiKeyError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ApiKeyError::NotFound { credentials_path } => {
+                    write!(f, "no xAI API key found: set {API_KEY_ENV}")?;
+                    match credentials_path {
+                        Some(path) => write!(f, " or add `api_key = ...` to {}", path.display()),
+                        None => Ok(()),
+                    }
+                }
+                ApiKeyError::Unreadable { path, source } => {
+                    write!(
+                        f,
+                        "failed to read credentials file {}: {source}",
+                        path.display()
+                    )
+                }
+                ApiKeyError::MissingEntry { path } => write!(
+                    f,
+                    "credentials file {} has no `api_key = ...` entry",
+                    path.display()
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for ApiKeyError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                ApiKeyError::Unreadable { source, .. } => Some(source),
+                _ => None,
+            }
+        }
+    }
+
+    /// Loads the API key from `XAI_API_KEY` or the credentials file.
+    ///
+    /// See the [module documentation](self) for the lookup order and file format.
+    ///
+    /// # Returns
+    /// * `Ok(String)` - The API key, with surrounding whitespace removed
+    /// * `Err(ApiKeyError)` - No key was found, or the credentials file is unusable
+    pub fn api_key_from_env() -> Result<String, ApiKeyError> {
+        if let Ok(key) = std::env::var(API_KEY_ENV)
+            && !key.trim().is_empty()
+        {
+            return Ok(key.trim().to_string());
+        }
+
+        match credentials_path() {
+            Some(path) if path.exists() => api_key_from_file(&path),
+            credentials_path => Err(ApiKeyError::NotFound { credentials_path }),
+        }
+    }
+
+    /// Returns the default credentials file location, if a home directory is known.
+    ///
+    /// # Returns
+    /// * `Option<PathBuf>` - `$XDG_CONFIG_HOME/xai/credentials` or `$HOME/.config/xai/credentials`
+    pub fn credentials_path() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .filter(|dir| !dir.is_empty())
+                    .map(|home| PathBuf::from(home).join(".config"))
+            })?;
+        Some(config.join("xai").join("credentials"))
+    }
+
+    /// Reads the API key from a credentials file.
+    ///
+    /// # Arguments
+    /// * `path` - Credentials file in the format described in the [module documentation](self)
+    ///
+    /// # Returns
+    /// * `Ok(String)` - Value of the `api_key` entry
+    /// * `Err(ApiKeyError)` - The file could not be read or has no `api_key` entry
+    pub fn api_key_from_file(path: impl AsRef<Path>) -> Result<String, ApiKeyError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(|source| ApiKeyError::Unreadable {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .filter(|(key, _)| key.trim() == "api_key")
+            .map(|(_, value)| value.trim().trim_matches('"').trim().to_string())
+            .find(|value| !value.is_empty())
+            .ok_or_else(|| ApiKeyError::MissingEntry {
+                path: path.to_path_buf(),
+            })
+    }
+}
+
+/// Information about an API key, as reported by the auth service.
+///
+/// The auth service does not report model allowlists, rate limits or an expiry date; list
+/// the models available to the key with the models service instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKeyInfo {
+    api_key_id: String,
+    redacted_api_key: String,
+    name: String,
+    team_id: String,
+    user_id: String,
+    acls: Vec<String>,
+    created_at: Option<Timestamp>,
+    modified_at: Option<Timestamp>,
+    disabled: bool,
+    api_key_blocked: bool,
+    team_blocked: bool,
+}
+
+impl ApiKeyInfo {
+    /// Returns true if the key can currently make API requests (not disabled or blocked).
+    pub fn is_usable(&self) -> bool {
+        !self.disabled && !self.api_key_blocked && !self.team_blocked
+    }
+
+    /// Returns the ID of the API key (not the key itself).
+    pub fn api_key_id(&self) -> &str {
+        &self.api_key_id
+    }
+
+    /// Returns the redacted form of the key, safe to log.
+    pub fn redacted_api_key(&self) -> &str {
+        &self.redacted_api_key
+    }
+
+    /// Returns the human-readable name of the key.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the ID of the team the key belongs to.
+    pub fn team_id(&self) -> &str {
+        &self.team_id
+    }
+
+    /// Returns the ID of the user who created the key.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns the access control lists: the resources the key has access to.
+    pub fn acls(&self) -> &[String] {
+        &self.acls
+    }
+
+    /// Returns when the key was created, or `None` if unknown or out of range.
+    pub fn created_at(&self) -> Option<SystemTime> {
+        self.created_at.and_then(|ts| SystemTime::try_from(ts).ok())
+    }
+
+    /// Returns when the key was last modified, or `None` if unknown or out of range.
+    pub fn modified_at(&self) -> Option<SystemTime> {
+        self.modified_at
+            .and_then(|ts| SystemTime::try_from(ts).ok())
+    }
+
+    /// Returns true if the key is disabled; see [`is_usable`](Self::is_usable) for blocks.
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+
+    /// Returns true if the key is blocked from making API requests.
+    pub fn api_key_blocked(&self) -> bool {
+        self.api_key_blocked
+    }
+
+    /// Returns true if the key's team is blocked from making API requests.
+    pub fn team_blocked(&self) -> bool {
+        self.team_blocked
+    }
+}
+
+impl From<ApiKey> for ApiKeyInfo {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            api_key_id: key.api_key_id,
+            redacted_api_key: key.redacted_api_key,
+            name: key.name,
+            team_id: key.team_id,
+            user_id: key.user_id,
+            acls: key.acls,
+            created_at: key.create_time,
+            modified_at: key.modify_time,
+            disabled: key.disabled,
+            api_key_blocked: key.api_key_blocked,
+            team_blocked: key.team_blocked,
+        }
+    }
+}
+
+/// Checks that an API key is accepted and usable, and returns its details.
+///
+/// Connects to the xAI API and calls the key-info RPC. Call this at startup to fail fast on
+/// a bad key instead of on the first real request. Failures are told apart by status code:
+///
+/// - `Unavailable` - The API could not be reached (network or TLS failure); the key was not
+///   checked
+/// - `Unauthenticated` / `PermissionDenied` - The server rejected the key; the status carries
+///   an [`AuthError`], see [`AuthError::from_status`]
+/// - `PermissionDenied` - The key was accepted but is disabled, or the key or its team is
+///   blocked
+///
+/// # Arguments
+/// * `api_key` - xAI API key to check
+///
+/// # Returns
+/// * `Ok(ApiKeyInfo)` - The key is valid and usable
+/// * `Err(Status)` - The key could not be checked or is not usable
+pub async fn validate(api_key: &str) -> Result<ApiKeyInfo, Status> {
+    let mut client = client::new(api_key)
+        .await
+        .map_err(|e| Status::unavailable(format!("Failed to connect to the xAI API: {e}")))?;
+
+    check_usable(get_info(&mut client).await?)
+}
+
+/// Returns the details of the API key a client authenticates with.
+///
+/// Unlike [`validate`], this reuses an existing client and does not treat a disabled or
+/// blocked key as an error; check [`ApiKeyInfo::is_usable`] or use [`check_usable`].
+///
+/// # Arguments
+/// * `client` - Auth client
+///
+/// # Returns
+/// * `Ok(ApiKeyInfo)` - Details of the key
+/// * `Err(Status)` - The RPC's status; authentication failures are mapped by [`map_status`]
+pub async fn get_info(client: &mut client::AuthClient) -> Result<ApiKeyInfo, Status> {
+    let key = client
+        .get_api_key_info(())
+        .await
+        .map_err(map_status)?
+        .into_inner();
+    Ok(key.into())
+}
+
+/// Turns key details into an error if the key cannot make API requests.
+///
+/// # Arguments
+/// * `info` - Key details returned by the auth service
+///
+/// # Returns
+/// * `Ok(ApiKeyInfo)` - The key is usable
+/// * `Err(Status)` - `PermissionDenied` naming why the key is not usable
+pub fn check_usable(info: ApiKeyInfo) -> Result<ApiKeyInfo, Status> {
+    let reason = if info.disabled {
+        "API key is disabled"
+    } else if info.api_key_blocked {
+        "API key is blocked"
+    } else if info.team_blocked {
+        "Team of the API key is blocked"
+    } else {
+        return Ok(info);
+    };
+    Err(Status::permission_denied(format!(
+        "{reason} ({})",
+        info.redacted_api_key
+    )))
+}
+
+/// An authentication failure: the server rejected the API key or denied it access.
+///
+/// SDK call wrappers (the client's [`ChatBackend`](crate::chat::backend::ChatBackend)
+/// implementation, the `*_timeout` functions, [`validate`] and the tokenize helpers) pass
+/// their errors through [`map_status`]. An `Unauthenticated` or `PermissionDenied` status then
+/// keeps its code, gets this error's message, and carries the `AuthError` as its source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthError {
+    /// `Unauthenticated` (key missing or invalid) or `PermissionDenied` (key not allowed).
+    pub code: Code,
+    /// Message sent by the server, possibly empty.
+    pub detail: String,
+}
+
+impl AuthError {
+    /// Extracts the authentication failure from a status, if it is one.
+    ///
+    /// # Arguments
+    /// * `status` - Status returned by an RPC, mapped by [`map_status`] or not
+    ///
+    /// # Returns
+    /// * `Some(AuthError)` - The status is `Unauthenticated` or `PermissionDenied`
+    /// * `None` - Any other status
+    pub fn from_status(status: &Status) -> Option<Self> {
+        if let Some(error) =
+            std::error::Error::source(status).and_then(|source| source.downcast_ref::<AuthError>())
+        {
+            return Some(error.clone());
+        }
+        matches!(
+            status.code(),
+            Code::Unauthenticated | Code::PermissionDenied
+        )
+        .then(|| Self {
+            code: status.code(),
+            detail: status.message().to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (what, hint) = match self.code {
+            Code::PermissionDenied => (
+                "access denied",
+                "check that XAI_API_KEY holds an enabled key with access to this resource",
+            ),
+            _ => (
+                "authentication failed",
+                "check that XAI_API_KEY holds a valid xAI API key",
+            ),
+        };
+        write!(f, "{what}")?;
+        if !self.detail.is_empty() {
+            write!(f, ": {}", self.detail)?;
+        }
+        write!(f, " ({hint})")
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl From<AuthError> for Status {
+    fn from(error: AuthError) -> Self {
+        let mut status = Status::new(error.code, error.to_string());
+        status.set_source(Arc::new(error));
+        status
+    }
+}
+
+/// Turns an `Unauthenticated` or `PermissionDenied` status into one carrying an [`AuthError`].
+///
+/// The status keeps its details and metadata (e.g. request ids from the server). Other
+/// statuses, and statuses that already carry an `AuthError`, are returned unchanged.
+///
+/// # Arguments
+/// * `status` - Status returned by an RPC
+///
+/// # Returns
+/// * `Status` - The status with an actionable message for authentication failures
+pub fn map_status(status: Status) -> Status {
+    let already_mapped =
+        std::error::Error::source(&status).is_some_and(|source| source.is::<AuthError>());
+    match AuthError::from_status(&status) {
+        Some(error) if !already_mapped => {
+            let mut mapped = Status::with_details_and_metadata(
+                error.code,
+                error.to_string(),
+                status.details().to_vec().into(),
+                status.metadata().clone(),
+            );
+            mapped.set_source(Arc::new(error));
+            mapped
+        }
+        _ => status,
+    }
+}
@@ -6,9 +6,10 @@
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::batch_mgmt_client::BatchMgmtClient as XBatchMgmtClient;
 
     pub type BatchClient = XBatchMgmtClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -21,9 +22,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<BatchClient, Error>` - Connected client or transport error
+    /// * `Result<BatchClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<BatchClient, Error> {
+    pub async fn new(api_key: &str) -> Result<BatchClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XBatchMgmtClient::with_interceptor(channel, auth_intercept);
@@ -31,6 +32,32 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `BatchClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<BatchClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<BatchClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XBatchMgmtClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `BatchClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -57,11 +84,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<BatchClient, Error>` - Intercepted client or connection error
+    /// * `Result<BatchClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<BatchClient, Error> {
+    ) -> Result<BatchClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client =
             XBatchMgmtClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
@@ -85,3 +112,102 @@ pub mod client {
         XBatchMgmtClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Streams over the paginated batch list RPCs.
+///
+/// Each helper follows the `pagination_token` of the responses with
+/// [`paginate::items`](crate::common::paginate::items) and yields the listed items one by
+/// one. The client is cloned, so the stream does not borrow it.
+pub mod list {
+    use crate::batch::client::BatchClient;
+    use crate::common::paginate::{self, PageStream};
+    use crate::export::Request;
+    use crate::xai_api::{
+        Batch, BatchRequestMetadata, BatchResult, ListBatchRequestMetadataRequest,
+        ListBatchResultsRequest, ListBatchesRequest,
+    };
+
+    /// Lists all batches of the team.
+    ///
+    /// # Arguments
+    /// * `client` - Batch client
+    /// * `limit` - Page size requested from the server
+    ///
+    /// # Returns
+    /// * `PageStream<Batch>` - Every batch, page by page
+    pub fn batches(client: &BatchClient, limit: i32) -> PageStream<Batch> {
+        let client = client.clone();
+        paginate::items(move |pagination_token| {
+            let mut client = client.clone();
+            let request = ListBatchesRequest {
+                limit,
+                pagination_token,
+            };
+            async move {
+                let response = client.list_batches(Request::new(request)).await?;
+                let response = response.into_inner();
+                Ok((response.batches, response.pagination_token))
+            }
+        })
+    }
+
+    /// Lists the metadata of all requests of a batch.
+    ///
+    /// # Arguments
+    /// * `client` - Batch client
+    /// * `batch_id` - Batch to list
+    /// * `limit` - Page size requested from the server
+    ///
+    /// # Returns
+    /// * `PageStream<BatchRequestMetadata>` - Metadata of every request, page by page
+    pub fn request_metadata(
+        client: &BatchClient,
+        batch_id: &str,
+        limit: i32,
+    ) -> PageStream<BatchRequestMetadata> {
+        let client = client.clone();
+        let batch_id = batch_id.to_string();
+        paginate::items(move |pagination_token| {
+            let mut client = client.clone();
+            let request = ListBatchRequestMetadataRequest {
+                batch_id: batch_id.clone(),
+                limit,
+                pagination_token,
+            };
+            async move {
+                let response = client
+                    .list_batch_request_metadata(Request::new(request))
+                    .await?;
+                let response = response.into_inner();
+                Ok((response.batch_request_metadata, response.pagination_token))
+            }
+        })
+    }
+
+    /// Lists all results of a batch.
+    ///
+    /// # Arguments
+    /// * `client` - Batch client
+    /// * `batch_id` - Batch to list
+    /// * `limit` - Page size requested from the server
+    ///
+    /// # Returns
+    /// * `PageStream<BatchResult>` - Every result, page by page
+    pub fn results(client: &BatchClient, batch_id: &str, limit: i32) -> PageStream<BatchResult> {
+        let client = client.clone();
+        let batch_id = batch_id.to_string();
+        paginate::items(move |pagination_token| {
+            let mut client = client.clone();
+            let request = ListBatchResultsRequest {
+                batch_id: batch_id.clone(),
+                limit,
+                pagination_token,
+            };
+            async move {
+                let response = client.list_batch_results(Request::new(request)).await?;
+                let response = response.into_inner();
+                Ok((response.results, response.pagination_token))
+            }
+        })
+    }
+}
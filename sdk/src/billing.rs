@@ -2,12 +2,28 @@
 //!
 //! Provides gRPC clients for managing billing information, payment methods, invoices,
 //! prepaid credits, and spending limits.
+//!
+//! Amounts are exchanged as integer USD cents; [`Money`] wraps them so callers never divide
+//! by 100 by hand, and [`summary`] gathers the figures most dashboards need in one call.
+//! Per-unit prices of invoice lines are finer-grained (millionths of a cent) and use
+//! [`UnitPrice`]; [`invoice_lines`] returns them already converted.
+
+use crate::export::{Request, Status};
+use crate::prod_charger::Cent;
+use crate::prod_mc_billing::{
+    BillingCycle, GetAmountToPayReq, GetSpendingLimitsReq, Line, ListInvoicesReq,
+};
+use client::BillingClient;
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::prod_mc_billing::ui_svc_client::UiSvcClient as XUiSvcClient;
 
     pub type BillingClient = XUiSvcClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +36,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<BillingClient, Error>` - Connected client or transport error
+    /// * `Result<BillingClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<BillingClient, Error> {
+    pub async fn new(api_key: &str) -> Result<BillingClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XUiSvcClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +46,32 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `BillingClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<BillingClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<BillingClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XUiSvcClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `BillingClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -56,11 +98,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<BillingClient, Error>` - Intercepted client or connection error
+    /// * `Result<BillingClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<BillingClient, Error> {
+    ) -> Result<BillingClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client = XUiSvcClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
 
@@ -84,3 +126,328 @@ pub mod client {
         XUiSvcClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// An amount of money in USD, stored as integer cents.
+///
+/// Arithmetic is exact integer arithmetic on cents. `Display` renders dollars with thousands
+/// separators, e.g. `$1,234.56` or `-$0.05`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    /// Zero dollars.
+    pub const ZERO: Money = Money(0);
+
+    /// Creates an amount from USD cents.
+    pub const fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    /// Returns the amount in USD cents.
+    pub const fn cents(self) -> i64 {
+        self.0
+    }
+
+    /// Returns the amount in USD, for display or ratios; use [`Money::cents`] for exact math.
+    pub fn as_dollars(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        let digits = (cents / 100).to_string();
+
+        let mut dollars = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                dollars.push(',');
+            }
+            dollars.push(digit);
+        }
+        write!(f, "{sign}${dollars}.{:02}", cents % 100)
+    }
+}
+
+impl From<Cent> for Money {
+    fn from(cent: Cent) -> Self {
+        Money(cent.val)
+    }
+}
+
+impl From<Money> for Cent {
+    fn from(money: Money) -> Self {
+        Cent { val: money.0 }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Mul<i64> for Money {
+    type Output = Money;
+
+    fn mul(self, rhs: i64) -> Money {
+        Money(self.0 * rhs)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+/// Monthly spending limits of a team.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpendingLimits {
+    /// Highest limit the team may set (staff override if any, otherwise the automatic one).
+    pub hard: Option<Money>,
+    /// Limit set by the team, if any.
+    pub soft: Option<Money>,
+    /// Limit actually enforced by the API.
+    pub effective: Option<Money>,
+}
+
+impl From<crate::prod_mc_billing::SpendingLimits> for SpendingLimits {
+    fn from(limits: crate::prod_mc_billing::SpendingLimits) -> Self {
+        Self {
+            hard: limits.effective_hard_sl.map(Money::from),
+            // Zero means the team has not set a soft limit
+            soft: limits
+                .soft_sl
+                .map(Money::from)
+                .filter(|soft| *soft != Money::ZERO),
+            effective: limits.effective_sl.map(Money::from),
+        }
+    }
+}
+
+/// Billing figures of a team for the current billing cycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BillingSummary {
+    /// Billing cycle the figures refer to, if reported.
+    pub billing_cycle: Option<BillingCycle>,
+    /// Invoice amount before VAT so far.
+    pub amount_before_vat: Money,
+    /// VAT on the invoice so far.
+    pub vat: Money,
+    /// Amount due for the cycle so far, after VAT and corrections.
+    pub amount_to_pay: Money,
+    /// Prepaid credits available before this invoice.
+    pub prepaid_credits: Money,
+    /// Prepaid credits used to pay this invoice.
+    pub prepaid_credits_used: Money,
+    /// Default (free) credits of the team.
+    pub default_credits: Money,
+    /// Spending limit in effect for the cycle, as reported with the invoice.
+    pub effective_spending_limit: Money,
+    /// Monthly spending limits.
+    pub spending_limits: SpendingLimits,
+}
+
+/// Fetches the current billing figures of a team in one call.
+///
+/// Combines the amount-to-pay and spending-limit RPCs into a [`BillingSummary`].
+///
+/// # Arguments
+/// * `client` - Billing client
+/// * `team_id` - ID of the team
+///
+/// # Returns
+/// * `Ok(BillingSummary)` - The team's billing figures
+/// * `Err(Status)` - The first failing RPC's status
+pub async fn summary(client: &mut BillingClient, team_id: &str) -> Result<BillingSummary, Status> {
+    let amount = client
+        .get_amount_to_pay(Request::new(GetAmountToPayReq {
+            team_id: team_id.to_string(),
+        }))
+        .await?
+        .into_inner();
+    let limits = client
+        .get_spending_limits(Request::new(GetSpendingLimitsReq {
+            team_id: team_id.to_string(),
+        }))
+        .await?
+        .into_inner();
+
+    let invoice = amount.core_invoice.unwrap_or_default();
+    Ok(BillingSummary {
+        billing_cycle: amount.billing_cycle,
+        amount_before_vat: Money::from_cents(invoice.amount_before_vat),
+        vat: Money::from_cents(invoice.vat_cost),
+        amount_to_pay: invoice
+            .total_with_corr
+            .map(Money::from)
+            .unwrap_or(Money::from_cents(invoice.amount_after_vat)),
+        prepaid_credits: invoice.prepaid_credits.map(Money::from).unwrap_or_default(),
+        prepaid_credits_used: invoice
+            .prepaid_credits_used
+            .map(Money::from)
+            .unwrap_or_default(),
+        default_credits: Money::from_cents(amount.default_credits),
+        effective_spending_limit: Money::from_cents(amount.effective_spending_limit),
+        spending_limits: limits
+            .spending_limits
+            .map(SpendingLimits::from)
+            .unwrap_or_default(),
+    })
+}
+
+/// Number of [`UnitPrice`] units in one USD cent.
+pub const MICRO_CENTS_PER_CENT: i64 = 1_000_000;
+
+/// A per-unit price in USD, stored as millionths of a cent.
+///
+/// Invoice lines price usage per unit (e.g. per token) at a resolution far below one cent,
+/// so unit prices cannot be stored as [`Money`] without rounding them to zero. The scaling
+/// factors are:
+///
+/// - 1 USD = 100 cents = 100 × [`MICRO_CENTS_PER_CENT`] micro-cents
+/// - `Line::unit_price` is in micro-cents (1/1,000,000 cent, i.e. 10⁻⁸ USD)
+/// - `Line::amount` is in cents
+///
+/// `Display` renders dollars with as many decimals as needed (at least two, at most eight),
+/// e.g. `$0.000002` or `$3.00`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnitPrice(i64);
+
+impl UnitPrice {
+    /// Creates a price from millionths of a USD cent.
+    pub const fn from_micro_cents(micro_cents: i64) -> Self {
+        UnitPrice(micro_cents)
+    }
+
+    /// Returns the price in millionths of a USD cent.
+    pub const fn micro_cents(self) -> i64 {
+        self.0
+    }
+
+    /// Returns the price in USD, for display or ratios.
+    pub fn as_dollars(self) -> f64 {
+        self.0 as f64 / (100 * MICRO_CENTS_PER_CENT) as f64
+    }
+
+    /// Returns the cost of `quantity` units, rounded to the nearest cent.
+    pub fn total(self, quantity: i64) -> Money {
+        let micro_cents = self.0 as i128 * quantity as i128;
+        let per_cent = MICRO_CENTS_PER_CENT as i128;
+        let cents = (micro_cents + micro_cents.signum() * per_cent / 2) / per_cent;
+        Money::from_cents(cents as i64)
+    }
+}
+
+impl fmt::Display for UnitPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const SCALE: u64 = 100 * MICRO_CENTS_PER_CENT as u64;
+
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let value = self.0.unsigned_abs();
+        let fraction = format!("{:08}", value % SCALE);
+        let fraction = fraction.trim_end_matches('0');
+        write!(f, "{sign}${}.{fraction:0<2}", value / SCALE)
+    }
+}
+
+/// One line item of an invoice, with amounts converted to typed money.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvoiceLine {
+    /// ID of the invoice the line belongs to (empty for the current, unissued invoice).
+    pub invoice_id: String,
+    /// Description of the line item.
+    pub description: String,
+    /// Number of units consumed.
+    pub quantity: i64,
+    /// Unit in which the quantity and price are measured.
+    pub unit_type: String,
+    /// Price per unit.
+    pub unit_price: UnitPrice,
+    /// Total amount of the line item.
+    pub amount: Money,
+}
+
+impl From<Line> for InvoiceLine {
+    fn from(line: Line) -> Self {
+        Self {
+            invoice_id: String::new(),
+            description: line.description,
+            quantity: line.num_units,
+            unit_type: line.unit_type,
+            unit_price: UnitPrice::from_micro_cents(line.unit_price),
+            amount: Money::from_cents(line.amount),
+        }
+    }
+}
+
+/// Lists the line items of all invoices of a team.
+///
+/// Lines are returned invoice by invoice, in the order the API lists the invoices, with
+/// `invoice_id` set. The current billing cycle's unissued invoice is not included; its lines
+/// are on `GetAmountToPayResp::core_invoice` and convert with `InvoiceLine::from`.
+///
+/// # Arguments
+/// * `client` - Billing client
+/// * `team_id` - ID of the team
+///
+/// # Returns
+/// * `Ok(Vec<InvoiceLine>)` - All line items
+/// * `Err(Status)` - The RPC's status
+pub async fn invoice_lines(
+    client: &mut BillingClient,
+    team_id: &str,
+) -> Result<Vec<InvoiceLine>, Status> {
+    let invoices = client
+        .list_invoices(Request::new(ListInvoicesReq {
+            team_id: team_id.to_string(),
+            filter: None,
+        }))
+        .await?
+        .into_inner()
+        .invoices;
+
+    Ok(invoices
+        .into_iter()
+        .flat_map(|invoice| {
+            let invoice_id = invoice.invoice_id;
+            invoice.lines.into_iter().map(move |line| InvoiceLine {
+                invoice_id: invoice_id.clone(),
+                ..InvoiceLine::from(line)
+            })
+        })
+        .collect())
+}
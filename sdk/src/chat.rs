@@ -4,12 +4,26 @@
 //! both blocking and streaming responses with comprehensive utilities for real-time
 //! token processing and response assembly.
 
+pub use backend::{ChatBackend, MockChatBackend};
+pub use continuation::continue_until_complete;
+pub use cost::{CostEstimate, estimate_cost};
+pub use pool::Pool;
+pub use request::RequestBuilder;
+pub use response::{CompletionStatus, ContentStats};
+pub use validate::{
+    BuildWarning, ValidationError, validate_sampling_params, validate_tool_results,
+};
+
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
+    use crate::common::types::BoxError;
+    use crate::export::codec::CompressionEncoding;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::{Channel, ClientTlsConfig};
     use crate::xai_api::chat_client::ChatClient as XChatClient;
+    use std::time::Duration;
 
     pub type ChatClient = XChatClient<InterceptedService<Channel, ClientInterceptor>>;
 
@@ -22,9 +36,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<ChatClient, Error>` - Connected client or transport error
+    /// * `Result<ChatClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<ChatClient, Error> {
+    pub async fn new(api_key: &str) -> Result<ChatClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XChatClient::with_interceptor(channel, auth_intercept);
@@ -32,9 +46,118 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `ChatClient` with a custom TLS configuration.
+    ///
+    /// Like [`new`], but connects with the given TLS settings instead of the native
+    /// roots, e.g. to trust a private CA or present a client certificate for mutual TLS.
+    /// See [`common::channel::with_tls_config`] for building the configuration.
+    ///
+    /// # Arguments
+    /// * `tls` - TLS configuration (trust roots and optional client identity)
+    /// * `api_key` - Valid xAI API key for authentication
+    ///
+    /// # Returns
+    /// * `Result<ChatClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_tls(
+        tls: ClientTlsConfig,
+        api_key: &str,
+    ) -> Result<ChatClient, ConnectError> {
+        let channel = common::channel::with_tls_config(tls).await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XChatClient::with_interceptor(channel, auth_intercept);
+
+        Ok(client)
+    }
+
+    /// Creates a new authenticated `ChatClient`, retrying the initial connection.
+    ///
+    /// Like [`new`], but connects with [`common::channel::connect_with_retry`] so that
+    /// transient DNS or network failures at startup do not fail client creation.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `attempts` - Maximum number of connection attempts; `0` is treated as `1`
+    /// * `backoff` - Wait before the first retry, doubled for each further one
+    ///
+    /// # Returns
+    /// * `Result<ChatClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_retry(
+        api_key: &str,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Result<ChatClient, ConnectError> {
+        let channel = common::channel::connect_with_retry(attempts, backoff).await?;
+        Ok(with_channel(channel, api_key))
+    }
+
+    /// Creates a new authenticated `ChatClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<ChatClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<ChatClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XChatClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
+    /// Creates a new authenticated `ChatClient` that compresses requests and accepts compressed
+    /// responses.
+    ///
+    /// Compression is off for the other constructors. Enable the crate's `gzip` feature to
+    /// use `CompressionEncoding::Gzip`.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `encoding` - Compression algorithm used in both directions
+    ///
+    /// # Returns
+    /// * `Result<ChatClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_compression(
+        api_key: &str,
+        encoding: CompressionEncoding,
+    ) -> Result<ChatClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        Ok(with_channel_and_compression(channel, api_key, encoding))
+    }
+
+    /// Creates a new authenticated `ChatClient` with the API key from the environment.
+    ///
+    /// The key is loaded with [`auth::api_key_from_env`](crate::auth::api_key_from_env):
+    /// from `XAI_API_KEY`, falling back to the `~/.config/xai/credentials` file.
+    ///
+    /// # Returns
+    /// * `Ok(ChatClient)` - Connected client
+    /// * `Err(BoxError)` - An [`ApiKeyError`](crate::auth::ApiKeyError) if no key was found,
+    ///   or the transport error
+    ///
+    pub async fn from_env() -> Result<ChatClient, BoxError> {
+        let api_key = crate::auth::api_key_from_env()?;
+        Ok(new(&api_key).await?)
+    }
+
     /// Creates a new authenticated `ChatClient` using an existing gRPC channel.
     ///
-    /// Useful for sharing connections across multiple service clients.
+    /// Useful for sharing connections across multiple service clients; see [`rekey`] for
+    /// serving several API keys over one channel.
     ///
     /// # Arguments
     /// * `channel` - Existing TLS-secured gRPC channel to xAI API
@@ -49,6 +172,54 @@ pub mod client {
         client
     }
 
+    /// Creates a `ChatClient` for a different API key on an existing channel.
+    ///
+    /// For multi-tenant gateways that hold one connection and serve many keys: the new
+    /// client reuses the channel's HTTP/2 connection (no new TCP or TLS handshake) and only
+    /// differs in the `authorization` header it sends. Clients for other tenants on the same
+    /// channel are unaffected. Generated clients do not expose their channel, so keep a clone
+    /// of the `Channel` the first client was built from.
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use xai_sdk::{chat, common};
+    ///
+    /// let channel = common::channel::new().await?;
+    /// let tenant_a = chat::client::rekey(channel.clone(), "key-a");
+    /// let tenant_b = chat::client::rekey(channel, "key-b");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `channel` - Channel shared by all tenants
+    /// * `api_key` - API key of the tenant
+    ///
+    /// # Returns
+    /// * `ChatClient` - Client authenticating with `api_key` over `channel`
+    pub fn rekey(channel: Channel, api_key: &str) -> ChatClient {
+        with_channel(channel, api_key)
+    }
+
+    /// Creates a new authenticated `ChatClient` with compression on an existing gRPC channel.
+    ///
+    /// # Arguments
+    /// * `channel` - Existing TLS-secured gRPC channel to xAI API
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `encoding` - Compression algorithm used in both directions
+    ///
+    /// # Returns
+    /// * `ChatClient` - Authenticated client using the provided channel
+    pub fn with_channel_and_compression(
+        channel: Channel,
+        api_key: &str,
+        encoding: CompressionEncoding,
+    ) -> ChatClient {
+        with_channel(channel, api_key)
+            .send_compressed(encoding)
+            .accept_compressed(encoding)
+    }
+
     /// Creates a new `ChatClient` with a custom interceptor.
     ///
     /// Creates a new TLS connection but uses the provided interceptor instead of
@@ -58,11 +229,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<ChatClient, Error>` - Intercepted client or connection error
+    /// * `Result<ChatClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<ChatClient, Error> {
+    ) -> Result<ChatClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client = XChatClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
 
@@ -87,345 +258,3587 @@ pub mod client {
     }
 }
 
-/// Streaming utilities for chat completions.
+/// Transport abstraction for chat completions.
 ///
-/// Provides high-performance utilities for processing real-time chat completion streams,
-/// including flexible callback-based consumers and chunk assembly into complete responses.
-pub mod stream {
-    use crate::common::types::{BoxError, BoxFuture};
-    use crate::export::Status;
+/// [`ChatBackend`] covers the two calls most helpers need, blocking and streaming
+/// completions. It is implemented for the real [`ChatClient`](client::ChatClient) and for
+/// [`MockChatBackend`], which serves canned responses so code built on the helpers in this
+/// module can be unit tested without network access.
+pub mod backend {
+    use crate::auth::map_status;
+    use crate::chat::client::ChatClient;
+    use crate::export::metadata::MetadataMap;
+    use crate::export::{IntoRequest, Status};
     use crate::xai_api::{
-        CompletionMessage, CompletionOutput, FinishReason, GetChatCompletionChunk,
-        GetChatCompletionResponse, InlineCitation, LogProbs, SamplingUsage, ToolCall, ToolCallType,
+        GetChatCompletionChunk, GetChatCompletionResponse, GetCompletionsRequest,
     };
-    use futures::lock::Mutex;
-    use futures::sink::Sink;
-    use futures::{SinkExt, Stream, StreamExt};
-    use std::collections::HashMap;
+    use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+    use std::collections::VecDeque;
     use std::future::Future;
-    use std::io::Write;
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex, MutexGuard};
 
-    #[derive(Debug, Clone)]
-    struct OutputStats {
-        index: i32,
-        total_reasoning_tokens: usize,
-        total_content_tokens: usize,
-        finish_reason: FinishReason,
-    }
+    /// Boxed stream of completion chunks, as returned by [`ChatBackend::get_completion_chunk`].
+    ///
+    /// Satisfies the bounds of [`process`](crate::chat::stream::process).
+    pub type ChunkStream = BoxStream<'static, Result<GetChatCompletionChunk, Status>>;
 
-    impl OutputStats {
-        fn init(index: i32, finish_reason: FinishReason) -> Self {
-            Self {
-                index,
-                total_reasoning_tokens: 0,
-                total_content_tokens: 0,
-                finish_reason,
-            }
-        }
+    /// A service that can answer chat completion requests.
+    ///
+    /// Both methods accept a bare `GetCompletionsRequest` or a `Request` carrying metadata,
+    /// such as one from [`RequestBuilder::build_request`](crate::chat::RequestBuilder::build_request).
+    pub trait ChatBackend {
+        /// Sends a blocking completion request.
+        fn get_completion(
+            &mut self,
+            request: impl IntoRequest<GetCompletionsRequest> + Send,
+        ) -> impl Future<Output = Result<GetChatCompletionResponse, Status>> + Send;
+
+        /// Sends a streaming completion request.
+        fn get_completion_chunk(
+            &mut self,
+            request: impl IntoRequest<GetCompletionsRequest> + Send,
+        ) -> impl Future<Output = Result<ChunkStream, Status>> + Send;
+    }
 
-        fn inc(&mut self, reason_token: &str, content_token: &str) {
-            if !reason_token.is_empty() {
-                self.total_reasoning_tokens += 1;
-            }
-            if !content_token.is_empty() {
-                self.total_content_tokens += 1;
-            }
+    impl ChatBackend for ChatClient {
+        async fn get_completion(
+            &mut self,
+            request: impl IntoRequest<GetCompletionsRequest> + Send,
+        ) -> Result<GetChatCompletionResponse, Status> {
+            ChatClient::get_completion(self, request)
+                .await
+                .map(|response| response.into_inner())
+                .map_err(map_status)
         }
 
-        fn merge(&mut self, other: &Self) {
-            if self.index == other.index {
-                self.total_reasoning_tokens += other.total_reasoning_tokens;
-                self.total_content_tokens += other.total_content_tokens;
-                self.finish_reason = other.finish_reason
-            }
+        async fn get_completion_chunk(
+            &mut self,
+            request: impl IntoRequest<GetCompletionsRequest> + Send,
+        ) -> Result<ChunkStream, Status> {
+            let stream = ChatClient::get_completion_chunk(self, request)
+                .await
+                .map_err(map_status)?
+                .into_inner();
+            Ok(stream.map_err(map_status).boxed())
         }
     }
 
-    /// Processes a streaming chat completion response with custom callbacks.
-    ///
-    /// Iterates through streaming chunks, invoking consumer callbacks for each token,
-    /// completion event, and metadata. Supports multi-output streams with proper
-    /// context tracking.
-    ///
-    /// # Arguments
-    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>` (e.g. from
-    ///   `get_completion_chunk` or a mock). Must implement `Stream + Send + Unpin + 'static`.
-    /// * `consumer` - Configured callback consumer for handling stream events
+    /// In-memory [`ChatBackend`] that replays canned results in FIFO order.
     ///
-    /// # Returns
-    /// * `Ok(Vec<GetChatCompletionChunk>)` - All chunks collected from the stream
-    /// * `Err(Status)` - gRPC error if streaming failed
-    pub async fn process<S>(
-        mut stream: S,
-        mut consumer: Consumer<'_>,
-    ) -> Result<Vec<GetChatCompletionChunk>, Status>
-    where
-        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
-    {
-        let mut chunks: Vec<GetChatCompletionChunk> = Vec::new();
-        let mut output_stats: HashMap<i32, OutputStats> = HashMap::new();
-        let mut reasoning_start_fired: HashMap<i32, bool> = HashMap::new();
-        let mut reasoning_complete_fired: HashMap<i32, bool> = HashMap::new();
-        let mut content_start_fired: HashMap<i32, bool> = HashMap::new();
-        let mut content_complete_fired: HashMap<i32, bool> = HashMap::new();
-        let mut last_chunk: Option<GetChatCompletionChunk> = None;
-        let mut max_output_index_seen: i32 = -1;
-
-        loop {
-            match stream.next().await {
-                None => break,
-                Some(Err(status)) => return Err(status),
-                Some(Ok(chunk)) => {
-                    if let Some(ref mut on_chunk) = consumer.on_chunk {
-                        on_chunk(&chunk).await;
-                    }
-
-                    for output in &chunk.outputs {
-                        let cur_output_index = output.index;
-
-                        // Update max early so total_outputs is as accurate as possible
-                        max_output_index_seen = max_output_index_seen.max(cur_output_index);
-
-                        // Always start with the latest finish_reason from this chunk
-                        let mut cur_output_stats =
-                            OutputStats::init(cur_output_index, output.finish_reason());
+    /// Blocking and streaming calls have separate queues. Every request received is
+    /// recorded and can be inspected with [`MockChatBackend::requests()`]. Clones share
+    /// the same queues and request log, so a clone can be handed to the code under test
+    /// while the original is kept for assertions. A call with an empty queue fails with
+    /// `Status::failed_precondition`.
+    #[derive(Clone, Default)]
+    pub struct MockChatBackend {
+        state: Arc<Mutex<MockState>>,
+    }
 
-                        let delta = output.delta.as_ref();
+    #[derive(Default)]
+    struct MockState {
+        responses: VecDeque<Result<GetChatCompletionResponse, Status>>,
+        streams: VecDeque<Result<Vec<Result<GetChatCompletionChunk, Status>>, Status>>,
+        requests: Vec<GetCompletionsRequest>,
+        metadata: Vec<MetadataMap>,
+    }
 
-                        // Increment token counts only if there is a delta
-                        if let Some(delta) = delta {
-                            cur_output_stats.inc(&delta.reasoning_content, &delta.content);
-                        }
+    impl MockChatBackend {
+        /// Creates a mock with empty queues.
+        pub fn new() -> Self {
+            Self::default()
+        }
 
-                        // Merge current chunk data into accumulated stats
-                        // Chained to avoid the move error
-                        let merged = output_stats
-                            .entry(cur_output_index)
-                            .and_modify(|e| e.merge(&cur_output_stats))
-                            .or_insert(cur_output_stats);
-
-                        let (reasoning_status, content_status) = get_output_status(merged);
-
-                        let total_outputs = (max_output_index_seen + 1) as usize;
-
-                        let output_ctx = OutputContext::new(
-                            total_outputs,
-                            cur_output_index as usize,
-                            reasoning_status.clone(),
-                            content_status.clone(),
-                        );
-
-                        // ####################
-                        // Reasoning
-                        // ####################
-                        if let Some(delta) = delta
-                            && !delta.reasoning_content.is_empty()
-                        {
-                            // Reasoning start (once)
-                            if reasoning_start_fired
-                                .get(&cur_output_index)
-                                .copied()
-                                .unwrap_or(false)
-                                == false
-                            {
-                                if let Some(ref mut on_reasoning_start) =
-                                    consumer.on_reasoning_start
-                                {
-                                    on_reasoning_start(&output_ctx).await;
-                                }
-                                reasoning_start_fired.insert(cur_output_index, true);
-                            }
-                            // Reasoning tokens (multiple)
-                            if let Some(ref mut on_reasoning_token) = consumer.on_reasoning_token {
-                                on_reasoning_token(&output_ctx, &delta.reasoning_content).await;
-                            }
-                        }
+        /// Queues a response for the next blocking call.
+        pub fn push_response(&self, response: GetChatCompletionResponse) -> &Self {
+            self.lock().responses.push_back(Ok(response));
+            self
+        }
 
-                        // Phase completion callback – fire immediately when we detect Complete
-                        // (runs even on chunks without delta, in case finish_reason updated)
-                        // Reasoning complete (once)
-                        if reasoning_status == PhaseStatus::Complete
-                            && reasoning_complete_fired
-                                .get(&cur_output_index)
-                                .copied()
-                                .unwrap_or(false)
-                                == false
-                            && merged.total_reasoning_tokens > 0
-                        {
-                            if let Some(ref mut on_reasoning_complete) =
-                                consumer.on_reasoning_complete
-                            {
-                                on_reasoning_complete(&output_ctx).await;
-                            }
-                            reasoning_complete_fired.insert(cur_output_index, true);
-                        }
+        /// Queues an error for the next blocking call.
+        pub fn push_error(&self, status: Status) -> &Self {
+            self.lock().responses.push_back(Err(status));
+            self
+        }
 
-                        // ####################
-                        // Content
-                        // ####################
-                        if let Some(delta) = delta
-                            && !delta.content.is_empty()
-                        {
-                            // Content start (once)
-                            if content_start_fired
-                                .get(&cur_output_index)
-                                .copied()
-                                .unwrap_or(false)
-                                == false
-                            {
-                                if let Some(ref mut on_content_start) = consumer.on_content_start {
-                                    on_content_start(&output_ctx).await;
-                                }
-                                content_start_fired.insert(cur_output_index, true);
-                            }
-                            // Content tokens (multiple)
-                            if let Some(ref mut on_content_token) = consumer.on_content_token {
-                                on_content_token(&output_ctx, &delta.content).await;
-                            }
-                        }
+        /// Queues a stream that yields `chunks` for the next streaming call.
+        pub fn push_stream(&self, chunks: Vec<GetChatCompletionChunk>) -> &Self {
+            self.lock()
+                .streams
+                .push_back(Ok(chunks.into_iter().map(Ok).collect()));
+            self
+        }
 
-                        // Phase completion callback – fire immediately when we detect Complete
-                        // (runs even on chunks without delta, in case finish_reason updated)
-                        // Content complete (once)
-                        if content_status == PhaseStatus::Complete
-                            && content_complete_fired
-                                .get(&cur_output_index)
-                                .copied()
-                                .unwrap_or(false)
-                                == false
-                            && merged.total_content_tokens > 0
-                        {
-                            if let Some(ref mut on_content_complete) = consumer.on_content_complete
-                            {
-                                on_content_complete(&output_ctx).await;
-                            }
-                            content_complete_fired.insert(cur_output_index, true);
-                        }
+        /// Queues a stream for the next streaming call that yields `chunks` and then fails
+        /// with `status`, e.g. to simulate a dropped connection.
+        pub fn push_stream_with_error(
+            &self,
+            chunks: Vec<GetChatCompletionChunk>,
+            status: Status,
+        ) -> &Self {
+            let items = chunks.into_iter().map(Ok).chain([Err(status)]).collect();
+            self.lock().streams.push_back(Ok(items));
+            self
+        }
 
-                        // ####################
-                        // Citations, tool calls
-                        // ####################
-                        if let Some(delta) = delta {
-                            // Inline citations
-                            if let Some(ref mut on_inline_citations) = consumer.on_inline_citations
-                                && !delta.citations.is_empty()
-                            {
-                                on_inline_citations(&output_ctx, &delta.citations).await;
-                            }
+        /// Queues an error returned by the next streaming call before any chunk.
+        pub fn push_stream_error(&self, status: Status) -> &Self {
+            self.lock().streams.push_back(Err(status));
+            self
+        }
 
-                            // Tool calls
-                            if !delta.tool_calls.is_empty() {
-                                let capacity = delta.tool_calls.len();
-                                let mut client_tool_calls = Vec::with_capacity(capacity);
-                                let mut server_tool_calls = Vec::with_capacity(capacity);
-
-                                for tool_call in &delta.tool_calls {
-                                    if tool_call.r#type == ToolCallType::ClientSideTool.into() {
-                                        client_tool_calls.push(tool_call.clone());
-                                    } else {
-                                        server_tool_calls.push(tool_call.clone());
-                                    }
-                                }
-
-                                if let Some(ref mut on_client_tool_calls) =
-                                    consumer.on_client_tool_calls
-                                    && !client_tool_calls.is_empty()
-                                {
-                                    on_client_tool_calls(&output_ctx, &client_tool_calls).await;
-                                }
-
-                                if let Some(ref mut on_server_tool_calls) =
-                                    consumer.on_server_tool_calls
-                                    && !server_tool_calls.is_empty()
-                                {
-                                    on_server_tool_calls(&output_ctx, &server_tool_calls).await;
-                                }
-                            }
-                        }
-                    }
+        /// Returns every request received so far, in call order.
+        pub fn requests(&self) -> Vec<GetCompletionsRequest> {
+            self.lock().requests.clone()
+        }
 
-                    last_chunk = Some(chunk.clone());
-                    chunks.push(chunk);
-                }
-            }
+        /// Returns the metadata of every request received so far, in call order.
+        pub fn metadata(&self) -> Vec<MetadataMap> {
+            self.lock().metadata.clone()
         }
 
-        // Final metadata callbacks (only on last chunk)
-        if let Some(ref last_chunk) = last_chunk {
-            if let Some(ref mut on_usage) = consumer.on_usage {
-                if let Some(ref usage) = last_chunk.usage {
-                    on_usage(usage).await;
-                }
-            }
-            if let Some(ref mut on_citations) = consumer.on_citations {
-                if !last_chunk.citations.is_empty() {
-                    on_citations(&last_chunk.citations).await;
-                }
-            }
+        fn record(
+            &self,
+            request: impl IntoRequest<GetCompletionsRequest>,
+        ) -> MutexGuard<'_, MockState> {
+            let (metadata, _, request) = request.into_request().into_parts();
+            let mut state = self.lock();
+            state.requests.push(request);
+            state.metadata.push(metadata);
+            state
         }
 
-        Ok(chunks)
+        fn lock(&self) -> MutexGuard<'_, MockState> {
+            // A panic while holding the lock can only come from a test assertion
+            self.state.lock().unwrap_or_else(|e| e.into_inner())
+        }
     }
 
-    /// Returns (reasoning_status, content_status) for the current output from accumulated stats.
-    fn get_output_status(cur_output_stats: &OutputStats) -> (PhaseStatus, PhaseStatus) {
-        let is_finished = cur_output_stats.finish_reason != FinishReason::ReasonInvalid;
-        let r = cur_output_stats.total_reasoning_tokens;
-        let c = cur_output_stats.total_content_tokens;
-
-        let reasoning_status = if is_finished {
-            PhaseStatus::Complete
-        } else if r == 0 && c > 0 {
-            PhaseStatus::Complete // no-reasoning model, content started
-        } else if r > 0 && c > 0 {
-            PhaseStatus::Complete // reasoning done, content in progress
-        } else if r == 1 && c == 0 {
-            PhaseStatus::Start // first reasoning token
-        } else if r > 1 && c == 0 {
-            PhaseStatus::Pending
-        } else {
-            PhaseStatus::Init // r == 0 && c == 0
-        };
-
-        let content_status = if is_finished {
-            PhaseStatus::Complete
-        } else if c == 1 {
-            PhaseStatus::Start // first content token
-        } else if c > 1 {
-            PhaseStatus::Pending
-        } else {
-            PhaseStatus::Init
-        };
+    impl ChatBackend for MockChatBackend {
+        fn get_completion(
+            &mut self,
+            request: impl IntoRequest<GetCompletionsRequest> + Send,
+        ) -> impl Future<Output = Result<GetChatCompletionResponse, Status>> + Send {
+            let result = {
+                let mut state = self.record(request);
+                state.responses.pop_front().unwrap_or_else(|| {
+                    Err(Status::failed_precondition(
+                        "MockChatBackend has no queued response",
+                    ))
+                })
+            };
+            async move { result }
+        }
 
-        (reasoning_status, content_status)
+        fn get_completion_chunk(
+            &mut self,
+            request: impl IntoRequest<GetCompletionsRequest> + Send,
+        ) -> impl Future<Output = Result<ChunkStream, Status>> + Send {
+            let result = {
+                let mut state = self.record(request);
+                state.streams.pop_front().unwrap_or_else(|| {
+                    Err(Status::failed_precondition(
+                        "MockChatBackend has no queued stream",
+                    ))
+                })
+            };
+            async move { result.map(|items| stream::iter(items).boxed()) }
+        }
     }
+}
 
-    /// Assembles streaming chunks into a complete chat completion response.
+/// Concurrency helpers for chat completions.
+///
+/// Provides utilities for fanning out many independent completion requests over a
+/// shared client while bounding the number of in-flight calls.
+pub mod parallel {
+    use crate::chat::backend::ChatBackend;
+    use crate::export::Status;
+    use crate::xai_api::{GetChatCompletionResponse, GetCompletionsRequest};
+    use futures::{StreamExt, stream};
+
+    /// Issues many blocking completions concurrently and collects the results in input order.
     ///
-    /// Reconstructs a full `GetChatCompletionResponse` from collected chunks by:
-    /// - Grouping chunks by output index for multi-output handling
-    /// - Accumulating content, reasoning, and tool calls across deltas
-    /// - Preserving metadata from first chunk and usage stats from last chunk
-    /// - Maintaining output ordering
+    /// Each request is sent on its own clone of `client` (clones share the underlying
+    /// channel). At most `max_concurrency` requests are in flight at any time; a value of
+    /// `0` is treated as `1`. Failures are reported per request and do not cancel the
+    /// remaining requests.
     ///
     /// # Arguments
-    /// * `chunks` - Vector of chunks from a streaming response
+    /// * `client` - Chat client (or other [`ChatBackend`]) to send the requests with
+    /// * `requests` - Completion requests to send
+    /// * `max_concurrency` - Maximum number of concurrent in-flight requests
     ///
     /// # Returns
-    /// * `Some(GetChatCompletionResponse)` - Complete assembled response
-    /// * `None` - If chunks vector is empty
+    /// * `Vec<Result<GetChatCompletionResponse, Status>>` - One result per request, in the
+    ///   same order as `requests`
+    pub async fn complete<B>(
+        client: &B,
+        requests: Vec<GetCompletionsRequest>,
+        max_concurrency: usize,
+    ) -> Vec<Result<GetChatCompletionResponse, Status>>
+    where
+        B: ChatBackend + Clone,
+    {
+        stream::iter(requests)
+            .map(|request| {
+                let mut client = client.clone();
+                async move { client.get_completion(request).await }
+            })
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+}
+
+/// Bounded sharing of one client between many concurrent streams.
+///
+/// Provides [`Pool`], which caps the number of streaming completions in flight so a server
+/// handling many users does not overwhelm the upstream.
+pub mod pool {
+    use crate::chat::backend::ChatBackend;
+    use crate::chat::client::ChatClient;
+    use crate::chat::stream::{Consumer, process};
+    use crate::export::Status;
+    use crate::xai_api::{GetChatCompletionChunk, GetCompletionsRequest};
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
+
+    /// A client shared by concurrent streaming completions, with a limit on how many run
+    /// at once.
     ///
-    pub fn assemble(chunks: Vec<GetChatCompletionChunk>) -> Option<GetChatCompletionResponse> {
-        if chunks.is_empty() {
-            return None;
+    /// Cloning a `Pool` is cheap; clones share the client and the limit. Callers beyond the
+    /// limit wait in FIFO order in [`Pool::acquire_and_stream`] until a running stream ends.
+    #[derive(Clone)]
+    pub struct Pool<B = ChatClient> {
+        client: B,
+        permits: Arc<Semaphore>,
+        max_in_flight: usize,
+    }
+
+    impl<B> Pool<B>
+    where
+        B: ChatBackend + Clone,
+    {
+        /// Creates a pool around `client`.
+        ///
+        /// # Arguments
+        /// * `client` - Chat client (or other [`ChatBackend`]) to stream with
+        /// * `max_in_flight` - Maximum number of concurrent streams; `0` is treated as `1`, and
+        ///   values above [`Semaphore::MAX_PERMITS`] as that maximum
+        ///
+        /// # Returns
+        /// * `Pool<B>` - The pool
+        pub fn new(client: B, max_in_flight: usize) -> Self {
+            let max_in_flight = max_in_flight.clamp(1, Semaphore::MAX_PERMITS);
+            Self {
+                client,
+                permits: Arc::new(Semaphore::new(max_in_flight)),
+                max_in_flight,
+            }
         }
 
-        // Use the first chunk for metadata that should be consistent across all chunks
-        let first_chunk = &chunks[0];
-        let last_chunk = &chunks[chunks.len() - 1];
+        /// Waits for a free slot, then streams the completion into `consumer`.
+        ///
+        /// The slot is held until the stream has been fully processed or fails, and is
+        /// released if the returned future is dropped.
+        ///
+        /// # Arguments
+        /// * `request` - Completion request to stream
+        /// * `consumer` - Callbacks for the stream, as for [`process`]
+        ///
+        /// # Returns
+        /// * `Ok(Vec<GetChatCompletionChunk>)` - All chunks received
+        /// * `Err(Status)` - The request or the stream failed
+        pub async fn acquire_and_stream(
+            &self,
+            request: GetCompletionsRequest,
+            consumer: Consumer<'_>,
+        ) -> Result<Vec<GetChatCompletionChunk>, Status> {
+            let _permit = self
+                .permits
+                .acquire()
+                .await
+                .map_err(|_| Status::internal("Pool semaphore closed"))?;
+            let mut client = self.client.clone();
+            let stream = client.get_completion_chunk(request).await?;
+            process(stream, consumer).await
+        }
 
-        // Group chunks by output index to handle multiple outputs
+        /// Returns the number of streams currently running.
+        pub fn in_flight(&self) -> usize {
+            self.max_in_flight - self.permits.available_permits()
+        }
+
+        /// Returns the maximum number of concurrent streams.
+        pub fn max_in_flight(&self) -> usize {
+            self.max_in_flight
+        }
+    }
+}
+
+/// Automatic continuation of truncated completions.
+///
+/// Provides [`continue_until_complete`], which keeps requesting more output while the
+/// model stops because it hit `max_tokens`.
+pub mod continuation {
+    use crate::chat::backend::ChatBackend;
+    use crate::export::Status;
+    use crate::xai_api::{
+        Content, FinishReason, GetChatCompletionResponse, GetCompletionsRequest, Message,
+        MessageRole, SamplingUsage, content,
+    };
+
+    /// Requests a completion and keeps continuing it while it is truncated by `max_tokens`.
+    ///
+    /// After each response whose first output finished with `ReasonMaxLen`, the partial
+    /// content generated so far is appended to the conversation as an assistant message
+    /// and the request is sent again, so the model picks up where it stopped. This repeats
+    /// until the output finishes for another reason or `max_rounds` requests were sent
+    /// (a value of `0` is treated as `1`).
+    ///
+    /// The merged response is the last response with the content and reasoning of output 0
+    /// concatenated across rounds and the usage summed over all rounds. Only output 0 is
+    /// continued, so use this with `n` unset or `1`.
+    ///
+    /// # Arguments
+    /// * `client` - Chat client (or other [`ChatBackend`]) to send the requests with
+    /// * `request` - Initial completion request
+    /// * `max_rounds` - Maximum number of requests to send, including the first
+    ///
+    /// # Returns
+    /// * `Ok(GetChatCompletionResponse)` - Merged response; still truncated if the round
+    ///   limit was reached
+    /// * `Err(Status)` - gRPC error from any round
+    pub async fn continue_until_complete<B>(
+        client: &B,
+        mut request: GetCompletionsRequest,
+        max_rounds: usize,
+    ) -> Result<GetChatCompletionResponse, Status>
+    where
+        B: ChatBackend + Clone,
+    {
+        let mut client = client.clone();
+        let mut merged: Option<GetChatCompletionResponse> = None;
+
+        for _ in 0..max_rounds.max(1) {
+            let response = client.get_completion(request.clone()).await?;
+
+            let truncated = response
+                .outputs
+                .iter()
+                .find(|output| output.index == 0)
+                .is_some_and(|output| output.finish_reason_enum() == FinishReason::ReasonMaxLen);
+
+            let partial = first_content(&response);
+            merged = Some(match merged {
+                None => response,
+                Some(previous) => merge(previous, response),
+            });
+
+            if !truncated {
+                break;
+            }
+            request.messages.push(Message {
+                role: MessageRole::RoleAssistant.into(),
+                content: vec![Content {
+                    content: Some(content::Content::Text(partial)),
+                }],
+                ..Default::default()
+            });
+        }
+
+        Ok(merged.expect("at least one round is always sent"))
+    }
+
+    /// Returns the content of output 0, or an empty string if it has none.
+    fn first_content(response: &GetChatCompletionResponse) -> String {
+        response
+            .outputs
+            .iter()
+            .find(|output| output.index == 0)
+            .and_then(|output| output.message.as_ref())
+            .map(|message| message.content.clone())
+            .unwrap_or_default()
+    }
+
+    /// Appends `next` to `previous`: output 0 text is concatenated, usage is summed and
+    /// everything else is taken from `next`.
+    fn merge(
+        previous: GetChatCompletionResponse,
+        mut next: GetChatCompletionResponse,
+    ) -> GetChatCompletionResponse {
+        let previous_message = previous
+            .outputs
+            .into_iter()
+            .find(|output| output.index == 0)
+            .and_then(|output| output.message);
+
+        if let (Some(previous_message), Some(message)) = (
+            previous_message,
+            next.outputs
+                .iter_mut()
+                .find(|output| output.index == 0)
+                .and_then(|output| output.message.as_mut()),
+        ) {
+            message.content = previous_message.content + &message.content;
+            message.reasoning_content =
+                previous_message.reasoning_content + &message.reasoning_content;
+        }
+
+        next.usage = match (previous.usage, next.usage) {
+            (Some(total), Some(usage)) => Some(add_usage(total, &usage)),
+            (total, usage) => total.or(usage),
+        };
+        next
+    }
+
+    fn add_usage(mut total: SamplingUsage, usage: &SamplingUsage) -> SamplingUsage {
+        total.completion_tokens += usage.completion_tokens;
+        total.reasoning_tokens += usage.reasoning_tokens;
+        total.prompt_tokens += usage.prompt_tokens;
+        total.total_tokens += usage.total_tokens;
+        total.prompt_text_tokens += usage.prompt_text_tokens;
+        total.cached_prompt_text_tokens += usage.cached_prompt_text_tokens;
+        total.prompt_image_tokens += usage.prompt_image_tokens;
+        total.num_sources_used += usage.num_sources_used;
+        total
+            .server_side_tools_used
+            .extend_from_slice(&usage.server_side_tools_used);
+        total
+    }
+}
+
+/// Request building for chat completions.
+///
+/// Provides [`RequestBuilder`], a fluent alternative to constructing
+/// `GetCompletionsRequest` by hand.
+pub mod request {
+    use crate::chat::validate::{BuildWarning, ValidationError};
+    use crate::export::{Request, Status};
+    use crate::xai_api::{
+        FormatType, GetChatCompletionResponse, GetCompletionsRequest, IncludeOption, Message,
+        ReasoningEffort, ResponseFormat, Tool, ToolChoice, ToolMode, tool_choice,
+    };
+
+    /// Builder for `GetCompletionsRequest`.
+    ///
+    /// Builder methods take `&mut self` and return `&mut Self`, matching
+    /// [`Consumer`](crate::chat::stream::Consumer). [`RequestBuilder::build()`] clones the
+    /// configured request, so one builder can produce several requests.
+    ///
+    /// # Stateful continuations
+    ///
+    /// The API can store a conversation server-side and continue it from a previous
+    /// response, so the full history does not have to be resent:
+    ///
+    /// 1. Send the first request with [`store_messages(true)`](RequestBuilder::store_messages).
+    /// 2. Take the `id` of the returned `GetChatCompletionResponse`.
+    /// 3. Build the next request with [`continue_from(id)`](RequestBuilder::continue_from),
+    ///    adding only the new messages.
+    ///
+    /// `encrypted_content` is the stateless alternative: with
+    /// [`use_encrypted_content(true)`](RequestBuilder::use_encrypted_content) the server
+    /// returns the reasoning trace as an opaque `encrypted_content` blob that the client
+    /// sends back on the assistant message (see [`to_messages`](crate::chat::utils::to_messages)).
+    /// When continuing from a stored response, the stored turns are rehydrated
+    /// server-side and there is no need to resend `encrypted_content` for them.
+    ///
+    /// # Sampling parameters
+    ///
+    /// Penalties and stop sequences are only honored by non-reasoning models; reasoning
+    /// models (e.g. `grok-4`, `grok-3-mini`) reject requests that set them. Logit bias is
+    /// not part of the gRPC API and has no builder method.
+    /// [`try_build()`](RequestBuilder::try_build) checks the documented ranges locally,
+    /// along with the model, messages, roles and `n`.
+    ///
+    /// # Retries
+    ///
+    /// An [`idempotency_key`](RequestBuilder::idempotency_key) is kept on the builder, not in
+    /// the `GetCompletionsRequest`. Retry loops should call
+    /// [`build_request()`](RequestBuilder::build_request) once per attempt on the same builder,
+    /// so every attempt carries the same key. The header is not documented by the xAI API; see
+    /// [`with_idempotency_key`](crate::common::request::with_idempotency_key).
+    #[derive(Debug, Clone, Default)]
+    pub struct RequestBuilder {
+        request: GetCompletionsRequest,
+        idempotency_key: Option<String>,
+    }
+
+    impl RequestBuilder {
+        /// Creates a new `RequestBuilder` for the given model with no messages.
+        pub fn new(model: impl Into<String>) -> Self {
+            Self {
+                request: GetCompletionsRequest {
+                    model: model.into(),
+                    ..Default::default()
+                },
+                idempotency_key: None,
+            }
+        }
+
+        /// Sets the model name.
+        pub fn model(&mut self, model: impl Into<String>) -> &mut Self {
+            self.request.model = model.into();
+            self
+        }
+
+        /// Appends a single message to the conversation.
+        pub fn message(&mut self, message: Message) -> &mut Self {
+            self.request.messages.push(message);
+            self
+        }
+
+        /// Appends multiple messages to the conversation, preserving their order.
+        pub fn messages(&mut self, messages: impl IntoIterator<Item = Message>) -> &mut Self {
+            self.request.messages.extend(messages);
+            self
+        }
+
+        /// Sets the number of completions to generate.
+        pub fn n(&mut self, n: i32) -> &mut Self {
+            self.request.n = Some(n);
+            self
+        }
+
+        /// Sets the maximum number of tokens to sample.
+        pub fn max_tokens(&mut self, max_tokens: i32) -> &mut Self {
+            self.request.max_tokens = Some(max_tokens);
+            self
+        }
+
+        /// Sets the sampling temperature.
+        pub fn temperature(&mut self, temperature: f32) -> &mut Self {
+            self.request.temperature = Some(temperature);
+            self
+        }
+
+        /// Sets the nucleus sampling probability mass.
+        pub fn top_p(&mut self, top_p: f32) -> &mut Self {
+            self.request.top_p = Some(top_p);
+            self
+        }
+
+        /// Sets the sampling seed.
+        ///
+        /// Seeding is best effort: a fixed request and seed usually reproduces the same
+        /// output, but this is not guaranteed and may change as the backend evolves. Honored
+        /// by all chat models.
+        pub fn seed(&mut self, seed: i32) -> &mut Self {
+            self.request.seed = Some(seed);
+            self
+        }
+
+        /// Sets the stop sequences, replacing any previously set.
+        ///
+        /// At most [`MAX_STOP_SEQUENCES`](crate::chat::validate::MAX_STOP_SEQUENCES) are
+        /// accepted. Reasoning models (e.g. `grok-4`, `grok-3-mini`) reject requests that set
+        /// stop sequences.
+        pub fn stop<I, S>(&mut self, stop: I) -> &mut Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+            self.request.stop = stop.into_iter().map(Into::into).collect();
+            self
+        }
+
+        /// Sets the frequency penalty, in `[-2.0, 2.0]`.
+        ///
+        /// Reasoning models (e.g. `grok-4`, `grok-3-mini`) reject requests that set it.
+        pub fn frequency_penalty(&mut self, penalty: f32) -> &mut Self {
+            self.request.frequency_penalty = Some(penalty);
+            self
+        }
+
+        /// Sets the presence penalty, in `[-2.0, 2.0]`.
+        ///
+        /// Reasoning models (e.g. `grok-4`, `grok-3-mini`) reject requests that set it.
+        pub fn presence_penalty(&mut self, penalty: f32) -> &mut Self {
+            self.request.presence_penalty = Some(penalty);
+            self
+        }
+
+        /// Sets the opaque end-user identifier stored with the request.
+        ///
+        /// See [`common::user`](crate::common::user) for what the field is used for.
+        pub fn user(&mut self, user: impl Into<String>) -> &mut Self {
+            self.request.user = user.into();
+            self
+        }
+
+        /// Sets the end-user identifier to the SHA-256 hex digest of `id`.
+        ///
+        /// See [`hash_id`](crate::common::user::hash_id).
+        pub fn user_hashed(&mut self, id: &str) -> &mut Self {
+            self.request.user = crate::common::user::hash_id(id);
+            self
+        }
+
+        /// Sets whether log probabilities of the output tokens are returned.
+        pub fn logprobs(&mut self, enabled: bool) -> &mut Self {
+            self.request.logprobs = enabled;
+            self
+        }
+
+        /// Sets how many alternatives, in `[0, 8]`, are returned with each token's log
+        /// probability. Requires [`logprobs(true)`](RequestBuilder::logprobs).
+        pub fn top_logprobs(&mut self, n: i32) -> &mut Self {
+            self.request.top_logprobs = Some(n);
+            self
+        }
+
+        /// Appends a tool the model may call.
+        pub fn tool(&mut self, tool: Tool) -> &mut Self {
+            self.request.tools.push(tool);
+            self
+        }
+
+        /// Appends multiple tools the model may call, preserving their order.
+        pub fn tools(&mut self, tools: impl IntoIterator<Item = Tool>) -> &mut Self {
+            self.request.tools.extend(tools);
+            self
+        }
+
+        /// Sets whether the model may call several tools in one response (default: true).
+        pub fn parallel_tool_calls(&mut self, enabled: bool) -> &mut Self {
+            self.request.parallel_tool_calls = Some(enabled);
+            self
+        }
+
+        /// Sets whether the model may (`Auto`), must not (`None`) or must (`Required`) call
+        /// tools, replacing any [`force_tool`](RequestBuilder::force_tool).
+        pub fn tool_choice(&mut self, mode: ToolMode) -> &mut Self {
+            self.request.tool_choice = Some(ToolChoice {
+                tool_choice: Some(tool_choice::ToolChoice::Mode(mode.into())),
+            });
+            self
+        }
+
+        /// Forces the model to call the function tool with the given name, replacing any
+        /// [`tool_choice`](RequestBuilder::tool_choice).
+        ///
+        /// [`try_build()`](RequestBuilder::try_build) checks that a function tool with this
+        /// name was added.
+        pub fn force_tool(&mut self, name: &str) -> &mut Self {
+            self.request.tool_choice = Some(ToolChoice {
+                tool_choice: Some(tool_choice::ToolChoice::FunctionName(name.to_string())),
+            });
+            self
+        }
+
+        /// Sets how long a reasoning model thinks before responding.
+        ///
+        /// `EffortLow` answers faster with fewer reasoning tokens, `EffortHigh` thinks longer
+        /// on hard problems. Only `grok-3-mini` accepts an effort (see
+        /// [`ModelId::supports_reasoning_effort`](crate::models::ModelId::supports_reasoning_effort));
+        /// [`try_build()`](RequestBuilder::try_build) rejects it for other known models, whose
+        /// reasoning is either automatic (`grok-4`, `grok-code`) or absent (`grok-2`, `grok-3`).
+        ///
+        /// Lowering the effort is the only way to spend fewer reasoning tokens: the API has no
+        /// option to turn reasoning off or to omit it from the response. To drop it from an
+        /// assembled stream, use
+        /// [`assemble_without_reasoning`](crate::chat::stream::assemble_without_reasoning).
+        pub fn reasoning_effort(&mut self, effort: ReasoningEffort) -> &mut Self {
+            self.request.reasoning_effort = Some(effort.into());
+            self
+        }
+
+        /// Constrains the response to a JSON object, without a schema.
+        ///
+        /// Replaces any [`json_schema_str`](RequestBuilder::json_schema_str). The prompt should still
+        /// describe the expected shape; the model only guarantees syntactically valid JSON.
+        pub fn json_object(&mut self) -> &mut Self {
+            self.request.response_format = Some(ResponseFormat {
+                format_type: FormatType::JsonObject.into(),
+                schema: None,
+            });
+            self
+        }
+
+        /// Constrains the response to JSON conforming to `schema`, given as a string.
+        ///
+        /// `schema` is a serialized JSON Schema document; with the `schemars` feature,
+        /// `json_schema::<T>()` generates it from a Rust type. The response is not deserialized;
+        /// parse `content` as needed. Replaces any [`json_object`](RequestBuilder::json_object).
+        /// [`try_build()`](RequestBuilder::try_build) checks that the schema is a JSON object and
+        /// that the model supports structured output, if the model is known.
+        pub fn json_schema_str(&mut self, schema: impl Into<String>) -> &mut Self {
+            self.request.response_format = Some(ResponseFormat {
+                format_type: FormatType::JsonSchema.into(),
+                schema: Some(schema.into()),
+            });
+            self
+        }
+
+        /// Constrains the response to JSON conforming to the schema of `T`.
+        ///
+        /// Generates the schema with [`schemars::schema_for!`] and attaches it as with
+        /// [`json_schema_str`](RequestBuilder::json_schema_str). The response is not deserialized;
+        /// parse `content` into `T` as needed. Requires the `schemars` feature.
+        #[cfg(feature = "schemars")]
+        pub fn json_schema<T: schemars::JsonSchema>(&mut self) -> &mut Self {
+            self.json_schema_str(schemars::schema_for!(T).as_value().to_string())
+        }
+
+        /// Sets whether the server stores the request and response for later continuation.
+        pub fn store_messages(&mut self, store: bool) -> &mut Self {
+            self.request.store_messages = store;
+            self
+        }
+
+        /// Sets whether the server returns the reasoning trace as `encrypted_content`.
+        pub fn use_encrypted_content(&mut self, enabled: bool) -> &mut Self {
+            self.request.use_encrypted_content = enabled;
+            self
+        }
+
+        /// Continues the stored conversation that produced `response_id`.
+        ///
+        /// Sets `previous_response_id` so the server reuses its stored context. The previous
+        /// request must have been sent with `store_messages(true)`.
+        pub fn continue_from(&mut self, response_id: impl Into<String>) -> &mut Self {
+            self.request.previous_response_id = Some(response_id.into());
+            self
+        }
+
+        /// Continues the stored conversation that produced `response`.
+        ///
+        /// Shorthand for [`continue_from(&response.id)`](RequestBuilder::continue_from).
+        pub fn continue_from_response(
+            &mut self,
+            response: &GetChatCompletionResponse,
+        ) -> &mut Self {
+            self.continue_from(response.id.clone())
+        }
+
+        /// Returns the configured `GetCompletionsRequest` without validating it.
+        ///
+        /// `build()` stays infallible and lenient, so invalid requests (e.g. without messages)
+        /// are only rejected by the server. Use [`try_build()`](RequestBuilder::try_build) to
+        /// validate locally, or [`build_checked()`](RequestBuilder::build_checked) to also get
+        /// warnings.
+        pub fn build(&self) -> GetCompletionsRequest {
+            self.request.clone()
+        }
+
+        /// Validates the request and returns the configured `GetCompletionsRequest`.
+        ///
+        /// Runs [`GetCompletionsRequest::validate`]: the model and at least one message must be
+        /// set, every message needs a valid role, `n` must be at least 1, a forced tool must be
+        /// among the tools, a reasoning effort must be supported by the model and the sampling
+        /// parameters must be within range.
+        ///
+        /// # Returns
+        /// * `Ok(GetCompletionsRequest)` - The request, if it passed all checks
+        /// * `Err(ValidationError)` - The first failed check
+        pub fn try_build(&self) -> Result<GetCompletionsRequest, ValidationError> {
+            self.request.validate()?;
+            Ok(self.build())
+        }
+
+        /// Validates the request like [`try_build()`](RequestBuilder::try_build) and also
+        /// reports likely mistakes.
+        ///
+        /// [`build()`](RequestBuilder::build) never checks anything and `try_build()` only
+        /// rejects invalid requests; this is the strict variant, returning the
+        /// [`warnings`](GetCompletionsRequest::warnings) alongside the request so callers can
+        /// log them or treat them as errors.
+        ///
+        /// # Returns
+        /// * `Ok((GetCompletionsRequest, Vec<BuildWarning>))` - The request and its warnings
+        /// * `Err(ValidationError)` - The first failed check
+        pub fn build_checked(
+            &self,
+        ) -> Result<(GetCompletionsRequest, Vec<BuildWarning>), ValidationError> {
+            let request = self.try_build()?;
+            let warnings = request.warnings();
+            Ok((request, warnings))
+        }
+
+        /// Adds an option to the request's `include` list, ignoring duplicates.
+        ///
+        /// Include options opt into extra response content, such as server-side tool
+        /// outputs, inline citations or verbose streaming (streaming requests only).
+        /// `IncludeOption::Invalid` is kept so that [`try_build()`](RequestBuilder::try_build)
+        /// can reject it.
+        pub fn include(&mut self, option: IncludeOption) -> &mut Self {
+            let value = option as i32;
+            if !self.request.include.contains(&value) {
+                self.request.include.push(value);
+            }
+            self
+        }
+
+        /// Adds several options to the request's `include` list, ignoring duplicates.
+        ///
+        /// Pass [`IncludeOption::all_outputs()`] to include every tool's call output.
+        pub fn include_all(&mut self, options: &[IncludeOption]) -> &mut Self {
+            for option in options {
+                self.include(*option);
+            }
+            self
+        }
+
+        /// Sets the idempotency key sent by [`build_request()`](RequestBuilder::build_request).
+        ///
+        /// See [`with_idempotency_key`](crate::common::request::with_idempotency_key).
+        pub fn idempotency_key(&mut self, key: &str) -> &mut Self {
+            self.idempotency_key = Some(key.to_string());
+            self
+        }
+
+        /// Returns the configured request wrapped in a `Request`, carrying the idempotency key.
+        ///
+        /// Call once per attempt when retrying; every attempt gets the same key.
+        ///
+        /// # Returns
+        /// * `Ok(Request<GetCompletionsRequest>)` - The request, with `x-idempotency-key` if set
+        /// * `Err(Status)` - `InvalidArgument` if the key is not a legal metadata value
+        pub fn build_request(&self) -> Result<Request<GetCompletionsRequest>, Status> {
+            let request = Request::new(self.build());
+            match &self.idempotency_key {
+                Some(key) => crate::common::request::with_idempotency_key(request, key),
+                None => Ok(request),
+            }
+        }
+    }
+
+    impl From<RequestBuilder> for GetCompletionsRequest {
+        fn from(builder: RequestBuilder) -> Self {
+            builder.request
+        }
+    }
+}
+
+/// Request hashing for response caches.
+///
+/// [`GetCompletionsRequest::content_hash`] gives requests that would produce the same
+/// completion the same key, so applications can cache responses in a store of their choice.
+pub mod cache {
+    use crate::xai_api::{GetCompletionsRequest, tool};
+    use prost::Message;
+    use ring::digest::{Context, SHA256};
+    use std::fmt::Write;
+
+    impl GetCompletionsRequest {
+        /// Returns a stable hash of the request content, as 64 lowercase hex characters.
+        ///
+        /// The hash covers every field that can change the completion: the model, the
+        /// messages (including images, tool calls and encrypted content), sampling parameters
+        /// (`temperature`, `top_p`, penalties, `seed`, `max_tokens`, `stop`, `n`, log
+        /// probabilities), tools and `tool_choice`, `response_format`, `reasoning_effort`,
+        /// search parameters, `previous_response_id`, `include` and the agent settings.
+        ///
+        /// It ignores `user` and `store_messages`, which only affect attribution and storage.
+        /// Request metadata such as an idempotency key or request id is not part of the
+        /// request message and never affects the hash. The extra headers of MCP tools are
+        /// hashed in sorted order, so their map order does not matter.
+        ///
+        /// The hash is a SHA-256 digest of the request's protobuf encoding; it is stable
+        /// across runs and processes of one SDK version, but may change when the API adds
+        /// fields.
+        ///
+        /// # Returns
+        /// * `String` - Hex SHA-256 digest of the request content
+        pub fn content_hash(&self) -> String {
+            let mut request = self.clone();
+            request.user.clear();
+            request.store_messages = false;
+
+            let mut headers: Vec<(String, String)> = Vec::new();
+            for (index, tool) in request.tools.iter_mut().enumerate() {
+                if let Some(tool::Tool::Mcp(mcp)) = tool.tool.as_mut() {
+                    headers.extend(
+                        mcp.extra_headers
+                            .drain()
+                            .map(|(name, value)| (format!("{index}:{name}"), value)),
+                    );
+                }
+            }
+            headers.sort();
+
+            let mut context = Context::new(&SHA256);
+            context.update(&request.encode_to_vec());
+            for (name, value) in &headers {
+                for part in [name, value] {
+                    context.update(&(part.len() as u64).to_le_bytes());
+                    context.update(part.as_bytes());
+                }
+            }
+            context
+                .finish()
+                .as_ref()
+                .iter()
+                .fold(String::with_capacity(64), |mut hex, byte| {
+                    let _ = write!(hex, "{byte:02x}");
+                    hex
+                })
+        }
+    }
+}
+
+/// Cost estimates for completion requests.
+///
+/// Prices come from the model's [`Pricing`]; the prompt is measured with the tokenize
+/// service, so the estimate can be shown before the request is sent.
+pub mod cost {
+    use crate::billing::Money;
+    use crate::export::Status;
+    use crate::models::Pricing;
+    use crate::tokenize::client::TokenizeClient;
+    use crate::xai_api::{GetCompletionsRequest, content};
+
+    /// 10^-8 cents (the unit of [`Pricing::cost_units`]) per cent.
+    const UNITS_PER_CENT: i128 = 100_000_000;
+
+    /// Estimated cost range of a completion request.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CostEstimate {
+        /// Number of prompt tokens the estimate is based on.
+        pub prompt_tokens: u64,
+        /// Cost of the prompt alone, rounded down to whole cents.
+        pub min: Money,
+        /// Cost if every completion uses all `max_tokens`, rounded up to whole cents; `None`
+        /// if the request sets no `max_tokens`.
+        pub max: Option<Money>,
+    }
+
+    impl CostEstimate {
+        /// Computes the estimate from a known prompt token count.
+        ///
+        /// # Arguments
+        /// * `prompt_tokens` - Number of prompt tokens
+        /// * `request` - Request whose `max_tokens` and `n` bound the completion
+        /// * `pricing` - Token prices of the request's model
+        ///
+        /// # Returns
+        /// * `CostEstimate` - The cost range
+        pub fn new(prompt_tokens: u64, request: &GetCompletionsRequest, pricing: &Pricing) -> Self {
+            let min = pricing.cost_units(prompt_tokens, 0);
+            let max = request.max_tokens.map(|max_tokens| {
+                let n = request.n.unwrap_or(1).max(1);
+                let completion_tokens = u64::try_from(max_tokens).unwrap_or(0) * n as u64;
+                pricing.cost_units(prompt_tokens, completion_tokens)
+            });
+            Self {
+                prompt_tokens,
+                min: Money::from_cents((min / UNITS_PER_CENT) as i64),
+                max: max.map(|max| {
+                    Money::from_cents(((max + UNITS_PER_CENT - 1) / UNITS_PER_CENT) as i64)
+                }),
+            }
+        }
+    }
+
+    /// Estimates what a request will cost, before sending it.
+    ///
+    /// This is an estimate, not a quote:
+    /// - The prompt is measured by tokenizing the text of all messages. Chat formatting
+    ///   adds a few tokens per message, and images and files are not counted.
+    /// - The completion is bounded by `max_tokens` (times `n`); reasoning tokens are billed
+    ///   as completion tokens. Without `max_tokens` there is no upper bound.
+    /// - Cached prompt tokens and server-side tool calls, which change the bill, are not
+    ///   taken into account.
+    ///
+    /// # Arguments
+    /// * `client` - Tokenize client
+    /// * `request` - Request to estimate
+    /// * `pricing` - Token prices of the request's model, e.g. `Pricing::from(&model)`
+    ///
+    /// # Returns
+    /// * `Ok(CostEstimate)` - The cost range
+    /// * `Err(Status)` - Status of the tokenize call
+    pub async fn estimate_cost(
+        client: &mut TokenizeClient,
+        request: &GetCompletionsRequest,
+        pricing: &Pricing,
+    ) -> Result<CostEstimate, Status> {
+        let text = request
+            .messages
+            .iter()
+            .flat_map(|message| &message.content)
+            .filter_map(|part| match &part.content {
+                Some(content::Content::Text(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt_tokens = if text.is_empty() {
+            0
+        } else {
+            crate::tokenize::count_tokens(client, &request.model, &text).await?
+        };
+        Ok(CostEstimate::new(prompt_tokens as u64, request, pricing))
+    }
+}
+
+/// Waiting for deferred chat completions.
+pub mod deferred {
+    use crate::chat::client::ChatClient;
+    use crate::common::deferred;
+    use crate::export::{Request, Status};
+    use crate::xai_api::{GetDeferredCompletionResponse, GetDeferredRequest};
+    use std::time::Duration;
+
+    /// Polls a completion started with `start_deferred_completion` until it finishes.
+    ///
+    /// Built on [`common::deferred::poll`](crate::common::deferred::poll): the first poll is
+    /// immediate, then the wait starts at `interval` and backs off exponentially.
+    ///
+    /// # Arguments
+    /// * `client` - Chat client
+    /// * `request_id` - ID from the `StartDeferredResponse`
+    /// * `interval` - Initial wait between polls
+    /// * `timeout` - Total time allowed before giving up
+    ///
+    /// # Returns
+    /// * `Ok(GetDeferredCompletionResponse)` - Response with status `Done` (carrying the
+    ///   completion), `Expired` or `Failed`
+    /// * `Err(Status)` - The RPC's status, or `DeadlineExceeded` after `timeout`
+    pub async fn wait(
+        client: &mut ChatClient,
+        request_id: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<GetDeferredCompletionResponse, Status> {
+        deferred::poll(
+            || {
+                let mut client = client.clone();
+                let request = Request::new(GetDeferredRequest {
+                    request_id: request_id.to_string(),
+                });
+                async move { Ok(client.get_deferred_completion(request).await?.into_inner()) }
+            },
+            interval,
+            timeout,
+        )
+        .await
+    }
+}
+
+/// Accessors for assembled chat completion responses.
+///
+/// Adds convenience methods to `GetChatCompletionResponse` for pulling tool calls out of
+/// multi-output responses without walking `outputs[i].message.tool_calls` by hand, for
+/// telling truncated outputs apart from finished ones via [`CompletionStatus`], for
+/// text statistics via [`ContentStats`], for display-ready text via
+/// [`normalize_whitespace`], for step-by-step reasoning traces, and for spotting refusals.
+pub mod response {
+    use crate::xai_api::{FinishReason, GetChatCompletionResponse, ToolCall, ToolCallType};
+
+    /// Why an output stopped, grouped by what the caller should do next.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum CompletionStatus {
+        /// The model stopped naturally (`ReasonStop`).
+        Complete,
+        /// Generation was cut off by `max_tokens` (`ReasonMaxLen`) or the context window
+        /// (`ReasonMaxContext`); the content is incomplete.
+        Truncated,
+        /// The model stopped to request client-side tool calls (`ReasonToolCalls`).
+        ToolCallsPending,
+        /// Generation hit the server time limit (`ReasonTimeLimit`).
+        TimedOut,
+        /// The finish reason is missing or unknown, or the output does not exist.
+        Unknown,
+    }
+
+    impl CompletionStatus {
+        /// Returns true if the output was cut off before the model finished.
+        pub fn is_truncated(&self) -> bool {
+            *self == CompletionStatus::Truncated
+        }
+    }
+
+    impl From<FinishReason> for CompletionStatus {
+        fn from(reason: FinishReason) -> Self {
+            match reason {
+                FinishReason::ReasonStop => CompletionStatus::Complete,
+                FinishReason::ReasonMaxLen | FinishReason::ReasonMaxContext => {
+                    CompletionStatus::Truncated
+                }
+                FinishReason::ReasonToolCalls => CompletionStatus::ToolCallsPending,
+                FinishReason::ReasonTimeLimit => CompletionStatus::TimedOut,
+                FinishReason::ReasonInvalid => CompletionStatus::Unknown,
+            }
+        }
+    }
+
+    /// Size of an output's text, for display and quota tracking.
+    ///
+    /// Characters are Unicode scalar values (`char`s), not bytes.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct ContentStats {
+        /// Characters in the content.
+        pub char_count: usize,
+        /// Whitespace-separated words in the content.
+        pub word_count: usize,
+        /// Characters in the reasoning content.
+        pub reasoning_char_count: usize,
+        /// Lines in the content; a trailing newline does not start a new line.
+        pub line_count: usize,
+    }
+
+    impl ContentStats {
+        fn of(content: &str, reasoning: &str) -> Self {
+            Self {
+                char_count: content.chars().count(),
+                word_count: content.split_whitespace().count(),
+                reasoning_char_count: reasoning.chars().count(),
+                line_count: content.lines().count(),
+            }
+        }
+    }
+
+    impl std::ops::Add for ContentStats {
+        type Output = Self;
+
+        fn add(self, other: Self) -> Self {
+            Self {
+                char_count: self.char_count + other.char_count,
+                word_count: self.word_count + other.word_count,
+                reasoning_char_count: self.reasoning_char_count + other.reasoning_char_count,
+                line_count: self.line_count + other.line_count,
+            }
+        }
+    }
+
+    impl GetChatCompletionResponse {
+        /// Returns text statistics of the output with the given index.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `ContentStats` - Statistics of the output's message, or all zeros if the output
+        ///   does not exist or has no message
+        pub fn stats(&self, index: usize) -> ContentStats {
+            self.outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .and_then(|output| output.message.as_ref())
+                .map(|message| ContentStats::of(&message.content, &message.reasoning_content))
+                .unwrap_or_default()
+        }
+
+        /// Returns text statistics summed over all outputs.
+        pub fn total_stats(&self) -> ContentStats {
+            self.outputs
+                .iter()
+                .filter_map(|output| output.message.as_ref())
+                .map(|message| ContentStats::of(&message.content, &message.reasoning_content))
+                .fold(ContentStats::default(), |total, stats| total + stats)
+        }
+
+        /// Returns the content of the output with the given index if it looks like a refusal.
+        ///
+        /// The API has no refusal field or finish reason, so this is a heuristic on the
+        /// content: the output must have finished normally (`ReasonStop`) without tool calls,
+        /// and its content must open with an English refusal such as "I'm sorry, but I can't
+        /// help with that" or "I cannot provide ...": an optional apology, then "I can't",
+        /// "I cannot", "I won't", "I will not" or "I'm (not) (un)able to", followed by a verb
+        /// like help, assist, provide or comply. Refusals in other languages, or worded
+        /// differently, are not detected, and a partial answer that opens with such a
+        /// sentence is reported as a refusal.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `Some(&str)` - The refusal message (the trimmed content)
+        /// * `None` - The output does not exist or does not look like a refusal
+        pub fn refusal(&self, index: usize) -> Option<&str> {
+            let output = self
+                .outputs
+                .iter()
+                .find(|output| output.index as usize == index)?;
+            let message = output.message.as_ref()?;
+            let refused = output.finish_reason_enum() == FinishReason::ReasonStop
+                && message.tool_calls.is_empty()
+                && looks_like_refusal(&message.content);
+            refused.then(|| message.content.trim())
+        }
+
+        /// Returns the reasoning trace of the output with the given index.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `Some(&str)` - The output's `reasoning_content`
+        /// * `None` - The output does not exist or has no reasoning (e.g. a non-reasoning
+        ///   model, or assembled with
+        ///   [`assemble_without_reasoning`](crate::chat::stream::assemble_without_reasoning))
+        pub fn reasoning(&self, index: usize) -> Option<&str> {
+            self.outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .and_then(|output| output.message.as_ref())
+                .map(|message| message.reasoning_content.as_str())
+                .filter(|reasoning| !reasoning.is_empty())
+        }
+
+        /// Splits the reasoning trace of the output with the given index into steps.
+        ///
+        /// Steps are separated by a blank line: the trace is split on double newlines
+        /// (`\n\n`, or `\r\n\r\n`), each step is trimmed and empty steps, e.g. from
+        /// longer runs of newlines, are dropped. Single newlines stay within a step. The
+        /// API does not mark steps itself, so this follows the paragraphs of the trace.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `Vec<&str>` - Reasoning steps in order, or empty if there is no reasoning
+        pub fn reasoning_steps(&self, index: usize) -> Vec<&str> {
+            self.reasoning(index)
+                .map(|reasoning| {
+                    reasoning
+                        .split("\n\n")
+                        .flat_map(|part| part.split("\r\n\r\n"))
+                        .map(str::trim)
+                        .filter(|step| !step.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        /// Returns the content of the output with the given index, cleaned up for display.
+        ///
+        /// Applies [`normalize_whitespace`]; the message's `content` field is left as is.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `String` - Normalized content, or empty if the output does not exist
+        pub fn trimmed(&self, index: usize) -> String {
+            self.outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .and_then(|output| output.message.as_ref())
+                .map(|message| normalize_whitespace(&message.content))
+                .unwrap_or_default()
+        }
+
+        /// Returns true if any output looks like a refusal; see [`refusal`](Self::refusal).
+        pub fn is_refusal(&self) -> bool {
+            self.outputs
+                .iter()
+                .any(|output| self.refusal(output.index as usize).is_some())
+        }
+
+        /// Returns the completion status of the output with the given index.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `CompletionStatus` - Derived from the output's finish reason, or
+        ///   `CompletionStatus::Unknown` if the output does not exist
+        pub fn completion_status(&self, index: usize) -> CompletionStatus {
+            self.outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .map(|output| CompletionStatus::from(output.finish_reason_enum()))
+                .unwrap_or(CompletionStatus::Unknown)
+        }
+
+        /// Returns true if any output was cut off by the token limit or context window.
+        pub fn is_truncated(&self) -> bool {
+            self.outputs
+                .iter()
+                .any(|output| CompletionStatus::from(output.finish_reason_enum()).is_truncated())
+        }
+
+        /// Returns the tool calls of the output with the given index.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `&[ToolCall]` - Tool calls of that output, or an empty slice if the output
+        ///   does not exist or has no message
+        pub fn tool_calls(&self, index: usize) -> &[ToolCall] {
+            self.outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .and_then(|output| output.message.as_ref())
+                .map(|message| message.tool_calls.as_slice())
+                .unwrap_or(&[])
+        }
+
+        /// Returns every tool call in the response, paired with its output index.
+        ///
+        /// # Returns
+        /// * `Vec<(usize, &ToolCall)>` - Tool calls in output order
+        pub fn all_tool_calls(&self) -> Vec<(usize, &ToolCall)> {
+            self.outputs
+                .iter()
+                .filter_map(|output| {
+                    output
+                        .message
+                        .as_ref()
+                        .map(|message| (output.index as usize, message))
+                })
+                .flat_map(|(index, message)| {
+                    message.tool_calls.iter().map(move |call| (index, call))
+                })
+                .collect()
+        }
+
+        /// Returns only the client-side tool calls, i.e. the ones the caller must execute
+        /// and answer with `RoleTool` messages.
+        ///
+        /// # Returns
+        /// * `Vec<(usize, &ToolCall)>` - Client-side tool calls paired with their output index
+        pub fn client_side_tool_calls(&self) -> Vec<(usize, &ToolCall)> {
+            self.all_tool_calls()
+                .into_iter()
+                .filter(|(_, call)| call.r#type == ToolCallType::ClientSideTool as i32)
+                .collect()
+        }
+    }
+
+    /// Normalizes whitespace in model output for display.
+    ///
+    /// The rules are deliberately conservative:
+    /// - Leading and trailing whitespace of the whole text is removed.
+    /// - Runs of two or more blank lines (three or more consecutive newlines, possibly with
+    ///   spaces in between) become a single empty line, so paragraphs stay separated.
+    /// - Inside fenced code blocks (between lines starting with `` ``` ``) nothing changes.
+    ///
+    /// Whitespace within lines, indentation, single blank lines and line endings are kept.
+    ///
+    /// ```
+    /// use xai_sdk::chat::response::normalize_whitespace;
+    ///
+    /// assert_eq!(normalize_whitespace("\n  Hello\n\n\n\nworld  \n"), "Hello\n\nworld");
+    /// ```
+    ///
+    /// # Arguments
+    /// * `text` - Text to normalize
+    ///
+    /// # Returns
+    /// * `String` - The normalized text
+    pub fn normalize_whitespace(text: &str) -> String {
+        normalize_indexed(text)
+            .into_iter()
+            .map(|(c, _)| c)
+            .collect()
+    }
+
+    /// Applies [`normalize_whitespace`] and remaps character offsets (e.g. of inline
+    /// citations) from `text` to the normalized text.
+    ///
+    /// The returned closure maps an offset in `text` to the number of kept characters before
+    /// it, so a span keeps covering the same kept characters.
+    pub(crate) fn normalize_with_offsets(text: &str) -> (String, impl Fn(i32) -> i32 + use<>) {
+        let kept = normalize_indexed(text);
+        let normalized = kept.iter().map(|(c, _)| *c).collect();
+        let positions: Vec<usize> = kept.into_iter().map(|(_, position)| position).collect();
+        let remap = move |offset: i32| {
+            positions.partition_point(|&position| (position as i64) < i64::from(offset)) as i32
+        };
+        (normalized, remap)
+    }
+
+    /// Kept characters of [`normalize_whitespace`] with their char position in `text`.
+    fn normalize_indexed(text: &str) -> Vec<(char, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut kept: Vec<(char, usize)> = Vec::with_capacity(chars.len());
+        let mut in_fence = false;
+        let mut blank_run = 0;
+        let mut start = 0;
+        while start <= chars.len() {
+            let end = chars[start..]
+                .iter()
+                .position(|&c| c == '\n')
+                .map_or(chars.len(), |offset| start + offset);
+            let line = &chars[start..end];
+            let is_fence = line
+                .iter()
+                .copied()
+                .skip_while(|c| c.is_whitespace())
+                .take(3)
+                .eq("```".chars());
+            let blank = !in_fence && line.iter().all(|c| c.is_whitespace());
+
+            blank_run = if blank { blank_run + 1 } else { 0 };
+            if blank_run < 2 {
+                let through = if end < chars.len() { end + 1 } else { end };
+                kept.extend((start..through).map(|position| (chars[position], position)));
+            }
+            if is_fence {
+                in_fence = !in_fence;
+            }
+            start = end + 1;
+        }
+
+        let leading = kept.iter().take_while(|(c, _)| c.is_whitespace()).count();
+        let trailing = kept[leading..]
+            .iter()
+            .rev()
+            .take_while(|(c, _)| c.is_whitespace())
+            .count();
+        kept.truncate(kept.len() - trailing);
+        kept.drain(..leading);
+        kept
+    }
+
+    /// Refusal heuristic behind [`GetChatCompletionResponse::refusal`].
+    fn looks_like_refusal(content: &str) -> bool {
+        const APOLOGIES: [&str; 4] = ["i'm sorry", "i am sorry", "sorry", "i apologize"];
+        const REFUSALS: [&str; 10] = [
+            "i can't",
+            "i cannot",
+            "i can not",
+            "i won't",
+            "i will not",
+            "i'm unable to",
+            "i am unable to",
+            "i'm not able to",
+            "i am not able to",
+            "i must decline to",
+        ];
+        const VERBS: [&str; 9] = [
+            "help", "assist", "provide", "comply", "fulfill", "do that", "share", "create", "write",
+        ];
+
+        let opening: String = content
+            .trim_start()
+            .chars()
+            .take(120)
+            .collect::<String>()
+            .to_lowercase()
+            .replace('\u{2019}', "'");
+        let mut rest = opening.as_str();
+        if let Some(after) = APOLOGIES
+            .iter()
+            .find_map(|apology| rest.strip_prefix(apology))
+        {
+            rest = after.trim_start_matches([',', '.', '!', ' ']);
+            rest = rest.strip_prefix("but ").unwrap_or(rest);
+        }
+        REFUSALS.iter().any(|refusal| {
+            rest.strip_prefix(refusal).is_some_and(|after| {
+                VERBS
+                    .iter()
+                    .any(|verb| after.trim_start().starts_with(verb))
+            })
+        })
+    }
+}
+
+/// Structured comparison of two chat completion responses.
+///
+/// Meant for regression and prompt-engineering harnesses that run the same request twice (e.g.
+/// across models or prompt revisions) and need to see what changed: a word-level content diff
+/// per output, finish-reason changes and token-usage deltas.
+pub mod diff {
+    use crate::xai_api::{FinishReason, GetChatCompletionResponse, SamplingUsage};
+    use std::fmt;
+
+    /// One step of a word-level diff.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub enum WordChange {
+        /// The word appears in both texts.
+        Same(String),
+        /// The word only appears in the second text.
+        Added(String),
+        /// The word only appears in the first text.
+        Removed(String),
+    }
+
+    /// Differences between the outputs with the same index in two responses.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ChoiceDiff {
+        /// Output index (matches `CompletionOutput::index`).
+        pub index: usize,
+        /// Word-level diff from the first output's content to the second's.
+        pub content: Vec<WordChange>,
+        /// Finish reasons of the first and second output; `ReasonInvalid` for a missing output.
+        pub finish_reasons: (FinishReason, FinishReason),
+    }
+
+    impl ChoiceDiff {
+        /// Returns true if any word was added or removed.
+        pub fn content_changed(&self) -> bool {
+            self.content
+                .iter()
+                .any(|change| !matches!(change, WordChange::Same(_)))
+        }
+
+        /// Returns true if the two outputs stopped for different reasons.
+        pub fn finish_reason_changed(&self) -> bool {
+            self.finish_reasons.0 != self.finish_reasons.1
+        }
+    }
+
+    /// Renders the content diff inline, marking removals as `[-word-]` and additions as
+    /// `{+word+}` (the `wdiff` convention).
+    impl fmt::Display for ChoiceDiff {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for (i, change) in self.content.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(" ")?;
+                }
+                match change {
+                    WordChange::Same(word) => f.write_str(word)?,
+                    WordChange::Added(word) => write!(f, "{{+{word}+}}")?,
+                    WordChange::Removed(word) => write!(f, "[-{word}-]")?,
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Token usage of the second response minus that of the first.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct UsageDelta {
+        /// Change in prompt tokens.
+        pub prompt_tokens: i64,
+        /// Change in completion tokens.
+        pub completion_tokens: i64,
+        /// Change in reasoning tokens.
+        pub reasoning_tokens: i64,
+        /// Change in prompt tokens served from the cache.
+        pub cached_prompt_text_tokens: i64,
+        /// Change in total tokens.
+        pub total_tokens: i64,
+    }
+
+    impl UsageDelta {
+        fn between(a: &SamplingUsage, b: &SamplingUsage) -> Self {
+            let delta = |a: i32, b: i32| i64::from(b) - i64::from(a);
+            UsageDelta {
+                prompt_tokens: delta(a.prompt_tokens, b.prompt_tokens),
+                completion_tokens: delta(a.completion_tokens, b.completion_tokens),
+                reasoning_tokens: delta(a.reasoning_tokens, b.reasoning_tokens),
+                cached_prompt_text_tokens: delta(
+                    a.cached_prompt_text_tokens,
+                    b.cached_prompt_text_tokens,
+                ),
+                total_tokens: delta(a.total_tokens, b.total_tokens),
+            }
+        }
+    }
+
+    /// Result of [`compare`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ResponseDiff {
+        /// One entry per output index present in either response, in index order.
+        pub choices: Vec<ChoiceDiff>,
+        /// Usage deltas; a missing `usage` counts as zero.
+        pub usage: UsageDelta,
+    }
+
+    impl ResponseDiff {
+        /// Returns true if no output differs in content or finish reason.
+        ///
+        /// Usage deltas are ignored, since identical text can still be billed differently
+        /// (e.g. with a warm prompt cache).
+        pub fn is_unchanged(&self) -> bool {
+            self.choices
+                .iter()
+                .all(|choice| !choice.content_changed() && !choice.finish_reason_changed())
+        }
+
+        /// Returns the outputs whose content or finish reason differs.
+        pub fn changed_choices(&self) -> impl Iterator<Item = &ChoiceDiff> {
+            self.choices
+                .iter()
+                .filter(|choice| choice.content_changed() || choice.finish_reason_changed())
+        }
+    }
+
+    /// Compares two responses output by output.
+    ///
+    /// Outputs are paired by `CompletionOutput::index`; an output present in only one response
+    /// is diffed against empty content. Outputs with a negative index are skipped.
+    ///
+    /// # Arguments
+    /// * `a` - Baseline response
+    /// * `b` - Response to compare against the baseline
+    ///
+    /// # Returns
+    /// * `ResponseDiff` - Per-output diffs and usage deltas (`b - a`)
+    pub fn compare(a: &GetChatCompletionResponse, b: &GetChatCompletionResponse) -> ResponseDiff {
+        fn output(response: &GetChatCompletionResponse, index: i32) -> (&str, FinishReason) {
+            response
+                .outputs
+                .iter()
+                .find(|output| output.index == index)
+                .map(|output| {
+                    let content = output
+                        .message
+                        .as_ref()
+                        .map(|message| message.content.as_str())
+                        .unwrap_or_default();
+                    (content, output.finish_reason_enum())
+                })
+                .unwrap_or(("", FinishReason::ReasonInvalid))
+        }
+
+        let mut indices: Vec<i32> = a
+            .outputs
+            .iter()
+            .chain(&b.outputs)
+            .map(|output| output.index)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        let choices = indices
+            .into_iter()
+            .filter_map(|index| Some((index, usize::try_from(index).ok()?)))
+            .map(|(index, position)| {
+                let (content_a, reason_a) = output(a, index);
+                let (content_b, reason_b) = output(b, index);
+                ChoiceDiff {
+                    index: position,
+                    content: diff_words(content_a, content_b),
+                    finish_reasons: (reason_a, reason_b),
+                }
+            })
+            .collect();
+
+        ResponseDiff {
+            choices,
+            usage: UsageDelta::between(
+                a.usage.as_ref().unwrap_or(&SamplingUsage::default()),
+                b.usage.as_ref().unwrap_or(&SamplingUsage::default()),
+            ),
+        }
+    }
+
+    /// Diffs two texts word by word.
+    ///
+    /// Words are whitespace-separated, so whitespace-only changes are not reported. The common
+    /// prefix and suffix are matched first; the words in between are diffed with Hirschberg's
+    /// longest-common-subsequence algorithm, whose time is quadratic in the number of those
+    /// words but whose memory is linear.
+    ///
+    /// # Arguments
+    /// * `a` - Original text
+    /// * `b` - New text
+    ///
+    /// # Returns
+    /// * `Vec<WordChange>` - Edit script turning `a` into `b`; removals come before additions
+    ///   at the same position
+    pub fn diff_words(a: &str, b: &str) -> Vec<WordChange> {
+        let a: Vec<&str> = a.split_whitespace().collect();
+        let b: Vec<&str> = b.split_whitespace().collect();
+
+        let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+        let suffix = a[prefix..]
+            .iter()
+            .rev()
+            .zip(b[prefix..].iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count();
+
+        let mut changes = Vec::with_capacity(a.len().max(b.len()));
+        changes.extend(
+            a[..prefix]
+                .iter()
+                .map(|word| WordChange::Same(word.to_string())),
+        );
+        lcs_diff(
+            &a[prefix..a.len() - suffix],
+            &b[prefix..b.len() - suffix],
+            &mut changes,
+        );
+        changes.extend(
+            a[a.len() - suffix..]
+                .iter()
+                .map(|word| WordChange::Same(word.to_string())),
+        );
+
+        // Recursion can leave an addition before a removal where two halves meet
+        for run in changes.split_mut(|change| matches!(change, WordChange::Same(_))) {
+            run.sort_by_key(|change| matches!(change, WordChange::Added(_)));
+        }
+        changes
+    }
+
+    /// Appends an LCS edit script from `a` to `b` using Hirschberg's divide and conquer.
+    fn lcs_diff(a: &[&str], b: &[&str], changes: &mut Vec<WordChange>) {
+        let added = |word: &&str| WordChange::Added(word.to_string());
+        match a {
+            [] => changes.extend(b.iter().map(added)),
+            [word] => match b.iter().position(|other| other == word) {
+                Some(at) => {
+                    changes.extend(b[..at].iter().map(added));
+                    changes.push(WordChange::Same(word.to_string()));
+                    changes.extend(b[at + 1..].iter().map(added));
+                }
+                None => {
+                    changes.push(WordChange::Removed(word.to_string()));
+                    changes.extend(b.iter().map(added));
+                }
+            },
+            _ if b.is_empty() => {
+                changes.extend(a.iter().map(|word| WordChange::Removed(word.to_string())));
+            }
+            _ => {
+                let mid = a.len() / 2;
+                let forward = lcs_lengths(a[..mid].iter().copied(), b.iter().copied());
+                let backward = lcs_lengths(a[mid..].iter().rev().copied(), b.iter().rev().copied());
+                // Split b where the two halves share the most words; iterating in reverse
+                // makes `max_by_key` (which keeps the last maximum) pick the smallest split
+                let split = (0..=b.len())
+                    .rev()
+                    .max_by_key(|&k| forward[k] + backward[b.len() - k])
+                    .unwrap_or(0);
+                lcs_diff(&a[..mid], &b[..split], changes);
+                lcs_diff(&a[mid..], &b[split..], changes);
+            }
+        }
+    }
+
+    /// Returns the LCS length of all of `a` and each prefix of `b`, indexed by prefix length,
+    /// using a single row of the LCS table.
+    fn lcs_lengths<'a>(
+        a: impl Iterator<Item = &'a str>,
+        b: impl Iterator<Item = &'a str> + Clone,
+    ) -> Vec<usize> {
+        let mut row = vec![0; b.clone().count() + 1];
+        for word in a {
+            // LCS of the previous `a` prefix and the current `b` prefix (the diagonal cell)
+            let mut diagonal = 0;
+            for (j, other) in b.clone().enumerate() {
+                let above = row[j + 1];
+                row[j + 1] = if word == other {
+                    diagonal + 1
+                } else {
+                    above.max(row[j])
+                };
+                diagonal = above;
+            }
+        }
+        row
+    }
+}
+
+/// Log probability helpers for chat completion responses.
+///
+/// Flattens the generated `LogProbs` messages into per-token records for confidence
+/// scoring and perplexity calculations. Log probabilities are only returned when the
+/// request sets `logprobs: true` (and `top_logprobs` for alternatives).
+///
+/// The sample service does not return log probabilities (`SampleChoice` has no such
+/// field), so there is no counterpart for `SampleTextResponse`.
+pub mod logprobs {
+    use crate::xai_api::{GetChatCompletionResponse, LogProbs};
+
+    /// Log probability of a single sampled token.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct TokenLogProb {
+        /// The sampled token.
+        pub token: String,
+        /// Natural log of the token's probability.
+        pub logprob: f32,
+        /// The most likely alternatives at this position as `(token, logprob)`, most likely
+        /// first. Empty unless `top_logprobs` was requested.
+        pub top_alternatives: Vec<(String, f32)>,
+    }
+
+    impl TokenLogProb {
+        /// Returns the token's probability in `[0, 1]`.
+        pub fn probability(&self) -> f32 {
+            self.logprob.exp()
+        }
+    }
+
+    impl LogProbs {
+        /// Returns one record per sampled token, in sampling order.
+        pub fn tokens(&self) -> Vec<TokenLogProb> {
+            self.content
+                .iter()
+                .map(|entry| TokenLogProb {
+                    token: entry.token.clone(),
+                    logprob: entry.logprob,
+                    top_alternatives: entry
+                        .top_logprobs
+                        .iter()
+                        .map(|alt| (alt.token.clone(), alt.logprob))
+                        .collect(),
+                })
+                .collect()
+        }
+
+        /// Returns the perplexity of the sampled tokens, `exp(-mean(logprob))`.
+        ///
+        /// # Returns
+        /// * `Some(f32)` - Perplexity over all tokens
+        /// * `None` - If there are no tokens
+        pub fn perplexity(&self) -> Option<f32> {
+            if self.content.is_empty() {
+                return None;
+            }
+            let total: f32 = self.content.iter().map(|entry| entry.logprob).sum();
+            Some((-total / self.content.len() as f32).exp())
+        }
+    }
+
+    impl GetChatCompletionResponse {
+        /// Returns the per-token log probabilities of the output with the given index.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `Vec<TokenLogProb>` - Tokens in sampling order, or empty if the output does not
+        ///   exist or carries no log probabilities
+        pub fn token_logprobs(&self, index: usize) -> Vec<TokenLogProb> {
+            self.outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .and_then(|output| output.logprobs.as_ref())
+                .map(LogProbs::tokens)
+                .unwrap_or_default()
+        }
+    }
+}
+
+/// Accessors for inline citations.
+///
+/// Adds typed views over the `InlineCitation` oneof (web page, X post or collections chunk)
+/// and helpers for rendering citation markers into response text.
+pub mod citations {
+    use crate::xai_api::inline_citation::Citation;
+    use crate::xai_api::{
+        CollectionsCitation, GetChatCompletionResponse, InlineCitation, WebCitation, XCitation,
+    };
+
+    impl Citation {
+        /// Returns the web page citation, if this is one.
+        pub fn as_web(&self) -> Option<&WebCitation> {
+            match self {
+                Citation::WebCitation(web) => Some(web),
+                _ => None,
+            }
+        }
+
+        /// Returns the X post or profile citation, if this is one.
+        pub fn as_x_post(&self) -> Option<&XCitation> {
+            match self {
+                Citation::XCitation(x) => Some(x),
+                _ => None,
+            }
+        }
+
+        /// Returns the collections search citation, if this is one.
+        pub fn as_collections(&self) -> Option<&CollectionsCitation> {
+            match self {
+                Citation::CollectionsCitation(collections) => Some(collections),
+                _ => None,
+            }
+        }
+
+        /// Returns the URL of a web or X citation.
+        pub fn url(&self) -> Option<&str> {
+            match self {
+                Citation::WebCitation(web) => Some(&web.url),
+                Citation::XCitation(x) => Some(&x.url),
+                Citation::CollectionsCitation(_) => None,
+            }
+        }
+
+        /// Returns the quoted text of a collections citation.
+        pub fn snippet(&self) -> Option<&str> {
+            self.as_collections()
+                .map(|collections| collections.chunk_content.as_str())
+        }
+    }
+
+    impl InlineCitation {
+        /// Returns the web page citation, if this is one.
+        pub fn as_web(&self) -> Option<&WebCitation> {
+            self.citation.as_ref().and_then(Citation::as_web)
+        }
+
+        /// Returns the X post or profile citation, if this is one.
+        pub fn as_x_post(&self) -> Option<&XCitation> {
+            self.citation.as_ref().and_then(Citation::as_x_post)
+        }
+
+        /// Returns the collections search citation, if this is one.
+        pub fn as_collections(&self) -> Option<&CollectionsCitation> {
+            self.citation.as_ref().and_then(Citation::as_collections)
+        }
+
+        /// Returns the URL of the cited web page or X post.
+        ///
+        /// `None` for collections citations, which point at a file chunk instead, and for
+        /// citations without data. The API does not return page titles.
+        pub fn url(&self) -> Option<&str> {
+            self.citation.as_ref().and_then(Citation::url)
+        }
+
+        /// Returns the quoted text of the cited collections chunk.
+        ///
+        /// Web and X citations carry no snippet and return `None`.
+        pub fn snippet(&self) -> Option<&str> {
+            self.citation.as_ref().and_then(Citation::snippet)
+        }
+    }
+
+    impl GetChatCompletionResponse {
+        /// Returns the response-level citations with duplicates removed.
+        ///
+        /// Keeps the first occurrence of each URL, in order. Responses produced by
+        /// [`stream::assemble`](super::stream::assemble) are already deduplicated; this is
+        /// for unary responses and responses built by hand.
+        ///
+        /// # Returns
+        /// * `Vec<&str>` - Unique citation URLs in first-seen order
+        pub fn citations_deduped(&self) -> Vec<&str> {
+            let mut unique: Vec<&str> = Vec::new();
+            for citation in &self.citations {
+                if !unique.contains(&citation.as_str()) {
+                    unique.push(citation);
+                }
+            }
+            unique
+        }
+
+        /// Returns the inline citations of the output with the given index.
+        ///
+        /// Responses produced by [`stream::assemble`](super::stream::assemble) keep the
+        /// citations ordered by position (`start_index`, then `end_index`), so adjacent entries
+        /// refer to adjacent parts of the text.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `&[InlineCitation]` - Citations of the output, or empty if the output does not exist
+        pub fn inline_citations(&self, index: usize) -> &[InlineCitation] {
+            self.outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .and_then(|output| output.message.as_ref())
+                .map(|message| message.citations.as_slice())
+                .unwrap_or_default()
+        }
+
+        /// Returns the content of the output with the given index with citation markers
+        /// interleaved at their recorded offsets.
+        ///
+        /// Offsets are character (not byte) positions. Each citation's span
+        /// `[start_index, end_index)`, which holds the citation link when the server embeds one,
+        /// is replaced by the marker `[id]`; an empty span inserts the marker without removing
+        /// any text.
+        ///
+        /// Edge cases are resolved as follows:
+        /// - Citations are applied in position order, regardless of their order in the response.
+        /// - Offsets are clamped to `[0, content length]`, so citations past the end of the
+        ///   content are appended after it.
+        /// - A citation whose span overlaps a previously applied one is placed immediately
+        ///   after it, and only the part of its span beyond the previous one is replaced.
+        ///
+        /// # Arguments
+        /// * `index` - Output index (matches `CompletionOutput::index`)
+        ///
+        /// # Returns
+        /// * `String` - Rendered content, or empty if the output does not exist
+        pub fn render_with_citations(&self, index: usize) -> String {
+            let Some(message) = self
+                .outputs
+                .iter()
+                .find(|output| output.index as usize == index)
+                .and_then(|output| output.message.as_ref())
+            else {
+                return String::new();
+            };
+
+            let chars: Vec<char> = message.content.chars().collect();
+            let clamp = |offset: i32| (offset.max(0) as usize).min(chars.len());
+
+            let mut citations: Vec<&InlineCitation> = message.citations.iter().collect();
+            citations.sort_by_key(|c| (c.start_index, c.end_index));
+
+            let mut rendered = String::with_capacity(message.content.len());
+            let mut cursor = 0;
+            for citation in citations {
+                let start = clamp(citation.start_index).max(cursor);
+                let end = clamp(citation.end_index).max(start);
+                rendered.extend(&chars[cursor..start]);
+                rendered.push('[');
+                rendered.push_str(&citation.id);
+                rendered.push(']');
+                cursor = end;
+            }
+            rendered.extend(&chars[cursor..]);
+            rendered
+        }
+    }
+}
+
+/// Streaming utilities for chat completions.
+///
+/// Provides high-performance utilities for processing real-time chat completion streams,
+/// including flexible callback-based consumers and chunk assembly into complete responses.
+pub mod stream {
+    use crate::chat::backend::{ChatBackend, ChunkStream};
+    use crate::chat::response::{normalize_whitespace, normalize_with_offsets};
+    use crate::common::types::{BoxError, BoxFuture};
+    use crate::export::{Request, Status};
+    use crate::xai_api::{
+        CompletionMessage, CompletionOutput, CompletionOutputChunk, Delta, FinishReason,
+        GetChatCompletionChunk, GetChatCompletionResponse, GetCompletionsRequest, InlineCitation,
+        LogProbs, RequestSettings, SamplingUsage, ToolCall, ToolCallType, tool_call,
+    };
+    use futures::future::{Either, select};
+    use futures::lock::Mutex;
+    use futures::sink::Sink;
+    use futures::task::AtomicWaker;
+    use futures::{SinkExt, Stream, StreamExt};
+    use prost::Message;
+    use serde_json::{Map, Value, json};
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use std::fs::File;
+    use std::future::Future;
+    use std::io::{self, Write};
+    use std::path::Path;
+    use std::pin::{Pin, pin};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::Poll;
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncWrite, AsyncWriteExt};
+    use tokio::sync::mpsc;
+    use tokio::task::JoinHandle;
+    use tonic::Code;
+
+    #[derive(Debug, Clone)]
+    struct OutputStats {
+        index: i32,
+        total_reasoning_tokens: usize,
+        total_content_tokens: usize,
+        finish_reason: FinishReason,
+    }
+
+    impl OutputStats {
+        fn init(index: i32, finish_reason: FinishReason) -> Self {
+            Self {
+                index,
+                total_reasoning_tokens: 0,
+                total_content_tokens: 0,
+                finish_reason,
+            }
+        }
+
+        fn inc(&mut self, reason_token: &str, content_token: &str) {
+            if !reason_token.is_empty() {
+                self.total_reasoning_tokens += 1;
+            }
+            if !content_token.is_empty() {
+                self.total_content_tokens += 1;
+            }
+        }
+
+        fn merge(&mut self, other: &Self) {
+            if self.index == other.index {
+                self.total_reasoning_tokens += other.total_reasoning_tokens;
+                self.total_content_tokens += other.total_content_tokens;
+                self.finish_reason = other.finish_reason
+            }
+        }
+    }
+
+    /// Per-stream bookkeeping shared by [`process`] and [`process_with_idle_timeout`].
+    #[derive(Default)]
+    struct ProcessState {
+        chunks: Vec<GetChatCompletionChunk>,
+        output_stats: HashMap<i32, OutputStats>,
+        reasoning_start_fired: HashMap<i32, bool>,
+        reasoning_complete_fired: HashMap<i32, bool>,
+        content_start_fired: HashMap<i32, bool>,
+        content_complete_fired: HashMap<i32, bool>,
+        max_output_index_seen: i32,
+    }
+
+    impl ProcessState {
+        fn new() -> Self {
+            Self {
+                max_output_index_seen: -1,
+                ..Default::default()
+            }
+        }
+
+        /// Dispatches all per-chunk callbacks and records the chunk.
+        async fn handle_chunk(
+            &mut self,
+            consumer: &mut Consumer<'_>,
+            chunk: GetChatCompletionChunk,
+        ) {
+            if let Some(ref mut on_chunk) = consumer.on_chunk {
+                on_chunk(&chunk).await;
+            }
+
+            if let Some(ref mut on_keepalive) = consumer.on_keepalive
+                && is_keepalive(&chunk)
+            {
+                on_keepalive(&chunk).await;
+            }
+
+            for output in &chunk.outputs {
+                let cur_output_index = output.index;
+
+                // Update max early so total_outputs is as accurate as possible
+                self.max_output_index_seen = self.max_output_index_seen.max(cur_output_index);
+
+                // Always start with the latest finish_reason from this chunk
+                let mut cur_output_stats =
+                    OutputStats::init(cur_output_index, output.finish_reason());
+
+                let delta = output.delta.as_ref();
+
+                // Increment token counts only if there is a delta
+                if let Some(delta) = delta {
+                    cur_output_stats.inc(&delta.reasoning_content, &delta.content);
+                }
+
+                // Merge current chunk data into accumulated stats
+                // Chained to avoid the move error
+                let merged = self
+                    .output_stats
+                    .entry(cur_output_index)
+                    .and_modify(|e| e.merge(&cur_output_stats))
+                    .or_insert(cur_output_stats);
+
+                let (reasoning_status, content_status) = get_output_status(merged);
+
+                let total_outputs = (self.max_output_index_seen + 1) as usize;
+
+                let mut output_ctx = OutputContext::new(
+                    total_outputs,
+                    cur_output_index as usize,
+                    reasoning_status.clone(),
+                    content_status.clone(),
+                );
+                output_ctx.finish_reason =
+                    Some(merged.finish_reason).filter(|r| *r != FinishReason::ReasonInvalid);
+
+                if let Some(ref mut on_delta) = consumer.on_delta
+                    && let Some(delta) = delta
+                {
+                    on_delta(&output_ctx, delta).await;
+                }
+
+                // ####################
+                // Reasoning
+                // ####################
+                if let Some(delta) = delta
+                    && !delta.reasoning_content.is_empty()
+                {
+                    // Reasoning start (once)
+                    if !self
+                        .reasoning_start_fired
+                        .get(&cur_output_index)
+                        .copied()
+                        .unwrap_or(false)
+                    {
+                        if let Some(ref mut on_reasoning_start) = consumer.on_reasoning_start {
+                            on_reasoning_start(&output_ctx).await;
+                        }
+                        self.reasoning_start_fired.insert(cur_output_index, true);
+                    }
+                    // Reasoning tokens (multiple)
+                    if let Some(ref mut on_reasoning_token) = consumer.on_reasoning_token {
+                        on_reasoning_token(&output_ctx, &delta.reasoning_content).await;
+                    }
+                }
+
+                // Phase completion callback – fire immediately when we detect Complete
+                // (runs even on chunks without delta, in case finish_reason updated)
+                // Reasoning complete (once)
+                if reasoning_status == PhaseStatus::Complete
+                    && !self
+                        .reasoning_complete_fired
+                        .get(&cur_output_index)
+                        .copied()
+                        .unwrap_or(false)
+                    && merged.total_reasoning_tokens > 0
+                {
+                    if let Some(ref mut on_reasoning_complete) = consumer.on_reasoning_complete {
+                        on_reasoning_complete(&output_ctx).await;
+                    }
+                    self.reasoning_complete_fired.insert(cur_output_index, true);
+                }
+
+                // ####################
+                // Content
+                // ####################
+                if let Some(delta) = delta
+                    && !delta.content.is_empty()
+                {
+                    // Content start (once)
+                    if !self
+                        .content_start_fired
+                        .get(&cur_output_index)
+                        .copied()
+                        .unwrap_or(false)
+                    {
+                        if let Some(ref mut on_content_start) = consumer.on_content_start {
+                            on_content_start(&output_ctx).await;
+                        }
+                        self.content_start_fired.insert(cur_output_index, true);
+                    }
+                    // Content tokens (multiple)
+                    if let Some(ref mut on_content_token) = consumer.on_content_token {
+                        on_content_token(&output_ctx, &delta.content).await;
+                    }
+                }
+
+                // Phase completion callback – fire immediately when we detect Complete
+                // (runs even on chunks without delta, in case finish_reason updated)
+                // Content complete (once)
+                if content_status == PhaseStatus::Complete
+                    && !self
+                        .content_complete_fired
+                        .get(&cur_output_index)
+                        .copied()
+                        .unwrap_or(false)
+                    && merged.total_content_tokens > 0
+                {
+                    if let Some(ref mut on_content_complete) = consumer.on_content_complete {
+                        on_content_complete(&output_ctx).await;
+                    }
+                    self.content_complete_fired.insert(cur_output_index, true);
+                }
+
+                // ####################
+                // Citations, tool calls
+                // ####################
+                if let Some(delta) = delta {
+                    // Inline citations
+                    if let Some(ref mut on_inline_citations) = consumer.on_inline_citations
+                        && !delta.citations.is_empty()
+                    {
+                        on_inline_citations(&output_ctx, &delta.citations).await;
+                    }
+
+                    // Tool calls
+                    if !delta.tool_calls.is_empty() {
+                        let capacity = delta.tool_calls.len();
+                        let mut client_tool_calls = Vec::with_capacity(capacity);
+                        let mut server_tool_calls = Vec::with_capacity(capacity);
+
+                        for tool_call in &delta.tool_calls {
+                            if tool_call.r#type == ToolCallType::ClientSideTool as i32 {
+                                client_tool_calls.push(tool_call.clone());
+                            } else {
+                                server_tool_calls.push(tool_call.clone());
+                            }
+                        }
+
+                        if let Some(ref mut on_client_tool_calls) = consumer.on_client_tool_calls
+                            && !client_tool_calls.is_empty()
+                        {
+                            on_client_tool_calls(&output_ctx, &client_tool_calls).await;
+                        }
+
+                        if let Some(ref mut on_server_tool_calls) = consumer.on_server_tool_calls
+                            && !server_tool_calls.is_empty()
+                        {
+                            on_server_tool_calls(&output_ctx, &server_tool_calls).await;
+                        }
+                    }
+                }
+            }
+
+            self.chunks.push(chunk);
+        }
+
+        /// Fires the final metadata callbacks and returns the chunks.
+        ///
+        /// `on_usage` receives the last usage reported by any chunk, so a trailing chunk
+        /// without usage does not hide it; `on_citations` only looks at the last chunk.
+        async fn finish(self, consumer: &mut Consumer<'_>) -> Vec<GetChatCompletionChunk> {
+            if let Some(ref mut on_usage) = consumer.on_usage
+                && let Some(usage) = last_usage(&self.chunks)
+            {
+                on_usage(&usage).await;
+            }
+            if let Some(last_chunk) = self.chunks.last()
+                && let Some(ref mut on_citations) = consumer.on_citations
+                && !last_chunk.citations.is_empty()
+            {
+                on_citations(&last_chunk.citations).await;
+            }
+            self.chunks
+        }
+    }
+
+    /// Adapts a chunk stream into a plain `futures::Stream` of decoded chunks.
+    ///
+    /// This is the composable counterpart to [`process`]: the result works with any
+    /// `StreamExt` combinator (`take`, `filter`, `timeout`, ...). The adapter ends the stream
+    /// after the first error, so an `Err` is always the last item and nothing is read from the
+    /// underlying stream once it has failed. [`process`] and [`process_with_idle_timeout`]
+    /// read their input through this adapter.
+    ///
+    /// ```no_run
+    /// # async fn example(streaming: tonic::Streaming<xai_sdk::api::GetChatCompletionChunk>) {
+    /// use futures::StreamExt;
+    /// use xai_sdk::chat::stream::chunks;
+    ///
+    /// let mut first_ten = chunks(streaming).take(10);
+    /// while let Some(chunk) = first_ten.next().await {
+    ///     println!("{:?}", chunk.map(|c| c.outputs.len()));
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`, such as the
+    ///   `tonic::Streaming` returned by `get_completion_chunk`
+    ///
+    /// # Returns
+    /// * `impl Stream<Item = Result<GetChatCompletionChunk, Status>>` - The chunks, ending
+    ///   after the first error
+    pub fn chunks<S>(
+        stream: S,
+    ) -> impl Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + 'static,
+    {
+        stream.boxed().scan(false, |failed, item| {
+            if *failed {
+                return futures::future::ready(None);
+            }
+            *failed = item.is_err();
+            futures::future::ready(Some(item))
+        })
+    }
+
+    /// When [`resilient`] restarts a failed request, and how long it waits in between.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RestartPolicy {
+        /// Maximum number of restarts; `0` disables them.
+        pub max_restarts: u32,
+        /// Wait before the first restart, doubled for each further one.
+        pub backoff: Duration,
+        /// Longest `retry-after` wait honoured; longer server requests are clamped to it.
+        pub max_retry_after: Duration,
+    }
+
+    impl Default for RestartPolicy {
+        /// Three restarts, waiting 500ms, 1s and 2s, and at most 60s for `retry-after`.
+        fn default() -> Self {
+            Self {
+                max_restarts: 3,
+                backoff: Duration::from_millis(500),
+                max_retry_after: Duration::from_secs(60),
+            }
+        }
+    }
+
+    impl RestartPolicy {
+        /// Returns true if a stream failing with `status` is worth restarting.
+        ///
+        /// Only `Unavailable` (connection dropped or reset) and `Aborted` qualify; other
+        /// errors, including `DeadlineExceeded`, would fail the same way again. In particular
+        /// authentication failures are permanent; use
+        /// [`AuthError::from_status`](crate::auth::AuthError::from_status) to report them.
+        pub fn is_retryable(status: &Status) -> bool {
+            matches!(status.code(), Code::Unavailable | Code::Aborted)
+        }
+
+        /// Returns true if an attempt failing with `status` before its first chunk is worth
+        /// restarting.
+        ///
+        /// Adds rate limiting (`ResourceExhausted`) to [`is_retryable`](Self::is_retryable):
+        /// a request rejected before generating anything can safely be sent again, while one
+        /// cut off mid-stream by a rate limit is not restarted, so no output is duplicated.
+        pub fn is_retryable_before_first_chunk(status: &Status) -> bool {
+            Self::is_retryable(status) || status.code() == Code::ResourceExhausted
+        }
+
+        /// Returns the wait requested by the server's `retry-after` header or trailer.
+        ///
+        /// Only the delay-seconds form (e.g. `2` or `0.5`) is understood; an HTTP date or a
+        /// malformed value yields `None`.
+        ///
+        /// # Arguments
+        /// * `status` - Status of the failed call
+        ///
+        /// # Returns
+        /// * `Some(Duration)` - The requested wait
+        /// * `None` - No usable `retry-after` value
+        pub fn retry_after(status: &Status) -> Option<Duration> {
+            let value = status.metadata().get("retry-after")?.to_str().ok()?;
+            let seconds: f64 = value.trim().parse().ok()?;
+            Duration::try_from_secs_f64(seconds).ok()
+        }
+
+        fn delay(&self, restart: u32, status: &Status) -> Duration {
+            let backoff = self
+                .backoff
+                .saturating_mul(2u32.saturating_pow(restart.saturating_sub(1)));
+            Self::retry_after(status)
+                .map_or(backoff, |wait| wait.min(self.max_retry_after).max(backoff))
+        }
+    }
+
+    /// Item of the stream returned by [`resilient`].
+    #[derive(Debug, Clone)]
+    // Chunks are the common case; boxing them would add an allocation per chunk
+    #[allow(clippy::large_enum_variant)]
+    pub enum ResilientEvent {
+        /// A chunk of the current attempt.
+        Chunk(GetChatCompletionChunk),
+        /// The stream failed after delivering chunks and the request was sent again. The
+        /// chunks that follow belong to a new completion, generated from scratch.
+        Restarted {
+            /// Number of the restart, starting at 1.
+            attempt: u32,
+            /// The error that ended the previous attempt.
+            status: Status,
+        },
+    }
+
+    /// Streams a completion, restarting the request when the connection drops.
+    ///
+    /// The API cannot resume a generation midway: `previous_response_id` continues a stored
+    /// conversation after a completed response, not a stream cut short. A failed attempt is
+    /// therefore restarted from scratch, up to `policy.max_restarts` times, when the error is
+    /// [retryable](RestartPolicy::is_retryable):
+    /// - If the attempt failed before its first chunk, the restart is invisible.
+    /// - Otherwise a [`ResilientEvent::Restarted`] is yielded before the new attempt's chunks.
+    ///   The new completion is sampled again and generally differs from the partial one, so
+    ///   callers should discard what they received so far. Setting a `seed` makes the new
+    ///   attempt likely, though not guaranteed, to repeat the same text.
+    ///
+    /// Rate limiting (`ResourceExhausted`) is retried only before the first token: an attempt
+    /// rejected before its first chunk is restarted after the `retry-after` wait the server
+    /// asked for (see [`RestartPolicy::retry_after`]), clamped to `policy.max_retry_after` so a
+    /// misbehaving server cannot stall the stream indefinitely, or the backoff if that is
+    /// longer or missing. Once chunks have arrived, a rate-limit error ends the stream, so output is
+    /// never generated twice because of one.
+    ///
+    /// Every restart sends the request again, so it is a new generation that is billed
+    /// separately, even when the failed attempt had already produced output. Each attempt's
+    /// `Request` comes from `request`, so metadata such as an idempotency key or request id
+    /// can be attached to every attempt, e.g. with `move || builder.build_request()`.
+    ///
+    /// Once restarts are exhausted, or on any other error, the error is yielded as the last
+    /// item. An error from `request` itself is yielded without a restart.
+    ///
+    /// # Arguments
+    /// * `backend` - Chat client or any other [`ChatBackend`]
+    /// * `request` - Builds the request for each attempt
+    /// * `policy` - How often to restart and how long to wait in between
+    ///
+    /// # Returns
+    /// * `impl Stream<Item = Result<ResilientEvent, Status>>` - Chunks and restart notices,
+    ///   ending after the first error that is not restarted
+    pub fn resilient<B, F>(
+        backend: B,
+        request: F,
+        policy: RestartPolicy,
+    ) -> impl Stream<Item = Result<ResilientEvent, Status>> + Send + 'static
+    where
+        B: ChatBackend + Send + 'static,
+        F: FnMut() -> Result<Request<GetCompletionsRequest>, Status> + Send + 'static,
+    {
+        struct State<B, F> {
+            backend: B,
+            request: F,
+            policy: RestartPolicy,
+            current: Option<ChunkStream>,
+            delivered: bool,
+            restarts: u32,
+            done: bool,
+        }
+
+        let state = State {
+            backend,
+            request,
+            policy,
+            current: None,
+            delivered: false,
+            restarts: 0,
+            done: false,
+        };
+
+        futures::stream::unfold(state, |mut state| async move {
+            loop {
+                if state.done {
+                    return None;
+                }
+                let status = match state.current.take() {
+                    Some(mut stream) => match stream.next().await {
+                        Some(Ok(chunk)) => {
+                            state.current = Some(stream);
+                            state.delivered = true;
+                            return Some((Ok(ResilientEvent::Chunk(chunk)), state));
+                        }
+                        Some(Err(status)) => status,
+                        None => return None,
+                    },
+                    None => {
+                        let request = match (state.request)() {
+                            Ok(request) => request,
+                            Err(status) => {
+                                state.done = true;
+                                return Some((Err(status), state));
+                            }
+                        };
+                        match state.backend.get_completion_chunk(request).await {
+                            Ok(stream) => {
+                                state.current = Some(stream);
+                                continue;
+                            }
+                            Err(status) => status,
+                        }
+                    }
+                };
+
+                let retryable = if state.delivered {
+                    RestartPolicy::is_retryable(&status)
+                } else {
+                    RestartPolicy::is_retryable_before_first_chunk(&status)
+                };
+                if !retryable || state.restarts >= state.policy.max_restarts {
+                    state.done = true;
+                    return Some((Err(status), state));
+                }
+                state.restarts += 1;
+                tokio::time::sleep(state.policy.delay(state.restarts, &status)).await;
+                if std::mem::take(&mut state.delivered) {
+                    let event = ResilientEvent::Restarted {
+                        attempt: state.restarts,
+                        status,
+                    };
+                    return Some((Ok(event), state));
+                }
+            }
+        })
+    }
+
+    /// Groups the content of a chunk stream into whole sentences, e.g. for text-to-speech.
+    ///
+    /// Content tokens of output 0 are buffered and a sentence is yielded as soon as its end
+    /// is seen: a newline, or `.`, `!` or `?` (optionally followed by closing quotes or
+    /// brackets) followed by whitespace. A terminator at the very end of the buffered text is
+    /// only treated as a boundary once the next token shows what follows it, so `3.` + `14`
+    /// stays one number. Sentences are trimmed and empty ones are skipped; whatever remains
+    /// when the stream ends is yielded as a final sentence. Reasoning and other outputs are
+    /// ignored.
+    ///
+    /// Abbreviations are handled minimally: a period after `Mr`, `Mrs`, `Ms`, `Dr`, `Prof`,
+    /// `Sr`, `Jr`, `St`, `vs`, `e.g` or `i.e` does not end a sentence. Other abbreviations
+    /// and initials (`U.S. law`, `J. Smith`) split the sentence early.
+    ///
+    /// A stream error is passed through as the last item; text buffered before it is
+    /// discarded.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    ///
+    /// # Returns
+    /// * `impl Stream<Item = Result<String, Status>>` - Sentences, ending after the first error
+    pub fn sentences<S>(
+        stream: S,
+    ) -> impl Stream<Item = Result<String, Status>> + Send + Unpin + 'static
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + 'static,
+    {
+        struct SentenceState<T> {
+            chunks: T,
+            buffer: String,
+            pending: VecDeque<Result<String, Status>>,
+            finished: bool,
+        }
+
+        let state = SentenceState {
+            chunks: chunks(stream),
+            buffer: String::new(),
+            pending: VecDeque::new(),
+            finished: false,
+        };
+        futures::stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(sentence) = state.pending.pop_front() {
+                    return Some((sentence, state));
+                }
+                if state.finished {
+                    return None;
+                }
+                match state.chunks.next().await {
+                    Some(Ok(chunk)) => {
+                        for output in chunk.outputs.iter().filter(|output| output.index == 0) {
+                            if let Some(delta) = &output.delta {
+                                state.buffer.push_str(&delta.content);
+                            }
+                        }
+                        state
+                            .pending
+                            .extend(take_sentences(&mut state.buffer).into_iter().map(Ok));
+                    }
+                    Some(Err(status)) => {
+                        state.finished = true;
+                        state.pending.push_back(Err(status));
+                    }
+                    None => {
+                        state.finished = true;
+                        let rest = state.buffer.trim();
+                        if !rest.is_empty() {
+                            state.pending.push_back(Ok(rest.to_string()));
+                        }
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// Removes the complete sentences from the front of `buffer` and returns them.
+    fn take_sentences(buffer: &mut String) -> Vec<String> {
+        const ABBREVIATIONS: [&str; 11] = [
+            "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "e.g", "i.e",
+        ];
+
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        let mut push = |sentence: &str| {
+            let sentence = sentence.trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence.to_string());
+            }
+        };
+
+        for (i, c) in buffer.char_indices() {
+            if i < start {
+                continue;
+            }
+            if c == '\n' {
+                push(&buffer[start..i]);
+                start = i + 1;
+                continue;
+            }
+            if !matches!(c, '.' | '!' | '?') {
+                continue;
+            }
+
+            let rest = &buffer[i + 1..];
+            let closing = rest
+                .find(|c: char| !matches!(c, '"' | '\'' | '\u{201d}' | '\u{2019}' | ')' | ']'))
+                .unwrap_or(rest.len());
+            let end = i + 1 + closing;
+            if !buffer[end..].starts_with(char::is_whitespace) {
+                continue;
+            }
+            if c == '.' {
+                let word = buffer[start..i]
+                    .rsplit(char::is_whitespace)
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+                if ABBREVIATIONS.contains(&word.as_str()) {
+                    continue;
+                }
+            }
+            push(&buffer[start..end]);
+            start = end;
+        }
+
+        buffer.drain(..start);
+        sentences
+    }
+
+    /// Terminal SSE frame emitted by [`to_sse`] after a stream completes normally.
+    pub const SSE_DONE: &str = "data: [DONE]\n\n";
+
+    /// Formats a chunk stream as Server-Sent Events for relaying to a browser.
+    ///
+    /// Every item is one complete SSE frame, `data: {json}\n\n`, ready to be written to a
+    /// `text/event-stream` response. Each event is a JSON object with a `type` field:
+    ///
+    /// - `{"type":"reasoning","index":0,"text":"..."}` - reasoning token of an output
+    /// - `{"type":"content","index":0,"text":"..."}` - content token of an output
+    /// - `{"type":"tool_calls","index":0,"tool_calls":[{"id":"...","type":"client_side_tool","name":"...","arguments":"..."}]}`
+    /// - `{"type":"finish","index":0,"reason":"stop"}` - an output finished
+    /// - `{"type":"usage","prompt_tokens":1,"completion_tokens":2,"reasoning_tokens":0,"total_tokens":3}`
+    ///   - sent once, after the last chunk, if the server reported usage
+    ///
+    /// Events of one chunk keep the order above. After the stream ends normally the final
+    /// frame is [`SSE_DONE`]. A stream error is passed through as the last item instead, with
+    /// no `[DONE]` frame, so the relay can close the response or send its own error event.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    ///
+    /// # Returns
+    /// * `impl Stream<Item = Result<String, Status>>` - SSE frames, ending after the first error
+    pub fn to_sse<S>(
+        stream: S,
+    ) -> impl Stream<Item = Result<String, Status>> + Send + Unpin + 'static
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + 'static,
+    {
+        struct SseState<T> {
+            chunks: T,
+            pending: VecDeque<Result<String, Status>>,
+            usage: Option<SamplingUsage>,
+            finished: bool,
+        }
+
+        let state = SseState {
+            chunks: chunks(stream),
+            pending: VecDeque::new(),
+            usage: None,
+            finished: false,
+        };
+        futures::stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(frame) = state.pending.pop_front() {
+                    return Some((frame, state));
+                }
+                if state.finished {
+                    return None;
+                }
+                match state.chunks.next().await {
+                    Some(Ok(chunk)) => {
+                        state.pending.extend(sse_events(&chunk).into_iter().map(Ok));
+                        if chunk.usage.is_some() {
+                            state.usage = chunk.usage;
+                        }
+                    }
+                    Some(Err(status)) => {
+                        state.finished = true;
+                        state.pending.push_back(Err(status));
+                    }
+                    None => {
+                        state.finished = true;
+                        if let Some(usage) = state.usage.take() {
+                            state.pending.push_back(Ok(sse_usage(&usage)));
+                        }
+                        state.pending.push_back(Ok(SSE_DONE.to_string()));
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// Builds the SSE frames for the outputs of one chunk, in output order.
+    fn sse_events(chunk: &GetChatCompletionChunk) -> Vec<String> {
+        let mut frames = Vec::new();
+        for output in &chunk.outputs {
+            let index = || ("index", json!(output.index));
+            if let Some(delta) = &output.delta {
+                if !delta.reasoning_content.is_empty() {
+                    frames.push(sse_frame(sse_object(
+                        "reasoning",
+                        vec![
+                            index(),
+                            ("text", Value::String(delta.reasoning_content.clone())),
+                        ],
+                    )));
+                }
+                if !delta.content.is_empty() {
+                    frames.push(sse_frame(sse_object(
+                        "content",
+                        vec![index(), ("text", Value::String(delta.content.clone()))],
+                    )));
+                }
+                if !delta.tool_calls.is_empty() {
+                    let calls = delta.tool_calls.iter().map(sse_tool_call).collect();
+                    frames.push(sse_frame(sse_object(
+                        "tool_calls",
+                        vec![index(), ("tool_calls", Value::Array(calls))],
+                    )));
+                }
+            }
+            if let Ok(reason) = FinishReason::try_from(output.finish_reason)
+                && reason != FinishReason::ReasonInvalid
+            {
+                frames.push(sse_frame(sse_object(
+                    "finish",
+                    vec![index(), ("reason", Value::String(reason.to_string()))],
+                )));
+            }
+        }
+        frames
+    }
+
+    fn sse_usage(usage: &SamplingUsage) -> String {
+        sse_frame(sse_object("usage", usage_fields(usage)))
+    }
+
+    fn usage_fields(usage: &SamplingUsage) -> Vec<(&'static str, Value)> {
+        let count = |n: i32| json!(n);
+        vec![
+            ("prompt_tokens", count(usage.prompt_tokens)),
+            ("completion_tokens", count(usage.completion_tokens)),
+            ("reasoning_tokens", count(usage.reasoning_tokens)),
+            ("total_tokens", count(usage.total_tokens)),
+        ]
+    }
+
+    fn sse_tool_call(call: &ToolCall) -> Value {
+        let call_type = ToolCallType::try_from(call.r#type)
+            .unwrap_or(ToolCallType::Invalid)
+            .to_string();
+        let (name, arguments) = match &call.tool {
+            Some(tool_call::Tool::Function(function)) => {
+                (function.name.clone(), function.arguments.clone())
+            }
+            None => (String::new(), String::new()),
+        };
+        json!({
+            "id": call.id,
+            "type": call_type,
+            "name": name,
+            "arguments": arguments,
+        })
+    }
+
+    fn sse_object(event_type: &str, fields: Vec<(&str, Value)>) -> Value {
+        let mut members = Map::new();
+        members.insert("type".to_string(), json!(event_type));
+        members.extend(fields.into_iter().map(|(k, v)| (k.to_string(), v)));
+        Value::Object(members)
+    }
+
+    /// Builds the [`Consumer::ndjson_logger`] line for an event, or `None` for events that
+    /// are not logged.
+    fn ndjson_event(event: &Event) -> Option<Value> {
+        let index = |ctx: &OutputContext| ("index", json!(ctx.output_index));
+        let (event_type, fields) = match event {
+            Event::Chunk(chunk) => (
+                "chunk",
+                vec![
+                    ("id", Value::String(chunk.id.clone())),
+                    ("model", Value::String(chunk.model.clone())),
+                    ("outputs", json!(chunk.outputs.len())),
+                ],
+            ),
+            Event::ReasoningStart(ctx) => ("reasoning_start", vec![index(ctx)]),
+            Event::ReasoningToken(ctx, token) => (
+                "reasoning",
+                vec![index(ctx), ("text", Value::String(token.clone()))],
+            ),
+            Event::ReasoningComplete(ctx) => ("reasoning_complete", vec![index(ctx)]),
+            Event::ContentStart(ctx) => ("content_start", vec![index(ctx)]),
+            Event::ContentToken(ctx, token) => (
+                "content",
+                vec![index(ctx), ("text", Value::String(token.clone()))],
+            ),
+            Event::ContentComplete(ctx) => ("content_complete", vec![index(ctx)]),
+            Event::InlineCitations(ctx, citations) => (
+                "inline_citations",
+                vec![index(ctx), ("count", json!(citations.len()))],
+            ),
+            Event::ClientToolCalls(ctx, calls) | Event::ServerToolCalls(ctx, calls) => {
+                let side = match event {
+                    Event::ClientToolCalls(..) => "client",
+                    _ => "server",
+                };
+                (
+                    "tool_calls",
+                    vec![
+                        index(ctx),
+                        ("side", Value::String(side.to_string())),
+                        (
+                            "tool_calls",
+                            Value::Array(calls.iter().map(sse_tool_call).collect()),
+                        ),
+                    ],
+                )
+            }
+            Event::Citations(urls) => (
+                "citations",
+                vec![(
+                    "urls",
+                    Value::Array(urls.iter().cloned().map(Value::String).collect()),
+                )],
+            ),
+            Event::Usage(Some(usage)) => ("usage", usage_fields(usage)),
+            Event::Usage(None) | Event::Complete | Event::Error(_) => return None,
+        };
+
+        let ts_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let mut members = Map::new();
+        members.insert("ts_ms".to_string(), json!(ts_ms));
+        if let Value::Object(rest) = sse_object(event_type, fields) {
+            members.extend(rest);
+        }
+        Some(Value::Object(members))
+    }
+
+    fn sse_frame(event: Value) -> String {
+        format!("data: {event}\n\n")
+    }
+
+    /// Processes a streaming chat completion response with custom callbacks.
+    ///
+    /// Iterates through streaming chunks, invoking consumer callbacks for each token,
+    /// completion event, and metadata. Supports multi-output streams with proper
+    /// context tracking.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>` (e.g. from
+    ///   `get_completion_chunk` or a mock). Must implement `Stream + Send + Unpin + 'static`.
+    /// * `consumer` - Configured callback consumer for handling stream events
+    ///
+    /// # Returns
+    /// * `Ok(Vec<GetChatCompletionChunk>)` - All chunks collected from the stream
+    /// * `Err(Status)` - gRPC error if streaming failed
+    pub async fn process<S>(
+        stream: S,
+        mut consumer: Consumer<'_>,
+    ) -> Result<Vec<GetChatCompletionChunk>, Status>
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    {
+        let mut stream = chunks(stream);
+        let mut state = ProcessState::new();
+
+        while let Some(chunk) = stream.next().await {
+            state.handle_chunk(&mut consumer, chunk?).await;
+        }
+
+        Ok(state.finish(&mut consumer).await)
+    }
+
+    /// Processes a stream like [`process`] and assembles the response in the same call.
+    ///
+    /// Combines [`process`] and [`assemble`], the pairing most callers want: the consumer
+    /// sees every event as it streams, and the caller gets both the assembled response and
+    /// the raw chunks (e.g. for [`record`]-style logging).
+    ///
+    /// The response's `usage` is the last usage the stream reported (see [`last_usage`]),
+    /// whether or not the consumer handles `on_usage`.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    /// * `consumer` - Configured callback consumer for handling stream events
+    ///
+    /// # Returns
+    /// * `Ok((GetChatCompletionResponse, Vec<GetChatCompletionChunk>))` - The assembled
+    ///   response and all chunks collected from the stream
+    /// * `Err(Status)` - gRPC error if streaming failed, or `Status::data_loss` if the stream
+    ///   ended without any chunks
+    pub async fn process_collect<S>(
+        stream: S,
+        consumer: Consumer<'_>,
+    ) -> Result<(GetChatCompletionResponse, Vec<GetChatCompletionChunk>), Status>
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    {
+        let chunks = process(stream, consumer).await?;
+        let response = assemble_or_err(chunks.clone())?;
+        Ok((response, chunks))
+    }
+
+    /// Processes a stream like [`process`] and measures its latency.
+    ///
+    /// The clock starts when this function is called, so create the stream right before
+    /// (e.g. `client.get_completion_chunk(request).await?` can be timed separately with
+    /// [`common::timed::call`](crate::common::timed::call)). See [`StreamMetrics`] for how
+    /// each figure is derived.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    /// * `consumer` - Configured callback consumer for handling stream events
+    ///
+    /// # Returns
+    /// * `Ok((Vec<GetChatCompletionChunk>, StreamMetrics))` - All chunks and the stream's
+    ///   metrics
+    /// * `Err(Status)` - gRPC error if streaming failed
+    pub async fn process_with_metrics<S>(
+        stream: S,
+        mut consumer: Consumer<'_>,
+    ) -> Result<(Vec<GetChatCompletionChunk>, StreamMetrics), Status>
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    {
+        let start = Instant::now();
+        let mut stream = chunks(stream);
+        let mut state = ProcessState::new();
+        let mut ttft = None;
+        let mut token_deltas = 0u32;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            let deltas = chunk
+                .outputs
+                .iter()
+                .filter_map(|output| output.delta.as_ref())
+                .filter(|delta| !delta.content.is_empty() || !delta.reasoning_content.is_empty())
+                .count() as u32;
+            if deltas > 0 && ttft.is_none() {
+                ttft = Some(start.elapsed());
+            }
+            token_deltas += deltas;
+            state.handle_chunk(&mut consumer, chunk).await;
+        }
+
+        let chunks = state.finish(&mut consumer).await;
+        let total_duration = start.elapsed();
+        let tokens = match last_usage(&chunks) {
+            Some(usage) => (usage.completion_tokens + usage.reasoning_tokens).max(0) as u32,
+            None => token_deltas,
+        };
+        let tps = ttft
+            .map(|ttft| total_duration.saturating_sub(ttft))
+            .filter(|generation| !generation.is_zero())
+            .map(|generation| f64::from(tokens) / generation.as_secs_f64());
+        let metrics = StreamMetrics {
+            ttft,
+            total_duration,
+            tokens,
+            tps,
+        };
+        Ok((chunks, metrics))
+    }
+
+    /// Processes a stream like [`process`], but keeps the chunks received before an error.
+    ///
+    /// Streams often fail near the end, after most of the content has already arrived.
+    /// [`process`] discards that content on error; this variant stops at the error and
+    /// returns it alongside the chunks collected so far, so callers can still
+    /// [`assemble`](StreamResult::assemble) and display the partial response. Final metadata
+    /// callbacks (`on_usage`, `on_citations`) only fire when the stream ends normally.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    /// * `consumer` - Configured callback consumer for handling stream events
+    ///
+    /// # Returns
+    /// * `StreamResult` - Every chunk received, plus the stream error if it failed
+    pub async fn process_lenient<S>(stream: S, mut consumer: Consumer<'_>) -> StreamResult
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    {
+        let mut stream = chunks(stream);
+        let mut state = ProcessState::new();
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(chunk) => state.handle_chunk(&mut consumer, chunk).await,
+                Err(status) => {
+                    return StreamResult {
+                        usage: last_usage(&state.chunks),
+                        chunks: state.chunks,
+                        error: Some(status),
+                    };
+                }
+            }
+        }
+
+        let chunks = state.finish(&mut consumer).await;
+        StreamResult {
+            usage: last_usage(&chunks),
+            chunks,
+            error: None,
+        }
+    }
+
+    /// Processes a streaming response like [`process`], but gives up if the server stalls.
+    ///
+    /// Each wait for the next chunk is bounded by `idle`. If no chunk (or end of stream)
+    /// arrives within that window, processing stops and the chunks received so far are
+    /// returned inside [`StreamError::IdleTimeout`] so the partial output is not lost.
+    /// Final metadata callbacks (`on_usage`, `on_citations`) only fire on normal completion.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    /// * `consumer` - Configured callback consumer for handling stream events
+    /// * `idle` - Maximum time to wait between consecutive chunks
+    ///
+    /// # Returns
+    /// * `Ok(Vec<GetChatCompletionChunk>)` - All chunks collected from the stream
+    /// * `Err(StreamError::Status)` - gRPC error if streaming failed
+    /// * `Err(StreamError::IdleTimeout)` - No chunk arrived within `idle`
+    pub async fn process_with_idle_timeout<S>(
+        stream: S,
+        mut consumer: Consumer<'_>,
+        idle: Duration,
+    ) -> Result<Vec<GetChatCompletionChunk>, StreamError>
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    {
+        let mut stream = chunks(stream);
+        let mut state = ProcessState::new();
+
+        loop {
+            match tokio::time::timeout(idle, stream.next()).await {
+                Err(_) => {
+                    return Err(StreamError::IdleTimeout {
+                        idle,
+                        chunks: state.chunks,
+                    });
+                }
+                Ok(None) => break,
+                Ok(Some(Err(status))) => return Err(StreamError::Status(status)),
+                Ok(Some(Ok(chunk))) => state.handle_chunk(&mut consumer, chunk).await,
+            }
+        }
+
+        Ok(state.finish(&mut consumer).await)
+    }
+
+    /// Processes a stream on a background task and delivers its [`Event`]s through a channel.
+    ///
+    /// Bridges the callback-based [`Consumer`] to code that prefers to `select!` on a receiver.
+    /// Events arrive in stream order, exactly as [`Consumer::with_sink`] would produce them,
+    /// followed by a final [`Event::Complete`] when the stream ends normally or
+    /// [`Event::Error`] when it fails. The channel then closes.
+    ///
+    /// The channel holds at most `buffer` events; processing pauses while it is full, so a slow
+    /// receiver applies backpressure to the stream. Dropping the receiver cancels processing:
+    /// the task stops reading the stream and resolves to `Status::cancelled`. Must be called
+    /// from within a Tokio runtime.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    /// * `buffer` - Channel capacity in events; `0` is treated as `1`
+    ///
+    /// # Returns
+    /// * `mpsc::Receiver<Event>` - Receiver for the stream events
+    /// * `JoinHandle<Result<Vec<GetChatCompletionChunk>, Status>>` - The processing task, which
+    ///   resolves to all chunks (as returned by [`process`]) or the stream error
+    pub fn into_channel<S>(
+        stream: S,
+        buffer: usize,
+    ) -> (
+        mpsc::Receiver<Event>,
+        JoinHandle<Result<Vec<GetChatCompletionChunk>, Status>>,
+    )
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    {
+        let (tx, rx) = mpsc::channel(buffer.max(1));
+
+        let handle = tokio::spawn(async move {
+            let consumer = Consumer::forwarding({
+                let tx = tx.clone();
+                move |event| {
+                    let tx = tx.clone();
+                    async move {
+                        let _ = tx.send(event).await;
+                    }
+                }
+            });
+
+            let processing = pin!(process(stream, consumer));
+            let cancelled = pin!(tx.closed());
+            match select(processing, cancelled).await {
+                Either::Left((Ok(chunks), _)) => {
+                    let _ = tx.send(Event::Complete).await;
+                    Ok(chunks)
+                }
+                Either::Left((Err(status), _)) => {
+                    let _ = tx.send(Event::Error(Box::new(status.clone()))).await;
+                    Err(status)
+                }
+                Either::Right(_) => Err(Status::cancelled("event receiver was dropped")),
+            }
+        });
+
+        (rx, handle)
+    }
+
+    /// Wraps a stream so that it can be cancelled from another task.
+    ///
+    /// Dropping the future that drives a stream only stops generation once the `tonic`
+    /// stream itself is dropped: that closes the HTTP/2 stream with `RST_STREAM(CANCEL)`,
+    /// which the server treats as a cancelled call and stops generating (and billing).
+    /// [`CancelHandle::cancel`] makes that happen promptly and explicitly: it wakes the task
+    /// polling the wrapper, which drops the inner stream on the spot, yields one
+    /// `Status::cancelled` error and then ends. Passing the wrapper to [`process`] (or any
+    /// other consumer) therefore ends processing with that error.
+    ///
+    /// The inner stream is dropped the next time the wrapper is polled, so a consumer that is
+    /// busy inside a callback delays the cancel until the callback returns. Cancelling after
+    /// the stream has ended does nothing.
+    ///
+    /// ```no_run
+    /// # async fn example(mut client: xai_sdk::chat::client::ChatClient, request: xai_sdk::api::GetCompletionsRequest) -> Result<(), tonic::Status> {
+    /// use std::time::Duration;
+    /// use xai_sdk::chat::stream::{Consumer, cancellable, process};
+    ///
+    /// let streaming = client.get_completion_chunk(request).await?.into_inner();
+    /// let (stream, cancel) = cancellable(streaming);
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(Duration::from_secs(30)).await;
+    ///     cancel.cancel(); // too slow: stop generating
+    /// });
+    /// let result = process(stream, Consumer::new()).await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<T, Status>`, such as the `tonic::Streaming`
+    ///   returned by `get_completion_chunk`
+    ///
+    /// # Returns
+    /// * `Cancellable<S>` - The wrapped stream
+    /// * `CancelHandle` - Cloneable handle that cancels it
+    pub fn cancellable<S>(stream: S) -> (Cancellable<S>, CancelHandle) {
+        let handle = CancelHandle::default();
+        let cancellable = Cancellable {
+            stream: Some(stream),
+            handle: handle.clone(),
+        };
+        (cancellable, handle)
+    }
+
+    /// Handle returned by [`cancellable`] that cancels the wrapped stream.
+    #[derive(Debug, Clone, Default)]
+    pub struct CancelHandle {
+        state: Arc<CancelState>,
+    }
+
+    #[derive(Debug, Default)]
+    struct CancelState {
+        cancelled: AtomicBool,
+        waker: AtomicWaker,
+    }
+
+    impl CancelHandle {
+        /// Cancels the stream: the inner stream is dropped as soon as the wrapper is polled,
+        /// and the wrapper yields `Status::cancelled`.
+        pub fn cancel(&self) {
+            self.state.cancelled.store(true, Ordering::SeqCst);
+            self.state.waker.wake();
+        }
+
+        /// Returns `true` once [`cancel`](Self::cancel) has been called.
+        pub fn is_cancelled(&self) -> bool {
+            self.state.cancelled.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Stream returned by [`cancellable`].
+    #[derive(Debug)]
+    pub struct Cancellable<S> {
+        stream: Option<S>,
+        handle: CancelHandle,
+    }
+
+    impl<S, T> Stream for Cancellable<S>
+    where
+        S: Stream<Item = Result<T, Status>> + Unpin,
+    {
+        type Item = Result<T, Status>;
+
+        fn poll_next(
+            self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> Poll<Option<Self::Item>> {
+            let this = self.get_mut();
+            this.handle.state.waker.register(cx.waker());
+            if this.handle.is_cancelled() {
+                // Dropping the inner stream resets the HTTP/2 stream
+                return Poll::Ready(
+                    this.stream
+                        .take()
+                        .map(|_| Err(Status::cancelled("stream cancelled by caller"))),
+                );
+            }
+            let Some(stream) = this.stream.as_mut() else {
+                return Poll::Ready(None);
+            };
+            let item = std::task::ready!(stream.poll_next_unpin(cx));
+            if item.is_none() {
+                this.stream = None;
+            }
+            Poll::Ready(item)
+        }
+    }
+
+    /// Wraps a chunk stream so that every chunk is also written to a file as it passes through.
+    ///
+    /// Pass the returned stream to [`process`] (or any other consumer) as usual; the file can
+    /// later be turned back into an identical stream with [`replay`], to iterate on consumer
+    /// logic offline without new API calls.
+    ///
+    /// Chunks are stored in their protobuf wire format, each prefixed with its length, which
+    /// round-trips every field exactly. This is not JSON lines: the generated types do not
+    /// implement `serde`, so a JSON encoding is not available. Stream errors are passed through
+    /// and not recorded.
+    ///
+    /// The file is created when `record` is called; writes go through `tokio::fs`, which runs
+    /// them on Tokio's blocking thread pool, so the returned stream must be polled within a
+    /// Tokio runtime. The file is flushed when the stream ends.
+    ///
+    /// # Arguments
+    /// * `stream` - Any stream yielding `Result<GetChatCompletionChunk, Status>`
+    /// * `path` - File to create (truncated if it exists)
+    ///
+    /// # Returns
+    /// * `Ok(impl Stream)` - The recording stream; a failed write is yielded as
+    ///   `Status::internal` and ends the stream
+    /// * `Err(io::Error)` - The file could not be created
+    pub fn record<S>(
+        stream: S,
+        path: impl AsRef<Path>,
+    ) -> io::Result<
+        impl Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    >
+    where
+        S: Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    {
+        let file = tokio::fs::File::from_std(File::create(path)?);
+        let write_failed =
+            |e: io::Error| Status::internal(format!("Failed to record chunk: {}", e));
+        let recording = futures::stream::unfold(Some((stream, file)), move |state| async move {
+            let (mut stream, mut file) = state?;
+            match stream.next().await {
+                Some(Ok(chunk)) => {
+                    match file
+                        .write_all(&chunk.encode_length_delimited_to_vec())
+                        .await
+                    {
+                        Ok(()) => Some((Ok(chunk), Some((stream, file)))),
+                        Err(e) => Some((Err(write_failed(e)), None)),
+                    }
+                }
+                Some(Err(status)) => Some((Err(status), Some((stream, file)))),
+                None => file
+                    .flush()
+                    .await
+                    .err()
+                    .map(|e| (Err(write_failed(e)), None)),
+            }
+        });
+        Ok(recording.boxed())
+    }
+
+    /// Reads chunks written by [`record`] and yields them as a stream.
+    ///
+    /// The whole file is read and decoded synchronously when `replay` is called; wrap the call
+    /// in `tokio::task::spawn_blocking` if large recordings must not block the runtime.
+    ///
+    /// # Arguments
+    /// * `path` - File produced by [`record`]
+    ///
+    /// # Returns
+    /// * `Ok(impl Stream)` - Stream yielding the recorded chunks in order
+    /// * `Err(io::Error)` - The file could not be read or is not a valid recording
+    pub fn replay(
+        path: impl AsRef<Path>,
+    ) -> io::Result<
+        impl Stream<Item = Result<GetChatCompletionChunk, Status>> + Send + Unpin + 'static,
+    > {
+        let bytes = std::fs::read(path)?;
+        let mut buf = bytes.as_slice();
+        let mut chunks = Vec::new();
+        while !buf.is_empty() {
+            let chunk = GetChatCompletionChunk::decode_length_delimited(&mut buf)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            chunks.push(Ok(chunk));
+        }
+        Ok(futures::stream::iter(chunks))
+    }
+
+    /// Returns true if the chunk carries no progress: no output has content, reasoning or
+    /// tool calls in its delta, no output has finished, and no usage is attached.
+    fn is_keepalive(chunk: &GetChatCompletionChunk) -> bool {
+        chunk.usage.is_none()
+            && chunk.outputs.iter().all(|output| {
+                output.finish_reason == FinishReason::ReasonInvalid as i32
+                    && output.delta.as_ref().is_none_or(|delta| {
+                        delta.content.is_empty()
+                            && delta.reasoning_content.is_empty()
+                            && delta.tool_calls.is_empty()
+                    })
+            })
+    }
+
+    /// Returns (reasoning_status, content_status) for the current output from accumulated stats.
+    fn get_output_status(cur_output_stats: &OutputStats) -> (PhaseStatus, PhaseStatus) {
+        let is_finished = cur_output_stats.finish_reason != FinishReason::ReasonInvalid;
+        let r = cur_output_stats.total_reasoning_tokens;
+        let c = cur_output_stats.total_content_tokens;
+
+        let reasoning_status = if is_finished {
+            PhaseStatus::Complete
+        } else if r == 0 && c > 0 {
+            PhaseStatus::Complete // no-reasoning model, content started
+        } else if r > 0 && c > 0 {
+            PhaseStatus::Complete // reasoning done, content in progress
+        } else if r == 1 && c == 0 {
+            PhaseStatus::Start // first reasoning token
+        } else if r > 1 && c == 0 {
+            PhaseStatus::Pending
+        } else {
+            PhaseStatus::Init // r == 0 && c == 0
+        };
+
+        let content_status = if is_finished {
+            PhaseStatus::Complete
+        } else if c == 1 {
+            PhaseStatus::Start // first content token
+        } else if c > 1 {
+            PhaseStatus::Pending
+        } else {
+            PhaseStatus::Init
+        };
+
+        (reasoning_status, content_status)
+    }
+
+    /// Returns the last token usage reported in a stream.
+    ///
+    /// The server attaches `SamplingUsage` to the final chunk only. Searching backwards
+    /// keeps the count when that chunk is followed by others without usage (e.g. a trailing
+    /// keepalive), so token accounting does not depend on which chunk came last. [`assemble`],
+    /// [`StreamResult::usage`] and the `on_usage` callback all use it.
+    ///
+    /// # Arguments
+    /// * `chunks` - Chunks of one stream, in stream order
+    ///
+    /// # Returns
+    /// * `Some(SamplingUsage)` - Usage of the last chunk that carried one
+    /// * `None` - If no chunk reported usage
+    pub fn last_usage(chunks: &[GetChatCompletionChunk]) -> Option<SamplingUsage> {
+        chunks.iter().rev().find_map(|chunk| chunk.usage.clone())
+    }
+
+    impl GetChatCompletionChunk {
+        /// Returns the chunk's usage, or an all-zero `SamplingUsage` if it has none.
+        ///
+        /// Only the final chunk of a stream carries usage; use [`last_usage`] to find it.
+        pub fn usage_or_empty(&self) -> SamplingUsage {
+            self.usage.clone().unwrap_or_default()
+        }
+    }
+
+    /// Assembles streaming chunks into a complete chat completion response.
+    ///
+    /// Reconstructs a full `GetChatCompletionResponse` from collected chunks by:
+    /// - Grouping chunks by output index for multi-output handling
+    /// - Accumulating content, reasoning, tool calls and log probabilities across deltas
+    /// - Preserving metadata from first chunk and the last reported usage stats
+    /// - Maintaining output ordering and ordering inline citations by position
+    ///
+    /// Chunks carry no sequence number, so they are concatenated in arrival order. Assembly is
+    /// hardened with the information that is available:
+    /// - Repeated tool calls and inline citations (exact duplicates) are kept only once.
+    /// - Response-level citations are merged across all chunks, each URL kept once in
+    ///   first-seen order.
+    /// - A chunk without a finish reason never clears one recorded by an earlier chunk.
+    ///
+    /// Duplicated content deltas cannot be told apart from genuinely repeated text and are
+    /// concatenated as received.
+    ///
+    /// `GetChatCompletionChunk` has no `settings` field, so the assembled response always has
+    /// `settings: None`. Use [`assemble_with_settings`] to fill it in from known settings.
+    ///
+    /// A stream whose connection drops before the first chunk can end without an error and
+    /// without chunks, so do not `unwrap` the result; [`assemble_or_err`] turns that case
+    /// into an error.
+    ///
+    /// # Arguments
+    /// * `chunks` - Vector of chunks from a streaming response
+    ///
+    /// # Returns
+    /// * `Some(GetChatCompletionResponse)` - Complete assembled response
+    /// * `None` - If chunks vector is empty
+    ///
+    pub fn assemble(chunks: Vec<GetChatCompletionChunk>) -> Option<GetChatCompletionResponse> {
+        assemble_outputs(chunks, true)
+    }
+
+    /// Assembles streaming chunks like [`assemble`], reporting an empty stream as an error.
+    ///
+    /// `AssembleError` converts into a `Status` (`DataLoss`), so the result can be
+    /// propagated with `?` alongside streaming errors.
+    ///
+    /// # Arguments
+    /// * `chunks` - Vector of chunks from a streaming response
+    ///
+    /// # Returns
+    /// * `Ok(GetChatCompletionResponse)` - Complete assembled response
+    /// * `Err(AssembleError::EmptyStream)` - If chunks vector is empty
+    pub fn assemble_or_err(
+        chunks: Vec<GetChatCompletionChunk>,
+    ) -> Result<GetChatCompletionResponse, AssembleError> {
+        assemble(chunks).ok_or(AssembleError::EmptyStream)
+    }
+
+    /// Assembles streaming chunks like [`assemble`], dropping the reasoning content.
+    ///
+    /// For callers that only want the final answer: reasoning deltas are skipped rather than
+    /// accumulated, so the assembled messages have an empty `reasoning_content`. This is
+    /// purely client-side. The API has no option to suppress reasoning, so reasoning models
+    /// still generate, stream and bill the reasoning tokens (see `reasoning_tokens` in the
+    /// usage). To actually spend fewer of them, lower the effort with
+    /// [`RequestBuilder::reasoning_effort`](crate::chat::RequestBuilder::reasoning_effort)
+    /// on models that support it.
+    ///
+    /// # Arguments
+    /// * `chunks` - Vector of chunks from a streaming response
+    ///
+    /// # Returns
+    /// * `Some(GetChatCompletionResponse)` - Assembled response without reasoning content
+    /// * `None` - If chunks vector is empty
+    ///
+    pub fn assemble_without_reasoning(
+        chunks: Vec<GetChatCompletionChunk>,
+    ) -> Option<GetChatCompletionResponse> {
+        assemble_outputs(chunks, false)
+    }
+
+    /// Assembles streaming chunks like [`assemble`], then cleans up the text for display.
+    ///
+    /// Applies [`normalize_whitespace`](crate::chat::response::normalize_whitespace) to the
+    /// content and reasoning content of every output, and shifts inline citation offsets so
+    /// they still point at the same text. Use [`assemble`] to keep the raw text, and
+    /// [`GetChatCompletionResponse::trimmed`] to normalize a single output on demand.
+    ///
+    /// # Arguments
+    /// * `chunks` - Vector of chunks from a streaming response
+    ///
+    /// # Returns
+    /// * `Some(GetChatCompletionResponse)` - Assembled response with normalized text
+    /// * `None` - If chunks vector is empty
+    ///
+    pub fn assemble_normalized(
+        chunks: Vec<GetChatCompletionChunk>,
+    ) -> Option<GetChatCompletionResponse> {
+        let mut response = assemble_outputs(chunks, true)?;
+        for message in response
+            .outputs
+            .iter_mut()
+            .filter_map(|output| output.message.as_mut())
+        {
+            let (content, remap) = normalize_with_offsets(&message.content);
+            message.content = content;
+            for citation in &mut message.citations {
+                citation.start_index = remap(citation.start_index);
+                citation.end_index = remap(citation.end_index);
+            }
+            message.reasoning_content = normalize_whitespace(&message.reasoning_content);
+        }
+        Some(response)
+    }
+
+    fn assemble_outputs(
+        chunks: Vec<GetChatCompletionChunk>,
+        keep_reasoning: bool,
+    ) -> Option<GetChatCompletionResponse> {
+        if chunks.is_empty() {
+            return None;
+        }
+
+        // Use the first chunk for metadata that should be consistent across all chunks
+        let first_chunk = &chunks[0];
+
+        // Group chunks by output index to handle multiple outputs
         let mut output_data: HashMap<i32, OutputData> = HashMap::new();
 
         for chunk in &chunks {
@@ -439,15 +3852,17 @@ pub mod stream {
                     encrypted_content: String::new(),
                     citations: Vec::new(),
                     finish_reason: output_chunk.finish_reason,
-                    logprobs: output_chunk.logprobs.clone(),
+                    logprobs: None,
                 });
 
                 // Accumulate content and reasoning from deltas
                 if let Some(delta) = &output_chunk.delta {
                     output_data.content.push_str(&delta.content);
-                    output_data
-                        .reasoning_content
-                        .push_str(&delta.reasoning_content);
+                    if keep_reasoning {
+                        output_data
+                            .reasoning_content
+                            .push_str(&delta.reasoning_content);
+                    }
                     output_data
                         .encrypted_content
                         .push_str(&delta.encrypted_content);
@@ -457,22 +3872,43 @@ pub mod stream {
                         output_data.role = delta.role;
                     }
 
-                    // Accumulate tool calls
-                    output_data.tool_calls.extend(delta.tool_calls.clone());
+                    // Accumulate tool calls, skipping re-delivered ones
+                    for tool_call in &delta.tool_calls {
+                        if !output_data.tool_calls.contains(tool_call) {
+                            output_data.tool_calls.push(tool_call.clone());
+                        }
+                    }
+
+                    // Accumulate citations from delta, skipping re-delivered ones
+                    for citation in &delta.citations {
+                        if !output_data.citations.contains(citation) {
+                            output_data.citations.push(citation.clone());
+                        }
+                    }
+                }
 
-                    // Accumulate citations from delta
-                    output_data.citations.extend(delta.citations.clone());
+                // Each chunk carries the log probabilities of its own tokens only
+                if let Some(logprobs) = &output_chunk.logprobs {
+                    output_data
+                        .logprobs
+                        .get_or_insert_with(Default::default)
+                        .content
+                        .extend(logprobs.content.iter().cloned());
                 }
 
-                // Update finish reason from the latest chunk
-                output_data.finish_reason = output_chunk.finish_reason;
-                output_data.logprobs = output_chunk.logprobs.clone();
+                // Update finish reason from the latest chunk that carries one
+                if output_chunk.finish_reason != 0 {
+                    output_data.finish_reason = output_chunk.finish_reason;
+                }
             }
         }
 
         // Convert output data to CompletionOutput objects
         let mut outputs = Vec::new();
-        for (index, data) in output_data {
+        for (index, mut data) in output_data {
+            // Keep inline citations in text order, whatever order the deltas delivered them in
+            data.citations.sort_by_key(|c| (c.start_index, c.end_index));
+
             let message = CompletionMessage {
                 content: data.content,
                 reasoning_content: data.reasoning_content,
@@ -493,11 +3929,17 @@ pub mod stream {
         // Sort outputs by index to maintain order
         outputs.sort_by_key(|o| o.index);
 
-        // Use the last chunk's usage data (should have the final token counts)
-        let usage = last_chunk.usage.clone();
+        // Usage normally arrives on the last chunk; take the last one reported
+        let usage = last_usage(&chunks);
 
-        // Use the last chunk's citations (should be populated in the final chunk)
-        let citations = last_chunk.citations.clone();
+        // Citations normally arrive on the final chunk, but search tools may repeat them on
+        // several chunks; merge them all, keeping each URL once in first-seen order
+        let mut citations: Vec<String> = Vec::new();
+        for citation in chunks.iter().flat_map(|chunk| &chunk.citations) {
+            if !citations.contains(citation) {
+                citations.push(citation.clone());
+            }
+        }
 
         Some(GetChatCompletionResponse {
             id: first_chunk.id.clone(),
@@ -507,11 +3949,143 @@ pub mod stream {
             system_fingerprint: first_chunk.system_fingerprint.clone(),
             usage,
             citations,
-            settings: None,     // Settings are not available in streaming responses
+            settings: None, // Chunks have no `settings` field; see `assemble_with_settings`
             debug_output: None, // Debug output is not available in streaming responses
         })
     }
 
+    /// Assembles streaming chunks like [`assemble`], setting the response's `settings`.
+    ///
+    /// Streaming responses never carry the request settings the server applied, unlike
+    /// `get_completion`. Callers that know them (e.g. from a stored non-streaming response
+    /// or their own request) can attach them here, so the assembled response matches what a
+    /// unary call would have returned.
+    ///
+    /// # Arguments
+    /// * `chunks` - Vector of chunks from a streaming response
+    /// * `settings` - Settings to store in the response
+    ///
+    /// # Returns
+    /// * `Some(GetChatCompletionResponse)` - Complete assembled response with `settings` set
+    /// * `None` - If chunks vector is empty
+    ///
+    pub fn assemble_with_settings(
+        chunks: Vec<GetChatCompletionChunk>,
+        settings: RequestSettings,
+    ) -> Option<GetChatCompletionResponse> {
+        assemble(chunks).map(|mut response| {
+            response.settings = Some(settings);
+            response
+        })
+    }
+
+    /// Splits a complete chat completion response into streaming chunks.
+    ///
+    /// The inverse of [`assemble`]: useful for synthesizing chunk streams in tests and for
+    /// adapting a non-streaming backend to a streaming UI. Each output is emitted in turn, first
+    /// its reasoning and then its content, in deltas of at most `chunk_size` characters. The
+    /// first delta of an output carries its role; the last one carries its tool calls, encrypted
+    /// content, inline citations, log probabilities and finish reason. Every chunk carries the
+    /// response metadata, and the final chunk carries the usage and citations.
+    ///
+    /// `assemble(to_chunks(&response, n))` equals `response` whenever the response has no
+    /// `settings` or `debug_output` (neither is streamed), every output has a message, and the
+    /// outputs are sorted by index with inline citations sorted by position.
+    ///
+    /// # Arguments
+    /// * `response` - Complete response to split
+    /// * `chunk_size` - Maximum number of characters per delta (`0` is treated as `1`)
+    ///
+    /// # Returns
+    /// * `Vec<GetChatCompletionChunk>` - Chunks in stream order, at least one per output
+    ///
+    pub fn to_chunks(
+        response: &GetChatCompletionResponse,
+        chunk_size: usize,
+    ) -> Vec<GetChatCompletionChunk> {
+        let chunk_size = chunk_size.max(1);
+        let mut chunks = Vec::new();
+
+        for output in &response.outputs {
+            let message = output.message.clone().unwrap_or_default();
+
+            let mut deltas: Vec<Delta> = split_chars(&message.reasoning_content, chunk_size)
+                .map(|reasoning_content| Delta {
+                    reasoning_content,
+                    ..Default::default()
+                })
+                .chain(
+                    split_chars(&message.content, chunk_size).map(|content| Delta {
+                        content,
+                        ..Default::default()
+                    }),
+                )
+                .collect();
+            if deltas.is_empty() {
+                deltas.push(Delta::default());
+            }
+
+            deltas[0].role = message.role;
+            let last = deltas.len() - 1;
+            deltas[last].tool_calls = message.tool_calls;
+            deltas[last].encrypted_content = message.encrypted_content;
+            deltas[last].citations = message.citations;
+
+            for (i, delta) in deltas.into_iter().enumerate() {
+                let is_last = i == last;
+                chunks.push(GetChatCompletionChunk {
+                    id: response.id.clone(),
+                    outputs: vec![CompletionOutputChunk {
+                        delta: Some(delta),
+                        logprobs: if is_last {
+                            output.logprobs.clone()
+                        } else {
+                            None
+                        },
+                        finish_reason: if is_last { output.finish_reason } else { 0 },
+                        index: output.index,
+                    }],
+                    created: response.created,
+                    model: response.model.clone(),
+                    system_fingerprint: response.system_fingerprint.clone(),
+                    usage: None,
+                    citations: Vec::new(),
+                    debug_output: None,
+                });
+            }
+        }
+
+        // Final metadata, like the server sends it on the last chunk
+        match chunks.last_mut() {
+            Some(last) => {
+                last.usage = response.usage.clone();
+                last.citations = response.citations.clone();
+            }
+            None => chunks.push(GetChatCompletionChunk {
+                id: response.id.clone(),
+                outputs: Vec::new(),
+                created: response.created,
+                model: response.model.clone(),
+                system_fingerprint: response.system_fingerprint.clone(),
+                usage: response.usage.clone(),
+                citations: response.citations.clone(),
+                debug_output: None,
+            }),
+        }
+
+        chunks
+    }
+
+    /// Splits `text` into pieces of at most `size` characters, yielding nothing for empty text.
+    fn split_chars(text: &str, size: usize) -> impl Iterator<Item = String> + '_ {
+        let chars: Vec<char> = text.chars().collect();
+        (0..chars.len()).step_by(size).map(move |start| {
+            chars[start..(start + size).min(chars.len())]
+                .iter()
+                .collect()
+        })
+    }
+
     /// Accumulates output data during chunk assembly process.
     #[derive(Default)]
     struct OutputData {
@@ -525,7 +4099,8 @@ pub mod stream {
         logprobs: Option<LogProbs>,
     }
 
-    /// A single event from a streaming chat completion, as produced by [`Consumer::with_sink`].
+    /// A single event from a streaming chat completion, as produced by [`Consumer::with_sink`]
+    /// and [`into_channel`].
     ///
     /// Events are emitted in stream order. Use [`OutputContext`] in variants to correlate
     /// reasoning, content, and tool-call events with the same output when `n > 1`.
@@ -562,13 +4137,31 @@ pub mod stream {
         /// Token usage from the last chunk, if present.
         Usage(Option<SamplingUsage>),
 
-        /// Stream finished (reserved for future use).
+        /// Stream finished normally (sent by [`into_channel`]).
         Complete,
 
         /// Stream or processing error.
         Error(BoxError),
     }
 
+    /// Handler for a single output (choice) of a stream, driven by [`Consumer::per_choice`].
+    ///
+    /// Methods are called synchronously in stream order; keep them cheap or hand the work off
+    /// (e.g. to a channel). All methods except [`ChoiceHandler::on_complete`] default to no-ops.
+    pub trait ChoiceHandler: Send {
+        /// Called for each reasoning token of the output.
+        fn on_reasoning_token(&mut self, _token: &str) {}
+
+        /// Called for each content token of the output.
+        fn on_content_token(&mut self, _token: &str) {}
+
+        /// Called for each delta of the output that carries tool calls.
+        fn on_tool_calls(&mut self, _tool_calls: &[ToolCall]) {}
+
+        /// Called once when the output finishes, after all of its tokens.
+        fn on_complete(&mut self, finish_reason: FinishReason);
+    }
+
     /// Callback-based consumer for processing streaming chat completion responses.
     ///
     /// Defines optional async callbacks invoked as streaming chunks arrive.
@@ -583,6 +4176,8 @@ pub mod stream {
     /// # Callback Signatures (execution order)
     /// All callbacks are async with `Future<Output = ()>`. Use references to avoid cloning.
     /// - `on_chunk`: `&GetChatCompletionChunk`
+    /// - `on_keepalive`: `&GetChatCompletionChunk` (chunks without content, reasoning or tool calls)
+    /// - `on_delta`: `(&OutputContext, &Delta)` (every delta, before its token callbacks)
     /// - `on_reasoning_start`: `&OutputContext` (once per output, before first reasoning token)
     /// - `on_reasoning_token`: `(&OutputContext, &str)`
     /// - `on_reasoning_complete`: `&OutputContext`
@@ -598,21 +4193,36 @@ pub mod stream {
         /// Callback invoked once per complete chunk received.
         ///
         /// Receives `&GetChatCompletionChunk`
-        pub on_chunk:
-            Option<Box<dyn FnMut(&GetChatCompletionChunk) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_chunk: Option<Callback<'a, GetChatCompletionChunk>>,
+
+        /// Callback invoked for chunks that carry no progress.
+        ///
+        /// Fired after `on_chunk` when no output in the chunk has content, reasoning or tool
+        /// calls in its delta, no output has finished, and no usage is attached. Long
+        /// reasoning sessions send such chunks to keep the stream alive, so this is a cheap
+        /// liveness signal (e.g. for a progress spinner).
+        /// Receives `&GetChatCompletionChunk`
+        pub on_keepalive: Option<Callback<'a, GetChatCompletionChunk>>,
+
+        /// Callback invoked for every output's delta in each chunk.
+        ///
+        /// Fired before the phase and token callbacks of the same delta, with the raw
+        /// `Delta` (role, content, reasoning, tool calls, citations, encrypted content) for
+        /// custom accumulators. Log probabilities are attached to the output chunk rather
+        /// than the delta; use `on_chunk` for those.
+        /// Receives `(&OutputContext, &Delta)`.
+        pub on_delta: Option<Callback2<'a, OutputContext, Delta>>,
 
         /// Callback invoked once when the reasoning phase starts for an output.
         ///
         /// Fired exactly once per output, immediately before the first reasoning token.
         /// Receives `&OutputContext` (with `reasoning_status == PhaseStatus::Start`).
-        pub on_reasoning_start:
-            Option<Box<dyn FnMut(&OutputContext) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_reasoning_start: Option<Callback<'a, OutputContext>>,
 
         /// Callback invoked for each reasoning token in the stream.
         ///
         /// Receives `(&OutputContext, token: &str)` — no cloning.
-        pub on_reasoning_token:
-            Option<Box<dyn FnMut(&OutputContext, &str) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_reasoning_token: Option<Callback2<'a, OutputContext, str>>,
 
         /// Callback invoked once when the reasoning phase completes for an output.
         ///
@@ -620,21 +4230,18 @@ pub mod stream {
         /// to `Complete`. Useful for performing cleanup or formatting when reasoning finishes.
         ///
         /// Receives `&OutputContext`.
-        pub on_reasoning_complete:
-            Option<Box<dyn FnMut(&OutputContext) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_reasoning_complete: Option<Callback<'a, OutputContext>>,
 
         /// Callback invoked once when the content phase starts for an output.
         ///
         /// Fired exactly once per output, immediately before the first content token.
         /// Receives `&OutputContext` (with `content_status == PhaseStatus::Start`).
-        pub on_content_start:
-            Option<Box<dyn FnMut(&OutputContext) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_content_start: Option<Callback<'a, OutputContext>>,
 
         /// Callback invoked for each content token in the stream.
         ///
         /// Receives `(&OutputContext, token: &str)` — no cloning.
-        pub on_content_token:
-            Option<Box<dyn FnMut(&OutputContext, &str) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_content_token: Option<Callback2<'a, OutputContext, str>>,
 
         /// Callback invoked once when the content phase completes for an output.
         ///
@@ -642,17 +4249,14 @@ pub mod stream {
         /// to `Complete`. Useful for performing cleanup or formatting when content generation finishes.
         ///
         /// Receives `&OutputContext`.
-        pub on_content_complete:
-            Option<Box<dyn FnMut(&OutputContext) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_content_complete: Option<Callback<'a, OutputContext>>,
 
         /// Callback invoked when inline citations are present in a delta.
         ///
         /// This callback is called whenever a delta contains inline citations for an output.
         ///
         /// Receives `(&OutputContext, &[InlineCitation])`.
-        pub on_inline_citations: Option<
-            Box<dyn FnMut(&OutputContext, &[InlineCitation]) -> BoxFuture<'a> + Send + Sync + 'a>,
-        >,
+        pub on_inline_citations: Option<Callback2<'a, OutputContext, [InlineCitation]>>,
 
         /// Callback invoked when client-side tool calls are present.
         ///
@@ -660,8 +4264,7 @@ pub mod stream {
         /// by the client) appear in a delta.
         ///
         /// Receives `(&OutputContext, &[ToolCall])`.
-        pub on_client_tool_calls:
-            Option<Box<dyn FnMut(&OutputContext, &[ToolCall]) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_client_tool_calls: Option<Callback2<'a, OutputContext, [ToolCall]>>,
 
         /// Callback invoked when server-side tool calls are present.
         ///
@@ -669,20 +4272,61 @@ pub mod stream {
         /// appear in a delta.
         ///
         /// Receives `(&OutputContext, &[ToolCall])`.
-        pub on_server_tool_calls:
-            Option<Box<dyn FnMut(&OutputContext, &[ToolCall]) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_server_tool_calls: Option<Callback2<'a, OutputContext, [ToolCall]>>,
 
         /// Callback invoked once on the last chunk with usage statistics.
         ///
         /// Called after the stream completes, only if the last chunk includes usage data.
         /// Receives `&SamplingUsage` with token usage information.
-        pub on_usage: Option<Box<dyn FnMut(&SamplingUsage) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_usage: Option<Callback<'a, SamplingUsage>>,
 
         /// Callback invoked once on the last chunk with citations.
         ///
         /// Called after the stream completes, only if the last chunk has non-empty citations.
         /// Receives `&[String]` with all citation URLs from the last chunk.
-        pub on_citations: Option<Box<dyn FnMut(&[String]) -> BoxFuture<'a> + Send + Sync + 'a>>,
+        pub on_citations: Option<Callback<'a, [String]>>,
+    }
+
+    /// A boxed single-argument [`Consumer`] callback.
+    type Callback<'a, A> = Box<dyn FnMut(&A) -> BoxFuture<'a> + Send + Sync + 'a>;
+
+    /// A boxed two-argument [`Consumer`] callback.
+    type Callback2<'a, A, B> = Box<dyn FnMut(&A, &B) -> BoxFuture<'a> + Send + Sync + 'a>;
+
+    /// Chains two optional callbacks for [`Consumer::tee`].
+    fn tee_callback<'a, A: ?Sized + 'a>(
+        first: Option<Callback<'a, A>>,
+        second: Option<Callback<'a, A>>,
+    ) -> Option<Callback<'a, A>> {
+        match (first, second) {
+            (Some(mut first), Some(mut second)) => Some(Box::new(move |a: &A| {
+                let first = first(a);
+                let second = second(a);
+                Box::pin(async move {
+                    first.await;
+                    second.await;
+                })
+            })),
+            (first, second) => first.or(second),
+        }
+    }
+
+    /// Chains two optional two-argument callbacks for [`Consumer::tee`].
+    fn tee_callback2<'a, A: ?Sized + 'a, B: ?Sized + 'a>(
+        first: Option<Callback2<'a, A, B>>,
+        second: Option<Callback2<'a, A, B>>,
+    ) -> Option<Callback2<'a, A, B>> {
+        match (first, second) {
+            (Some(mut first), Some(mut second)) => Some(Box::new(move |a: &A, b: &B| {
+                let first = first(a, b);
+                let second = second(a, b);
+                Box::pin(async move {
+                    first.await;
+                    second.await;
+                })
+            })),
+            (first, second) => first.or(second),
+        }
     }
 
     impl<'a> Consumer<'a> {
@@ -693,6 +4337,8 @@ pub mod stream {
         pub fn new() -> Self {
             Self {
                 on_chunk: None,
+                on_keepalive: None,
+                on_delta: None,
                 on_reasoning_start: None,
                 on_reasoning_token: None,
                 on_reasoning_complete: None,
@@ -761,78 +4407,328 @@ pub mod stream {
         pub fn with_buffered_stdout() -> Consumer<'static> {
             #[derive(Default)]
             struct ChoiceBuffer {
+                index: usize,
                 content: String,
                 reasoning: String,
             }
 
-            let buffers: Arc<Mutex<HashMap<i32, ChoiceBuffer>>> =
-                Arc::new(Mutex::new(HashMap::new()));
-            let finished: Arc<Mutex<HashMap<i32, bool>>> = Arc::new(Mutex::new(HashMap::new()));
+            impl ChoiceHandler for ChoiceBuffer {
+                fn on_reasoning_token(&mut self, token: &str) {
+                    self.reasoning.push_str(token);
+                }
+
+                fn on_content_token(&mut self, token: &str) {
+                    self.content.push_str(token);
+                }
+
+                fn on_complete(&mut self, finish_reason: FinishReason) {
+                    if self.reasoning.is_empty() && self.content.is_empty() {
+                        return;
+                    }
+                    println!("\n--- Output {} ---", self.index);
+                    if !self.reasoning.is_empty() {
+                        println!("Reasoning:\n{}\n", self.reasoning);
+                    }
+                    if !self.content.is_empty() {
+                        println!("Content:\n{}\n", self.content);
+                    }
+                    println!("Finish reason: {}\n", finish_reason as i32);
+                    std::io::stdout().flush().expect("Error flushing stdout");
+                }
+            }
+
+            Consumer::per_choice(|index| ChoiceBuffer {
+                index,
+                ..Default::default()
+            })
+        }
 
-            let buffers_content = buffers.clone();
-            let buffers_reason = buffers.clone();
-            let buffers_chunk = buffers.clone();
-            let finished_clone = finished.clone();
+        /// Creates a `Consumer` that dispatches each output (choice) to its own [`ChoiceHandler`].
+        ///
+        /// `factory` is called with the output index the first time an output appears in the
+        /// stream. Its handler then receives that output's reasoning and content tokens in stream
+        /// order, followed by exactly one [`ChoiceHandler::on_complete`] when the output's finish
+        /// reason arrives, after the tokens of the same chunk. The consumer tracks finished
+        /// outputs itself: a handler is dropped after completion and later deltas for that output
+        /// are ignored. Outputs that never receive a finish reason (e.g. the stream was cut off)
+        /// are not completed.
+        ///
+        /// Dispatch happens in the `on_chunk` callback, so the returned consumer can be extended
+        /// with any callback except `on_chunk`, which would replace the dispatcher.
+        ///
+        /// # Arguments
+        /// * `factory` - Creates the handler for an output, given its index
+        ///
+        /// # Returns
+        /// * `Consumer<'static>` - Consumer that drives one handler per output
+        pub fn per_choice<F, H>(factory: F) -> Consumer<'static>
+        where
+            F: FnMut(usize) -> H + Send + 'static,
+            H: ChoiceHandler + 'static,
+        {
+            struct Dispatch<F, H> {
+                factory: F,
+                handlers: HashMap<i32, H>,
+                finished: HashSet<i32>,
+            }
 
-            let buffers_reason_clone = buffers_reason.clone();
-            let buffers_content_clone = buffers_content.clone();
+            let dispatch = std::sync::Mutex::new(Dispatch {
+                factory,
+                handlers: HashMap::new(),
+                finished: HashSet::new(),
+            });
 
             let mut consumer = Consumer::new_static();
+            consumer.on_chunk(move |chunk: &GetChatCompletionChunk| {
+                let mut guard = dispatch.lock().unwrap_or_else(|e| e.into_inner());
+                let Dispatch {
+                    factory,
+                    handlers,
+                    finished,
+                } = &mut *guard;
+
+                for output in &chunk.outputs {
+                    let index = output.index;
+                    if finished.contains(&index) {
+                        continue;
+                    }
+                    let handler = handlers
+                        .entry(index)
+                        .or_insert_with(|| factory(index.max(0) as usize));
+
+                    if let Some(delta) = &output.delta {
+                        if !delta.reasoning_content.is_empty() {
+                            handler.on_reasoning_token(&delta.reasoning_content);
+                        }
+                        if !delta.content.is_empty() {
+                            handler.on_content_token(&delta.content);
+                        }
+                        if !delta.tool_calls.is_empty() {
+                            handler.on_tool_calls(&delta.tool_calls);
+                        }
+                    }
+
+                    if output.finish_reason != 0 {
+                        if let Some(mut handler) = handlers.remove(&index) {
+                            handler.on_complete(output.finish_reason());
+                        }
+                        finished.insert(index);
+                    }
+                }
+                async {}
+            });
             consumer
-                .on_chunk(move |chunk: &GetChatCompletionChunk| {
-                    let buffers_chunk = buffers_chunk.clone();
-                    let finished_clone = finished_clone.clone();
-                    let outputs = chunk.outputs.clone();
-                    Box::pin(async move {
-                        let mut buffers = buffers_chunk.lock().await;
-                        let mut finished = finished_clone.lock().await;
-
-                        for output in &outputs {
-                            let idx = output.index;
-
-                            // Check if this output just finished
-                            if output.finish_reason != 0 && !finished.contains_key(&idx) {
-                                finished.insert(idx, true);
-
-                                // Print the buffered content for this output
-                                if let Some(output_buf) = buffers.remove(&idx) {
-                                    println!("\n--- Output {idx} ---");
-                                    if !output_buf.reasoning.is_empty() {
-                                        println!("Reasoning:\n{}\n", output_buf.reasoning);
-                                    }
-                                    if !output_buf.content.is_empty() {
-                                        println!("Content:\n{}\n", output_buf.content);
-                                    }
-                                    println!("Finish reason: {}\n", output.finish_reason);
-                                    std::io::stdout().flush().expect("Error flushing stdout");
-                                }
-                            }
+        }
+
+        /// Combines two consumers so that every event reaches both.
+        ///
+        /// For each callback set on both consumers, the combined callback invokes `self`'s
+        /// callback and then `other`'s with the same arguments, and awaits their futures in
+        /// that order. Callbacks set on only one consumer are kept as they are. Useful to, for
+        /// example, print tokens with [`Consumer::with_stdout()`] while also accumulating them
+        /// with [`Consumer::collecting()`].
+        ///
+        /// # Arguments
+        /// * `other` - Consumer whose callbacks run after this one's
+        ///
+        /// # Returns
+        /// * `Consumer<'a>` - Consumer invoking both sets of callbacks
+        pub fn tee(self, other: Consumer<'a>) -> Consumer<'a> {
+            Consumer {
+                on_chunk: tee_callback(self.on_chunk, other.on_chunk),
+                on_keepalive: tee_callback(self.on_keepalive, other.on_keepalive),
+                on_delta: tee_callback2(self.on_delta, other.on_delta),
+                on_reasoning_start: tee_callback(self.on_reasoning_start, other.on_reasoning_start),
+                on_reasoning_token: tee_callback2(
+                    self.on_reasoning_token,
+                    other.on_reasoning_token,
+                ),
+                on_reasoning_complete: tee_callback(
+                    self.on_reasoning_complete,
+                    other.on_reasoning_complete,
+                ),
+                on_content_start: tee_callback(self.on_content_start, other.on_content_start),
+                on_content_token: tee_callback2(self.on_content_token, other.on_content_token),
+                on_content_complete: tee_callback(
+                    self.on_content_complete,
+                    other.on_content_complete,
+                ),
+                on_inline_citations: tee_callback2(
+                    self.on_inline_citations,
+                    other.on_inline_citations,
+                ),
+                on_client_tool_calls: tee_callback2(
+                    self.on_client_tool_calls,
+                    other.on_client_tool_calls,
+                ),
+                on_server_tool_calls: tee_callback2(
+                    self.on_server_tool_calls,
+                    other.on_server_tool_calls,
+                ),
+                on_usage: tee_callback(self.on_usage, other.on_usage),
+                on_citations: tee_callback(self.on_citations, other.on_citations),
+            }
+        }
+
+        /// Creates a [`Consumer`] that collects the content of the first output into a `String`.
+        ///
+        /// Only content tokens of output index 0 are buffered; reasoning is ignored. Read the
+        /// returned buffer after [`process`] finishes to get the complete text without calling
+        /// [`assemble`]. The consumer can be extended with additional callbacks (e.g. to show
+        /// progress), but setting `on_content_token` again replaces the collector.
+        ///
+        /// # Returns
+        /// * `(Consumer<'static>, Arc<Mutex<String>>)` - The consumer and the shared buffer
+        pub fn collecting() -> (Consumer<'static>, Arc<Mutex<String>>) {
+            let text = Arc::new(Mutex::new(String::new()));
+
+            let mut consumer = Consumer::new_static();
+            consumer.on_content_token({
+                let text = text.clone();
+                move |ctx, token| {
+                    let text = text.clone();
+                    let token = (ctx.output_index == 0).then(|| token.to_string());
+                    async move {
+                        if let Some(token) = token {
+                            text.lock().await.push_str(&token);
                         }
-                    })
-                })
-                .on_reasoning_token(move |ctx: &OutputContext, token: &str| {
-                    let output_index = ctx.output_index as i32;
-                    let token = token.to_string();
-                    let buffers_reason = buffers_reason_clone.clone();
-                    Box::pin(async move {
-                        let mut buffers = buffers_reason.lock().await;
-                        let output_buf = buffers.entry(output_index).or_default();
-                        output_buf.reasoning.push_str(&token);
-                    })
-                })
-                .on_content_token(move |ctx: &OutputContext, token: &str| {
-                    let output_index = ctx.output_index as i32;
+                    }
+                }
+            });
+            (consumer, text)
+        }
+
+        /// Creates a [`Consumer`] that collects the content of every output, keyed by output index.
+        ///
+        /// Multi-output counterpart of [`Consumer::collecting()`].
+        ///
+        /// # Returns
+        /// * `(Consumer<'static>, Arc<Mutex<HashMap<usize, String>>>)` - The consumer and the
+        ///   shared per-output buffers
+        pub fn collecting_all() -> (Consumer<'static>, Arc<Mutex<HashMap<usize, String>>>) {
+            let texts = Arc::new(Mutex::new(HashMap::new()));
+
+            let mut consumer = Consumer::new_static();
+            consumer.on_content_token({
+                let texts = texts.clone();
+                move |ctx, token| {
+                    let texts = texts.clone();
+                    let output_index = ctx.output_index;
                     let token = token.to_string();
-                    let buffers_content = buffers_content_clone.clone();
-                    Box::pin(async move {
-                        let mut buffers = buffers_content.lock().await;
-                        let output_buf = buffers.entry(output_index).or_default();
-                        output_buf.content.push_str(&token);
-                    })
-                });
+                    async move {
+                        texts
+                            .lock()
+                            .await
+                            .entry(output_index)
+                            .or_insert_with(String::new)
+                            .push_str(&token);
+                    }
+                }
+            });
+            (consumer, texts)
+        }
+
+        /// Creates a [`Consumer`] that streams the content of one output to an async writer.
+        ///
+        /// Content tokens of output `choice_index` are written to `writer` as UTF-8 and the
+        /// writer is flushed after each one. A chunk carries at most one token per output, so
+        /// this flushes once per chunk and the receiving end (e.g. an HTTP response body)
+        /// sees text as soon as it arrives. Reasoning tokens and other outputs are ignored.
+        ///
+        /// Each write is awaited before the next chunk is read, so a slow writer applies
+        /// backpressure to the stream instead of buffering without bound. The first write
+        /// or flush error (e.g. the client disconnected) stops all further writes; the
+        /// stream itself keeps being processed. The writer is not shut down at the end.
+        ///
+        /// # Arguments
+        /// * `writer` - Destination for the content, such as a socket or response body
+        /// * `choice_index` - Output index whose content is written
+        ///
+        /// # Returns
+        /// * `Consumer<'static>` - Consumer that can be extended with additional callbacks
+        pub fn with_async_writer<W>(writer: W, choice_index: usize) -> Consumer<'static>
+        where
+            W: AsyncWrite + Unpin + Send + 'static,
+        {
+            let writer = Arc::new(std::sync::Mutex::new(writer));
+            let failed = Arc::new(AtomicBool::new(false));
+
+            let mut consumer = Consumer::new_static();
+            consumer.on_content_token(move |ctx, token| {
+                let writer = writer.clone();
+                let failed = failed.clone();
+                let bytes = (ctx.output_index == choice_index).then(|| token.as_bytes().to_vec());
+                async move {
+                    let Some(bytes) = bytes else { return };
+                    if failed.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    if write_and_flush(&writer, &bytes).await.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
             consumer
         }
 
+        /// Creates a [`Consumer`] that appends every stream event to a newline-delimited JSON
+        /// file.
+        ///
+        /// Unlike [`record`], which stores raw chunks for replay, this writes a flat event log
+        /// for analytics tools. Each line is one JSON object with a `ts_ms` field (Unix time in
+        /// milliseconds when the event was handled) and a `type` field:
+        ///
+        /// - `{"ts_ms":..,"type":"chunk","id":"...","model":"...","outputs":1}` - a chunk arrived
+        /// - `{"ts_ms":..,"type":"reasoning_start","index":0}`, likewise `reasoning_complete`,
+        ///   `content_start` and `content_complete` - phase changes of an output
+        /// - `{"ts_ms":..,"type":"reasoning","index":0,"text":"..."}`, likewise `content` - a token
+        /// - `{"ts_ms":..,"type":"tool_calls","index":0,"side":"client","tool_calls":[{"id":"...","type":"client_side_tool","name":"...","arguments":"..."}]}`
+        ///   - `side` is `client` or `server`
+        /// - `{"ts_ms":..,"type":"inline_citations","index":0,"count":2}` - inline citations of
+        ///   an output
+        /// - `{"ts_ms":..,"type":"citations","urls":["..."]}` - final citations
+        /// - `{"ts_ms":..,"type":"usage","prompt_tokens":1,"completion_tokens":2,"reasoning_tokens":0,"total_tokens":3}`
+        ///
+        /// The file is created, or truncated if it exists. Writes go through `tokio::fs`, as in
+        /// [`record`], so the consumer must run within a Tokio runtime. Each line is written and
+        /// flushed before its callback returns, so the log is complete once [`process`] returns.
+        /// The first write error stops all further writes; the stream itself keeps being
+        /// processed.
+        ///
+        /// # Arguments
+        /// * `path` - File to write the log to
+        ///
+        /// # Returns
+        /// * `Ok(Consumer<'static>)` - Consumer that can be extended with additional callbacks
+        /// * `Err(io::Error)` - If the file cannot be created
+        pub fn ndjson_logger(path: impl AsRef<Path>) -> io::Result<Consumer<'static>> {
+            struct Log {
+                file: tokio::fs::File,
+                failed: bool,
+            }
+
+            let log = Arc::new(tokio::sync::Mutex::new(Log {
+                file: tokio::fs::File::from_std(File::create(path)?),
+                failed: false,
+            }));
+
+            Ok(Consumer::forwarding(move |event| {
+                let log = log.clone();
+                let line = ndjson_event(&event);
+                async move {
+                    let Some(line) = line else { return };
+                    let mut log = log.lock().await;
+                    if !log.failed {
+                        let mut result = log.file.write_all(format!("{line}\n").as_bytes()).await;
+                        if result.is_ok() {
+                            result = log.file.flush().await;
+                        }
+                        log.failed = result.is_err();
+                    }
+                }
+            }))
+        }
+
         /// Creates a `Consumer` that forwards all stream activity as [`Event`]s into a [`Sink`].
         ///
         /// Each callback (chunk, reasoning/content phases, tool calls, citations, usage) is
@@ -840,151 +4736,79 @@ pub mod stream {
         /// order as the underlying stream. Use the consumer with [`process`] while draining
         /// the receiver (e.g. with `StreamExt::next()`) to process events.
         ///
-        /// Create the channel yourself (e.g. [`mpsc::unbounded`] or [`mpsc::channel`]) and pass
-        /// the sender as the sink. Bounded senders apply backpressure when the receiver lags.
+        /// Create the channel yourself (e.g. [`futures::channel::mpsc::unbounded`] or
+        /// [`futures::channel::mpsc::channel`]) and pass the sender as the sink. Bounded senders
+        /// apply backpressure when the receiver lags. For a Tokio channel with a spawned
+        /// processing task, see [`into_channel`].
         pub fn with_sink<S>(sink: S) -> Consumer<'static>
         where
             S: Sink<Event> + Clone + Send + Sync + Unpin + 'static,
             S::Error: Send,
         {
             let snk = Arc::new(sink);
+            Consumer::forwarding(move |event| {
+                let mut snd = (*snk).clone();
+                async move {
+                    let _ = snd.send(event).await;
+                }
+            })
+        }
 
+        /// Creates a `Consumer` that translates every callback into an [`Event`] passed to `send`.
+        fn forwarding<F, Fut>(send: F) -> Consumer<'static>
+        where
+            F: Fn(Event) -> Fut + Clone + Send + Sync + 'static,
+            Fut: Future<Output = ()> + Send + Sync + 'static,
+        {
             let mut consumer = Consumer::new_static();
             consumer
                 .on_chunk({
-                    let snd = snk.clone();
-                    move |chunk| {
-                        let snd = snd.clone();
-                        let chunk = chunk.clone();
-                        async move {
-                            let _ = (*snd).clone().send(Event::Chunk(chunk)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |chunk| send(Event::Chunk(chunk.clone()))
                 })
                 .on_reasoning_start({
-                    let snd = snk.clone();
-                    move |ctx| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        async move {
-                            let _ = (*snd).clone().send(Event::ReasoningStart(ctx)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx| send(Event::ReasoningStart(ctx.clone()))
                 })
                 .on_reasoning_token({
-                    let snd = snk.clone();
-                    move |ctx, token| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        let token = token.to_string();
-                        async move {
-                            let _ = (*snd).clone().send(Event::ReasoningToken(ctx, token)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx, token| send(Event::ReasoningToken(ctx.clone(), token.to_string()))
                 })
                 .on_reasoning_complete({
-                    let snd = snk.clone();
-                    move |ctx| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        async move {
-                            let _ = (*snd).clone().send(Event::ReasoningComplete(ctx)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx| send(Event::ReasoningComplete(ctx.clone()))
                 })
                 .on_content_start({
-                    let snd = snk.clone();
-                    move |ctx| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        async move {
-                            let _ = (*snd).clone().send(Event::ContentStart(ctx)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx| send(Event::ContentStart(ctx.clone()))
                 })
                 .on_content_token({
-                    let snd = snk.clone();
-                    move |ctx, token| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        let token = token.to_string();
-                        async move {
-                            let _ = (*snd).clone().send(Event::ContentToken(ctx, token)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx, token| send(Event::ContentToken(ctx.clone(), token.to_string()))
                 })
                 .on_content_complete({
-                    let snd = snk.clone();
-                    move |ctx| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        async move {
-                            let _ = (*snd).clone().send(Event::ContentComplete(ctx)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx| send(Event::ContentComplete(ctx.clone()))
                 })
                 .on_inline_citations({
-                    let snd = snk.clone();
+                    let send = send.clone();
                     move |ctx, citations| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        let citations = citations.to_vec();
-                        async move {
-                            let _ = (*snd)
-                                .clone()
-                                .send(Event::InlineCitations(ctx, citations))
-                                .await;
-                        }
+                        send(Event::InlineCitations(ctx.clone(), citations.to_vec()))
                     }
                 })
                 .on_client_tool_calls({
-                    let snd = snk.clone();
-                    move |ctx, calls| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        let calls = calls.to_vec();
-                        async move {
-                            let _ = (*snd)
-                                .clone()
-                                .send(Event::ClientToolCalls(ctx, calls))
-                                .await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx, calls| send(Event::ClientToolCalls(ctx.clone(), calls.to_vec()))
                 })
                 .on_server_tool_calls({
-                    let snd = snk.clone();
-                    move |ctx, calls| {
-                        let snd = snd.clone();
-                        let ctx = ctx.clone();
-                        let calls = calls.to_vec();
-                        async move {
-                            let _ = (*snd)
-                                .clone()
-                                .send(Event::ServerToolCalls(ctx, calls))
-                                .await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |ctx, calls| send(Event::ServerToolCalls(ctx.clone(), calls.to_vec()))
                 })
                 .on_citations({
-                    let snd = snk.clone();
-                    move |citations| {
-                        let snd = snd.clone();
-                        let citations = citations.to_vec();
-                        async move {
-                            let _ = (*snd).clone().send(Event::Citations(citations)).await;
-                        }
-                    }
+                    let send = send.clone();
+                    move |citations| send(Event::Citations(citations.to_vec()))
                 })
-                .on_usage({
-                    let snd = snk.clone();
-                    move |usage| {
-                        let snd = snd.clone();
-                        let usage = usage.clone();
-                        async move {
-                            let _ = (*snd).clone().send(Event::Usage(Some(usage))).await;
-                        }
-                    }
-                });
+                .on_usage(move |usage| send(Event::Usage(Some(usage.clone()))));
 
             consumer
         }
@@ -999,6 +4823,26 @@ pub mod stream {
             self
         }
 
+        /// Sets the keepalive callback, invoked for chunks without content, reasoning or tool calls.
+        pub fn on_keepalive<F, Fut>(&mut self, mut f: F) -> &mut Self
+        where
+            F: FnMut(&GetChatCompletionChunk) -> Fut + Send + Sync + 'a,
+            Fut: Future<Output = ()> + Send + Sync + 'a,
+        {
+            self.on_keepalive = Some(Box::new(move |chunk| Box::pin(f(chunk))));
+            self
+        }
+
+        /// Sets the delta callback, invoked for every output's delta before its token callbacks.
+        pub fn on_delta<F, Fut>(&mut self, mut f: F) -> &mut Self
+        where
+            F: FnMut(&OutputContext, &Delta) -> Fut + Send + Sync + 'a,
+            Fut: Future<Output = ()> + Send + Sync + 'a,
+        {
+            self.on_delta = Some(Box::new(move |ctx, delta| Box::pin(f(ctx, delta))));
+            self
+        }
+
         /// Sets the reasoning start callback, invoked once when the reasoning phase starts.
         pub fn on_reasoning_start<F, Fut>(&mut self, mut f: F) -> &mut Self
         where
@@ -1109,16 +4953,243 @@ pub mod stream {
             self.on_citations = Some(Box::new(move |citations| Box::pin(f(citations))));
             self
         }
-    }
+    }
+
+    impl<'a> Default for Consumer<'a> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// Writes all of `bytes` to the shared writer, then flushes it.
+    ///
+    /// The lock is only held inside each poll, never across an await, so the returned future
+    /// is `Send + Sync` for any `Send` writer.
+    async fn write_and_flush<W>(writer: &std::sync::Mutex<W>, bytes: &[u8]) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut written = 0;
+        std::future::poll_fn(|cx| {
+            let mut writer = writer
+                .lock()
+                .map_err(|_| io::Error::other("writer lock poisoned"))?;
+            while written < bytes.len() {
+                match std::task::ready!(Pin::new(&mut *writer).poll_write(cx, &bytes[written..]))? {
+                    0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    n => written += n,
+                }
+            }
+            Pin::new(&mut *writer).poll_flush(cx)
+        })
+        .await
+    }
+
+    /// Error returned by [`process_with_idle_timeout`].
+    #[derive(Debug)]
+    pub enum StreamError {
+        /// The underlying stream yielded a gRPC error.
+        Status(Status),
+        /// No chunk arrived within the idle window. Carries every chunk received before the stall.
+        IdleTimeout {
+            /// The idle window that elapsed without a chunk.
+            idle: Duration,
+            /// Chunks received before the stall, in order.
+            chunks: Vec<GetChatCompletionChunk>,
+        },
+    }
+
+    impl std::fmt::Display for StreamError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                StreamError::Status(status) => write!(f, "stream failed: {status}"),
+                StreamError::IdleTimeout { idle, chunks } => write!(
+                    f,
+                    "stream idle for {idle:?} after {} chunk(s)",
+                    chunks.len()
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for StreamError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                StreamError::Status(status) => Some(status),
+                StreamError::IdleTimeout { .. } => None,
+            }
+        }
+    }
+
+    impl From<Status> for StreamError {
+        fn from(status: Status) -> Self {
+            StreamError::Status(status)
+        }
+    }
+
+    /// Error returned by [`assemble_or_err`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum AssembleError {
+        /// The stream ended without delivering any chunk and without reporting an error,
+        /// e.g. because the connection dropped right after the call was accepted.
+        EmptyStream,
+    }
+
+    impl std::fmt::Display for AssembleError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                AssembleError::EmptyStream => write!(f, "stream ended without any chunks"),
+            }
+        }
+    }
+
+    impl std::error::Error for AssembleError {}
+
+    impl From<AssembleError> for Status {
+        fn from(err: AssembleError) -> Self {
+            Status::data_loss(err.to_string())
+        }
+    }
+
+    /// Latency figures of one stream, returned by [`process_with_metrics`].
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct StreamMetrics {
+        /// Time to first token: from the start of processing to the first chunk with
+        /// content or reasoning content. `None` if no token arrived.
+        pub ttft: Option<Duration>,
+        /// Time from the start of processing to the end of the stream.
+        pub total_duration: Duration,
+        /// Generated tokens: completion plus reasoning tokens from the reported usage, or
+        /// the number of deltas with content if the stream reported no usage.
+        pub tokens: u32,
+        /// Generation throughput in tokens per second, measured after the first token
+        /// (`tokens / (total_duration - ttft)`). `None` without a first token or when the
+        /// stream ended at the same instant.
+        pub tps: Option<f64>,
+    }
+
+    /// Outcome of [`process_lenient`]: the chunks that arrived and the error that ended the
+    /// stream, if any.
+    #[derive(Debug)]
+    pub struct StreamResult {
+        /// Every chunk received, in stream order.
+        pub chunks: Vec<GetChatCompletionChunk>,
+        /// The gRPC error that cut the stream short, or `None` if it completed normally.
+        pub error: Option<Status>,
+        /// The last usage reported by any chunk (see [`last_usage`]). Usually `None` for a
+        /// partial stream, since usage is sent at the end.
+        pub usage: Option<SamplingUsage>,
+    }
+
+    impl StreamResult {
+        /// Returns `true` if the stream failed before completing.
+        pub fn is_partial(&self) -> bool {
+            self.error.is_some()
+        }
+
+        /// Assembles the received chunks into a (possibly partial) response.
+        ///
+        /// Returns `None` if no chunk arrived. See [`assemble`].
+        pub fn assemble(&self) -> Option<GetChatCompletionResponse> {
+            assemble(self.chunks.clone())
+        }
+
+        /// Converts into a `Result`, discarding the chunks if the stream failed.
+        ///
+        /// # Returns
+        /// * `Ok(Vec<GetChatCompletionChunk>)` - All chunks, if the stream completed
+        /// * `Err(Status)` - The stream error
+        pub fn into_result(self) -> Result<Vec<GetChatCompletionChunk>, Status> {
+            match self.error {
+                Some(status) => Err(status),
+                None => Ok(self.chunks),
+            }
+        }
+    }
+
+    /// Incremental UTF-8 decoder for byte-level token deltas.
+    ///
+    /// Deltas decoded by the SDK are `String`s, which prost has already validated, so they
+    /// always hold whole characters. Transports that surface raw bytes (custom proxies,
+    /// recorded byte logs) can split a multi-byte character across two deltas; feeding each
+    /// delta through [`decode`](Utf8StreamDecoder::decode) buffers the incomplete trailing
+    /// bytes and only returns complete characters, so a callback such as `on_content_token`
+    /// never sees half a character. Bytes that can never form valid UTF-8 are replaced with
+    /// `U+FFFD`.
+    #[derive(Debug, Clone, Default)]
+    pub struct Utf8StreamDecoder {
+        pending: Vec<u8>,
+    }
+
+    impl Utf8StreamDecoder {
+        /// Creates a decoder with nothing buffered.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Decodes the next delta, returning every character completed by it.
+        ///
+        /// An incomplete character at the end of `bytes` is held back until the next call.
+        /// The result is empty if the delta completes no character.
+        ///
+        /// # Arguments
+        /// * `bytes` - Next chunk of the UTF-8 byte stream
+        ///
+        /// # Returns
+        /// * `String` - The complete characters decoded so far and not yet returned
+        pub fn decode(&mut self, bytes: &[u8]) -> String {
+            self.pending.extend_from_slice(bytes);
+
+            let mut decoded = String::new();
+            let mut rest = self.pending.as_slice();
+            loop {
+                match std::str::from_utf8(rest) {
+                    Ok(valid) => {
+                        decoded.push_str(valid);
+                        rest = &[];
+                        break;
+                    }
+                    Err(err) => {
+                        let (valid, after) = rest.split_at(err.valid_up_to());
+                        decoded.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                        match err.error_len() {
+                            Some(invalid) => {
+                                decoded.push(char::REPLACEMENT_CHARACTER);
+                                rest = &after[invalid..];
+                            }
+                            // Incomplete character at the end: keep it for the next delta
+                            None => {
+                                rest = after;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.pending = rest.to_vec();
+            decoded
+        }
 
-    impl<'a> Default for Consumer<'a> {
-        fn default() -> Self {
-            Self::new()
+        /// Returns true if an incomplete character is buffered.
+        pub fn has_pending(&self) -> bool {
+            !self.pending.is_empty()
+        }
+
+        /// Ends the stream, returning any buffered bytes as `U+FFFD`.
+        ///
+        /// A well-formed stream leaves nothing buffered, so the result is normally empty.
+        pub fn finish(&mut self) -> String {
+            let decoded = String::from_utf8_lossy(&self.pending).into_owned();
+            self.pending.clear();
+            decoded
         }
     }
 
     /// Status of reasoning or content generation phases in streaming responses.
-    #[derive(Clone, Debug, PartialEq)]
+    ///
+    /// Variants are ordered by progress, from `Init` to `Complete`.
+    #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
     pub enum PhaseStatus {
         /// Initial state - the phase has not started yet.
         Init,
@@ -1148,10 +5219,18 @@ pub mod stream {
 
         /// Current status of the content phase for this output.
         pub content_status: PhaseStatus,
+
+        /// Why this output finished, e.g. `ReasonStop`, `ReasonMaxLen` or `ReasonToolCalls`.
+        ///
+        /// Set from the chunk that reports it, so it is available in `on_content_complete`;
+        /// `None` while the output is still generating. Reasoning completes when the first
+        /// content token arrives, before the output finishes, so `on_reasoning_complete`
+        /// usually sees `None`.
+        pub finish_reason: Option<FinishReason>,
     }
 
     impl OutputContext {
-        /// Creates a new `OutputContext` with the specified values.
+        /// Creates a new `OutputContext` with the specified values and no finish reason.
         pub fn new(
             total_outputs: usize,
             output_index: usize,
@@ -1163,6 +5242,7 @@ pub mod stream {
                 output_index,
                 reasoning_status,
                 content_status,
+                finish_reason: None,
             }
         }
     }
@@ -1170,6 +5250,7 @@ pub mod stream {
     #[cfg(test)]
     mod tests {
         use super::*;
+        use crate::xai_api::{CompletionOutputChunk, Delta};
 
         fn stats(
             index: i32,
@@ -1219,56 +5300,784 @@ pub mod stream {
             assert_eq!(c, PhaseStatus::Complete);
         }
 
-        #[test]
-        fn get_output_status_complete_complete_with_tokens() {
-            let s = stats(0, 5, 10, FinishReason::ReasonStop);
-            let (r, c) = get_output_status(&s);
-            assert_eq!(r, PhaseStatus::Complete);
-            assert_eq!(c, PhaseStatus::Complete);
-        }
+        #[test]
+        fn get_output_status_complete_complete_with_tokens() {
+            let s = stats(0, 5, 10, FinishReason::ReasonStop);
+            let (r, c) = get_output_status(&s);
+            assert_eq!(r, PhaseStatus::Complete);
+            assert_eq!(c, PhaseStatus::Complete);
+        }
+
+        #[test]
+        fn get_output_status_tool_calls_only() {
+            let s = stats(0, 0, 0, FinishReason::ReasonToolCalls);
+            let (r, c) = get_output_status(&s);
+            assert_eq!(r, PhaseStatus::Complete);
+            assert_eq!(c, PhaseStatus::Complete);
+        }
+
+        #[test]
+        fn get_output_status_reasoning_done_content_pending() {
+            // Reasoning phase finished, content streaming (not yet finished)
+            let s = stats(0, 5, 10, FinishReason::ReasonInvalid);
+            let (r, c) = get_output_status(&s);
+            assert_eq!(r, PhaseStatus::Complete);
+            assert_eq!(c, PhaseStatus::Pending);
+        }
+
+        #[test]
+        fn get_output_status_reasoning_start() {
+            // First reasoning token, no content yet
+            let s = stats(0, 1, 0, FinishReason::ReasonInvalid);
+            let (r, c) = get_output_status(&s);
+            assert_eq!(r, PhaseStatus::Start);
+            assert_eq!(c, PhaseStatus::Init);
+        }
+
+        #[test]
+        fn get_output_status_content_start() {
+            // First content token (no reasoning), stream not finished
+            let s = stats(0, 0, 1, FinishReason::ReasonInvalid);
+            let (r, c) = get_output_status(&s);
+            assert_eq!(r, PhaseStatus::Complete); // no-reasoning model
+            assert_eq!(c, PhaseStatus::Start);
+        }
+
+        fn chunk_with_delta(
+            delta: Option<Delta>,
+            finish_reason: FinishReason,
+        ) -> GetChatCompletionChunk {
+            GetChatCompletionChunk {
+                outputs: vec![CompletionOutputChunk {
+                    delta,
+                    finish_reason: finish_reason.into(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn is_keepalive_empty_delta() {
+            let chunk = chunk_with_delta(Some(Delta::default()), FinishReason::ReasonInvalid);
+            assert!(is_keepalive(&chunk));
+        }
+
+        #[test]
+        fn is_keepalive_no_outputs() {
+            assert!(is_keepalive(&GetChatCompletionChunk::default()));
+        }
+
+        #[test]
+        fn is_keepalive_content_delta() {
+            let delta = Delta {
+                content: "hi".to_string(),
+                ..Default::default()
+            };
+            let chunk = chunk_with_delta(Some(delta), FinishReason::ReasonInvalid);
+            assert!(!is_keepalive(&chunk));
+        }
+
+        #[test]
+        fn is_keepalive_finish_chunk() {
+            let chunk = chunk_with_delta(None, FinishReason::ReasonStop);
+            assert!(!is_keepalive(&chunk));
+        }
+
+        #[test]
+        fn get_output_status_content_start_with_reasoning() {
+            // First content token after reasoning (r=1, c=1 in same or previous chunk)
+            let s = stats(0, 1, 1, FinishReason::ReasonInvalid);
+            let (r, c) = get_output_status(&s);
+            assert_eq!(r, PhaseStatus::Complete);
+            assert_eq!(c, PhaseStatus::Start);
+        }
+    }
+}
+
+/// High-level progress reporting for streaming responses.
+///
+/// Turns the fine-grained [`Consumer`](stream::Consumer) callbacks into a handful of
+/// [`ProgressEvent`]s ("thinking", "writing", "calling a tool") for status lines and
+/// spinners, without tracking phase flags by hand.
+pub mod progress {
+    use crate::chat::stream::{Consumer, OutputContext};
+    use crate::xai_api::{ToolCall, tool_call};
+    use std::sync::Arc;
+
+    /// A phase transition of one output of a streaming response.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ProgressEvent {
+        /// The output started reasoning.
+        ReasoningStarted {
+            /// Index of the output.
+            output_index: usize,
+        },
+        /// The output finished reasoning.
+        ReasoningDone {
+            /// Index of the output.
+            output_index: usize,
+        },
+        /// The output started writing content.
+        ContentStarted {
+            /// Index of the output.
+            output_index: usize,
+        },
+        /// The output finished writing content.
+        ContentDone {
+            /// Index of the output.
+            output_index: usize,
+        },
+        /// The output called a tool (client- or server-side).
+        ToolCallStarted {
+            /// Index of the output.
+            output_index: usize,
+            /// Name of the called function, or empty if the call carries none.
+            name: String,
+        },
+    }
+
+    /// Receiver of [`ProgressEvent`]s.
+    ///
+    /// Called synchronously from the stream's callbacks, so implementations should return
+    /// quickly (e.g. update a status line or send into a channel).
+    pub trait ProgressSink: Send + Sync {
+        /// Handles one progress event.
+        fn on_progress(&self, event: ProgressEvent);
+    }
+
+    /// A [`ProgressSink`] that prints one status line per event to stderr.
+    ///
+    /// Prints `Thinking...`, `Writing...` and `Calling tool <name>...` when a phase starts,
+    /// and `Done thinking.` when reasoning ends. Lines are prefixed with the output index
+    /// for outputs other than the first.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct StderrProgress;
+
+    impl ProgressSink for StderrProgress {
+        fn on_progress(&self, event: ProgressEvent) {
+            let (output_index, message) = match event {
+                ProgressEvent::ReasoningStarted { output_index } => {
+                    (output_index, "Thinking...".to_string())
+                }
+                ProgressEvent::ReasoningDone { output_index } => {
+                    (output_index, "Done thinking.".to_string())
+                }
+                ProgressEvent::ContentStarted { output_index } => {
+                    (output_index, "Writing...".to_string())
+                }
+                ProgressEvent::ContentDone { .. } => return,
+                ProgressEvent::ToolCallStarted { output_index, name } => {
+                    (output_index, format!("Calling tool {name}..."))
+                }
+            };
+            if output_index == 0 {
+                eprintln!("{message}");
+            } else {
+                eprintln!("[{output_index}] {message}");
+            }
+        }
+    }
+
+    /// Builds [`Consumer`]s that report phase transitions to a [`ProgressSink`].
+    ///
+    /// ```no_run
+    /// use xai_sdk::chat::progress::{ProgressConsumer, StderrProgress};
+    /// use xai_sdk::chat::stream::Consumer;
+    ///
+    /// // Status lines on stderr, tokens on stdout
+    /// let consumer = ProgressConsumer::new(StderrProgress).wrap(Consumer::with_stdout());
+    /// ```
+    #[derive(Clone)]
+    pub struct ProgressConsumer {
+        sink: Arc<dyn ProgressSink>,
+    }
+
+    impl ProgressConsumer {
+        /// Creates a progress reporter sending events to `sink`.
+        pub fn new(sink: impl ProgressSink + 'static) -> Self {
+            Self {
+                sink: Arc::new(sink),
+            }
+        }
+
+        /// Creates a progress reporter from a shared sink.
+        pub fn from_arc(sink: Arc<dyn ProgressSink>) -> Self {
+            Self { sink }
+        }
+
+        /// Returns a consumer that only reports progress.
+        pub fn consumer<'a>(&self) -> Consumer<'a> {
+            let mut consumer = Consumer::new();
+            consumer
+                .on_reasoning_start(self.report(|ctx| ProgressEvent::ReasoningStarted {
+                    output_index: ctx.output_index,
+                }))
+                .on_reasoning_complete(self.report(|ctx| ProgressEvent::ReasoningDone {
+                    output_index: ctx.output_index,
+                }))
+                .on_content_start(self.report(|ctx| ProgressEvent::ContentStarted {
+                    output_index: ctx.output_index,
+                }))
+                .on_content_complete(self.report(|ctx| ProgressEvent::ContentDone {
+                    output_index: ctx.output_index,
+                }))
+                .on_client_tool_calls(self.report_tool_calls())
+                .on_server_tool_calls(self.report_tool_calls());
+            consumer
+        }
+
+        /// Wraps `consumer` so that progress is reported alongside its own callbacks.
+        ///
+        /// Progress events are emitted before the wrapped consumer's callback for the same
+        /// event (see [`Consumer::tee`]).
+        pub fn wrap<'a>(&self, consumer: Consumer<'a>) -> Consumer<'a> {
+            self.consumer().tee(consumer)
+        }
+
+        fn report(
+            &self,
+            event: fn(&OutputContext) -> ProgressEvent,
+        ) -> impl FnMut(&OutputContext) -> std::future::Ready<()> + Send + Sync + 'static {
+            let sink = self.sink.clone();
+            move |ctx| {
+                sink.on_progress(event(ctx));
+                std::future::ready(())
+            }
+        }
+
+        fn report_tool_calls(
+            &self,
+        ) -> impl FnMut(&OutputContext, &[ToolCall]) -> std::future::Ready<()> + Send + Sync + 'static
+        {
+            let sink = self.sink.clone();
+            move |ctx, tool_calls| {
+                for tool_call in tool_calls {
+                    let name = match &tool_call.tool {
+                        Some(tool_call::Tool::Function(function)) => function.name.clone(),
+                        None => String::new(),
+                    };
+                    sink.on_progress(ProgressEvent::ToolCallStarted {
+                        output_index: ctx.output_index,
+                        name,
+                    });
+                }
+                std::future::ready(())
+            }
+        }
+    }
+}
+
+/// Builders for server-side tools.
+///
+/// Provides [`web_search`](tools::web_search) and [`x_search`](tools::x_search), which
+/// expose the options of the `WebSearch` and `XSearch` tools, [`mcp`](tools::mcp) for
+/// remote MCP servers and [`collections_search`](tools::collections_search) for document
+/// collections. All of them validate the configuration before the request is sent.
+///
+/// Server-side calls are read back with
+/// [`ToolCall::as_code_execution`](crate::xai_api::ToolCall::as_code_execution) and
+/// [`ToolCall::as_collections_search`](crate::xai_api::ToolCall::as_collections_search),
+/// which parse the call's arguments only. The API documents the output included with
+/// `IncludeOption::CodeExecutionCallOutput` and `IncludeOption::CollectionsSearchCallOutput`
+/// as plaintext, but not its format or which field carries it, so there is no typed
+/// accessor for tool output.
+pub mod tools {
+    use crate::xai_api::{
+        CollectionsSearch, Mcp, Tool, ToolCall, ToolCallType, WebSearch, WebSearchUserLocation,
+        XSearch, tool, tool_call,
+    };
+    use serde_json::Value;
+    use std::fmt;
+
+    /// Re-exported so callers can build date ranges without depending on `prost-types`.
+    pub use prost_types::Timestamp;
+
+    /// Maximum number of allowed or excluded domains accepted by the web search tool.
+    pub const MAX_WEB_SEARCH_DOMAINS: usize = 5;
+
+    /// Maximum number of collections searched by one collections search tool.
+    pub const MAX_SEARCH_COLLECTIONS: usize = 10;
+
+    /// Error returned when a search tool builder holds an invalid configuration.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ToolBuildError {
+        /// `from_date` is later than `to_date`.
+        InvalidDateRange {
+            /// Start of the date range.
+            from: Timestamp,
+            /// End of the date range.
+            to: Timestamp,
+        },
+        /// An allow list and a block list were both set; the API accepts only one.
+        ConflictingFilters {
+            /// Name of the allow list field.
+            allowed: &'static str,
+            /// Name of the block list field.
+            excluded: &'static str,
+        },
+        /// More domains were given than the API accepts.
+        TooManyDomains {
+            /// Name of the domain list field.
+            field: &'static str,
+            /// Number of domains given.
+            count: usize,
+        },
+        /// A server URL is not an absolute `http` or `https` URL with a host.
+        InvalidUrl {
+            /// The rejected URL.
+            url: String,
+            /// Why the URL was rejected.
+            reason: &'static str,
+        },
+        /// A collections search lists no collections, or more than the API accepts.
+        InvalidCollections {
+            /// Number of collection ids given.
+            count: usize,
+        },
+        /// A collections search asks for zero chunks, or more than fit the request.
+        InvalidTopK {
+            /// The rejected number of chunks.
+            top_k: u32,
+        },
+    }
+
+    impl fmt::Display for ToolBuildError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ToolBuildError::InvalidDateRange { from, to } => {
+                    write!(f, "from_date {from} is after to_date {to}")
+                }
+                ToolBuildError::ConflictingFilters { allowed, excluded } => {
+                    write!(f, "`{allowed}` and `{excluded}` cannot be set together")
+                }
+                ToolBuildError::TooManyDomains { field, count } => write!(
+                    f,
+                    "`{field}` has {count} entries, at most {MAX_WEB_SEARCH_DOMAINS} are allowed"
+                ),
+                ToolBuildError::InvalidUrl { url, reason } => {
+                    write!(f, "invalid server URL `{url}`: {reason}")
+                }
+                ToolBuildError::InvalidCollections { count } => write!(
+                    f,
+                    "collections search needs 1 to {MAX_SEARCH_COLLECTIONS} collection ids, got {count}"
+                ),
+                ToolBuildError::InvalidTopK { top_k } => {
+                    write!(f, "collections search top_k must be positive, got {top_k}")
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for ToolBuildError {}
+
+    /// Starts building a web search tool.
+    pub fn web_search() -> WebSearchBuilder {
+        WebSearchBuilder::default()
+    }
+
+    /// Starts building an X search tool.
+    pub fn x_search() -> XSearchBuilder {
+        XSearchBuilder::default()
+    }
+
+    /// How the API authenticates against a remote MCP server.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum McpAuth {
+        /// Sends `Authorization: Bearer <token>`.
+        Bearer(String),
+        /// Sends the value as the `Authorization` header verbatim, e.g. `Basic ...`.
+        Authorization(String),
+        /// Sends these headers, e.g. a custom API key header.
+        Headers(Vec<(String, String)>),
+    }
+
+    /// Builds a tool that connects the model to a remote MCP server.
+    ///
+    /// The API calls the server on the model's behalf, so the URL must be reachable from
+    /// xAI's servers, not just from the caller. Use `https` for anything but local testing,
+    /// as credentials in `auth` are sent with every call.
+    ///
+    /// ```
+    /// use xai_sdk::chat::tools::{self, McpAuth};
+    ///
+    /// let tool = tools::mcp(
+    ///     "https://mcp.example.com/sse",
+    ///     vec!["search_issues".to_string()],
+    ///     Some(McpAuth::Bearer("token".to_string())),
+    /// )
+    /// .unwrap();
+    /// ```
+    ///
+    /// # Arguments
+    /// * `server_url` - Absolute `http` or `https` URL of the MCP server
+    /// * `allowed_tools` - Names of the server's tools the model may call; empty allows all
+    /// * `auth` - Credentials for the server, if it requires any
+    ///
+    /// # Returns
+    /// * `Ok(Tool)` - MCP tool ready to be added to a request
+    /// * `Err(ToolBuildError)` - The URL is invalid
+    pub fn mcp(
+        server_url: impl Into<String>,
+        allowed_tools: Vec<String>,
+        auth: Option<McpAuth>,
+    ) -> Result<Tool, ToolBuildError> {
+        let server_url = server_url.into();
+        validate_server_url(&server_url)?;
+
+        let mut inner = Mcp {
+            server_url,
+            allowed_tool_names: allowed_tools,
+            ..Default::default()
+        };
+        match auth {
+            Some(McpAuth::Bearer(token)) => inner.authorization = Some(format!("Bearer {token}")),
+            Some(McpAuth::Authorization(value)) => inner.authorization = Some(value),
+            Some(McpAuth::Headers(headers)) => inner.extra_headers = headers.into_iter().collect(),
+            None => {}
+        }
+        Ok(Tool {
+            tool: Some(tool::Tool::Mcp(inner)),
+        })
+    }
+
+    /// Builds a tool that lets the model search document collections.
+    ///
+    /// Collections are managed with the documents service (see [`crate::documents`]). The
+    /// matching chunks are returned to the model; to also receive the search output in the
+    /// response, add `IncludeOption::CollectionsSearchCallOutput` to the request and inspect
+    /// the calls with [`ToolCall::as_collections_search`].
+    ///
+    /// # Arguments
+    /// * `collection_ids` - Collections to search, 1 to [`MAX_SEARCH_COLLECTIONS`]
+    /// * `top_k` - Number of chunks to return per search
+    ///
+    /// # Returns
+    /// * `Ok(Tool)` - Collections search tool ready to be added to a request
+    /// * `Err(ToolBuildError)` - The collection count or `top_k` is out of range
+    pub fn collections_search(
+        collection_ids: Vec<String>,
+        top_k: u32,
+    ) -> Result<Tool, ToolBuildError> {
+        if collection_ids.is_empty() || collection_ids.len() > MAX_SEARCH_COLLECTIONS {
+            return Err(ToolBuildError::InvalidCollections {
+                count: collection_ids.len(),
+            });
+        }
+        let limit = i32::try_from(top_k)
+            .ok()
+            .filter(|limit| *limit > 0)
+            .ok_or(ToolBuildError::InvalidTopK { top_k })?;
+        Ok(Tool {
+            tool: Some(tool::Tool::CollectionsSearch(CollectionsSearch {
+                collection_ids,
+                limit: Some(limit),
+                ..Default::default()
+            })),
+        })
+    }
+
+    fn validate_server_url(url: &str) -> Result<(), ToolBuildError> {
+        let invalid = |reason| {
+            Err(ToolBuildError::InvalidUrl {
+                url: url.to_string(),
+                reason,
+            })
+        };
+        let Some(rest) = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+        else {
+            return invalid("expected an http:// or https:// URL");
+        };
+        if url.chars().any(char::is_whitespace) {
+            return invalid("URL contains whitespace");
+        }
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let host = authority.rsplit('@').next().unwrap_or_default();
+        if host.is_empty() || host.starts_with(':') {
+            return invalid("URL has no host");
+        }
+        Ok(())
+    }
+
+    /// Fluent builder for the server-side web search tool.
+    #[derive(Debug, Clone, Default)]
+    pub struct WebSearchBuilder {
+        inner: WebSearch,
+    }
+
+    impl WebSearchBuilder {
+        /// Restricts results to these domains (without protocol or subdomains).
+        pub fn allowed_domains<I, S>(&mut self, domains: I) -> &mut Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+            self.inner.allowed_domains = domains.into_iter().map(Into::into).collect();
+            self
+        }
+
+        /// Excludes these domains (without protocol or subdomains) from results.
+        pub fn excluded_domains<I, S>(&mut self, domains: I) -> &mut Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+            self.inner.excluded_domains = domains.into_iter().map(Into::into).collect();
+            self
+        }
+
+        /// Enables image understanding in downstream tools.
+        pub fn enable_image_understanding(&mut self, enable: bool) -> &mut Self {
+            self.inner.enable_image_understanding = Some(enable);
+            self
+        }
+
+        /// Prefers results for a country (two-letter ISO 3166-1 alpha-2 code, e.g. `"US"`).
+        pub fn country(&mut self, country: impl Into<String>) -> &mut Self {
+            self.location().country = Some(country.into());
+            self
+        }
+
+        /// Prefers results for a city.
+        pub fn city(&mut self, city: impl Into<String>) -> &mut Self {
+            self.location().city = Some(city.into());
+            self
+        }
+
+        /// Prefers results for a region.
+        pub fn region(&mut self, region: impl Into<String>) -> &mut Self {
+            self.location().region = Some(region.into());
+            self
+        }
+
+        /// Sets the user's IANA timezone (e.g. `"Europe/London"`).
+        pub fn timezone(&mut self, timezone: impl Into<String>) -> &mut Self {
+            self.location().timezone = Some(timezone.into());
+            self
+        }
+
+        fn location(&mut self) -> &mut WebSearchUserLocation {
+            self.inner
+                .user_location
+                .get_or_insert_with(Default::default)
+        }
+
+        /// Validates the configuration and returns the tool.
+        ///
+        /// # Returns
+        /// * `Ok(Tool)` - Web search tool ready to be added to a request
+        /// * `Err(ToolBuildError)` - Both domain lists are set, or one has too many entries
+        pub fn build(&self) -> Result<Tool, ToolBuildError> {
+            let WebSearch {
+                allowed_domains,
+                excluded_domains,
+                ..
+            } = &self.inner;
+            if !allowed_domains.is_empty() && !excluded_domains.is_empty() {
+                return Err(ToolBuildError::ConflictingFilters {
+                    allowed: "allowed_domains",
+                    excluded: "excluded_domains",
+                });
+            }
+            for (field, domains) in [
+                ("allowed_domains", allowed_domains),
+                ("excluded_domains", excluded_domains),
+            ] {
+                if domains.len() > MAX_WEB_SEARCH_DOMAINS {
+                    return Err(ToolBuildError::TooManyDomains {
+                        field,
+                        count: domains.len(),
+                    });
+                }
+            }
+            Ok(Tool {
+                tool: Some(tool::Tool::WebSearch(self.inner.clone())),
+            })
+        }
+    }
+
+    /// Fluent builder for the server-side X search tool.
+    #[derive(Debug, Clone, Default)]
+    pub struct XSearchBuilder {
+        inner: XSearch,
+    }
+
+    impl XSearchBuilder {
+        /// Only considers posts after this date.
+        ///
+        /// Use `Timestamp::date(year, month, day)` for calendar dates.
+        pub fn from_date(&mut self, from: impl Into<Timestamp>) -> &mut Self {
+            self.inner.from_date = Some(from.into());
+            self
+        }
+
+        /// Only considers posts before this date.
+        pub fn to_date(&mut self, to: impl Into<Timestamp>) -> &mut Self {
+            self.inner.to_date = Some(to.into());
+            self
+        }
+
+        /// Sets both ends of the search window.
+        pub fn date_range(
+            &mut self,
+            from: impl Into<Timestamp>,
+            to: impl Into<Timestamp>,
+        ) -> &mut Self {
+            self.from_date(from).to_date(to)
+        }
+
+        /// Restricts results to posts from these handles (without `@`).
+        pub fn allowed_x_handles<I, S>(&mut self, handles: I) -> &mut Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+            self.inner.allowed_x_handles = handles.into_iter().map(Into::into).collect();
+            self
+        }
+
+        /// Excludes posts from these handles (without `@`).
+        pub fn excluded_x_handles<I, S>(&mut self, handles: I) -> &mut Self
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<String>,
+        {
+            self.inner.excluded_x_handles = handles.into_iter().map(Into::into).collect();
+            self
+        }
+
+        /// Enables image understanding in downstream tools.
+        pub fn enable_image_understanding(&mut self, enable: bool) -> &mut Self {
+            self.inner.enable_image_understanding = Some(enable);
+            self
+        }
+
+        /// Enables video understanding in downstream tools.
+        pub fn enable_video_understanding(&mut self, enable: bool) -> &mut Self {
+            self.inner.enable_video_understanding = Some(enable);
+            self
+        }
+
+        /// Validates the configuration and returns the tool.
+        ///
+        /// # Returns
+        /// * `Ok(Tool)` - X search tool ready to be added to a request
+        /// * `Err(ToolBuildError)` - `from_date` is after `to_date`, or both handle lists are set
+        pub fn build(&self) -> Result<Tool, ToolBuildError> {
+            if let (Some(from), Some(to)) = (&self.inner.from_date, &self.inner.to_date)
+                && (from.seconds, from.nanos) > (to.seconds, to.nanos)
+            {
+                return Err(ToolBuildError::InvalidDateRange {
+                    from: *from,
+                    to: *to,
+                });
+            }
+            if !self.inner.allowed_x_handles.is_empty() && !self.inner.excluded_x_handles.is_empty()
+            {
+                return Err(ToolBuildError::ConflictingFilters {
+                    allowed: "allowed_x_handles",
+                    excluded: "excluded_x_handles",
+                });
+            }
+            Ok(Tool {
+                tool: Some(tool::Tool::XSearch(self.inner.clone())),
+            })
+        }
+    }
+
+    /// The arguments of a server-side code execution call.
+    ///
+    /// Read from the `FunctionCall::arguments` of a `ToolCallType::CodeExecutionTool` call.
+    /// A failed call carries `ToolCall::error_message`.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct CodeExecutionCall {
+        /// Source code the model executed; empty if the arguments have none.
+        pub code: String,
+        /// `FunctionCall::arguments` exactly as received.
+        pub arguments: String,
+    }
 
-        #[test]
-        fn get_output_status_tool_calls_only() {
-            let s = stats(0, 0, 0, FinishReason::ReasonToolCalls);
-            let (r, c) = get_output_status(&s);
-            assert_eq!(r, PhaseStatus::Complete);
-            assert_eq!(c, PhaseStatus::Complete);
+    impl CodeExecutionCall {
+        /// Reads the code from the JSON arguments of a code execution call.
+        ///
+        /// Arguments that are not a JSON object are taken verbatim as the code.
+        pub fn from_arguments(arguments: &str) -> Self {
+            Self {
+                code: string_argument(arguments, "code"),
+                arguments: arguments.to_string(),
+            }
         }
+    }
 
-        #[test]
-        fn get_output_status_reasoning_done_content_pending() {
-            // Reasoning phase finished, content streaming (not yet finished)
-            let s = stats(0, 5, 10, FinishReason::ReasonInvalid);
-            let (r, c) = get_output_status(&s);
-            assert_eq!(r, PhaseStatus::Complete);
-            assert_eq!(c, PhaseStatus::Pending);
+    /// The arguments of a server-side collections search call.
+    ///
+    /// Read from the `FunctionCall::arguments` of a `ToolCallType::CollectionsSearchTool` call.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct CollectionsSearchCall {
+        /// Query the model searched for; empty if the arguments have none.
+        pub query: String,
+        /// `FunctionCall::arguments` exactly as received.
+        pub arguments: String,
+    }
+
+    impl CollectionsSearchCall {
+        /// Reads the query from the JSON arguments of a collections search call.
+        ///
+        /// Arguments that are not a JSON object are taken verbatim as the query.
+        pub fn from_arguments(arguments: &str) -> Self {
+            Self {
+                query: string_argument(arguments, "query"),
+                arguments: arguments.to_string(),
+            }
         }
+    }
 
-        #[test]
-        fn get_output_status_reasoning_start() {
-            // First reasoning token, no content yet
-            let s = stats(0, 1, 0, FinishReason::ReasonInvalid);
-            let (r, c) = get_output_status(&s);
-            assert_eq!(r, PhaseStatus::Start);
-            assert_eq!(c, PhaseStatus::Init);
+    /// Reads the string member `key` of JSON object `arguments`; arguments that are not a
+    /// JSON object are returned verbatim.
+    fn string_argument(arguments: &str, key: &str) -> String {
+        match serde_json::from_str::<Value>(arguments) {
+            Ok(object @ Value::Object(_)) => object
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            _ => arguments.to_string(),
         }
+    }
 
-        #[test]
-        fn get_output_status_content_start() {
-            // First content token (no reasoning), stream not finished
-            let s = stats(0, 0, 1, FinishReason::ReasonInvalid);
-            let (r, c) = get_output_status(&s);
-            assert_eq!(r, PhaseStatus::Complete); // no-reasoning model
-            assert_eq!(c, PhaseStatus::Start);
+    impl ToolCall {
+        /// Returns the parsed arguments of a server-side collections search call.
+        ///
+        /// # Returns
+        /// * `Some(CollectionsSearchCall)` - If this is a `CollectionsSearchTool` call with a
+        ///   function payload
+        /// * `None` - For any other tool call
+        pub fn as_collections_search(&self) -> Option<CollectionsSearchCall> {
+            if self.r#type != ToolCallType::CollectionsSearchTool as i32 {
+                return None;
+            }
+            let Some(tool_call::Tool::Function(function)) = &self.tool else {
+                return None;
+            };
+            Some(CollectionsSearchCall::from_arguments(&function.arguments))
         }
 
-        #[test]
-        fn get_output_status_content_start_with_reasoning() {
-            // First content token after reasoning (r=1, c=1 in same or previous chunk)
-            let s = stats(0, 1, 1, FinishReason::ReasonInvalid);
-            let (r, c) = get_output_status(&s);
-            assert_eq!(r, PhaseStatus::Complete);
-            assert_eq!(c, PhaseStatus::Start);
+        /// Returns the parsed arguments of a server-side code execution call.
+        ///
+        /// # Returns
+        /// * `Some(CodeExecutionCall)` - If this is a `CodeExecutionTool` call with a function
+        ///   payload
+        /// * `None` - For any other tool call
+        pub fn as_code_execution(&self) -> Option<CodeExecutionCall> {
+            if self.r#type != ToolCallType::CodeExecutionTool as i32 {
+                return None;
+            }
+            let Some(tool_call::Tool::Function(function)) = &self.tool else {
+                return None;
+            };
+            Some(CodeExecutionCall::from_arguments(&function.arguments))
         }
     }
 }
@@ -1293,21 +6102,392 @@ pub mod traits {
     }
 }
 
+/// Helpers for assembling request messages.
+pub mod message {
+    use crate::xai_api::{Content, ImageDetail, ImageUrlContent, Message, MessageRole, content};
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use std::fmt;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    /// Error returned by [`named`] for a name the API would reject.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum NameError {
+        /// Only user messages may carry a name.
+        RoleNotUser {
+            /// The role that was given.
+            role: MessageRole,
+        },
+        /// The name is empty, blank or contains control characters.
+        InvalidName {
+            /// The rejected name.
+            name: String,
+        },
+    }
+
+    impl fmt::Display for NameError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                NameError::RoleNotUser { role } => {
+                    write!(f, "only user messages can have a name, got role {role}")
+                }
+                NameError::InvalidName { name } => write!(
+                    f,
+                    "invalid message name {name:?}: must be non-blank without control characters"
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for NameError {}
+
+    /// Builds a message with a participant name and a single text part.
+    ///
+    /// Names label who said what in multi-party conversations (e.g. several users or agents
+    /// speaking as users). The API only accepts a name on user messages, so other roles are
+    /// rejected here instead of by the server. The name must not be blank or contain control
+    /// characters; it is sent as given.
+    ///
+    /// # Arguments
+    /// * `role` - Message role; must be `RoleUser`
+    /// * `name` - Participant name
+    /// * `content` - Message text
+    ///
+    /// # Returns
+    /// * `Ok(Message)` - The named message
+    /// * `Err(NameError)` - The role cannot carry a name, or the name is invalid
+    pub fn named(
+        role: MessageRole,
+        name: impl Into<String>,
+        content: impl Into<String>,
+    ) -> Result<Message, NameError> {
+        if role != MessageRole::RoleUser {
+            return Err(NameError::RoleNotUser { role });
+        }
+        let name = name.into();
+        if name.trim().is_empty() || name.chars().any(char::is_control) {
+            return Err(NameError::InvalidName { name });
+        }
+        let mut message = Message::from((role, content.into()));
+        message.name = name;
+        Ok(message)
+    }
+
+    impl Message {
+        /// Returns the participant name of the message, or `None` if it has none.
+        pub fn name(&self) -> Option<&str> {
+            (!self.name.is_empty()).then_some(self.name.as_str())
+        }
+    }
+
+    /// Merges consecutive system messages into one, joining their text with newlines.
+    ///
+    /// Equivalent to [`merge_system_with`] using `"\n"` as the separator.
+    ///
+    /// # Arguments
+    /// * `messages` - Messages in conversation order
+    ///
+    /// # Returns
+    /// * `Vec<Message>` - The messages with each run of system messages merged
+    pub fn merge_system(messages: Vec<Message>) -> Vec<Message> {
+        merge_system_with(messages, "\n")
+    }
+
+    /// Merges consecutive system messages into one, joining their text with `separator`.
+    ///
+    /// Some models only honor the first system message, so prompts composed from several
+    /// fragments are best sent as a single one. Each run of adjacent system messages becomes
+    /// one message holding the run's text parts joined by `separator`, followed by any
+    /// non-text parts in their original order; the remaining fields are taken from the first
+    /// message of the run. Messages of other roles, and the overall order, are unchanged.
+    ///
+    /// # Arguments
+    /// * `messages` - Messages in conversation order
+    /// * `separator` - Text placed between the merged fragments
+    ///
+    /// # Returns
+    /// * `Vec<Message>` - The messages with each run of system messages merged
+    pub fn merge_system_with(messages: Vec<Message>, separator: &str) -> Vec<Message> {
+        let system: i32 = MessageRole::RoleSystem.into();
+        let mut merged: Vec<Message> = Vec::with_capacity(messages.len());
+        let mut run: Vec<Message> = Vec::new();
+
+        for message in messages {
+            if message.role == system {
+                run.push(message);
+                continue;
+            }
+            if !run.is_empty() {
+                merged.push(merge_run(std::mem::take(&mut run), separator));
+            }
+            merged.push(message);
+        }
+        if !run.is_empty() {
+            merged.push(merge_run(run, separator));
+        }
+        merged
+    }
+
+    /// Reads an image from disk and returns it as base64-encoded image content.
+    ///
+    /// The MIME type is detected from the file's magic bytes, falling back to its extension
+    /// when the bytes are not recognized. The API accepts PNG, JPEG and WebP images; any
+    /// other format is rejected before the file is encoded.
+    ///
+    /// # Arguments
+    /// * `path` - Path to a PNG, JPEG or WebP file
+    /// * `detail` - Pre-processing resolution the model should use
+    ///
+    /// # Returns
+    /// * `Ok(Content)` - Image content holding a `data:<mime>;base64,...` URL
+    /// * `Err(io::Error)` - The file could not be read, or `InvalidData` if it is not a
+    ///   supported image format
+    pub fn image_file(path: impl AsRef<Path>, detail: ImageDetail) -> io::Result<Content> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let mime = image_mime_type(&bytes)
+            .or_else(|| {
+                let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+                match extension.as_str() {
+                    "png" => Some("image/png"),
+                    "jpg" | "jpeg" => Some("image/jpeg"),
+                    "webp" => Some("image/webp"),
+                    _ => None,
+                }
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "unsupported image format: {} (expected PNG, JPEG or WebP)",
+                        path.display()
+                    ),
+                )
+            })?;
+
+        Ok(Content {
+            content: Some(content::Content::ImageUrl(ImageUrlContent {
+                image_url: format!("data:{mime};base64,{}", STANDARD.encode(&bytes)),
+                detail: detail.into(),
+            })),
+        })
+    }
+
+    /// Returns the MIME type of a supported image from its magic bytes.
+    fn image_mime_type(bytes: &[u8]) -> Option<&'static str> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some("image/png")
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some("image/jpeg")
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some("image/webp")
+        } else {
+            None
+        }
+    }
+
+    /// Collapses a non-empty run of system messages into one message.
+    fn merge_run(mut run: Vec<Message>, separator: &str) -> Message {
+        if run.len() == 1 {
+            return run.remove(0);
+        }
+
+        let mut texts = Vec::with_capacity(run.len());
+        let mut other_parts = Vec::new();
+        for message in &mut run {
+            let mut text = String::new();
+            for part in message.content.drain(..) {
+                match part.content {
+                    Some(content::Content::Text(fragment)) => text.push_str(&fragment),
+                    _ => other_parts.push(part),
+                }
+            }
+            texts.push(text);
+        }
+
+        let mut first = run.swap_remove(0);
+        first.content = std::iter::once(Content {
+            content: Some(content::Content::Text(texts.join(separator))),
+        })
+        .chain(other_parts)
+        .collect();
+        first
+    }
+}
+
+/// Human-readable transcripts of conversations.
+///
+/// Provides [`render`](transcript::render) for compact `ROLE: content` logs and
+/// [`render_markdown`](transcript::render_markdown) for documents with a heading per
+/// message. Images and files are shown as `[image]` and `[file <id>]` placeholders, tool
+/// calls as `[tool call name(arguments)]`. Reasoning and encrypted content are left out.
+pub mod transcript {
+    use crate::xai_api::{Message, MessageRole, ToolCall, content, tool_call};
+    use std::fmt::Write;
+
+    /// Renders messages as a plain-text transcript, one `ROLE: content` block per message.
+    ///
+    /// The role is followed by the sender name, or the answered tool call id for tool
+    /// messages, e.g. `USER (alice): Hi` or `TOOL (call_1): 21°C`. Content parts are joined
+    /// with spaces and tool calls follow the content.
+    ///
+    /// # Arguments
+    /// * `messages` - Conversation to render
+    ///
+    /// # Returns
+    /// * `String` - The transcript, messages separated by newlines
+    pub fn render(messages: &[Message]) -> String {
+        let mut out = String::new();
+        for (i, message) in messages.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&message.role().to_string().to_uppercase());
+            if let Some(qualifier) = qualifier(message) {
+                let _ = write!(out, " ({qualifier})");
+            }
+            out.push(':');
+            for part in parts(message) {
+                out.push(' ');
+                out.push_str(part.text());
+            }
+        }
+        out
+    }
+
+    /// Renders messages as Markdown, with a `###` heading per message.
+    ///
+    /// Text is copied verbatim; placeholders and tool calls are rendered as inline code.
+    ///
+    /// # Arguments
+    /// * `messages` - Conversation to render
+    ///
+    /// # Returns
+    /// * `String` - The transcript, messages separated by blank lines
+    pub fn render_markdown(messages: &[Message]) -> String {
+        let mut out = String::new();
+        for (i, message) in messages.iter().enumerate() {
+            if i > 0 {
+                out.push_str("\n\n");
+            }
+            let role = message.role().to_string();
+            let mut chars = role.chars();
+            let heading: String = chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default();
+            let _ = write!(out, "### {heading}");
+            if let Some(qualifier) = qualifier(message) {
+                let _ = write!(out, " ({qualifier})");
+            }
+            for part in parts(message) {
+                out.push_str("\n\n");
+                match part {
+                    Part::Text(text) => out.push_str(&text),
+                    Part::Placeholder(placeholder) => {
+                        let _ = write!(out, "`{placeholder}`");
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn qualifier(message: &Message) -> Option<&str> {
+        match message.role() {
+            MessageRole::RoleTool => message.tool_call_id.as_deref(),
+            _ => Some(message.name.as_str()),
+        }
+        .filter(|qualifier| !qualifier.is_empty())
+    }
+
+    enum Part {
+        Text(String),
+        Placeholder(String),
+    }
+
+    impl Part {
+        fn text(&self) -> &str {
+            match self {
+                Part::Text(text) | Part::Placeholder(text) => text,
+            }
+        }
+    }
+
+    /// Text parts and placeholders of a message, in order, followed by its tool calls.
+    fn parts(message: &Message) -> Vec<Part> {
+        let content = message
+            .content
+            .iter()
+            .filter_map(|part| match &part.content {
+                Some(content::Content::Text(text)) => Some(Part::Text(text.clone())),
+                Some(content::Content::ImageUrl(_)) => {
+                    Some(Part::Placeholder("[image]".to_string()))
+                }
+                Some(content::Content::File(file)) => {
+                    Some(Part::Placeholder(format!("[file {}]", file.file_id)))
+                }
+                None => None,
+            });
+        content
+            .chain(
+                message
+                    .tool_calls
+                    .iter()
+                    .map(|call| Part::Placeholder(tool_call_placeholder(call))),
+            )
+            .collect()
+    }
+
+    fn tool_call_placeholder(call: &ToolCall) -> String {
+        match &call.tool {
+            Some(tool_call::Tool::Function(function)) => {
+                format!("[tool call {}({})]", function.name, function.arguments)
+            }
+            None => format!("[tool call {}]", call.id),
+        }
+    }
+}
+
 /// General utilities for chat related functionality.
 ///
 /// Provides utilities for converting completion outputs to messages and related chat operations.
 pub mod utils {
     use crate::xai_api::{CompletionOutput, Content, Message, MessageRole, content};
 
+    impl From<String> for Content {
+        /// Wraps the text in a `Content::Text` part.
+        fn from(text: String) -> Self {
+            Content {
+                content: Some(content::Content::Text(text)),
+            }
+        }
+    }
+
+    impl From<&str> for Content {
+        /// Wraps the text in a `Content::Text` part.
+        fn from(text: &str) -> Self {
+            Content::from(text.to_string())
+        }
+    }
+
     impl From<(MessageRole, &str)> for Message {
-        fn from(value: (MessageRole, &str)) -> Self {
-            todo!()
+        /// Builds a message with the given role and a single text part.
+        fn from((role, text): (MessageRole, &str)) -> Self {
+            Message::from((role, text.to_string()))
         }
     }
 
     impl From<(MessageRole, String)> for Message {
-        fn from(value: (MessageRole, String)) -> Self {
-            todo!()
+        /// Builds a message with the given role and a single text part.
+        fn from((role, text): (MessageRole, String)) -> Self {
+            Message {
+                role: role.into(),
+                content: vec![Content::from(text)],
+                ..Default::default()
+            }
         }
     }
 
@@ -1343,3 +6523,471 @@ pub mod utils {
         messages
     }
 }
+
+/// Local request validation for chat completions.
+///
+/// Provides checks that catch common request mistakes before they are sent to the API,
+/// returning a descriptive [`ValidationError`] instead of an opaque server error.
+pub mod validate {
+    use crate::models::ModelId;
+    use crate::xai_api::{
+        FormatType, GetCompletionsRequest, IncludeOption, MessageRole, tool, tool_choice,
+    };
+    use serde_json::Value;
+    use std::collections::HashSet;
+    use std::fmt;
+
+    /// Maximum number of stop sequences accepted by the API.
+    pub const MAX_STOP_SEQUENCES: usize = 8;
+
+    /// Maximum number of alternatives per token accepted in `top_logprobs`.
+    pub const MAX_TOP_LOGPROBS: i32 = 8;
+
+    /// Error returned when a request fails local validation.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ValidationError {
+        /// A tool result message (`RoleTool`) has no `tool_call_id`.
+        MissingToolCallId {
+            /// Index of the offending message in `messages`.
+            message_index: usize,
+        },
+        /// A tool result message references a tool call id that no preceding assistant
+        /// message issued.
+        UnknownToolCallId {
+            /// Index of the offending message in `messages`.
+            message_index: usize,
+            /// The dangling tool call id.
+            tool_call_id: String,
+        },
+        /// A numeric sampling parameter is outside its documented range.
+        OutOfRange {
+            /// Name of the request field.
+            field: &'static str,
+            /// The rejected value.
+            value: f32,
+            /// Inclusive lower bound.
+            min: f32,
+            /// Inclusive upper bound.
+            max: f32,
+        },
+        /// More stop sequences were given than the API accepts.
+        TooManyStopSequences {
+            /// Number of stop sequences in the request.
+            count: usize,
+        },
+        /// The request has no model name.
+        MissingModel,
+        /// The request has no messages.
+        NoMessages,
+        /// A message has `InvalidRole` or a role value the SDK does not know.
+        InvalidRole {
+            /// Index of the offending message in `messages`.
+            message_index: usize,
+            /// The raw role value.
+            role: i32,
+        },
+        /// The number of completions (`n`) is less than one.
+        InvalidN {
+            /// The rejected value.
+            n: i32,
+        },
+        /// `reasoning_effort` is set for a known model that does not accept it.
+        UnsupportedReasoningEffort {
+            /// The requested model.
+            model: String,
+        },
+        /// `tool_choice` forces a function that is not among the request's tools.
+        UnknownForcedTool {
+            /// The forced function name.
+            name: String,
+        },
+        /// The `include` list holds `IncludeOption::Invalid` or a value the SDK does not know.
+        InvalidIncludeOption {
+            /// The raw option value.
+            value: i32,
+        },
+        /// `response_format` asks for JSON output from a known model that does not support it.
+        UnsupportedStructuredOutput {
+            /// The requested model.
+            model: String,
+        },
+        /// `response_format` is a JSON schema whose `schema` is missing or not a JSON object.
+        InvalidJsonSchema,
+        /// A message other than a user message has a `name`.
+        UnexpectedName {
+            /// Index of the offending message in `messages`.
+            message_index: usize,
+            /// The role of the message.
+            role: MessageRole,
+        },
+    }
+
+    impl fmt::Display for ValidationError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ValidationError::MissingToolCallId { message_index } => write!(
+                    f,
+                    "Tool result message at index {message_index} has no tool_call_id"
+                ),
+                ValidationError::UnknownToolCallId {
+                    message_index,
+                    tool_call_id,
+                } => write!(
+                    f,
+                    "Tool result message at index {message_index} references unknown tool call '{tool_call_id}'"
+                ),
+                ValidationError::OutOfRange {
+                    field,
+                    value,
+                    min,
+                    max,
+                } => write!(
+                    f,
+                    "`{field}` is {value}, expected a value in [{min}, {max}]"
+                ),
+                ValidationError::TooManyStopSequences { count } => write!(
+                    f,
+                    "`stop` has {count} entries, at most {MAX_STOP_SEQUENCES} are allowed"
+                ),
+                ValidationError::MissingModel => write!(f, "`model` is empty"),
+                ValidationError::NoMessages => write!(f, "`messages` is empty"),
+                ValidationError::InvalidRole {
+                    message_index,
+                    role,
+                } => write!(
+                    f,
+                    "Message at index {message_index} has invalid role {role}"
+                ),
+                ValidationError::InvalidN { n } => {
+                    write!(f, "`n` is {n}, expected at least 1")
+                }
+                ValidationError::UnsupportedReasoningEffort { model } => write!(
+                    f,
+                    "`reasoning_effort` is set, but model '{model}' does not support it"
+                ),
+                ValidationError::UnknownForcedTool { name } => write!(
+                    f,
+                    "`tool_choice` forces function '{name}', which is not among the tools"
+                ),
+                ValidationError::InvalidIncludeOption { value } => {
+                    write!(f, "`include` holds invalid option {value}")
+                }
+                ValidationError::UnsupportedStructuredOutput { model } => write!(
+                    f,
+                    "`response_format` requests JSON, but model '{model}' does not support structured output"
+                ),
+                ValidationError::InvalidJsonSchema => {
+                    write!(f, "`response_format.schema` is not a JSON object")
+                }
+                ValidationError::UnexpectedName {
+                    message_index,
+                    role,
+                } => write!(
+                    f,
+                    "Message at index {message_index} has a name, but only user messages may have one (role is {role})"
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for ValidationError {}
+
+    /// A request setting that is valid but likely a mistake, reported by
+    /// [`GetCompletionsRequest::warnings`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum BuildWarning {
+        /// Both `temperature` and `top_p` are moved away from their neutral values (1.0).
+        /// They interact; adjusting one of them gives more predictable results.
+        TemperatureAndTopP {
+            /// The configured temperature.
+            temperature: f32,
+            /// The configured nucleus probability mass.
+            top_p: f32,
+        },
+        /// `top_logprobs` is set without `logprobs`, so no log probabilities are returned.
+        TopLogprobsWithoutLogprobs,
+        /// `top_logprobs` is outside `[0, MAX_TOP_LOGPROBS]`, which the API is documented to
+        /// reject.
+        TopLogprobsOutOfRange {
+            /// The configured number of alternatives per token.
+            top_logprobs: i32,
+        },
+        /// Log probabilities are requested from a known reasoning model, which does not
+        /// return them.
+        LogprobsUnsupported {
+            /// The requested model.
+            model: String,
+        },
+        /// A sampling option is set for a known reasoning model, which rejects it.
+        UnsupportedByReasoningModel {
+            /// Name of the option.
+            field: &'static str,
+            /// The requested model.
+            model: String,
+        },
+    }
+
+    impl fmt::Display for BuildWarning {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                BuildWarning::TemperatureAndTopP { temperature, top_p } => write!(
+                    f,
+                    "both `temperature` ({temperature}) and `top_p` ({top_p}) are set; adjust one of them"
+                ),
+                BuildWarning::TopLogprobsWithoutLogprobs => {
+                    write!(f, "`top_logprobs` has no effect unless `logprobs` is true")
+                }
+                BuildWarning::TopLogprobsOutOfRange { top_logprobs } => write!(
+                    f,
+                    "`top_logprobs` ({top_logprobs}) is outside [0, {MAX_TOP_LOGPROBS}]"
+                ),
+                BuildWarning::LogprobsUnsupported { model } => write!(
+                    f,
+                    "`logprobs` is set, but reasoning model '{model}' does not return log probabilities"
+                ),
+                BuildWarning::UnsupportedByReasoningModel { field, model } => write!(
+                    f,
+                    "`{field}` is set, but reasoning model '{model}' rejects it"
+                ),
+            }
+        }
+    }
+
+    /// Checks that every tool result message answers a tool call issued earlier in the conversation.
+    ///
+    /// Walks `messages` in order, recording the ids of all tool calls made by assistant
+    /// messages. Each `RoleTool` message must carry a `tool_call_id` matching one of the
+    /// ids recorded before it.
+    ///
+    /// # Arguments
+    /// * `request` - Completion request whose messages are checked
+    ///
+    /// # Returns
+    /// * `Ok(())` - All tool result messages reference a preceding tool call
+    /// * `Err(ValidationError)` - The first tool result message that is missing or dangling
+    pub fn validate_tool_results(request: &GetCompletionsRequest) -> Result<(), ValidationError> {
+        let mut issued: HashSet<&str> = HashSet::new();
+
+        for (message_index, message) in request.messages.iter().enumerate() {
+            match MessageRole::try_from(message.role) {
+                Ok(MessageRole::RoleAssistant) => {
+                    issued.extend(message.tool_calls.iter().map(|call| call.id.as_str()));
+                }
+                Ok(MessageRole::RoleTool) => match message.tool_call_id.as_deref() {
+                    None | Some("") => {
+                        return Err(ValidationError::MissingToolCallId { message_index });
+                    }
+                    Some(id) if !issued.contains(id) => {
+                        return Err(ValidationError::UnknownToolCallId {
+                            message_index,
+                            tool_call_id: id.to_string(),
+                        });
+                    }
+                    Some(_) => {}
+                },
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the sampling parameters of a request are within their documented ranges.
+    ///
+    /// Checks `temperature` (`[0, 2]`), `top_p` (`[0, 1]`), `frequency_penalty` and
+    /// `presence_penalty` (`[-2, 2]`), and the number of `stop` sequences. Unset parameters
+    /// are not checked.
+    ///
+    /// # Arguments
+    /// * `request` - Completion request whose sampling parameters are checked
+    ///
+    /// # Returns
+    /// * `Ok(())` - All set parameters are within range
+    /// * `Err(ValidationError)` - The first parameter that is out of range
+    pub fn validate_sampling_params(
+        request: &GetCompletionsRequest,
+    ) -> Result<(), ValidationError> {
+        let ranges = [
+            ("temperature", request.temperature, 0.0, 2.0),
+            ("top_p", request.top_p, 0.0, 1.0),
+            ("frequency_penalty", request.frequency_penalty, -2.0, 2.0),
+            ("presence_penalty", request.presence_penalty, -2.0, 2.0),
+        ];
+        for (field, value, min, max) in ranges {
+            if let Some(value) = value
+                && !(min..=max).contains(&value)
+            {
+                return Err(ValidationError::OutOfRange {
+                    field,
+                    value,
+                    min,
+                    max,
+                });
+            }
+        }
+
+        if request.stop.len() > MAX_STOP_SEQUENCES {
+            return Err(ValidationError::TooManyStopSequences {
+                count: request.stop.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    impl GetCompletionsRequest {
+        /// Checks the request locally, before it is sent.
+        ///
+        /// Catches mistakes that would otherwise come back as an opaque server error:
+        /// - `model` must not be empty
+        /// - `messages` must not be empty, and no message may have `InvalidRole` or an
+        ///   unknown role value; only user messages may have a `name`
+        /// - `n`, if set, must be at least 1
+        /// - a function forced by `tool_choice` must be among the function `tools`
+        /// - every `include` option must be a known value other than `Invalid`
+        /// - `reasoning_effort`, if set, must be supported by the model; unknown models are
+        ///   not checked (see [`ModelId::supports_reasoning_effort`])
+        /// - a JSON `response_format` must be supported by the model, again only for known
+        ///   models (see [`ModelId::supports_structured_output`]), and a JSON schema must be a
+        ///   JSON object
+        /// - sampling parameters must be within range (see [`validate_sampling_params`])
+        ///
+        /// Tool result messages are not checked, since they may answer tool calls of a stored
+        /// conversation (see [`validate_tool_results`] for self-contained conversations).
+        ///
+        /// # Returns
+        /// * `Ok(())` - The request passed all checks
+        /// * `Err(ValidationError)` - The first failed check, naming the offending field
+        pub fn validate(&self) -> Result<(), ValidationError> {
+            if self.model.is_empty() {
+                return Err(ValidationError::MissingModel);
+            }
+            if self.messages.is_empty() {
+                return Err(ValidationError::NoMessages);
+            }
+            for (message_index, message) in self.messages.iter().enumerate() {
+                if !matches!(MessageRole::try_from(message.role), Ok(role) if role != MessageRole::InvalidRole)
+                {
+                    return Err(ValidationError::InvalidRole {
+                        message_index,
+                        role: message.role,
+                    });
+                }
+                if !message.name.is_empty() && message.role_enum() != MessageRole::RoleUser {
+                    return Err(ValidationError::UnexpectedName {
+                        message_index,
+                        role: message.role_enum(),
+                    });
+                }
+            }
+            if let Some(n) = self.n
+                && n < 1
+            {
+                return Err(ValidationError::InvalidN { n });
+            }
+            if let Some(tool_choice::ToolChoice::FunctionName(name)) = self
+                .tool_choice
+                .as_ref()
+                .and_then(|choice| choice.tool_choice.as_ref())
+                && !self.tools.iter().any(|t| {
+                    matches!(&t.tool, Some(tool::Tool::Function(function)) if function.name == *name)
+                })
+            {
+                return Err(ValidationError::UnknownForcedTool { name: name.clone() });
+            }
+            if let Some(&value) = self.include.iter().find(|&&value| {
+                !matches!(IncludeOption::try_from(value), Ok(option) if option != IncludeOption::Invalid)
+            }) {
+                return Err(ValidationError::InvalidIncludeOption { value });
+            }
+            if self.reasoning_effort.is_some() {
+                let model = ModelId::parse(&self.model);
+                if model.family().is_some() && !model.supports_reasoning_effort() {
+                    return Err(ValidationError::UnsupportedReasoningEffort {
+                        model: self.model.clone(),
+                    });
+                }
+            }
+            if let Some(format) = &self.response_format {
+                let format_type = FormatType::try_from(format.format_type);
+                if matches!(
+                    format_type,
+                    Ok(FormatType::JsonObject | FormatType::JsonSchema)
+                ) {
+                    let model = ModelId::parse(&self.model);
+                    if model.family().is_some() && !model.supports_structured_output() {
+                        return Err(ValidationError::UnsupportedStructuredOutput {
+                            model: self.model.clone(),
+                        });
+                    }
+                }
+                if format_type == Ok(FormatType::JsonSchema)
+                    && !matches!(
+                        format
+                            .schema
+                            .as_deref()
+                            .and_then(|schema| serde_json::from_str(schema).ok()),
+                        Some(Value::Object(_))
+                    )
+                {
+                    return Err(ValidationError::InvalidJsonSchema);
+                }
+            }
+            validate_sampling_params(self)
+        }
+
+        /// Returns settings that are valid but likely mistakes.
+        ///
+        /// Unlike [`validate`](GetCompletionsRequest::validate), these do not make the request
+        /// fail, so they are reported separately:
+        /// - `temperature` and `top_p` both moved away from 1.0
+        /// - `top_logprobs` without `logprobs`
+        /// - `top_logprobs` outside `[0, MAX_TOP_LOGPROBS]`
+        /// - `logprobs`, penalties or stop sequences for a known reasoning model (see
+        ///   [`ModelId::is_reasoning_model`]); unknown models are not checked
+        ///
+        /// # Returns
+        /// * `Vec<BuildWarning>` - Warnings in the order listed above; empty if none apply
+        pub fn warnings(&self) -> Vec<BuildWarning> {
+            let mut warnings = Vec::new();
+
+            if let (Some(temperature), Some(top_p)) = (self.temperature, self.top_p)
+                && temperature != 1.0
+                && top_p != 1.0
+            {
+                warnings.push(BuildWarning::TemperatureAndTopP { temperature, top_p });
+            }
+            if self.top_logprobs.is_some() && !self.logprobs {
+                warnings.push(BuildWarning::TopLogprobsWithoutLogprobs);
+            }
+            if let Some(top_logprobs) = self.top_logprobs
+                && !(0..=MAX_TOP_LOGPROBS).contains(&top_logprobs)
+            {
+                warnings.push(BuildWarning::TopLogprobsOutOfRange { top_logprobs });
+            }
+
+            if ModelId::parse(&self.model).is_reasoning_model() {
+                if self.logprobs {
+                    warnings.push(BuildWarning::LogprobsUnsupported {
+                        model: self.model.clone(),
+                    });
+                }
+                let unsupported = [
+                    ("frequency_penalty", self.frequency_penalty.is_some()),
+                    ("presence_penalty", self.presence_penalty.is_some()),
+                    ("stop", !self.stop.is_empty()),
+                ];
+                for (field, set) in unsupported {
+                    if set {
+                        warnings.push(BuildWarning::UnsupportedByReasoningModel {
+                            field,
+                            model: self.model.clone(),
+                        });
+                    }
+                }
+            }
+
+            warnings
+        }
+    }
+}
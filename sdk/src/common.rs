@@ -2,40 +2,617 @@
 //!
 //! Provides shared functionality and utilities used across the xAI SDK,
 //! including channel creation, authentication interceptors, and common types.
+//!
+//! # Message size limits
+//!
+//! By default tonic rejects responses larger than 4 MiB with an `OutOfRange` status
+//! ("decoded message length too large") and does not limit requests. Each service client's
+//! `new_with_limits` constructor sets both limits: raise `max_decode` for services whose
+//! responses can grow that large, and use `max_encode` to cap outgoing requests. Clients from
+//! the other constructors can call `max_decoding_message_size` / `max_encoding_message_size`
+//! directly.
 
 pub mod channel {
     use crate::XAI_API_URL;
-    use crate::export::transport::{Channel, ClientTlsConfig, Error};
+    use crate::common::interceptor::ClientInterceptor;
+    use crate::common::types::BoxError;
+    use crate::export::service::Interceptor;
+    use crate::export::transport::{Channel, ClientTlsConfig, Endpoint, Error};
+    use crate::export::{Request, Status};
+    use hyper_util::client::legacy::connect::HttpConnector;
+    use hyper_util::rt::TokioIo;
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+    use tokio::net::TcpStream;
+    use tokio::sync::futures::OwnedNotified;
+    use tokio::sync::{Notify, Semaphore};
+    use tonic::codegen::Service;
+    use tonic::codegen::http::Uri;
 
     /// Creates a TLS-enabled gRPC `Channel` to the xAI API endpoint.
     ///
     /// Configures Tonic with native root certificates and connects to
     /// the SDK's default endpoint defined by [`XAI_API_URL`].
     ///
+    /// Minimal container images (`scratch`, distroless) often ship without a CA bundle, in
+    /// which case this fails with [`ConnectError::NativeRoots`], whose message explains how to
+    /// fix it: use [`new_webpki`] (behind the `webpki-roots` feature) or install the system CA
+    /// certificates in such images.
+    ///
+    /// # Returns
+    /// * `Result<Channel, ConnectError>` - Connected channel, missing root certificates or
+    ///   transport error
+    ///
+    pub async fn new() -> Result<Channel, ConnectError> {
+        Ok(native_endpoint()?.connect().await?)
+    }
+
+    /// Creates a TLS-enabled gRPC `Channel` to the xAI API endpoint using bundled Mozilla roots.
+    ///
+    /// Same as [`new`], but trusts the root certificates compiled into the binary by the
+    /// `webpki-roots` crate instead of the ones installed on the system, so it also works in
+    /// images without a CA bundle. The bundled roots only change when the SDK is rebuilt.
+    ///
+    /// # Returns
+    /// * `Result<Channel, Error>` - Connected channel or transport error
+    ///
+    #[cfg(feature = "webpki-roots")]
+    pub async fn new_webpki() -> Result<Channel, Error> {
+        with_tls_config(ClientTlsConfig::new().with_webpki_roots()).await
+    }
+
+    /// Error returned by [`check_native_roots`] when the system provides no root certificates.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct NativeRootsError {
+        /// Problems reported while loading the certificates, if any
+        pub errors: Vec<String>,
+    }
+
+    impl std::fmt::Display for NativeRootsError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "no native root certificates found")?;
+            if !self.errors.is_empty() {
+                write!(f, " ({})", self.errors.join("; "))?;
+            }
+            write!(
+                f,
+                "; install the system CA bundle (e.g. the `ca-certificates` package) or enable \
+                 the `webpki-roots` feature and connect with `common::channel::new_webpki()`"
+            )
+        }
+    }
+
+    impl std::error::Error for NativeRootsError {}
+
+    /// Error returned when connecting to the xAI API with the native root certificates.
+    #[derive(Debug)]
+    pub enum ConnectError {
+        /// The system provides no root certificates to trust
+        NativeRoots(NativeRootsError),
+        /// Any other TLS configuration or connection failure
+        Transport(Error),
+    }
+
+    impl std::fmt::Display for ConnectError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::NativeRoots(err) => err.fmt(f),
+                Self::Transport(err) => err.fmt(f),
+            }
+        }
+    }
+
+    impl std::error::Error for ConnectError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Self::NativeRoots(_) => None,
+                Self::Transport(err) => err.source(),
+            }
+        }
+    }
+
+    impl From<Error> for ConnectError {
+        fn from(err: Error) -> Self {
+            Self::Transport(err)
+        }
+    }
+
+    /// Configures the default endpoint with the native root certificates.
+    ///
+    /// tonic reports missing roots as a bare "no native certs found" source error; they are
+    /// only looked up again on that error path, to return [`ConnectError::NativeRoots`].
+    fn native_endpoint() -> Result<Endpoint, ConnectError> {
+        Channel::from_static(XAI_API_URL)
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .map_err(|err| match check_native_roots() {
+                Err(roots) => ConnectError::NativeRoots(roots),
+                Ok(_) => ConnectError::Transport(err),
+            })
+    }
+
+    /// Checks that the system provides root certificates for [`new`] to trust.
+    ///
+    /// Loads the certificates the same way [`new`] does, honouring the `SSL_CERT_FILE` and
+    /// `SSL_CERT_DIR` environment variables. Loading touches the filesystem, so call this once
+    /// at startup rather than per connection.
+    ///
+    /// # Returns
+    /// * `Ok(usize)` - Number of root certificates found
+    /// * `Err(NativeRootsError)` - No certificates were found, with a hint on how to fix it
+    ///
+    pub fn check_native_roots() -> Result<usize, NativeRootsError> {
+        let loaded = rustls_native_certs::load_native_certs();
+        if loaded.certs.is_empty() {
+            return Err(NativeRootsError {
+                errors: loaded.errors.iter().map(ToString::to_string).collect(),
+            });
+        }
+        Ok(loaded.certs.len())
+    }
+
+    /// Creates a gRPC `Channel` to the xAI API endpoint with a caller-supplied TLS configuration.
+    ///
+    /// Use this when the default native roots are not enough, e.g. behind a TLS-terminating
+    /// proxy that presents a certificate signed by a private CA, or when the proxy requires
+    /// mutual TLS. The configuration fully replaces the default one, so call
+    /// `with_native_roots()` on it as well if public CAs should still be trusted.
+    ///
+    /// ```no_run
+    /// use xai_sdk::common;
+    /// use xai_sdk::export::transport::{Certificate, ClientTlsConfig, Identity};
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let ca = std::fs::read_to_string("proxy-ca.pem")?;
+    /// let cert = std::fs::read_to_string("client.pem")?;
+    /// let key = std::fs::read_to_string("client.key")?;
+    ///
+    /// let tls = ClientTlsConfig::new()
+    ///     .ca_certificate(Certificate::from_pem(ca))
+    ///     .identity(Identity::from_pem(cert, key)); // optional, for mutual TLS
+    /// let channel = common::channel::with_tls_config(tls).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `tls` - TLS configuration (trust roots and optional client identity)
+    ///
     /// # Returns
     /// * `Result<Channel, Error>` - Connected channel or transport error
     ///
-    pub async fn new() -> Result<Channel, Error> {
+    pub async fn with_tls_config(tls: ClientTlsConfig) -> Result<Channel, Error> {
         Channel::from_static(XAI_API_URL)
-            .tls_config(ClientTlsConfig::new().with_native_roots())?
+            .tls_config(tls)?
             .connect()
             .await
     }
+
+    /// Creates a TLS-enabled gRPC `Channel` to the xAI API endpoint, retrying the initial
+    /// connection.
+    ///
+    /// Like [`new`], but a failed connection attempt (DNS lookup, TCP connect or TLS
+    /// handshake) is retried up to `attempts` times in total, waiting `backoff` before the
+    /// first retry and doubling the wait for each further one. This rides out short network
+    /// blips at startup, e.g. while a sidecar proxy or DNS comes up in an orchestrated
+    /// environment. An invalid TLS configuration (including missing native roots, see
+    /// [`check_native_roots`]) is detected before connecting and returned at once, since
+    /// retrying cannot fix it.
+    ///
+    /// # Arguments
+    /// * `attempts` - Maximum number of connection attempts; `0` is treated as `1`
+    /// * `backoff` - Wait before the first retry, doubled for each further one
+    ///
+    /// # Returns
+    /// * `Result<Channel, ConnectError>` - Connected channel, the TLS configuration error, or
+    ///   the error of the last attempt
+    ///
+    pub async fn connect_with_retry(
+        attempts: u32,
+        backoff: Duration,
+    ) -> Result<Channel, ConnectError> {
+        let endpoint = native_endpoint()?;
+        Ok(connect_endpoint_with_retry(endpoint, attempts, backoff).await?)
+    }
+
+    /// Connects to a configured endpoint, retrying failed attempts.
+    ///
+    /// The retry logic behind [`connect_with_retry`], for endpoints with custom settings
+    /// (TLS, keepalive, timeouts).
+    ///
+    /// # Arguments
+    /// * `endpoint` - Endpoint to connect to
+    /// * `attempts` - Maximum number of connection attempts; `0` is treated as `1`
+    /// * `backoff` - Wait before the first retry, doubled for each further one
+    ///
+    /// # Returns
+    /// * `Result<Channel, Error>` - Connected channel or the error of the last attempt
+    ///
+    pub async fn connect_endpoint_with_retry(
+        endpoint: Endpoint,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Result<Channel, Error> {
+        let mut delay = backoff;
+        let mut remaining = attempts.max(1);
+        loop {
+            remaining -= 1;
+            match endpoint.connect().await {
+                Ok(channel) => return Ok(channel),
+                Err(error) if remaining == 0 => return Err(error),
+                Err(_) => {
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                }
+            }
+        }
+    }
+
+    /// Creates a TLS-enabled gRPC `Channel` to the xAI API endpoint with HTTP/2 keepalive pings.
+    ///
+    /// Without keepalives a TCP connection that dies mid-stream (NAT timeout, load balancer
+    /// reset, network change) can leave a streaming call hanging indefinitely. With keepalives
+    /// enabled the client sends HTTP/2 PING frames every `interval` and fails the connection if
+    /// no acknowledgement arrives within `timeout`, surfacing the failure as a transport error.
+    ///
+    /// Recommended values for streaming workloads are an `interval` of 20–30 seconds, a
+    /// `timeout` of 10–20 seconds and `while_idle` set to `true`, so that a channel kept around
+    /// between requests is also probed. Avoid intervals much shorter than 10 seconds: servers
+    /// may treat aggressive pings as abuse and close the connection.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use xai_sdk::common;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let channel =
+    ///     common::channel::with_keepalive(Duration::from_secs(30), Duration::from_secs(10), true)
+    ///         .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `interval` - Time between HTTP/2 keepalive pings
+    /// * `timeout` - How long to wait for a ping acknowledgement before closing the connection
+    /// * `while_idle` - Whether to keep pinging while no requests are in flight
+    ///
+    /// # Returns
+    /// * `Result<Channel, ConnectError>` - Connected channel, missing root certificates or
+    ///   transport error
+    ///
+    pub async fn with_keepalive(
+        interval: Duration,
+        timeout: Duration,
+        while_idle: bool,
+    ) -> Result<Channel, ConnectError> {
+        Ok(native_endpoint()?
+            .http2_keep_alive_interval(interval)
+            .keep_alive_timeout(timeout)
+            .keep_alive_while_idle(while_idle)
+            .connect()
+            .await?)
+    }
+
+    /// A channel meant to be shared by several clients, with explicit shutdown.
+    ///
+    /// # Connection lifecycle
+    ///
+    /// A tonic [`Channel`] is a cheap handle to a background task that owns the HTTP/2
+    /// connection. Cloning a channel (or a client built on it, such as `ChatClient`) does not
+    /// open a new connection: all clones multiplex their requests over the same one. Dropping a
+    /// client only drops its handle; the connection stays open until *every* handle is dropped.
+    /// Conversely, each call to [`new`] or [`with_tls_config`] opens a separate connection, so
+    /// servers should create one channel at startup and hand clones to their clients rather than
+    /// connecting per request.
+    ///
+    /// `Shared` makes this explicit. Build clients from [`Shared::channel`] with an interceptor
+    /// from [`Shared::auth`] or [`Shared::guard`], run calls through [`Shared::run`], and await
+    /// [`Shared::shutdown`] on exit. Shutdown rejects new calls with `Unavailable`, waits for
+    /// the calls in [`Shared::run`] to finish and then closes the connection, even while
+    /// clients built on it are still alive. Clones of a `Shared` share its state, so shutting
+    /// down one shuts down all of them.
+    ///
+    /// ```no_run
+    /// use xai_sdk::chat;
+    /// use xai_sdk::common::channel::Shared;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let shared = Shared::connect().await?;
+    /// let mut chat =
+    ///     chat::client::with_channel_and_interceptor(shared.channel(), shared.auth("key"));
+    /// let other =
+    ///     chat::client::with_channel_and_interceptor(shared.channel(), shared.auth("other-key"));
+    /// // ... both clients use the same connection ...
+    /// # let request = xai_sdk::api::GetCompletionsRequest::default();
+    /// let response = shared.run(chat.get_completion(request)).await?;
+    /// shared.shutdown().await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[derive(Clone)]
+    pub struct Shared {
+        channel: Channel,
+        state: Arc<ShutdownState>,
+    }
+
+    /// Shutdown progress shared by a [`Shared`] channel, its guards and its connections.
+    struct ShutdownState {
+        /// Set when shutdown starts; guarded requests are rejected from then on
+        closed: AtomicBool,
+        /// One permit per call in [`Shared::run`]; shutdown waits to acquire all of them
+        in_flight: Semaphore,
+        /// Set, and `disconnect` notified, once the in-flight calls have finished
+        disconnected: AtomicBool,
+        disconnect: Arc<Notify>,
+    }
+
+    /// Permits in [`ShutdownState::in_flight`], i.e. the maximum number of concurrent calls.
+    const MAX_IN_FLIGHT: u32 = if Semaphore::MAX_PERMITS < u32::MAX as usize {
+        Semaphore::MAX_PERMITS as u32
+    } else {
+        u32::MAX
+    };
+
+    impl Shared {
+        /// Creates a shared channel to the given endpoint.
+        ///
+        /// The connection is opened in the background; connection errors surface on the first
+        /// request. Must be called from within a Tokio runtime.
+        ///
+        /// # Arguments
+        /// * `endpoint` - Endpoint configuration (URL, TLS, keepalive, timeouts)
+        ///
+        pub fn new(endpoint: Endpoint) -> Self {
+            let state = ShutdownState::new();
+            let connector = ClosableConnector::new(&endpoint, state.clone());
+            Self {
+                channel: endpoint.connect_with_connector_lazy(connector),
+                state,
+            }
+        }
+
+        /// Creates a shared TLS-enabled channel to the xAI API endpoint.
+        ///
+        /// Uses the same configuration as [`new`]: native root certificates and the SDK's
+        /// default endpoint defined by [`XAI_API_URL`]. Unlike [`Shared::new`], the connection
+        /// is opened before returning.
+        ///
+        /// # Returns
+        /// * `Result<Shared, ConnectError>` - Connected shared channel, missing root
+        ///   certificates or transport error
+        ///
+        pub async fn connect() -> Result<Self, ConnectError> {
+            let endpoint = native_endpoint()?;
+            let state = ShutdownState::new();
+            let connector = ClosableConnector::new(&endpoint, state.clone());
+            Ok(Self {
+                channel: endpoint.connect_with_connector(connector).await?,
+                state,
+            })
+        }
+
+        /// Returns a handle to the shared connection, for building a client.
+        pub fn channel(&self) -> Channel {
+            self.channel.clone()
+        }
+
+        /// Wraps an interceptor so that it rejects requests once the channel is shut down.
+        ///
+        /// # Arguments
+        /// * `inner` - Interceptor to run while the channel is open (must handle authentication)
+        ///
+        /// # Returns
+        /// * `ClientInterceptor` - Interceptor failing with `Unavailable` after
+        ///   [`Shared::shutdown`] has started, and delegating to `inner` before
+        pub fn guard(&self, inner: impl Interceptor + Send + Sync + 'static) -> ClientInterceptor {
+            let state = self.state.clone();
+            let mut inner = ClientInterceptor::new(inner);
+            ClientInterceptor::new(move |request: Request<()>| {
+                if state.closed.load(Ordering::Acquire) {
+                    return Err(shut_down());
+                }
+                inner.call(request)
+            })
+        }
+
+        /// Creates the default authentication interceptor, guarded like [`Shared::guard`].
+        ///
+        /// # Arguments
+        /// * `api_key` - Valid xAI API key for Bearer authentication
+        ///
+        /// # Returns
+        /// * `ClientInterceptor` - Guarded [`auth`](super::interceptor::auth) interceptor
+        pub fn auth(&self, api_key: &str) -> ClientInterceptor {
+            self.guard(super::interceptor::auth(api_key))
+        }
+
+        /// Runs a call on the channel, counting it as in flight until it completes.
+        ///
+        /// Interceptors only see a request before it is sent, so [`Shared::shutdown`] cannot
+        /// tell on its own when a call has finished. Wrap each call in `run`, including the
+        /// consumption of a streaming response, for shutdown to wait for it.
+        ///
+        /// # Arguments
+        /// * `call` - Future performing the call, e.g. `client.get_completion(request)`
+        ///
+        /// # Returns
+        /// * `Ok(T)` - Output of the call
+        /// * `Err(Status)` - Error of the call, or `Unavailable` if shutdown has started
+        pub async fn run<F, T>(&self, call: F) -> Result<T, Status>
+        where
+            F: Future<Output = Result<T, Status>>,
+        {
+            if self.state.closed.load(Ordering::Acquire) {
+                return Err(shut_down());
+            }
+            let _permit = self
+                .state
+                .in_flight
+                .try_acquire()
+                .map_err(|_| shut_down())?;
+            call.await
+        }
+
+        /// Shuts the channel down.
+        ///
+        /// New requests through an interceptor from [`Shared::guard`] or [`Shared::auth`] and
+        /// new calls to [`Shared::run`] fail with `Unavailable` from now on. Once the calls
+        /// already in [`Shared::run`] have finished, the connection is closed and no new one is
+        /// opened; requests on clients built without a guard then fail with a transport error.
+        pub async fn shutdown(self) {
+            self.state.closed.store(true, Ordering::Release);
+            // Only fails once closed, i.e. after an earlier shutdown has already finished
+            if let Ok(permits) = self.state.in_flight.acquire_many(MAX_IN_FLIGHT).await {
+                permits.forget();
+                self.state.in_flight.close();
+            }
+            self.state.disconnected.store(true, Ordering::Release);
+            self.state.disconnect.notify_waiters();
+        }
+    }
+
+    impl ShutdownState {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                closed: AtomicBool::new(false),
+                in_flight: Semaphore::new(MAX_IN_FLIGHT as usize),
+                disconnected: AtomicBool::new(false),
+                disconnect: Arc::new(Notify::new()),
+            })
+        }
+    }
+
+    fn shut_down() -> Status {
+        Status::unavailable("channel shut down")
+    }
+
+    /// Opens TCP connections like tonic's default connector, as [`Closable`] streams.
+    #[derive(Clone)]
+    struct ClosableConnector {
+        http: HttpConnector,
+        state: Arc<ShutdownState>,
+    }
+
+    impl ClosableConnector {
+        fn new(endpoint: &Endpoint, state: Arc<ShutdownState>) -> Self {
+            let mut http = HttpConnector::new();
+            http.enforce_http(false);
+            http.set_nodelay(endpoint.get_tcp_nodelay());
+            http.set_keepalive(endpoint.get_tcp_keepalive());
+            http.set_keepalive_interval(endpoint.get_tcp_keepalive_interval());
+            http.set_keepalive_retries(endpoint.get_tcp_keepalive_retries());
+            http.set_connect_timeout(endpoint.get_connect_timeout());
+            Self { http, state }
+        }
+    }
+
+    impl Service<Uri> for ClosableConnector {
+        type Response = TokioIo<Closable>;
+        type Error = BoxError;
+        type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+            self.http.poll_ready(cx).map_err(Into::into)
+        }
+
+        fn call(&mut self, uri: Uri) -> Self::Future {
+            let state = self.state.clone();
+            let connecting = self.http.call(uri);
+            Box::pin(async move {
+                if state.disconnected.load(Ordering::Acquire) {
+                    return Err(shut_down().into());
+                }
+                let stream = connecting.await?.into_inner();
+                Ok(TokioIo::new(Closable::new(stream, state)))
+            })
+        }
+    }
+
+    /// A TCP stream that reports end-of-file once [`Shared::shutdown`] disconnects, so the
+    /// connection task closes the connection and drops the socket.
+    struct Closable {
+        stream: TcpStream,
+        state: Arc<ShutdownState>,
+        disconnect: Pin<Box<OwnedNotified>>,
+    }
+
+    impl Closable {
+        fn new(stream: TcpStream, state: Arc<ShutdownState>) -> Self {
+            // Registered before `disconnected` is checked in `poll_disconnected`, so a
+            // concurrent shutdown is never missed
+            let disconnect = Box::pin(state.disconnect.clone().notified_owned());
+            Self {
+                stream,
+                state,
+                disconnect,
+            }
+        }
+
+        fn poll_disconnected(&mut self, cx: &mut Context<'_>) -> bool {
+            self.state.disconnected.load(Ordering::Acquire)
+                || self.disconnect.as_mut().poll(cx).is_ready()
+        }
+    }
+
+    impl AsyncRead for Closable {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if self.poll_disconnected(cx) {
+                return Poll::Ready(Ok(()));
+            }
+            Pin::new(&mut self.stream).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for Closable {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.poll_disconnected(cx) {
+                return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+            }
+            Pin::new(&mut self.stream).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.stream).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.stream).poll_shutdown(cx)
+        }
+    }
 }
 
 pub mod interceptor {
     use crate::export::metadata::MetadataValue;
     use crate::export::service::Interceptor;
     use crate::export::{Request, Status};
+    use std::sync::{Arc, Mutex};
+    use tonic::metadata::Ascii;
 
     /// Concrete interceptor type for client contexts.
     ///
     /// Erases the concrete interceptor implementation, allowing use as a concrete type
     /// in return positions and stored in structs where `impl Interceptor` cannot be used.
     ///
-    /// `Send + Sync`, making it safe to use across thread boundaries.
+    /// `Send + Sync`, making it safe to use across thread boundaries. Cloning is cheap and
+    /// clones share the same underlying interceptor, which makes every service client built
+    /// on it (e.g. `ChatClient`) `Clone` as well.
+    #[derive(Clone)]
     pub struct ClientInterceptor {
-        inner: Box<dyn Interceptor + Send + Sync>,
+        inner: Arc<Mutex<Box<dyn Interceptor + Send + Sync>>>,
     }
 
     impl ClientInterceptor {
@@ -48,21 +625,25 @@ pub mod interceptor {
         /// * `inner` - Any `Send + Sync` type implementing `Interceptor`
         ///
         pub fn new(inner: impl Interceptor + Send + Sync + 'static) -> Self {
-            Self {
-                inner: Box::new(inner),
-            }
+            Self::from(Box::new(inner) as Box<dyn Interceptor + Send + Sync>)
         }
     }
 
     impl From<Box<dyn Interceptor + Send + Sync>> for ClientInterceptor {
         fn from(inner: Box<dyn Interceptor + Send + Sync>) -> Self {
-            Self { inner }
+            Self {
+                inner: Arc::new(Mutex::new(inner)),
+            }
         }
     }
 
     impl Interceptor for ClientInterceptor {
         fn call(&mut self, request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
-            self.inner.call(request)
+            let mut inner = self
+                .inner
+                .lock()
+                .map_err(|_| Status::internal("Interceptor lock poisoned"))?;
+            inner.call(request)
         }
     }
 
@@ -90,6 +671,98 @@ pub mod interceptor {
         })
     }
 
+    /// Metadata key used by [`request_id`] and [`request_id_from`].
+    pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+    /// Creates an interceptor that stamps every request with an `x-request-id` header.
+    ///
+    /// A random UUID (v4) is generated per request unless the request already carries an
+    /// `x-request-id` (e.g. set on a single `Request`), in which case that id is kept. The
+    /// id used for the most recent request is written to the returned cell so it can be
+    /// logged and correlated with server-side records. With concurrent requests the cell
+    /// holds whichever id was stamped last.
+    ///
+    /// # Returns
+    /// * `(ClientInterceptor, Arc<Mutex<Option<String>>>)` - The interceptor and the cell
+    ///   holding the last request id (`None` until the first request)
+    ///
+    pub fn request_id() -> (ClientInterceptor, Arc<Mutex<Option<String>>>) {
+        let last_id = Arc::new(Mutex::new(None));
+        let interceptor = ClientInterceptor::new({
+            let last_id = last_id.clone();
+            move |mut req: Request<()>| -> Result<Request<()>, Status> {
+                let id = match req.metadata().get(REQUEST_ID_HEADER) {
+                    Some(existing) => existing
+                        .to_str()
+                        .map_err(|e| {
+                            Status::invalid_argument(format!("Invalid request id header: {}", e))
+                        })?
+                        .to_string(),
+                    None => {
+                        let id = uuid_v4()?;
+                        req.metadata_mut()
+                            .insert(REQUEST_ID_HEADER, metadata_value(&id)?);
+                        id
+                    }
+                };
+
+                *last_id
+                    .lock()
+                    .map_err(|_| Status::internal("Request id lock poisoned"))? = Some(id);
+
+                Ok(req)
+            }
+        });
+
+        (interceptor, last_id)
+    }
+
+    /// Creates an interceptor that stamps every request with the given `x-request-id`.
+    ///
+    /// Use this to propagate an incoming parent id (e.g. from an HTTP request being served)
+    /// to all calls made on its behalf.
+    ///
+    /// # Arguments
+    /// * `id` - Request id to send; must be a valid ASCII metadata value
+    ///
+    /// # Returns
+    /// * `ClientInterceptor` - Interceptor that adds the request id metadata
+    ///
+    pub fn request_id_from(id: &str) -> ClientInterceptor {
+        let id = id.to_string();
+        ClientInterceptor::new(move |mut req: Request<()>| -> Result<Request<()>, Status> {
+            req.metadata_mut()
+                .insert(REQUEST_ID_HEADER, metadata_value(&id)?);
+
+            Ok(req)
+        })
+    }
+
+    fn metadata_value(value: &str) -> Result<MetadataValue<Ascii>, Status> {
+        MetadataValue::try_from(value).map_err(|e| {
+            Status::invalid_argument(format!("Failed to create metadata value: {}", e))
+        })
+    }
+
+    /// Generates a random (version 4) UUID in its hyphenated lowercase form.
+    fn uuid_v4() -> Result<String, Status> {
+        let mut bytes = [0u8; 16];
+        getrandom::fill(&mut bytes)
+            .map_err(|e| Status::internal(format!("Failed to generate request id: {}", e)))?;
+        bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+        bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[0..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..32]
+        ))
+    }
+
     /// Composes multiple interceptors into a single interceptor, applied in order.
     ///
     /// Each interceptor receives the output request of the previous one. If any interceptor
@@ -111,6 +784,410 @@ pub mod interceptor {
     }
 }
 
+/// Per-request metadata helpers.
+///
+/// Interceptors apply to every request sent through a client. These helpers attach headers
+/// to a single `Request` instead, so one-off metadata (e.g. an idempotency key) can be sent
+/// over a shared, already authenticated client. Interceptors run after the request is built
+/// and may still overwrite a header set here.
+pub mod request {
+    use crate::export::metadata::MetadataValue;
+    use crate::export::{Request, Status};
+    use tonic::metadata::MetadataKey;
+
+    /// Attaches a single ASCII header to a request, replacing any existing value for `key`.
+    ///
+    /// # Arguments
+    /// * `request` - Request to add the header to
+    /// * `key` - Header name; must be a valid gRPC metadata key (not ending in `-bin`)
+    /// * `value` - Header value; must be visible ASCII
+    ///
+    /// # Returns
+    /// * `Ok(Request<T>)` - The request carrying the header
+    /// * `Err(Status)` - `InvalidArgument` if the name or value is not legal metadata
+    ///
+    pub fn with_header<T>(
+        mut request: Request<T>,
+        key: &str,
+        value: &str,
+    ) -> Result<Request<T>, Status> {
+        insert(&mut request, key, value)?;
+        Ok(request)
+    }
+
+    /// Attaches several ASCII headers to a request, in iteration order.
+    ///
+    /// Later entries replace earlier ones with the same name. Validation stops at the first
+    /// illegal entry and no partially updated request is returned.
+    ///
+    /// # Arguments
+    /// * `request` - Request to add the headers to
+    /// * `headers` - Name/value pairs, e.g. a `HashMap<String, String>` or an array of tuples
+    ///
+    /// # Returns
+    /// * `Ok(Request<T>)` - The request carrying all headers
+    /// * `Err(Status)` - `InvalidArgument` naming the first illegal header
+    ///
+    pub fn with_headers<T, I, K, V>(
+        mut request: Request<T>,
+        headers: I,
+    ) -> Result<Request<T>, Status>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (key, value) in headers {
+            insert(&mut request, key.as_ref(), value.as_ref())?;
+        }
+        Ok(request)
+    }
+
+    /// Metadata key used by [`with_idempotency_key`].
+    pub const IDEMPOTENCY_KEY_HEADER: &str = "x-idempotency-key";
+
+    /// Attaches an `x-idempotency-key` header to a request.
+    ///
+    /// Requests that bill per call (e.g. image generation or sampling) are not safe to retry
+    /// blindly: a transient error may arrive after the server already did the work. This
+    /// header marks every attempt of one logical request with the same key, for proxies or
+    /// gateways that deduplicate on it.
+    ///
+    /// The xAI API does not document an idempotency header, so the header name is unverified
+    /// and the xAI servers may ignore it: do not rely on it to prevent duplicate work or
+    /// charges. A retry loop should build a fresh `Request` per attempt (tonic requests are
+    /// not `Clone`) and attach the same key each time. Generate a new key only for a new
+    /// logical request.
+    ///
+    /// # Arguments
+    /// * `request` - Request to add the key to
+    /// * `key` - Caller-chosen unique key, e.g. a UUID; must be visible ASCII
+    ///
+    /// # Returns
+    /// * `Ok(Request<T>)` - The request carrying the key
+    /// * `Err(Status)` - `InvalidArgument` if the key is not a legal metadata value
+    ///
+    pub fn with_idempotency_key<T>(request: Request<T>, key: &str) -> Result<Request<T>, Status> {
+        with_header(request, IDEMPOTENCY_KEY_HEADER, key)
+    }
+
+    fn insert<T>(request: &mut Request<T>, key: &str, value: &str) -> Result<(), Status> {
+        let name = MetadataKey::from_bytes(key.as_bytes()).map_err(|e| {
+            Status::invalid_argument(format!("Invalid header name '{}': {}", key, e))
+        })?;
+        let value = MetadataValue::try_from(value).map_err(|e| {
+            Status::invalid_argument(format!("Invalid value for header '{}': {}", key, e))
+        })?;
+        request.metadata_mut().insert(name, value);
+        Ok(())
+    }
+}
+
+/// Request transforms.
+///
+/// Interceptors only see request metadata, not the message. Transforms are functions from
+/// `Request<T>` to `Result<Request<T>, Status>` that can inspect and change the message
+/// itself; apply one with `?` right before the call, so a rejected request never reaches
+/// the network.
+pub mod transform {
+    use crate::export::{Request, Status};
+    use crate::xai_api::GetCompletionsRequest;
+    use prost::Message;
+
+    /// Returns the size of the prompt of a chat request, in bytes.
+    ///
+    /// The size is the protobuf-encoded length of `messages`, as sent on the wire before
+    /// compression. Text and base64 data URLs count in full; images referenced by an
+    /// `https` URL only count the URL. Tools, the response format and other request fields
+    /// are not included.
+    ///
+    /// # Arguments
+    /// * `request` - Chat request to measure
+    ///
+    /// # Returns
+    /// * `usize` - Encoded size of the messages
+    pub fn prompt_bytes(request: &GetCompletionsRequest) -> usize {
+        request
+            .messages
+            .iter()
+            .map(|message| {
+                let len = message.encoded_len();
+                // Field tag plus length prefix of each repeated `messages` entry
+                1 + prost::encoding::encoded_len_varint(len as u64) + len
+            })
+            .sum()
+    }
+
+    /// Creates a transform that rejects chat requests whose prompt exceeds `limit` bytes.
+    ///
+    /// The prompt is measured with [`prompt_bytes`]. Oversized requests fail locally with
+    /// `InvalidArgument` instead of being sent, rejected by the server and possibly billed.
+    /// The limit is a byte count, not a token count; use the tokenize service for exact
+    /// token limits.
+    ///
+    /// ```no_run
+    /// # async fn example(mut client: xai_sdk::chat::client::ChatClient, request: xai_sdk::api::GetCompletionsRequest) -> Result<(), tonic::Status> {
+    /// use xai_sdk::common::transform;
+    ///
+    /// let guard = transform::max_prompt_bytes(1 << 20);
+    /// let response = client.get_completion(guard(xai_sdk::Request::new(request))?).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum prompt size in bytes
+    ///
+    /// # Returns
+    /// * `impl Fn(Request<GetCompletionsRequest>) -> Result<Request<GetCompletionsRequest>, Status>` -
+    ///   Transform passing requests within the limit through unchanged
+    pub fn max_prompt_bytes(
+        limit: usize,
+    ) -> impl Fn(Request<GetCompletionsRequest>) -> Result<Request<GetCompletionsRequest>, Status>
+    + Clone
+    + Send
+    + Sync
+    + 'static {
+        move |request| {
+            let size = prompt_bytes(request.get_ref());
+            if size > limit {
+                return Err(Status::invalid_argument(format!(
+                    "prompt is {size} bytes, exceeding the limit of {limit} bytes"
+                )));
+            }
+            Ok(request)
+        }
+    }
+}
+
+/// End-user identifiers.
+///
+/// Chat, sample, image and embedding requests have a `user` field: an opaque string the
+/// client supplies to identify its end user. xAI stores it in the request logs, uses it for
+/// abuse monitoring, and it can be quoted in support requests to find specific requests.
+/// It should be stable per user and must not contain personal data such as names or email
+/// addresses. [`hash_id`] derives such an identifier from an internal user id.
+pub mod user {
+    use ring::digest::{SHA256, digest};
+    use std::fmt::Write;
+
+    /// Returns the lowercase hex SHA-256 digest of `id`.
+    ///
+    /// The result is stable, so requests of one user can still be correlated, but does not
+    /// reveal the id. Ids from a small or guessable space (e.g. email addresses or sequential
+    /// numbers) can be recovered by hashing candidates; prefix them with an
+    /// application-specific secret before hashing if that matters.
+    ///
+    /// ```
+    /// let user = xai_sdk::common::user::hash_id("user-42");
+    /// assert_eq!(user.len(), 64);
+    /// ```
+    pub fn hash_id(id: &str) -> String {
+        digest(&SHA256, id.as_bytes()).as_ref().iter().fold(
+            String::with_capacity(64),
+            |mut hex, byte| {
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            },
+        )
+    }
+}
+
+/// Deadlines for unary calls.
+///
+/// Streaming calls can bound the gap between chunks (see
+/// [`process_with_idle_timeout`](crate::chat::stream::process_with_idle_timeout)); unary calls
+/// such as `generate_image` need a bound on the whole call instead. [`unary`] provides it for
+/// any client method, and each service module wraps it for its main calls.
+pub mod timeout {
+    use crate::export::{Request, Response, Status};
+    use std::future::Future;
+    use std::time::Duration;
+
+    /// Runs a unary call that must finish within `timeout`.
+    ///
+    /// The deadline is enforced twice: the request carries it as `grpc-timeout` metadata (set
+    /// with `Request::set_timeout`), so the server can abandon the work, and the call is
+    /// cancelled locally once it expires, so a stalled connection cannot hang the caller.
+    ///
+    /// ```no_run
+    /// # async fn example(mut client: xai_sdk::image::client::ImageClient, request: xai_sdk::api::GenerateImageRequest) -> Result<(), tonic::Status> {
+    /// use std::time::Duration;
+    /// use xai_sdk::common::timeout;
+    ///
+    /// let response = timeout::unary(xai_sdk::Request::new(request), Duration::from_secs(60), |r| {
+    ///     client.generate_image(r)
+    /// })
+    /// .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `request` - Request to send
+    /// * `timeout` - Maximum duration of the call
+    /// * `call` - Client method invocation, e.g. `|r| client.generate_image(r)`
+    ///
+    /// # Returns
+    /// * `Ok(Response<T>)` - The call's response
+    /// * `Err(Status)` - The call's status, or `DeadlineExceeded` once `timeout` expires;
+    ///   authentication failures are mapped by [`auth::map_status`](crate::auth::map_status)
+    pub async fn unary<Req, T, F, Fut>(
+        mut request: Request<Req>,
+        timeout: Duration,
+        call: F,
+    ) -> Result<Response<T>, Status>
+    where
+        F: FnOnce(Request<Req>) -> Fut,
+        Fut: Future<Output = Result<Response<T>, Status>>,
+    {
+        request.set_timeout(timeout);
+        tokio::time::timeout(timeout, call(request))
+            .await
+            .unwrap_or_else(|_| {
+                Err(Status::deadline_exceeded(format!(
+                    "call did not complete within {timeout:?}"
+                )))
+            })
+            .map_err(crate::auth::map_status)
+    }
+}
+
+/// Call latency measurement.
+///
+/// [`call`] times any call; [`unary`] also enforces a deadline like
+/// [`timeout::unary`](super::timeout::unary) and reports how much of it was used, for
+/// tuning timeouts against real latencies.
+pub mod timed {
+    use crate::export::{Request, Response, Status};
+    use std::future::Future;
+    use std::time::{Duration, Instant};
+
+    /// Elapsed time of a call and the deadline it ran under.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Timing {
+        /// Wall-clock time from sending the call to its result.
+        pub elapsed: Duration,
+        /// Deadline of the call, if it had one.
+        pub deadline: Option<Duration>,
+    }
+
+    impl Timing {
+        /// Returns the share of the deadline used, e.g. `0.8` for 8 seconds of a 10 second
+        /// deadline; `None` without a deadline. Can exceed `1.0` slightly when the deadline
+        /// expired.
+        pub fn deadline_used(&self) -> Option<f64> {
+            self.deadline
+                .filter(|deadline| !deadline.is_zero())
+                .map(|deadline| self.elapsed.as_secs_f64() / deadline.as_secs_f64())
+        }
+
+        /// Returns `true` if the call used at least `threshold` of its deadline.
+        ///
+        /// # Arguments
+        /// * `threshold` - Share of the deadline, e.g. `0.8`
+        ///
+        /// # Returns
+        /// * `bool` - Whether the call came that close; `false` without a deadline
+        pub fn near_deadline(&self, threshold: f64) -> bool {
+            self.deadline_used().is_some_and(|used| used >= threshold)
+        }
+    }
+
+    /// Awaits a call and measures how long it took.
+    ///
+    /// # Arguments
+    /// * `fut` - The call, e.g. `client.list_language_models(request)`
+    ///
+    /// # Returns
+    /// * `(Result<T, Status>, Duration)` - The call's result and its elapsed time
+    pub async fn call<T, Fut>(fut: Fut) -> (Result<T, Status>, Duration)
+    where
+        Fut: Future<Output = Result<T, Status>>,
+    {
+        let start = Instant::now();
+        let result = fut.await;
+        (result, start.elapsed())
+    }
+
+    /// Runs a unary call with [`timeout::unary`](super::timeout::unary) and measures it.
+    ///
+    /// # Arguments
+    /// * `request` - Request to send
+    /// * `timeout` - Maximum duration of the call
+    /// * `call` - Client method invocation, e.g. `|r| client.generate_image(r)`
+    ///
+    /// # Returns
+    /// * `(Result<Response<T>, Status>, Timing)` - The call's result and its timing, whose
+    ///   `deadline` is `timeout`
+    pub async fn unary<Req, T, F, Fut>(
+        request: Request<Req>,
+        timeout: Duration,
+        call: F,
+    ) -> (Result<Response<T>, Status>, Timing)
+    where
+        F: FnOnce(Request<Req>) -> Fut,
+        Fut: Future<Output = Result<Response<T>, Status>>,
+    {
+        let (result, elapsed) = self::call(super::timeout::unary(request, timeout, call)).await;
+        let timing = Timing {
+            elapsed,
+            deadline: Some(timeout),
+        };
+        (result, timing)
+    }
+}
+
+/// Iteration over paginated list RPCs.
+///
+/// List RPCs return one page of items and a `pagination_token` for the next page. [`items`]
+/// hides that loop behind a [`PageStream`] of the individual items.
+pub mod paginate {
+    use crate::export::Status;
+    use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+    use std::future::Future;
+    use std::pin::Pin;
+
+    /// Stream of the items of all pages of a list RPC.
+    pub type PageStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+    /// Fetches pages with `fetch` and yields their items until the page token runs out.
+    ///
+    /// The first call gets `None`; every further call gets the token returned by the
+    /// previous one. The stream ends after a page without a token (`None` or empty). An
+    /// error from `fetch` is yielded once and ends the stream. Pages are fetched lazily, as
+    /// the stream is polled.
+    ///
+    /// # Arguments
+    /// * `fetch` - Async closure fetching the page for a token, returning its items and the
+    ///   token of the next page
+    ///
+    /// # Returns
+    /// * `PageStream<T>` - The items of all pages, in order
+    pub fn items<T, F, Fut>(fetch: F) -> PageStream<T>
+    where
+        T: Send + 'static,
+        F: Fn(Option<String>) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(Vec<T>, Option<String>), Status>> + Send + 'static,
+    {
+        let pages = stream::try_unfold(Some(None), move |token: Option<Option<String>>| {
+            let next = token.map(&fetch);
+            async move {
+                let Some(next) = next else {
+                    return Ok::<_, Status>(None);
+                };
+                let (page, token) = next.await?;
+                let token = token.filter(|token| !token.is_empty());
+                Ok(Some((page, token.map(Some))))
+            }
+        });
+        pages
+            .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+}
+
 pub mod types {
     use std::error::Error;
     use std::pin::Pin;
@@ -122,3 +1199,100 @@ pub mod types {
     /// Boxed future type for async callbacks. Allows references without `Send` requirement.
     pub type BoxFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
 }
+
+/// Polling for deferred requests.
+///
+/// Deferred chat completions and video generations are started by one RPC and collected by
+/// polling another until the request leaves `DeferredStatus::Pending`. [`poll`] implements
+/// that loop once for every service.
+pub mod deferred {
+    use crate::export::Status;
+    use crate::xai_api::{DeferredStatus, GetDeferredCompletionResponse, GetDeferredVideoResponse};
+    use std::future::Future;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    /// Longest wait between two polls, however far the interval has backed off.
+    pub const MAX_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// Shortest wait between two polls; smaller intervals are raised to it.
+    pub const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// A poll result that carries a [`DeferredStatus`].
+    pub trait Deferred {
+        /// Returns the status of the deferred request.
+        fn deferred_status(&self) -> DeferredStatus;
+    }
+
+    impl Deferred for DeferredStatus {
+        fn deferred_status(&self) -> DeferredStatus {
+            *self
+        }
+    }
+
+    impl Deferred for GetDeferredCompletionResponse {
+        fn deferred_status(&self) -> DeferredStatus {
+            self.status()
+        }
+    }
+
+    impl Deferred for GetDeferredVideoResponse {
+        fn deferred_status(&self) -> DeferredStatus {
+            self.status()
+        }
+    }
+
+    /// Polls a deferred request until it is no longer pending.
+    ///
+    /// Calls `poll_fn` until it returns a result whose status is `Done`, `Expired` or
+    /// `Failed`, and returns that result. The wait between calls starts at `interval` (at
+    /// least [`MIN_INTERVAL`]) and doubles after every pending result, up to
+    /// [`MAX_INTERVAL`] (or `interval`, if larger). Errors from `poll_fn` are returned
+    /// immediately. Must be called from within a Tokio runtime.
+    ///
+    /// # Arguments
+    /// * `poll_fn` - Async closure fetching the current state of the request
+    /// * `interval` - Wait before the second poll (the first one is immediate)
+    /// * `timeout` - Total time allowed before giving up; `Duration::MAX` (or any timeout
+    ///   too large to represent as a deadline) polls without a deadline
+    ///
+    /// # Returns
+    /// * `Ok(T)` - The first result with a final status
+    /// * `Err(Status)` - The error of `poll_fn`, or `DeadlineExceeded` if the request was
+    ///   still pending after `timeout`
+    pub async fn poll<F, Fut, T>(
+        mut poll_fn: F,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<T, Status>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Status>>,
+        T: Deferred,
+    {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut interval = interval.max(MIN_INTERVAL);
+        let max_interval = interval.max(MAX_INTERVAL);
+
+        loop {
+            let result = poll_fn().await?;
+            match result.deferred_status() {
+                DeferredStatus::Pending | DeferredStatus::InvalidDeferredStatus => {}
+                _ => return Ok(result),
+            }
+
+            let mut wait = interval;
+            if let Some(deadline) = deadline {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(Status::deadline_exceeded(format!(
+                        "deferred request still pending after {timeout:?}"
+                    )));
+                }
+                wait = wait.min(deadline - now);
+            }
+            tokio::time::sleep(wait).await;
+            interval = interval.saturating_mul(2).min(max_interval);
+        }
+    }
+}
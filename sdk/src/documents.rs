@@ -5,9 +5,11 @@
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
+    use crate::export::codec::CompressionEncoding;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::documents_client::DocumentsClient as XDocumentsClient;
 
     pub type DocumentsClient = XDocumentsClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +22,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<DocumentsClient, Error>` - Connected client or transport error
+    /// * `Result<DocumentsClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<DocumentsClient, Error> {
+    pub async fn new(api_key: &str) -> Result<DocumentsClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XDocumentsClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +32,53 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `DocumentsClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<DocumentsClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<DocumentsClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XDocumentsClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
+    /// Creates a new authenticated `DocumentsClient` that compresses requests and accepts compressed
+    /// responses.
+    ///
+    /// Compression is off for the other constructors. Enable the crate's `gzip` feature to
+    /// use `CompressionEncoding::Gzip`.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `encoding` - Compression algorithm used in both directions
+    ///
+    /// # Returns
+    /// * `Result<DocumentsClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_compression(
+        api_key: &str,
+        encoding: CompressionEncoding,
+    ) -> Result<DocumentsClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        Ok(with_channel_and_compression(channel, api_key, encoding))
+    }
+
     /// Creates a new authenticated `DocumentsClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -47,6 +96,25 @@ pub mod client {
         client
     }
 
+    /// Creates a new authenticated `DocumentsClient` with compression on an existing gRPC channel.
+    ///
+    /// # Arguments
+    /// * `channel` - Existing TLS-secured gRPC channel to xAI API
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `encoding` - Compression algorithm used in both directions
+    ///
+    /// # Returns
+    /// * `DocumentsClient` - Authenticated client using the provided channel
+    pub fn with_channel_and_compression(
+        channel: Channel,
+        api_key: &str,
+        encoding: CompressionEncoding,
+    ) -> DocumentsClient {
+        with_channel(channel, api_key)
+            .send_compressed(encoding)
+            .accept_compressed(encoding)
+    }
+
     /// Creates a new `DocumentsClient` with a custom interceptor.
     ///
     /// Creates a new TLS connection but uses the provided interceptor instead of
@@ -56,11 +124,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<DocumentsClient, Error>` - Intercepted client or connection error
+    /// * `Result<DocumentsClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<DocumentsClient, Error> {
+    ) -> Result<DocumentsClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client =
             XDocumentsClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
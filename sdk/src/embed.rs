@@ -1,13 +1,24 @@
 //! Embedding service client.
 //!
 //! Provides gRPC clients for generating high-quality vector embeddings from text
-//! and images for semantic search and similarity operations.
+//! and images for semantic search and similarity operations, and a [`RequestBuilder`] for
+//! mixing text and image inputs in one request. The [`vectors`] module adds typed views of
+//! the returned embeddings.
+
+use crate::embed::client::EmbedClient;
+use crate::export::{Request, Response, Status};
+use crate::xai_api::{EmbedRequest, EmbedResponse};
+use std::time::Duration;
+
+pub use request::{EmbedBuildError, RequestBuilder};
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
+    use crate::export::codec::CompressionEncoding;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::embedder_client::EmbedderClient as XEmbedderClient;
 
     pub type EmbedClient = XEmbedderClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +31,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<EmbedClient, Error>` - Connected client or transport error
+    /// * `Result<EmbedClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<EmbedClient, Error> {
+    pub async fn new(api_key: &str) -> Result<EmbedClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XEmbedderClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +41,53 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `EmbedClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<EmbedClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<EmbedClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XEmbedderClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
+    /// Creates a new authenticated `EmbedClient` that compresses requests and accepts compressed
+    /// responses.
+    ///
+    /// Compression is off for the other constructors. Enable the crate's `gzip` feature to
+    /// use `CompressionEncoding::Gzip`.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `encoding` - Compression algorithm used in both directions
+    ///
+    /// # Returns
+    /// * `Result<EmbedClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_compression(
+        api_key: &str,
+        encoding: CompressionEncoding,
+    ) -> Result<EmbedClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        Ok(with_channel_and_compression(channel, api_key, encoding))
+    }
+
     /// Creates a new authenticated `EmbedClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -47,6 +105,25 @@ pub mod client {
         client
     }
 
+    /// Creates a new authenticated `EmbedClient` with compression on an existing gRPC channel.
+    ///
+    /// # Arguments
+    /// * `channel` - Existing TLS-secured gRPC channel to xAI API
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `encoding` - Compression algorithm used in both directions
+    ///
+    /// # Returns
+    /// * `EmbedClient` - Authenticated client using the provided channel
+    pub fn with_channel_and_compression(
+        channel: Channel,
+        api_key: &str,
+        encoding: CompressionEncoding,
+    ) -> EmbedClient {
+        with_channel(channel, api_key)
+            .send_compressed(encoding)
+            .accept_compressed(encoding)
+    }
+
     /// Creates a new `EmbedClient` with a custom interceptor.
     ///
     /// Creates a new TLS connection but uses the provided interceptor instead of
@@ -56,11 +133,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<EmbedClient, Error>` - Intercepted client or connection error
+    /// * `Result<EmbedClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<EmbedClient, Error> {
+    ) -> Result<EmbedClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client =
             XEmbedderClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
@@ -84,3 +161,266 @@ pub mod client {
         XEmbedderClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Builder for embedding requests.
+pub mod request {
+    use crate::xai_api::{
+        EmbedEncodingFormat, EmbedInput, EmbedRequest, ImageDetail, ImageUrlContent, embed_input,
+    };
+    use std::fmt;
+
+    /// Maximum number of inputs accepted in a single request.
+    pub const MAX_INPUTS: usize = 128;
+
+    /// Error returned by [`RequestBuilder::try_build`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum EmbedBuildError {
+        /// The request has no model name.
+        MissingModel,
+        /// The request has no inputs.
+        NoInputs,
+        /// More inputs were added than the API accepts.
+        TooManyInputs {
+            /// Number of inputs in the request.
+            count: usize,
+        },
+    }
+
+    impl fmt::Display for EmbedBuildError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                EmbedBuildError::MissingModel => write!(f, "`model` is empty"),
+                EmbedBuildError::NoInputs => write!(f, "`input` is empty"),
+                EmbedBuildError::TooManyInputs { count } => write!(
+                    f,
+                    "`input` has {count} entries, at most {MAX_INPUTS} are allowed"
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for EmbedBuildError {}
+
+    /// Builder for `EmbedRequest`.
+    ///
+    /// Inputs are embedded in the order they are added; each `Embedding` in the response
+    /// carries the `index` of the input it was produced from. Text and image inputs can be
+    /// mixed, but not every model supports both. Embeddings are returned as floats unless
+    /// [`encoding_format`](RequestBuilder::encoding_format) says otherwise. The API has no
+    /// parameter for the output dimensionality; it is fixed by the model.
+    ///
+    /// ```
+    /// use xai_sdk::embed::RequestBuilder;
+    ///
+    /// let request = RequestBuilder::new("embedding-model")
+    ///     .text("a red bicycle")
+    ///     .image_url("https://example.com/bicycle.png")
+    ///     .try_build()
+    ///     .unwrap();
+    /// assert_eq!(request.input.len(), 2);
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct RequestBuilder {
+        request: EmbedRequest,
+    }
+
+    impl RequestBuilder {
+        /// Creates a new `RequestBuilder` for the given model with no inputs.
+        pub fn new(model: impl Into<String>) -> Self {
+            Self {
+                request: EmbedRequest {
+                    model: model.into(),
+                    encoding_format: EmbedEncodingFormat::FormatFloat.into(),
+                    ..Default::default()
+                },
+            }
+        }
+
+        /// Sets the model name.
+        pub fn model(&mut self, model: impl Into<String>) -> &mut Self {
+            self.request.model = model.into();
+            self
+        }
+
+        /// Appends a text input.
+        pub fn text(&mut self, text: &str) -> &mut Self {
+            self.push(embed_input::Input::String(text.to_string()))
+        }
+
+        /// Appends multiple text inputs, preserving their order.
+        pub fn texts(&mut self, texts: Vec<String>) -> &mut Self {
+            for text in texts {
+                self.push(embed_input::Input::String(text));
+            }
+            self
+        }
+
+        /// Appends an image input, given as a URL or a base64 data URL.
+        pub fn image_url(&mut self, url: &str) -> &mut Self {
+            self.push(embed_input::Input::ImageUrl(ImageUrlContent {
+                image_url: url.to_string(),
+                detail: ImageDetail::DetailAuto.into(),
+            }))
+        }
+
+        /// Sets the format of the returned embeddings (floats by default).
+        pub fn encoding_format(&mut self, format: EmbedEncodingFormat) -> &mut Self {
+            self.request.encoding_format = format.into();
+            self
+        }
+
+        /// Sets the opaque end-user identifier stored with the request.
+        ///
+        /// See [`common::user`](crate::common::user) for what the field is used for.
+        pub fn user(&mut self, user: impl Into<String>) -> &mut Self {
+            self.request.user = user.into();
+            self
+        }
+
+        /// Sets the end-user identifier to the SHA-256 hex digest of `id`.
+        ///
+        /// See [`hash_id`](crate::common::user::hash_id).
+        pub fn user_hashed(&mut self, id: &str) -> &mut Self {
+            self.request.user = crate::common::user::hash_id(id);
+            self
+        }
+
+        /// Returns the configured `EmbedRequest`.
+        pub fn build(&self) -> EmbedRequest {
+            self.request.clone()
+        }
+
+        /// Validates the request and returns the configured `EmbedRequest`.
+        ///
+        /// # Returns
+        /// * `Ok(EmbedRequest)` - The request, if it has a model and 1 to [`MAX_INPUTS`]
+        ///   inputs
+        /// * `Err(EmbedBuildError)` - The first failed check
+        pub fn try_build(&self) -> Result<EmbedRequest, EmbedBuildError> {
+            if self.request.model.is_empty() {
+                return Err(EmbedBuildError::MissingModel);
+            }
+            match self.request.input.len() {
+                0 => Err(EmbedBuildError::NoInputs),
+                count if count > MAX_INPUTS => Err(EmbedBuildError::TooManyInputs { count }),
+                _ => Ok(self.build()),
+            }
+        }
+
+        fn push(&mut self, input: embed_input::Input) -> &mut Self {
+            self.request.input.push(EmbedInput { input: Some(input) });
+            self
+        }
+    }
+
+    impl From<RequestBuilder> for EmbedRequest {
+        fn from(builder: RequestBuilder) -> Self {
+            builder.request
+        }
+    }
+}
+
+/// Typed access to embedding vectors.
+///
+/// `EmbedResponse` nests vectors two levels deep (input, then feature vector) and encodes
+/// them as floats or base64 depending on the request's encoding format. These accessors
+/// flatten and decode them into rows.
+pub mod vectors {
+    use crate::xai_api::{EmbedResponse, FeatureVector};
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+
+    /// Decodes a feature vector from its float array, or else from its base64 string of
+    /// little-endian `f32` values. An undecodable base64 string yields an empty vector.
+    fn decode(vector: &FeatureVector) -> Vec<f32> {
+        if !vector.float_array.is_empty() || vector.base64_array.is_empty() {
+            return vector.float_array.clone();
+        }
+        STANDARD
+            .decode(&vector.base64_array)
+            .map(|bytes| {
+                bytes
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the length shared by all `vectors`, or `None` if there are none or they differ.
+    fn shared_len(vectors: &[Vec<f32>]) -> Option<usize> {
+        let first = vectors.first()?.len();
+        vectors
+            .iter()
+            .all(|vector| vector.len() == first)
+            .then_some(first)
+    }
+
+    impl EmbedResponse {
+        /// Returns every feature vector of the response as a row of floats.
+        ///
+        /// Rows follow input order: embeddings are sorted by their `index` (the position of
+        /// the input in the request), whatever order the server returned them in. Text
+        /// inputs produce one vector each, so row `i` belongs to input `i` for text-only
+        /// requests; an image input may produce several vectors, which appear consecutively.
+        /// Base64-encoded vectors are decoded, so the result is the same for both encoding
+        /// formats.
+        ///
+        /// # Returns
+        /// * `Vec<Vec<f32>>` - One row per feature vector
+        pub fn vectors(&self) -> Vec<Vec<f32>> {
+            let mut embeddings: Vec<_> = self.embeddings.iter().collect();
+            embeddings.sort_by_key(|embedding| embedding.index);
+            embeddings
+                .into_iter()
+                .flat_map(|embedding| embedding.embeddings.iter().map(decode))
+                .collect()
+        }
+
+        /// Returns the dimension shared by all vectors of the response.
+        ///
+        /// # Returns
+        /// * `Some(usize)` - Length of every vector
+        /// * `None` - If the response has no vectors or their lengths differ
+        pub fn dimensions(&self) -> Option<usize> {
+            shared_len(&self.vectors())
+        }
+
+        /// Returns the vectors as a matrix with one row per vector, in the order of
+        /// [`vectors`](EmbedResponse::vectors).
+        ///
+        /// # Returns
+        /// * `Some(Array2<f32>)` - Matrix of shape `(vectors, dimensions)`; `(0, 0)` for a
+        ///   response without vectors
+        /// * `None` - If the vectors have different lengths
+        #[cfg(feature = "ndarray")]
+        pub fn as_array2(&self) -> Option<ndarray::Array2<f32>> {
+            let vectors = self.vectors();
+            if vectors.is_empty() {
+                return Some(ndarray::Array2::zeros((0, 0)));
+            }
+            let dimensions = shared_len(&vectors)?;
+            let rows = vectors.len();
+            let flat: Vec<f32> = vectors.into_iter().flatten().collect();
+            ndarray::Array2::from_shape_vec((rows, dimensions), flat).ok()
+        }
+    }
+}
+
+/// Computes embeddings within `timeout`; see [`timeout::unary`](crate::common::timeout::unary).
+///
+/// # Arguments
+/// * `client` - Embed client
+/// * `request` - Request to send
+/// * `timeout` - Maximum duration of the call
+///
+/// # Returns
+/// * `Ok(Response<EmbedResponse>)` - The call's response
+/// * `Err(Status)` - The call's status, or `DeadlineExceeded` once `timeout` expires
+pub async fn embed_timeout(
+    client: &mut EmbedClient,
+    request: Request<EmbedRequest>,
+    timeout: Duration,
+) -> Result<Response<EmbedResponse>, Status> {
+    crate::common::timeout::unary(request, timeout, |r| client.embed(r)).await
+}
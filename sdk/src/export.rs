@@ -7,20 +7,30 @@
 
 /// Core gRPC types re-exported from `tonic`.
 ///
+/// - [`IntoRequest`] - Conversion of a message or a [`Request`] into a [`Request`]
 /// - [`Request`] - Wrapper for gRPC request messages
 /// - [`Response`] - Wrapper for gRPC response messages
 /// - [`Status`] - gRPC status codes and error information
 /// - [`Streaming`] - Stream of gRPC response messages
-pub use tonic::{Request, Response, Status, Streaming};
+pub use tonic::{IntoRequest, Request, Response, Status, Streaming};
 
 /// gRPC transport types re-exported from `tonic::transport`.
 ///
+/// - [`Certificate`] - PEM-encoded CA certificate for custom trust roots
 /// - [`Channel`] - gRPC connection channel
 /// - [`ClientTlsConfig`] - TLS configuration for secure connections
 /// - [`Endpoint`] - gRPC endpoint configuration
 /// - [`Error`] - Transport-level errors
+/// - [`Identity`] - PEM-encoded client certificate and key for mutual TLS
 pub mod transport {
-    pub use tonic::transport::{Channel, ClientTlsConfig, Endpoint, Error};
+    pub use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Error, Identity};
+}
+
+/// gRPC codec types re-exported from `tonic::codec`.
+///
+/// - [`CompressionEncoding`] - Message compression algorithms (variants depend on enabled features)
+pub mod codec {
+    pub use tonic::codec::CompressionEncoding;
 }
 
 /// gRPC service utilities re-exported from `tonic::service`.
@@ -37,7 +47,8 @@ pub mod service {
 
 /// gRPC metadata types re-exported from `tonic::metadata`.
 ///
+/// - [`MetadataMap`] - Metadata of a request or response
 /// - [`MetadataValue`] - HTTP header/metadata values
 pub mod metadata {
-    pub use tonic::metadata::MetadataValue;
+    pub use tonic::metadata::{MetadataMap, MetadataValue};
 }
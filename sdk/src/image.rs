@@ -1,13 +1,22 @@
 //! Image generation service client.
 //!
 //! Provides gRPC clients for generating high-quality images from text prompts
-//! using xAI's advanced image generation models.
+//! using xAI's advanced image generation models, and a [`RequestBuilder`] for
+//! configuring generation options.
+
+use crate::export::{Request, Response, Status};
+use crate::image::client::ImageClient;
+use crate::xai_api::{GenerateImageRequest, ImageResponse};
+use std::time::Duration;
+
+pub use request::{ImageBuildError, RequestBuilder};
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::image_client::ImageClient as XImageClient;
 
     pub type ImageClient = XImageClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +29,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<ImageClient, Error>` - Connected client or transport error
+    /// * `Result<ImageClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<ImageClient, Error> {
+    pub async fn new(api_key: &str) -> Result<ImageClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XImageClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +39,33 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `ImageClient` with custom gRPC message size limits.
+    ///
+    /// Base64-encoded images (`ImageFormat::ImgFormatBase64`) can exceed the default 4 MiB
+    /// response limit; see [message size limits](crate::common#message-size-limits).
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<ImageClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<ImageClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XImageClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `ImageClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -56,11 +92,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<ImageClient, Error>` - Intercepted client or connection error
+    /// * `Result<ImageClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<ImageClient, Error> {
+    ) -> Result<ImageClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client = XImageClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
         Ok(client)
@@ -83,3 +119,242 @@ pub mod client {
         XImageClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Builder for image generation requests.
+pub mod request {
+    use crate::xai_api::{
+        GenerateImageRequest, ImageAspectRatio, ImageDetail, ImageFormat, ImageResolution,
+        ImageUrlContent,
+    };
+    use std::fmt;
+
+    /// Maximum number of images generated by a single request.
+    pub const MAX_IMAGES: u32 = 10;
+
+    /// Error returned by [`RequestBuilder::try_build`].
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ImageBuildError {
+        /// The request has no model name.
+        MissingModel,
+        /// The request has no prompt.
+        MissingPrompt,
+        /// The number of images (`n`) is outside `[1, MAX_IMAGES]`.
+        InvalidN {
+            /// The rejected value.
+            n: u32,
+        },
+        /// An enum option was set to its `Invalid` value.
+        InvalidOption {
+            /// Name of the offending field.
+            field: &'static str,
+        },
+    }
+
+    impl fmt::Display for ImageBuildError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ImageBuildError::MissingModel => write!(f, "`model` is empty"),
+                ImageBuildError::MissingPrompt => write!(f, "`prompt` is empty"),
+                ImageBuildError::InvalidN { n } => {
+                    write!(f, "`n` is {n}, expected a value in [1, {MAX_IMAGES}]")
+                }
+                ImageBuildError::InvalidOption { field } => {
+                    write!(f, "`{field}` is set to its invalid value")
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for ImageBuildError {}
+
+    /// Builder for `GenerateImageRequest`.
+    ///
+    /// Images are returned as URLs unless [`format`](RequestBuilder::format) says otherwise.
+    /// Aspect ratio and resolution are only honored by `grok-imagine` models. The request has
+    /// no quality or seed parameter; quality is chosen by the model.
+    ///
+    /// ```
+    /// use xai_sdk::api::{ImageAspectRatio, ImageResolution};
+    /// use xai_sdk::image::RequestBuilder;
+    ///
+    /// let request = RequestBuilder::new("grok-imagine-image")
+    ///     .prompt("A lighthouse at dawn")
+    ///     .n(2)
+    ///     .aspect_ratio(ImageAspectRatio::ImgAspectRatio169)
+    ///     .resolution(ImageResolution::ImgResolution2k)
+    ///     .try_build()
+    ///     .unwrap();
+    /// assert_eq!(request.n, Some(2));
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct RequestBuilder {
+        request: GenerateImageRequest,
+        n: Option<u32>,
+        detail: ImageDetail,
+    }
+
+    impl RequestBuilder {
+        /// Creates a new `RequestBuilder` for the given model with no prompt.
+        pub fn new(model: impl Into<String>) -> Self {
+            Self {
+                request: GenerateImageRequest {
+                    model: model.into(),
+                    format: ImageFormat::ImgFormatUrl.into(),
+                    ..Default::default()
+                },
+                n: None,
+                detail: ImageDetail::DetailAuto,
+            }
+        }
+
+        /// Sets the model name.
+        pub fn model(&mut self, model: impl Into<String>) -> &mut Self {
+            self.request.model = model.into();
+            self
+        }
+
+        /// Sets the prompt describing the image.
+        pub fn prompt(&mut self, prompt: impl Into<String>) -> &mut Self {
+            self.request.prompt = prompt.into();
+            self
+        }
+
+        /// Sets the number of images to generate, in `[1, MAX_IMAGES]` (default: 1).
+        pub fn n(&mut self, n: u32) -> &mut Self {
+            self.n = Some(n);
+            self
+        }
+
+        /// Sets the aspect ratio of the generated images (default: 1:1).
+        pub fn aspect_ratio(&mut self, aspect_ratio: ImageAspectRatio) -> &mut Self {
+            self.request.aspect_ratio = Some(aspect_ratio.into());
+            self
+        }
+
+        /// Sets the resolution of the generated images (default: 1k).
+        pub fn resolution(&mut self, resolution: ImageResolution) -> &mut Self {
+            self.request.resolution = Some(resolution.into());
+            self
+        }
+
+        /// Sets whether images are returned as URLs (default) or base64 strings.
+        pub fn format(&mut self, format: ImageFormat) -> &mut Self {
+            self.request.format = format.into();
+            self
+        }
+
+        /// Appends an input image to edit or use as a reference, given as a URL or a base64
+        /// data URL.
+        pub fn image_url(&mut self, url: &str) -> &mut Self {
+            self.request.images.push(ImageUrlContent {
+                image_url: url.to_string(),
+                detail: self.detail.into(),
+            });
+            self
+        }
+
+        /// Sets the detail level at which input images are processed (default: auto).
+        ///
+        /// Applies to every input image, including ones added before this call.
+        pub fn detail(&mut self, detail: ImageDetail) -> &mut Self {
+            self.detail = detail;
+            for image in &mut self.request.images {
+                image.detail = detail.into();
+            }
+            self
+        }
+
+        /// Sets the opaque end-user identifier stored with the request.
+        ///
+        /// See [`common::user`](crate::common::user) for what the field is used for.
+        pub fn user(&mut self, user: impl Into<String>) -> &mut Self {
+            self.request.user = user.into();
+            self
+        }
+
+        /// Sets the end-user identifier to the SHA-256 hex digest of `id`.
+        ///
+        /// See [`hash_id`](crate::common::user::hash_id).
+        pub fn user_hashed(&mut self, id: &str) -> &mut Self {
+            self.request.user = crate::common::user::hash_id(id);
+            self
+        }
+
+        /// Returns the configured `GenerateImageRequest` without validating it.
+        ///
+        /// `n` values above `i32::MAX` saturate and invalid requests are only rejected by the
+        /// server; use [`try_build()`](RequestBuilder::try_build) instead.
+        #[deprecated(
+            since = "0.10.0",
+            note = "use `try_build`, which rejects invalid requests instead of sending them"
+        )]
+        pub fn build(&self) -> GenerateImageRequest {
+            self.request()
+        }
+
+        /// Validates the request and returns the configured `GenerateImageRequest`.
+        ///
+        /// # Returns
+        /// * `Ok(GenerateImageRequest)` - The request, if it has a model and a prompt, `n` is
+        ///   in `[1, MAX_IMAGES]` and no option is set to its `Invalid` value
+        /// * `Err(ImageBuildError)` - The first failed check
+        pub fn try_build(&self) -> Result<GenerateImageRequest, ImageBuildError> {
+            if self.request.model.is_empty() {
+                return Err(ImageBuildError::MissingModel);
+            }
+            if self.request.prompt.is_empty() {
+                return Err(ImageBuildError::MissingPrompt);
+            }
+            if let Some(n) = self.n
+                && !(1..=MAX_IMAGES).contains(&n)
+            {
+                return Err(ImageBuildError::InvalidN { n });
+            }
+            let invalid = [
+                (
+                    "format",
+                    self.request.format == ImageFormat::ImgFormatInvalid as i32,
+                ),
+                (
+                    "aspect_ratio",
+                    self.request.aspect_ratio
+                        == Some(ImageAspectRatio::ImgAspectRatioInvalid as i32),
+                ),
+                (
+                    "resolution",
+                    self.request.resolution == Some(ImageResolution::ImgResolutionInvalid as i32),
+                ),
+                ("detail", self.detail == ImageDetail::DetailInvalid),
+            ];
+            if let Some((field, _)) = invalid.into_iter().find(|(_, invalid)| *invalid) {
+                return Err(ImageBuildError::InvalidOption { field });
+            }
+            Ok(self.request())
+        }
+
+        fn request(&self) -> GenerateImageRequest {
+            GenerateImageRequest {
+                n: self.n.map(|n| i32::try_from(n).unwrap_or(i32::MAX)),
+                ..self.request.clone()
+            }
+        }
+    }
+}
+
+/// Generates images within `timeout`; see [`timeout::unary`](crate::common::timeout::unary).
+///
+/// # Arguments
+/// * `client` - Image client
+/// * `request` - Request to send
+/// * `timeout` - Maximum duration of the call
+///
+/// # Returns
+/// * `Ok(Response<ImageResponse>)` - The call's response
+/// * `Err(Status)` - The call's status, or `DeadlineExceeded` once `timeout` expires
+pub async fn generate_timeout(
+    client: &mut ImageClient,
+    request: Request<GenerateImageRequest>,
+    timeout: Duration,
+) -> Result<Response<ImageResponse>, Status> {
+    crate::common::timeout::unary(request, timeout, |r| client.generate_image(r)).await
+}
@@ -0,0 +1,711 @@
+//! Interoperability with other API formats.
+//!
+//! Converts between the SDK's gRPC types and the JSON shapes of other chat APIs, so existing
+//! clients and frontends can be reused. Requires the `openai` feature.
+
+/// Conversions to and from the OpenAI Chat Completions JSON format.
+///
+/// [`from_openai_request`] accepts an OpenAI request body, so a proxy can forward OpenAI
+/// traffic to xAI; see its documentation for the supported fields. Responses map onto the
+/// `chat.completion` / `chat.completion.chunk` objects: each output becomes one entry of
+/// `choices`, with `message` (or `delta` when streaming) holding the role, content and
+/// client-side tool calls. Fields without an OpenAI counterpart are
+/// represented as follows:
+///
+/// - `reasoning_content` is emitted as a non-standard `reasoning_content` field on the
+///   message or delta, and only when non-empty
+/// - `encrypted_content` is opaque and only meaningful to the xAI API, so it is dropped
+/// - server-side tool calls (web search, code execution, ...) are dropped, since OpenAI
+///   clients would try to execute every entry of `tool_calls` themselves
+/// - response-level `citations` are emitted as a non-standard top-level `citations` array
+/// - `finish_reason` maps `stop` and `tool_calls` directly; `max_len`, `max_context` and
+///   `time_limit` become `length`; an unset reason becomes `null`
+/// - usage maps to `prompt_tokens`, `completion_tokens` and `total_tokens`, with reasoning
+///   and cached prompt tokens under `completion_tokens_details` / `prompt_tokens_details`
+pub mod openai {
+    use crate::xai_api::{
+        CompletionMessage, CompletionOutput, CompletionOutputChunk, Content, FinishReason,
+        FormatType, Function, FunctionCall, GetChatCompletionChunk, GetChatCompletionResponse,
+        GetCompletionsRequest, ImageDetail, ImageUrlContent, Message, MessageRole, ReasoningEffort,
+        ResponseFormat, SamplingUsage, Tool, ToolCall, ToolCallType, ToolChoice, ToolMode, content,
+        tool, tool_call, tool_choice,
+    };
+    use serde_json::{Map, Value, json};
+    use std::collections::HashMap;
+    use std::fmt;
+
+    /// Converts a complete response into an OpenAI `chat.completion` object.
+    ///
+    /// # Arguments
+    /// * `response` - Response returned by `get_completion` or assembled from chunks
+    ///
+    /// # Returns
+    /// * `Value` - The `chat.completion` JSON object
+    pub fn to_openai_chat_completion(response: &GetChatCompletionResponse) -> Value {
+        let choices: Vec<Value> = response.outputs.iter().map(choice).collect();
+        let mut object = json!({
+            "id": response.id,
+            "object": "chat.completion",
+            "created": response.created.map_or(0, |created| created.seconds),
+            "model": response.model,
+            "choices": choices,
+        });
+        add_common_fields(
+            &mut object,
+            &response.system_fingerprint,
+            response.usage.as_ref(),
+            &response.citations,
+        );
+        object
+    }
+
+    /// Converts a single streaming chunk into an OpenAI `chat.completion.chunk` object.
+    ///
+    /// Tool calls are numbered within this chunk only. To relay a whole stream, use a
+    /// [`ChunkConverter`], which keeps each tool call's `index` stable across chunks.
+    ///
+    /// # Arguments
+    /// * `chunk` - Chunk received from `get_completion_chunk`
+    ///
+    /// # Returns
+    /// * `Value` - The `chat.completion.chunk` JSON object
+    pub fn to_openai_chunk(chunk: &GetChatCompletionChunk) -> Value {
+        ChunkConverter::new().convert(chunk)
+    }
+
+    /// Converts the chunks of one stream into OpenAI `chat.completion.chunk` objects.
+    ///
+    /// OpenAI clients merge streamed tool-call deltas by `index`, so a call must keep its
+    /// index in every chunk and distinct calls need distinct indices. Tool calls usually
+    /// arrive in separate chunks; the converter numbers them per output in order of first
+    /// appearance, keyed by tool-call id. Use one converter per stream.
+    #[derive(Debug, Default)]
+    pub struct ChunkConverter {
+        tool_call_indices: HashMap<i32, HashMap<String, usize>>,
+    }
+
+    impl ChunkConverter {
+        /// Creates a converter for a new stream.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Converts the next chunk of the stream.
+        ///
+        /// # Arguments
+        /// * `chunk` - Chunk received from `get_completion_chunk`
+        ///
+        /// # Returns
+        /// * `Value` - The `chat.completion.chunk` JSON object
+        pub fn convert(&mut self, chunk: &GetChatCompletionChunk) -> Value {
+            let choices: Vec<Value> = chunk
+                .outputs
+                .iter()
+                .map(|output| {
+                    let indices = self.tool_call_indices.entry(output.index).or_default();
+                    chunk_choice(output, indices)
+                })
+                .collect();
+            let mut object = json!({
+                "id": chunk.id,
+                "object": "chat.completion.chunk",
+                "created": chunk.created.map_or(0, |created| created.seconds),
+                "model": chunk.model,
+                "choices": choices,
+            });
+            add_common_fields(
+                &mut object,
+                &chunk.system_fingerprint,
+                chunk.usage.as_ref(),
+                &chunk.citations,
+            );
+            object
+        }
+    }
+
+    fn add_common_fields(
+        object: &mut Value,
+        system_fingerprint: &str,
+        usage: Option<&SamplingUsage>,
+        citations: &[String],
+    ) {
+        let Value::Object(map) = object else {
+            return;
+        };
+        if !system_fingerprint.is_empty() {
+            map.insert("system_fingerprint".into(), json!(system_fingerprint));
+        }
+        if let Some(usage) = usage {
+            map.insert("usage".into(), usage_object(usage));
+        }
+        if !citations.is_empty() {
+            map.insert("citations".into(), json!(citations));
+        }
+    }
+
+    fn choice(output: &CompletionOutput) -> Value {
+        let default_message = CompletionMessage::default();
+        let message = output.message.as_ref().unwrap_or(&default_message);
+
+        let mut body = Map::new();
+        body.insert("role".into(), json!(role_name(message.role)));
+        body.insert(
+            "content".into(),
+            if message.content.is_empty() && !message.tool_calls.is_empty() {
+                Value::Null
+            } else {
+                json!(message.content)
+            },
+        );
+        if !message.reasoning_content.is_empty() {
+            body.insert("reasoning_content".into(), json!(message.reasoning_content));
+        }
+        let tool_calls = tool_calls(&message.tool_calls, None);
+        if !tool_calls.is_empty() {
+            body.insert("tool_calls".into(), Value::Array(tool_calls));
+        }
+
+        json!({
+            "index": output.index,
+            "message": body,
+            "finish_reason": finish_reason(output.finish_reason),
+            "logprobs": null,
+        })
+    }
+
+    fn chunk_choice(
+        output: &CompletionOutputChunk,
+        tool_call_indices: &mut HashMap<String, usize>,
+    ) -> Value {
+        let mut delta = Map::new();
+        if let Some(d) = &output.delta {
+            if d.role != MessageRole::InvalidRole as i32 {
+                delta.insert("role".into(), json!(role_name(d.role)));
+            }
+            if !d.content.is_empty() {
+                delta.insert("content".into(), json!(d.content));
+            }
+            if !d.reasoning_content.is_empty() {
+                delta.insert("reasoning_content".into(), json!(d.reasoning_content));
+            }
+            let tool_calls = tool_calls(&d.tool_calls, Some(tool_call_indices));
+            if !tool_calls.is_empty() {
+                delta.insert("tool_calls".into(), Value::Array(tool_calls));
+            }
+        }
+
+        json!({
+            "index": output.index,
+            "delta": delta,
+            "finish_reason": finish_reason(output.finish_reason),
+            "logprobs": null,
+        })
+    }
+
+    /// Client-side tool calls in OpenAI form; streamed calls carry the `index` recorded for
+    /// their id in `indices`, or the next free one.
+    fn tool_calls(
+        calls: &[ToolCall],
+        mut indices: Option<&mut HashMap<String, usize>>,
+    ) -> Vec<Value> {
+        calls
+            .iter()
+            .filter(|call| call.r#type == ToolCallType::ClientSideTool as i32)
+            .filter_map(|call| {
+                let Some(tool_call::Tool::Function(function)) = &call.tool else {
+                    return None;
+                };
+                Some((call, function))
+            })
+            .map(|(call, function)| {
+                let mut value = json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": {
+                        "name": function.name,
+                        "arguments": function.arguments,
+                    },
+                });
+                if let Some(indices) = indices.as_deref_mut()
+                    && let Value::Object(map) = &mut value
+                {
+                    let next = indices.len();
+                    let index = *indices.entry(call.id.clone()).or_insert(next);
+                    map.insert("index".into(), json!(index));
+                }
+                value
+            })
+            .collect()
+    }
+
+    fn usage_object(usage: &SamplingUsage) -> Value {
+        json!({
+            "prompt_tokens": usage.prompt_tokens,
+            "completion_tokens": usage.completion_tokens,
+            "total_tokens": usage.total_tokens,
+            "prompt_tokens_details": {
+                "cached_tokens": usage.cached_prompt_text_tokens,
+            },
+            "completion_tokens_details": {
+                "reasoning_tokens": usage.reasoning_tokens,
+            },
+        })
+    }
+
+    fn role_name(role: i32) -> &'static str {
+        match MessageRole::try_from(role) {
+            Ok(MessageRole::RoleUser) => "user",
+            Ok(MessageRole::RoleSystem) => "system",
+            Ok(MessageRole::RoleTool) => "tool",
+            _ => "assistant",
+        }
+    }
+
+    fn finish_reason(reason: i32) -> Value {
+        match FinishReason::try_from(reason) {
+            Ok(FinishReason::ReasonStop) => json!("stop"),
+            Ok(FinishReason::ReasonToolCalls) => json!("tool_calls"),
+            Ok(
+                FinishReason::ReasonMaxLen
+                | FinishReason::ReasonMaxContext
+                | FinishReason::ReasonTimeLimit,
+            ) => json!("length"),
+            Ok(FinishReason::ReasonInvalid) | Err(_) => Value::Null,
+        }
+    }
+
+    /// Error returned by [`from_openai_request`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ConvertError {
+        /// A required field is absent.
+        MissingField {
+            /// Path of the field, e.g. `messages[0].role`.
+            field: String,
+        },
+        /// A field has the wrong JSON type or an unrecognized value.
+        InvalidField {
+            /// Path of the field.
+            field: String,
+            /// What was expected.
+            expected: &'static str,
+        },
+        /// A field is valid OpenAI input but has no xAI equivalent.
+        UnsupportedField {
+            /// Path of the field.
+            field: String,
+        },
+    }
+
+    impl fmt::Display for ConvertError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ConvertError::MissingField { field } => write!(f, "missing field `{field}`"),
+                ConvertError::InvalidField { field, expected } => {
+                    write!(f, "field `{field}` must be {expected}")
+                }
+                ConvertError::UnsupportedField { field } => {
+                    write!(f, "field `{field}` is not supported by the xAI API")
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for ConvertError {}
+
+    /// Converts an OpenAI Chat Completions request body into a `GetCompletionsRequest`.
+    ///
+    /// Maps `model`, `messages`, `temperature`, `top_p`, `max_tokens` (or
+    /// `max_completion_tokens`), `n`, `stop`, `seed`, `user`, `presence_penalty`,
+    /// `frequency_penalty`, `logprobs`, `top_logprobs`, `tools`, `tool_choice`,
+    /// `parallel_tool_calls`, `response_format` and `reasoning_effort`. `stream` and
+    /// `stream_options` are ignored, since streaming is chosen by the RPC that sends the
+    /// request. Any other field is rejected rather than silently dropped.
+    ///
+    /// Messages may use the `system`, `developer` (mapped to system), `user`, `assistant` and
+    /// `tool` roles. Content is a string, `null` or an array of `text` and `image_url` parts.
+    /// Assistant messages keep their `tool_calls` and a non-standard `reasoning_content`, so a
+    /// message produced by [`to_openai_chat_completion`] converts back unchanged.
+    ///
+    /// # Arguments
+    /// * `value` - The request body as JSON
+    ///
+    /// # Returns
+    /// * `Ok(GetCompletionsRequest)` - The equivalent xAI request
+    /// * `Err(ConvertError)` - The first missing, invalid or unsupported field
+    pub fn from_openai_request(value: &Value) -> Result<GetCompletionsRequest, ConvertError> {
+        let body = object(value, "request")?;
+        let mut request = GetCompletionsRequest::default();
+
+        for (key, field) in body {
+            match key.as_str() {
+                "model" => request.model = string(field, key)?.to_string(),
+                "messages" => {
+                    request.messages = array(field, key)?
+                        .iter()
+                        .enumerate()
+                        .map(|(i, message)| convert_message(message, &format!("messages[{i}]")))
+                        .collect::<Result<_, _>>()?;
+                }
+                "temperature" => request.temperature = optional(field, key, float)?,
+                "top_p" => request.top_p = optional(field, key, float)?,
+                "max_tokens" | "max_completion_tokens" => {
+                    request.max_tokens = optional(field, key, integer)?
+                }
+                "n" => request.n = optional(field, key, integer)?,
+                "seed" => request.seed = optional(field, key, integer)?,
+                "presence_penalty" => request.presence_penalty = optional(field, key, float)?,
+                "frequency_penalty" => request.frequency_penalty = optional(field, key, float)?,
+                "top_logprobs" => request.top_logprobs = optional(field, key, integer)?,
+                "logprobs" => request.logprobs = optional(field, key, boolean)?.unwrap_or(false),
+                "parallel_tool_calls" => {
+                    request.parallel_tool_calls = optional(field, key, boolean)?
+                }
+                "user" => request.user = optional(field, key, string)?.unwrap_or("").to_string(),
+                "stop" => {
+                    request.stop = match field {
+                        Value::Null => Vec::new(),
+                        Value::String(stop) => vec![stop.clone()],
+                        _ => array(field, key)?
+                            .iter()
+                            .enumerate()
+                            .map(|(i, s)| string(s, &format!("stop[{i}]")).map(str::to_string))
+                            .collect::<Result<_, _>>()?,
+                    }
+                }
+                "tools" => {
+                    request.tools = array(field, key)?
+                        .iter()
+                        .enumerate()
+                        .map(|(i, tool)| convert_tool(tool, &format!("tools[{i}]")))
+                        .collect::<Result<_, _>>()?;
+                }
+                "tool_choice" => request.tool_choice = Some(convert_tool_choice(field)?),
+                "response_format" => {
+                    request.response_format = Some(convert_response_format(field)?)
+                }
+                "reasoning_effort" => {
+                    request.reasoning_effort = match string(field, key)? {
+                        "low" => Some(ReasoningEffort::EffortLow as i32),
+                        "medium" => Some(ReasoningEffort::EffortMedium as i32),
+                        "high" => Some(ReasoningEffort::EffortHigh as i32),
+                        _ => return Err(invalid(key, "\"low\", \"medium\" or \"high\"")),
+                    }
+                }
+                "stream" | "stream_options" => {}
+                _ => return Err(ConvertError::UnsupportedField { field: key.clone() }),
+            }
+        }
+
+        if request.model.is_empty() {
+            return Err(missing("model"));
+        }
+        if request.messages.is_empty() {
+            return Err(missing("messages"));
+        }
+        Ok(request)
+    }
+
+    fn convert_message(value: &Value, path: &str) -> Result<Message, ConvertError> {
+        let body = object(value, path)?;
+        let field = |key: &str| format!("{path}.{key}");
+        let role_path = field("role");
+        let role = match string(
+            body.get("role").ok_or_else(|| missing(&role_path))?,
+            &role_path,
+        )? {
+            "system" | "developer" => MessageRole::RoleSystem,
+            "user" => MessageRole::RoleUser,
+            "assistant" => MessageRole::RoleAssistant,
+            "tool" => MessageRole::RoleTool,
+            "function" => return Err(ConvertError::UnsupportedField { field: role_path }),
+            _ => return Err(invalid(&role_path, "a known role")),
+        };
+        let mut message = Message {
+            role: role.into(),
+            ..Default::default()
+        };
+
+        for (key, value) in body {
+            let path = field(key);
+            match key.as_str() {
+                "role" => {}
+                "content" => message.content = convert_content(value, &path)?,
+                "name" => message.name = optional(value, &path, string)?.unwrap_or("").to_string(),
+                "tool_call_id" => {
+                    message.tool_call_id = optional(value, &path, string)?.map(str::to_string)
+                }
+                "reasoning_content" => {
+                    message.reasoning_content = optional(value, &path, string)?.map(str::to_string)
+                }
+                "tool_calls" => {
+                    if !value.is_null() {
+                        message.tool_calls = array(value, &path)?
+                            .iter()
+                            .enumerate()
+                            .map(|(i, call)| convert_tool_call(call, &format!("{path}[{i}]")))
+                            .collect::<Result<_, _>>()?;
+                    }
+                }
+                _ => return Err(ConvertError::UnsupportedField { field: path }),
+            }
+        }
+        Ok(message)
+    }
+
+    fn convert_content(value: &Value, path: &str) -> Result<Vec<Content>, ConvertError> {
+        match value {
+            Value::Null => Ok(Vec::new()),
+            Value::String(text) => Ok(vec![Content::from(text.as_str())]),
+            _ => array(value, path)?
+                .iter()
+                .enumerate()
+                .map(|(i, part)| {
+                    let path = format!("{path}[{i}]");
+                    let body = object(part, &path)?;
+                    let type_path = format!("{path}.type");
+                    match string(required(body, "type", &path)?, &type_path)? {
+                        "text" => {
+                            let text_path = format!("{path}.text");
+                            Ok(Content::from(string(
+                                required(body, "text", &path)?,
+                                &text_path,
+                            )?))
+                        }
+                        "image_url" => {
+                            let image_path = format!("{path}.image_url");
+                            let image = required(body, "image_url", &path)?;
+                            let (url, detail) = match image {
+                                Value::String(url) => (url.as_str(), None),
+                                _ => {
+                                    let image_body = object(image, &image_path)?;
+                                    let url_path = format!("{image_path}.url");
+                                    let detail_path = format!("{image_path}.detail");
+                                    (
+                                        string(
+                                            required(image_body, "url", &image_path)?,
+                                            &url_path,
+                                        )?,
+                                        image_body
+                                            .get("detail")
+                                            .map(|d| string(d, &detail_path))
+                                            .transpose()?,
+                                    )
+                                }
+                            };
+                            let detail = match detail {
+                                None | Some("auto") => ImageDetail::DetailAuto,
+                                Some("low") => ImageDetail::DetailLow,
+                                Some("high") => ImageDetail::DetailHigh,
+                                Some(_) => {
+                                    return Err(invalid(
+                                        &format!("{image_path}.detail"),
+                                        "\"auto\", \"low\" or \"high\"",
+                                    ));
+                                }
+                            };
+                            Ok(Content {
+                                content: Some(content::Content::ImageUrl(ImageUrlContent {
+                                    image_url: url.to_string(),
+                                    detail: detail.into(),
+                                })),
+                            })
+                        }
+                        _ => Err(ConvertError::UnsupportedField { field: type_path }),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn convert_tool_call(value: &Value, path: &str) -> Result<ToolCall, ConvertError> {
+        let body = object(value, path)?;
+        let function_path = format!("{path}.function");
+        let function = object(required(body, "function", path)?, &function_path)?;
+        Ok(ToolCall {
+            id: string(required(body, "id", path)?, &format!("{path}.id"))?.to_string(),
+            r#type: ToolCallType::ClientSideTool.into(),
+            tool: Some(tool_call::Tool::Function(FunctionCall {
+                name: string(
+                    required(function, "name", &function_path)?,
+                    &format!("{function_path}.name"),
+                )?
+                .to_string(),
+                arguments: function
+                    .get("arguments")
+                    .map(|a| string(a, &format!("{function_path}.arguments")))
+                    .transpose()?
+                    .unwrap_or("{}")
+                    .to_string(),
+            })),
+            ..Default::default()
+        })
+    }
+
+    fn convert_tool(value: &Value, path: &str) -> Result<Tool, ConvertError> {
+        let body = object(value, path)?;
+        let type_path = format!("{path}.type");
+        if string(required(body, "type", path)?, &type_path)? != "function" {
+            return Err(ConvertError::UnsupportedField { field: type_path });
+        }
+        let function_path = format!("{path}.function");
+        let function = object(required(body, "function", path)?, &function_path)?;
+        let text = |key: &str| -> Result<String, ConvertError> {
+            let path = format!("{function_path}.{key}");
+            Ok(function
+                .get(key)
+                .map(|v| optional(v, &path, string))
+                .transpose()?
+                .flatten()
+                .unwrap_or("")
+                .to_string())
+        };
+        let name = text("name")?;
+        if name.is_empty() {
+            return Err(missing(&format!("{function_path}.name")));
+        }
+        Ok(Tool {
+            tool: Some(tool::Tool::Function(Function {
+                name,
+                description: text("description")?,
+                strict: function
+                    .get("strict")
+                    .map(|v| optional(v, &format!("{function_path}.strict"), boolean))
+                    .transpose()?
+                    .flatten()
+                    .unwrap_or(false),
+                parameters: function
+                    .get("parameters")
+                    .filter(|p| !p.is_null())
+                    .map(Value::to_string)
+                    .unwrap_or_default(),
+            })),
+        })
+    }
+
+    fn convert_tool_choice(value: &Value) -> Result<ToolChoice, ConvertError> {
+        let choice = match value {
+            Value::String(mode) => tool_choice::ToolChoice::Mode(
+                match mode.as_str() {
+                    "auto" => ToolMode::Auto,
+                    "none" => ToolMode::None,
+                    "required" => ToolMode::Required,
+                    _ => return Err(invalid("tool_choice", "\"auto\", \"none\" or \"required\"")),
+                }
+                .into(),
+            ),
+            _ => {
+                let body = object(value, "tool_choice")?;
+                let function = object(
+                    required(body, "function", "tool_choice")?,
+                    "tool_choice.function",
+                )?;
+                tool_choice::ToolChoice::FunctionName(
+                    string(
+                        required(function, "name", "tool_choice.function")?,
+                        "tool_choice.function.name",
+                    )?
+                    .to_string(),
+                )
+            }
+        };
+        Ok(ToolChoice {
+            tool_choice: Some(choice),
+        })
+    }
+
+    fn convert_response_format(value: &Value) -> Result<ResponseFormat, ConvertError> {
+        let body = object(value, "response_format")?;
+        let (format_type, schema) = match string(
+            required(body, "type", "response_format")?,
+            "response_format.type",
+        )? {
+            "text" => (FormatType::Text, None),
+            "json_object" => (FormatType::JsonObject, None),
+            "json_schema" => {
+                let json_schema = object(
+                    required(body, "json_schema", "response_format")?,
+                    "response_format.json_schema",
+                )?;
+                let schema = required(json_schema, "schema", "response_format.json_schema")?;
+                (FormatType::JsonSchema, Some(schema.to_string()))
+            }
+            _ => {
+                return Err(invalid(
+                    "response_format.type",
+                    "\"text\", \"json_object\" or \"json_schema\"",
+                ));
+            }
+        };
+        Ok(ResponseFormat {
+            format_type: format_type.into(),
+            schema,
+        })
+    }
+
+    fn missing(field: &str) -> ConvertError {
+        ConvertError::MissingField {
+            field: field.to_string(),
+        }
+    }
+
+    fn invalid(field: &str, expected: &'static str) -> ConvertError {
+        ConvertError::InvalidField {
+            field: field.to_string(),
+            expected,
+        }
+    }
+
+    fn required<'v>(
+        body: &'v Map<String, Value>,
+        key: &str,
+        path: &str,
+    ) -> Result<&'v Value, ConvertError> {
+        body.get(key)
+            .ok_or_else(|| missing(&format!("{path}.{key}")))
+    }
+
+    fn object<'v>(value: &'v Value, path: &str) -> Result<&'v Map<String, Value>, ConvertError> {
+        value.as_object().ok_or_else(|| invalid(path, "an object"))
+    }
+
+    fn array<'v>(value: &'v Value, path: &str) -> Result<&'v [Value], ConvertError> {
+        value
+            .as_array()
+            .map(Vec::as_slice)
+            .ok_or_else(|| invalid(path, "an array"))
+    }
+
+    fn string<'v>(value: &'v Value, path: &str) -> Result<&'v str, ConvertError> {
+        value.as_str().ok_or_else(|| invalid(path, "a string"))
+    }
+
+    fn float(value: &Value, path: &str) -> Result<f32, ConvertError> {
+        value
+            .as_f64()
+            .map(|n| n as f32)
+            .ok_or_else(|| invalid(path, "a number"))
+    }
+
+    fn integer(value: &Value, path: &str) -> Result<i32, ConvertError> {
+        value
+            .as_i64()
+            .and_then(|n| i32::try_from(n).ok())
+            .ok_or_else(|| invalid(path, "a 32-bit integer"))
+    }
+
+    fn boolean(value: &Value, path: &str) -> Result<bool, ConvertError> {
+        value.as_bool().ok_or_else(|| invalid(path, "a boolean"))
+    }
+
+    /// Applies `convert` unless the value is `null`.
+    fn optional<'v, T>(
+        value: &'v Value,
+        path: &str,
+        convert: fn(&'v Value, &str) -> Result<T, ConvertError>,
+    ) -> Result<Option<T>, ConvertError> {
+        match value {
+            Value::Null => Ok(None),
+            _ => convert(value, path).map(Some),
+        }
+    }
+}
@@ -16,7 +16,10 @@ pub mod documents;
 pub mod embed;
 pub mod export;
 pub mod image;
+#[cfg(feature = "openai")]
+pub mod interop;
 pub mod models;
+pub mod prelude;
 pub mod sample;
 pub mod tokenize;
 pub mod utils;
@@ -2,12 +2,25 @@
 //!
 //! Provides gRPC clients for querying available xAI models including language models,
 //! embedding models, and image generation models with their capabilities and metadata.
+//! [`list_all`] fetches every model category at once, [`resolve`] turns user-supplied model
+//! names into canonical ones, and [`ModelId`] gives raw model-id strings a structure to
+//! branch on.
+
+use crate::export::{Request, Response, Status};
+use crate::models::client::ModelsClient;
+use crate::xai_api::{GetModelRequest, LanguageModel, ListLanguageModelsResponse};
+use std::time::Duration;
+
+pub use catalog::{AllModels, ModelError, ModelRef, list_all, resolve};
+pub use id::{ModelFamily, ModelId, ModelSize};
+pub use pricing::Pricing;
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::models_client::ModelsClient as XModelsClient;
 
     pub type ModelsClient = XModelsClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +33,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<ModelsClient, Error>` - Connected client or transport error
+    /// * `Result<ModelsClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<ModelsClient, Error> {
+    pub async fn new(api_key: &str) -> Result<ModelsClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XModelsClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +43,32 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `ModelsClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<ModelsClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<ModelsClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XModelsClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `ModelsClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -56,11 +95,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<ModelsClient, Error>` - Intercepted client or connection error
+    /// * `Result<ModelsClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<ModelsClient, Error> {
+    ) -> Result<ModelsClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client = XModelsClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
         Ok(client)
@@ -83,3 +122,566 @@ pub mod client {
         XModelsClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Structured model identifiers.
+///
+/// Parses ids such as `grok-3-mini`, `grok-4-latest` or `grok-code-fast-1` into their parts
+/// so applications can pick parameters per model family instead of matching on strings.
+pub mod id {
+    use std::convert::Infallible;
+    use std::fmt;
+    use std::str::FromStr;
+
+    /// Known families of xAI models.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub enum ModelFamily {
+        /// `grok-2-*`
+        Grok2,
+        /// `grok-3-*`
+        Grok3,
+        /// `grok-4-*`
+        Grok4,
+        /// `grok-code-*`
+        GrokCode,
+        /// `grok-imagine-*`
+        GrokImagine,
+    }
+
+    impl ModelFamily {
+        fn as_str(self) -> &'static str {
+            match self {
+                ModelFamily::Grok2 => "2",
+                ModelFamily::Grok3 => "3",
+                ModelFamily::Grok4 => "4",
+                ModelFamily::GrokCode => "code",
+                ModelFamily::GrokImagine => "imagine",
+            }
+        }
+
+        fn parse(token: &str) -> Option<Self> {
+            match token {
+                "2" => Some(ModelFamily::Grok2),
+                "3" => Some(ModelFamily::Grok3),
+                "4" => Some(ModelFamily::Grok4),
+                "code" => Some(ModelFamily::GrokCode),
+                "imagine" => Some(ModelFamily::GrokImagine),
+                _ => None,
+            }
+        }
+    }
+
+    /// Size tier of a model within its family.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub enum ModelSize {
+        /// `*-mini`
+        Mini,
+    }
+
+    /// A model id, parsed into its parts where the format is recognized.
+    ///
+    /// Recognized ids have the form `grok-<family>[-<size>][-<variant>][-latest]`, where
+    /// `variant` is everything between the size and the `-latest` suffix (e.g. `fast`,
+    /// `vision-1212`, `fast-reasoning`, `0709`). Ids that do not match, including families
+    /// released after this SDK, parse as [`ModelId::Unknown`]. Either way the id converts
+    /// back to exactly the string it was parsed from.
+    ///
+    /// The ordering is structural, for sorted collections: known ids sort before unknown
+    /// ones, then by family, size, variant and the `-latest` flag. It does not reflect
+    /// release order or capability.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub enum ModelId {
+        /// An id in a recognized format.
+        Known {
+            /// Model family.
+            family: ModelFamily,
+            /// Size tier, if any.
+            size: Option<ModelSize>,
+            /// Remaining qualifiers, such as `fast` or a release date.
+            variant: Option<String>,
+            /// Whether the id is the `-latest` alias.
+            is_latest: bool,
+        },
+        /// An id in an unrecognized format, kept verbatim.
+        Unknown(String),
+    }
+
+    impl ModelId {
+        /// Parses a raw model id. Never fails; unrecognized ids become [`ModelId::Unknown`].
+        pub fn parse(raw: &str) -> Self {
+            Self::parse_known(raw)
+                // Only accept the parse if it round-trips, e.g. not for `grok-4-`
+                .filter(|id| id.to_string() == raw)
+                .unwrap_or_else(|| ModelId::Unknown(raw.to_string()))
+        }
+
+        fn parse_known(raw: &str) -> Option<Self> {
+            let rest = raw.strip_prefix("grok-")?;
+            let (rest, is_latest) = match rest.strip_suffix("-latest") {
+                Some(rest) => (rest, true),
+                None => (rest, false),
+            };
+
+            let mut tokens = rest.splitn(2, '-');
+            let family = ModelFamily::parse(tokens.next()?)?;
+            let mut rest = tokens.next();
+
+            let mut size = None;
+            if let Some(remaining) = rest {
+                let mut tokens = remaining.splitn(2, '-');
+                if tokens.next() == Some("mini") {
+                    size = Some(ModelSize::Mini);
+                    rest = tokens.next();
+                }
+            }
+
+            Some(ModelId::Known {
+                family,
+                size,
+                variant: rest.filter(|v| !v.is_empty()).map(str::to_string),
+                is_latest,
+            })
+        }
+
+        /// Returns the model family, or `None` for unknown ids.
+        pub fn family(&self) -> Option<ModelFamily> {
+            match self {
+                ModelId::Known { family, .. } => Some(*family),
+                ModelId::Unknown(_) => None,
+            }
+        }
+
+        /// Returns true if the model thinks before responding.
+        ///
+        /// True for `grok-3-mini`, `grok-code` and the `grok-4` family, except variants
+        /// marked `non-reasoning`. Unknown ids return false.
+        pub fn is_reasoning_model(&self) -> bool {
+            match self {
+                ModelId::Known {
+                    family: ModelFamily::Grok3,
+                    size,
+                    ..
+                } => *size == Some(ModelSize::Mini),
+                ModelId::Known {
+                    family: ModelFamily::Grok4,
+                    variant,
+                    ..
+                } => !variant
+                    .as_deref()
+                    .is_some_and(|v| v.contains("non-reasoning")),
+                ModelId::Known {
+                    family: ModelFamily::GrokCode,
+                    ..
+                } => true,
+                _ => false,
+            }
+        }
+
+        /// Returns true if the model accepts a `reasoning_effort` setting.
+        ///
+        /// Only `grok-3-mini` (including its `fast` and `latest` variants) lets the caller
+        /// choose how long it thinks. Other reasoning models (`grok-4`, `grok-code`) decide
+        /// for themselves and, like non-reasoning models, reject requests that set an effort.
+        /// Unknown ids return false.
+        pub fn supports_reasoning_effort(&self) -> bool {
+            matches!(
+                self,
+                ModelId::Known {
+                    family: ModelFamily::Grok3,
+                    size: Some(ModelSize::Mini),
+                    ..
+                }
+            )
+        }
+
+        /// Returns true if the model accepts image input.
+        ///
+        /// True for the `grok-4` family and `grok-2` vision variants. Unknown ids return false.
+        pub fn supports_vision(&self) -> bool {
+            match self {
+                ModelId::Known {
+                    family: ModelFamily::Grok4,
+                    ..
+                } => true,
+                ModelId::Known {
+                    family: ModelFamily::Grok2,
+                    variant,
+                    ..
+                } => variant.as_deref().is_some_and(|v| v.contains("vision")),
+                _ => false,
+            }
+        }
+
+        /// Returns true if the model accepts a `response_format` of type JSON object or JSON
+        /// schema.
+        ///
+        /// True for the language model families except `grok-2` vision variants; false for
+        /// image generation models. Unknown ids return false.
+        pub fn supports_structured_output(&self) -> bool {
+            match self {
+                ModelId::Known {
+                    family: ModelFamily::Grok2,
+                    variant,
+                    ..
+                } => !variant.as_deref().is_some_and(|v| v.contains("vision")),
+                ModelId::Known {
+                    family: ModelFamily::Grok3 | ModelFamily::Grok4 | ModelFamily::GrokCode,
+                    ..
+                } => true,
+                _ => false,
+            }
+        }
+    }
+
+    impl fmt::Display for ModelId {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ModelId::Known {
+                    family,
+                    size,
+                    variant,
+                    is_latest,
+                } => {
+                    write!(f, "grok-{}", family.as_str())?;
+                    if let Some(ModelSize::Mini) = size {
+                        f.write_str("-mini")?;
+                    }
+                    if let Some(variant) = variant {
+                        write!(f, "-{variant}")?;
+                    }
+                    if *is_latest {
+                        f.write_str("-latest")?;
+                    }
+                    Ok(())
+                }
+                ModelId::Unknown(raw) => f.write_str(raw),
+            }
+        }
+    }
+
+    impl FromStr for ModelId {
+        type Err = Infallible;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(ModelId::parse(s))
+        }
+    }
+
+    impl From<&str> for ModelId {
+        fn from(value: &str) -> Self {
+            ModelId::parse(value)
+        }
+    }
+}
+
+/// Combined listing of all model categories.
+pub mod catalog {
+    use crate::export::{Request, Status};
+    use crate::models::client::ModelsClient;
+    use crate::xai_api::{EmbeddingModel, ImageGenerationModel, LanguageModel};
+    use futures::future::try_join3;
+
+    /// All models available to the team of the API key, by category.
+    #[derive(Debug, Clone, Default, PartialEq)]
+    pub struct AllModels {
+        /// Language (chat) models.
+        pub language: Vec<LanguageModel>,
+        /// Embedding models.
+        pub embedding: Vec<EmbeddingModel>,
+        /// Image generation models.
+        pub image_generation: Vec<ImageGenerationModel>,
+    }
+
+    /// A model of any category, as found by [`AllModels::find`].
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ModelRef<'a> {
+        /// A language (chat) model.
+        Language(&'a LanguageModel),
+        /// An embedding model.
+        Embedding(&'a EmbeddingModel),
+        /// An image generation model.
+        ImageGeneration(&'a ImageGenerationModel),
+    }
+
+    impl<'a> ModelRef<'a> {
+        /// Returns the canonical name of the model.
+        pub fn name(&self) -> &'a str {
+            match self {
+                ModelRef::Language(model) => &model.name,
+                ModelRef::Embedding(model) => &model.name,
+                ModelRef::ImageGeneration(model) => &model.name,
+            }
+        }
+
+        /// Returns the aliases of the model.
+        pub fn aliases(&self) -> &'a [String] {
+            match self {
+                ModelRef::Language(model) => &model.aliases,
+                ModelRef::Embedding(model) => &model.aliases,
+                ModelRef::ImageGeneration(model) => &model.aliases,
+            }
+        }
+
+        fn matches(&self, name: &str) -> bool {
+            self.name() == name || self.aliases().iter().any(|alias| alias == name)
+        }
+    }
+
+    impl AllModels {
+        /// Iterates over the models of all categories: language, embedding, then image
+        /// generation.
+        pub fn iter(&self) -> impl Iterator<Item = ModelRef<'_>> {
+            let language = self.language.iter().map(ModelRef::Language);
+            let embedding = self.embedding.iter().map(ModelRef::Embedding);
+            let image = self.image_generation.iter().map(ModelRef::ImageGeneration);
+            language.chain(embedding).chain(image)
+        }
+
+        /// Finds a model of any category by name or alias.
+        ///
+        /// # Arguments
+        /// * `name` - Model name or one of its aliases
+        ///
+        /// # Returns
+        /// * `Some(ModelRef)` - The first model (language, embedding, then image generation)
+        ///   whose name or aliases match
+        /// * `None` - No model matches
+        pub fn find(&self, name: &str) -> Option<ModelRef<'_>> {
+            self.iter().find(|model| model.matches(name))
+        }
+
+        /// Resolves a user-supplied model name to the canonical name of an available model.
+        ///
+        /// Tries, in order:
+        /// 1. An exact match on a name or alias
+        /// 2. A match ignoring case, surrounding whitespace and a `-latest` suffix on either
+        ///    side, so `Grok-4` and `grok-4-latest` both find `grok-4-0709` if it has the
+        ///    alias `grok-4`
+        ///
+        /// Otherwise the error lists up to three names or aliases within a small edit
+        /// distance, closest first.
+        ///
+        /// # Arguments
+        /// * `name` - Model name as typed by the user
+        ///
+        /// # Returns
+        /// * `Ok(String)` - Canonical name of the matching model
+        /// * `Err(ModelError::Suggestion)` - No model matches
+        pub fn resolve(&self, name: &str) -> Result<String, ModelError> {
+            if let Some(model) = self.find(name) {
+                return Ok(model.name().to_string());
+            }
+
+            let wanted = normalize(name);
+            let normalized_match = self.iter().find(|model| {
+                std::iter::once(model.name())
+                    .chain(model.aliases().iter().map(String::as_str))
+                    .any(|candidate| normalize(candidate) == wanted)
+            });
+            if let Some(model) = normalized_match {
+                return Ok(model.name().to_string());
+            }
+
+            let max_distance = (wanted.chars().count() / 4).max(2);
+            let mut close: Vec<(usize, &str)> = self
+                .iter()
+                .flat_map(|model| {
+                    std::iter::once(model.name()).chain(model.aliases().iter().map(String::as_str))
+                })
+                .map(|candidate| (edit_distance(&wanted, &normalize(candidate)), candidate))
+                .filter(|(distance, _)| *distance <= max_distance)
+                .collect();
+            close.sort();
+            let mut suggestions: Vec<String> = Vec::new();
+            for (_, candidate) in close {
+                if suggestions.len() == 3 {
+                    break;
+                }
+                if !suggestions.iter().any(|s| s == candidate) {
+                    suggestions.push(candidate.to_string());
+                }
+            }
+            Err(ModelError::Suggestion {
+                name: name.to_string(),
+                suggestions,
+            })
+        }
+    }
+
+    fn normalize(name: &str) -> String {
+        let name = name.trim().to_lowercase();
+        name.strip_suffix("-latest").unwrap_or(&name).to_string()
+    }
+
+    /// Levenshtein distance between two strings, counted in chars.
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut previous: Vec<usize> = (0..=b.len()).collect();
+        for (i, ca) in a.chars().enumerate() {
+            let mut current = vec![i + 1];
+            for (j, cb) in b.iter().enumerate() {
+                let substitution = previous[j] + usize::from(ca != *cb);
+                current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+            }
+            previous = current;
+        }
+        previous[b.len()]
+    }
+
+    /// Error returned by [`resolve`] and [`AllModels::resolve`].
+    #[derive(Debug, Clone)]
+    pub enum ModelError {
+        /// Listing the models failed.
+        Rpc(Status),
+        /// No available model matches the name.
+        Suggestion {
+            /// The name that was looked up.
+            name: String,
+            /// Close names or aliases, closest first; may be empty.
+            suggestions: Vec<String>,
+        },
+    }
+
+    impl std::fmt::Display for ModelError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                ModelError::Rpc(status) => write!(f, "failed to list models: {}", status.message()),
+                ModelError::Suggestion { name, suggestions } => {
+                    write!(f, "unknown model `{name}`")?;
+                    if !suggestions.is_empty() {
+                        write!(f, "; did you mean `{}`?", suggestions.join("`, `"))?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for ModelError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                ModelError::Rpc(status) => Some(status),
+                ModelError::Suggestion { .. } => None,
+            }
+        }
+    }
+
+    impl From<Status> for ModelError {
+        fn from(status: Status) -> Self {
+            ModelError::Rpc(status)
+        }
+    }
+
+    /// Resolves a model name against the models available to the API key.
+    ///
+    /// Lists every model category with [`list_all`] and matches as described in
+    /// [`AllModels::resolve`]. Call this once at startup to turn typos into a helpful error
+    /// before the first request; cache the [`AllModels`] when resolving many names.
+    ///
+    /// # Arguments
+    /// * `client` - Models client
+    /// * `name` - Model name as typed by the user
+    ///
+    /// # Returns
+    /// * `Ok(String)` - Canonical name of the matching model
+    /// * `Err(ModelError)` - Listing failed, or no model matches (with suggestions)
+    pub async fn resolve(client: &ModelsClient, name: &str) -> Result<String, ModelError> {
+        list_all(client).await?.resolve(name)
+    }
+
+    /// Lists the language, embedding and image generation models in one call.
+    ///
+    /// The three listing RPCs are sent concurrently on clones of `client`, which share its
+    /// connection.
+    ///
+    /// # Arguments
+    /// * `client` - Models client
+    ///
+    /// # Returns
+    /// * `Ok(AllModels)` - The models of every category
+    /// * `Err(Status)` - The first failed RPC's status
+    pub async fn list_all(client: &ModelsClient) -> Result<AllModels, Status> {
+        let (mut language, mut embedding, mut image) =
+            (client.clone(), client.clone(), client.clone());
+        let (language, embedding, image_generation) = try_join3(
+            language.list_language_models(Request::new(())),
+            embedding.list_embedding_models(Request::new(())),
+            image.list_image_generation_models(Request::new(())),
+        )
+        .await?;
+
+        Ok(AllModels {
+            language: language.into_inner().models,
+            embedding: embedding.into_inner().models,
+            image_generation: image_generation.into_inner().models,
+        })
+    }
+}
+
+/// Token prices of language models.
+pub mod pricing {
+    use crate::xai_api::LanguageModel;
+
+    /// Text token prices of a language model, in 1/100 USD cents per million tokens (the
+    /// unit used by `LanguageModel`).
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct Pricing {
+        /// Price of one million prompt text tokens.
+        pub prompt_text_token_price: i64,
+        /// Price of one million completion tokens, including reasoning tokens.
+        pub completion_text_token_price: i64,
+    }
+
+    impl Pricing {
+        /// Returns the exact cost of the given token counts, in 1/100 USD cents per million
+        /// tokens (i.e. units of 10^-8 cents).
+        pub fn cost_units(&self, prompt_tokens: u64, completion_tokens: u64) -> i128 {
+            i128::from(prompt_tokens) * i128::from(self.prompt_text_token_price)
+                + i128::from(completion_tokens) * i128::from(self.completion_text_token_price)
+        }
+    }
+
+    impl From<&LanguageModel> for Pricing {
+        fn from(model: &LanguageModel) -> Self {
+            Self {
+                prompt_text_token_price: model.prompt_text_token_price,
+                completion_text_token_price: model.completion_text_token_price,
+            }
+        }
+    }
+}
+
+/// Fetches a model within `timeout`; see [`timeout::unary`](crate::common::timeout::unary).
+///
+/// # Arguments
+/// * `client` - Models client
+/// * `request` - Request to send
+/// * `timeout` - Maximum duration of the call
+///
+/// # Returns
+/// * `Ok(Response<LanguageModel>)` - The call's response
+/// * `Err(Status)` - The call's status, or `DeadlineExceeded` once `timeout` expires
+pub async fn get_language_model_timeout(
+    client: &mut ModelsClient,
+    request: Request<GetModelRequest>,
+    timeout: Duration,
+) -> Result<Response<LanguageModel>, Status> {
+    crate::common::timeout::unary(request, timeout, |r| client.get_language_model(r)).await
+}
+
+/// Lists language models within `timeout`; see [`timeout::unary`](crate::common::timeout::unary).
+///
+/// # Arguments
+/// * `client` - Models client
+/// * `request` - Request to send
+/// * `timeout` - Maximum duration of the call
+///
+/// # Returns
+/// * `Ok(Response<ListLanguageModelsResponse>)` - The call's response
+/// * `Err(Status)` - The call's status, or `DeadlineExceeded` once `timeout` expires
+pub async fn list_language_models_timeout(
+    client: &mut ModelsClient,
+    request: Request<()>,
+    timeout: Duration,
+) -> Result<Response<ListLanguageModelsResponse>, Status> {
+    crate::common::timeout::unary(request, timeout, |r| client.list_language_models(r)).await
+}
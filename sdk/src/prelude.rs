@@ -0,0 +1,43 @@
+//! Curated re-exports for typical applications.
+//!
+//! `use xai_sdk::prelude::*;` brings the service modules, the core request and response
+//! types of chat, sampling and embeddings, and the gRPC `Request`/`Status`/`Streaming`
+//! wrappers into scope. The list is hand-picked: less common types stay in [`crate::api`].
+//!
+//! ```no_run
+//! use xai_sdk::prelude::*;
+//!
+//! # async fn example(api_key: &str) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = chat::client::new(api_key).await?;
+//! let message = Message {
+//!     role: MessageRole::RoleUser.into(),
+//!     content: vec![Content {
+//!         content: Some(content::Content::Text("Hello".to_string())),
+//!     }],
+//!     ..Default::default()
+//! };
+//! let request = chat::RequestBuilder::new("grok-4").message(message).build();
+//! let response: Response<GetChatCompletionResponse> =
+//!     client.get_completion(Request::new(request)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+// Service modules
+pub use crate::{chat, embed, models, sample};
+
+// gRPC wrappers
+pub use crate::export::{Request, Response, Status, Streaming};
+
+// Messages and content
+pub use crate::api::{Content, Message, MessageRole, content};
+
+// Chat
+pub use crate::api::{
+    CompletionMessage, CompletionOutput, FinishReason, GetChatCompletionChunk,
+    GetChatCompletionResponse, GetCompletionsRequest, IncludeOption, ReasoningEffort, Tool,
+    ToolCall, ToolCallType,
+};
+
+// Sampling and embeddings
+pub use crate::api::{EmbedRequest, EmbedResponse, SampleTextRequest, SampleTextResponse};
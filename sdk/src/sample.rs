@@ -1,13 +1,20 @@
 //! Text sampling service client.
 //!
 //! Provides gRPC clients for raw text generation and sampling operations with
-//! support for both blocking and streaming text generation.
+//! support for both blocking and streaming text generation. The [`convert`] module maps
+//! sampling responses onto chat completion responses for code that handles both.
+
+use crate::export::{Request, Response, Status};
+use crate::sample::client::SampleClient;
+use crate::xai_api::{SampleTextRequest, SampleTextResponse};
+use std::time::Duration;
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::sample_client::SampleClient as XSampleClient;
 
     pub type SampleClient = XSampleClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +27,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<SampleClient, Error>` - Connected client or transport error
+    /// * `Result<SampleClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<SampleClient, Error> {
+    pub async fn new(api_key: &str) -> Result<SampleClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XSampleClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +37,32 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `SampleClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<SampleClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<SampleClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XSampleClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `SampleClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -56,11 +89,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<SampleClient, Error>` - Intercepted client or connection error
+    /// * `Result<SampleClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<SampleClient, Error> {
+    ) -> Result<SampleClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client = XSampleClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
         Ok(client)
@@ -83,3 +116,76 @@ pub mod client {
         XSampleClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Conversions from sampling responses to chat completion responses.
+pub mod convert {
+    use crate::xai_api::{
+        CompletionMessage, CompletionOutput, GetChatCompletionResponse, MessageRole,
+        SampleTextResponse,
+    };
+
+    impl SampleTextResponse {
+        /// Converts the response into the shape of a chat completion response.
+        ///
+        /// Each choice becomes an output with the same `index` and `finish_reason`, whose
+        /// message has the choice's `text` as `content` and the `RoleAssistant` role. The id,
+        /// creation time, model, system fingerprint and usage are copied.
+        ///
+        /// Sampling has no counterpart for the remaining chat fields, which are left empty:
+        /// reasoning content, tool calls, encrypted content, inline and response citations,
+        /// log probabilities, `settings` and `debug_output`.
+        ///
+        /// # Returns
+        /// * `GetChatCompletionResponse` - One output per choice, in the original order
+        pub fn into_chat_response(self) -> GetChatCompletionResponse {
+            let outputs = self
+                .choices
+                .into_iter()
+                .map(|choice| CompletionOutput {
+                    finish_reason: choice.finish_reason,
+                    index: choice.index,
+                    message: Some(CompletionMessage {
+                        content: choice.text,
+                        role: MessageRole::RoleAssistant.into(),
+                        ..Default::default()
+                    }),
+                    logprobs: None,
+                })
+                .collect();
+            GetChatCompletionResponse {
+                id: self.id,
+                outputs,
+                created: self.created,
+                model: self.model,
+                system_fingerprint: self.system_fingerprint,
+                usage: self.usage,
+                ..Default::default()
+            }
+        }
+    }
+
+    impl From<SampleTextResponse> for GetChatCompletionResponse {
+        /// Same as [`SampleTextResponse::into_chat_response`].
+        fn from(response: SampleTextResponse) -> Self {
+            response.into_chat_response()
+        }
+    }
+}
+
+/// Samples text within `timeout`; see [`timeout::unary`](crate::common::timeout::unary).
+///
+/// # Arguments
+/// * `client` - Sample client
+/// * `request` - Request to send
+/// * `timeout` - Maximum duration of the call
+///
+/// # Returns
+/// * `Ok(Response<SampleTextResponse>)` - The call's response
+/// * `Err(Status)` - The call's status, or `DeadlineExceeded` once `timeout` expires
+pub async fn sample_text_timeout(
+    client: &mut SampleClient,
+    request: Request<SampleTextRequest>,
+    timeout: Duration,
+) -> Result<Response<SampleTextResponse>, Status> {
+    crate::common::timeout::unary(request, timeout, |r| client.sample_text(r)).await
+}
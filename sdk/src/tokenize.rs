@@ -1,13 +1,21 @@
 //! Tokenization service client.
 //!
 //! Provides gRPC clients for tokenizing text into tokens, enabling precise control
-//! over text processing and token counting for xAI models.
+//! over text processing and token counting for xAI models, and helpers for inspecting
+//! how a text is split into tokens.
+
+use crate::export::{Request, Status};
+use crate::tokenize::client::TokenizeClient;
+use crate::xai_api::{Token, TokenizeTextRequest};
+use futures::{StreamExt, stream};
+use std::ops::Range;
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::tokenize_client::TokenizeClient as XTokenizeClient;
 
     pub type TokenizeClient = XTokenizeClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +28,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<TokenizeClient, Error>` - Connected client or transport error
+    /// * `Result<TokenizeClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<TokenizeClient, Error> {
+    pub async fn new(api_key: &str) -> Result<TokenizeClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XTokenizeClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +38,32 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `TokenizeClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<TokenizeClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<TokenizeClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XTokenizeClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `TokenizeClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -56,11 +90,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<TokenizeClient, Error>` - Intercepted client or connection error
+    /// * `Result<TokenizeClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<TokenizeClient, Error> {
+    ) -> Result<TokenizeClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client =
             XTokenizeClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
@@ -84,3 +118,157 @@ pub mod client {
         XTokenizeClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Returns the number of tokens `text` takes up for `model`.
+///
+/// # Arguments
+/// * `client` - Tokenize client
+/// * `model` - Model whose tokenizer is used
+/// * `text` - Text to tokenize
+///
+/// # Returns
+/// * `Ok(usize)` - Number of tokens
+/// * `Err(Status)` - The RPC's status
+pub async fn count_tokens(
+    client: &mut TokenizeClient,
+    model: &str,
+    text: &str,
+) -> Result<usize, Status> {
+    let request = TokenizeTextRequest {
+        text: text.to_string(),
+        model: model.to_string(),
+        ..Default::default()
+    };
+    let response = client
+        .tokenize_text(Request::new(request))
+        .await
+        .map_err(crate::auth::map_status)?;
+    Ok(response.into_inner().tokens.len())
+}
+
+/// Counts the tokens of many texts concurrently and returns the counts in input order.
+///
+/// Each text is tokenized with [`count_tokens`] on its own clone of `client` (clones share
+/// the underlying channel). At most `concurrency` calls are in flight at any time; a value
+/// of `0` is treated as `1`. Failures are reported per text and do not cancel the remaining
+/// calls, so a corpus estimate can retry or skip just the failed entries.
+///
+/// # Arguments
+/// * `client` - Tokenize client
+/// * `model` - Model whose tokenizer is used
+/// * `texts` - Texts to tokenize
+/// * `concurrency` - Maximum number of concurrent in-flight calls
+///
+/// # Returns
+/// * `Vec<Result<usize, Status>>` - One token count or error per text, in the same order
+///   as `texts`
+pub async fn count_many(
+    client: &TokenizeClient,
+    model: &str,
+    texts: Vec<String>,
+    concurrency: usize,
+) -> Vec<Result<usize, Status>> {
+    stream::iter(texts)
+        .map(|text| {
+            let mut client = client.clone();
+            async move { count_tokens(&mut client, model, &text).await }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// A token together with the part of the input text it covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenSegment {
+    /// Token id in the model's vocabulary.
+    pub id: u32,
+    /// Text of the token. Tokens that split a multi-byte character hold `U+FFFD` for the
+    /// partial character.
+    pub text: String,
+    /// Byte range of the token in the tokenized text.
+    pub byte_range: Range<usize>,
+}
+
+/// Tokenizes `text` and returns one segment per token, in order.
+///
+/// # Arguments
+/// * `client` - Tokenize client
+/// * `model` - Model whose tokenizer is used
+/// * `text` - Text to tokenize
+///
+/// # Returns
+/// * `Ok(Vec<TokenSegment>)` - The tokens with their text and byte ranges
+/// * `Err(Status)` - The RPC's status
+pub async fn segments(
+    client: &mut TokenizeClient,
+    model: &str,
+    text: &str,
+) -> Result<Vec<TokenSegment>, Status> {
+    let request = TokenizeTextRequest {
+        text: text.to_string(),
+        model: model.to_string(),
+        ..Default::default()
+    };
+    let response = client
+        .tokenize_text(Request::new(request))
+        .await
+        .map_err(crate::auth::map_status)?;
+    Ok(to_segments(&response.into_inner().tokens))
+}
+
+/// Converts tokens from a `TokenizeTextResponse` into segments.
+///
+/// Byte ranges are the running sum of the tokens' byte lengths, so they index into the
+/// tokenized text. Tokens without `token_bytes` fall back to `string_token`.
+///
+/// # Arguments
+/// * `tokens` - Tokens in response order
+///
+/// # Returns
+/// * `Vec<TokenSegment>` - One segment per token
+pub fn to_segments(tokens: &[Token]) -> Vec<TokenSegment> {
+    let mut offset = 0;
+    tokens
+        .iter()
+        .map(|token| {
+            let bytes = if token.token_bytes.is_empty() {
+                token.string_token.as_bytes()
+            } else {
+                &token.token_bytes
+            };
+            let start = offset;
+            offset += bytes.len();
+            TokenSegment {
+                id: token.token_id,
+                text: String::from_utf8_lossy(bytes).into_owned(),
+                byte_range: start..offset,
+            }
+        })
+        .collect()
+}
+
+/// Background colors alternated by [`render_colored`] (black text on cyan, then yellow).
+const TOKEN_COLORS: [&str; 2] = ["\x1b[30;46m", "\x1b[30;43m"];
+const RESET: &str = "\x1b[0m";
+
+/// Renders segments for a terminal, with alternating background colors marking token
+/// boundaries.
+///
+/// Each token is wrapped in an ANSI color sequence and a reset, so the output only makes
+/// sense on a terminal that understands ANSI escapes.
+///
+/// # Arguments
+/// * `segments` - Segments from [`segments`] or [`to_segments`]
+///
+/// # Returns
+/// * `String` - The tokens' text with color codes
+pub fn render_colored(segments: &[TokenSegment]) -> String {
+    let mut out = String::new();
+    for (i, segment) in segments.iter().enumerate() {
+        out.push_str(TOKEN_COLORS[i % TOKEN_COLORS.len()]);
+        out.push_str(&segment.text);
+        out.push_str(RESET);
+    }
+    out
+}
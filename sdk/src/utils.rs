@@ -51,6 +51,8 @@
 //!
 //! - `Display` trait implementations for human-readable output
 //! - `FromStr` trait implementations for parsing from strings
+//! - Typed accessors on generated messages (e.g. `ToolCall::call_type()`) so callers
+//!   don't have to `try_from` raw `i32` enum fields
 //! - Additional convenience methods where appropriate
 //!
 //! All implementations handle deprecated enum variants gracefully
@@ -67,6 +69,10 @@ pub mod enums {
         ToolCallType, ToolMode, VideoAspectRatio, VideoResolution,
     };
 
+    use crate::xai_api::{
+        CompletionMessage, CompletionOutput, CompletionOutputChunk, Message, SampleChoice, ToolCall,
+    };
+
     impl fmt::Display for DeferredStatus {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             let s = match self {
@@ -576,6 +582,28 @@ pub mod enums {
         }
     }
 
+    impl IncludeOption {
+        /// Every option that includes a server-side tool's call output in the response.
+        pub const CALL_OUTPUTS: [IncludeOption; 6] = [
+            IncludeOption::WebSearchCallOutput,
+            IncludeOption::XSearchCallOutput,
+            IncludeOption::CodeExecutionCallOutput,
+            IncludeOption::CollectionsSearchCallOutput,
+            IncludeOption::AttachmentSearchCallOutput,
+            IncludeOption::McpCallOutput,
+        ];
+
+        /// Returns every call-output option ([`CALL_OUTPUTS`](Self::CALL_OUTPUTS)).
+        pub fn all_outputs() -> &'static [IncludeOption] {
+            &Self::CALL_OUTPUTS
+        }
+
+        /// Returns `true` for options that include a tool's call output.
+        pub fn is_call_output(&self) -> bool {
+            Self::CALL_OUTPUTS.contains(self)
+        }
+    }
+
     impl fmt::Display for ToolCallType {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             let s = match self {
@@ -701,4 +729,58 @@ pub mod enums {
             }
         }
     }
+
+    // ####################
+    // Typed accessors
+    // ####################
+
+    impl ToolCall {
+        /// Returns the tool call type, mapping unknown values to [`ToolCallType::Invalid`].
+        pub fn call_type(&self) -> ToolCallType {
+            ToolCallType::try_from(self.r#type).unwrap_or(ToolCallType::Invalid)
+        }
+
+        /// Returns the tool call status.
+        ///
+        /// `ToolCallStatus` has no invalid variant (its zero value is `InProgress`), so
+        /// unknown values are reported as `None` rather than silently mapped.
+        pub fn call_status(&self) -> Option<ToolCallStatus> {
+            ToolCallStatus::try_from(self.status).ok()
+        }
+    }
+
+    impl SampleChoice {
+        /// Returns the finish reason, mapping unknown values to [`FinishReason::ReasonInvalid`].
+        pub fn finish_reason_enum(&self) -> FinishReason {
+            FinishReason::try_from(self.finish_reason).unwrap_or(FinishReason::ReasonInvalid)
+        }
+    }
+
+    impl CompletionOutput {
+        /// Returns the finish reason, mapping unknown values to [`FinishReason::ReasonInvalid`].
+        pub fn finish_reason_enum(&self) -> FinishReason {
+            FinishReason::try_from(self.finish_reason).unwrap_or(FinishReason::ReasonInvalid)
+        }
+    }
+
+    impl CompletionOutputChunk {
+        /// Returns the finish reason, mapping unknown values to [`FinishReason::ReasonInvalid`].
+        pub fn finish_reason_enum(&self) -> FinishReason {
+            FinishReason::try_from(self.finish_reason).unwrap_or(FinishReason::ReasonInvalid)
+        }
+    }
+
+    impl Message {
+        /// Returns the message role, mapping unknown values to [`MessageRole::InvalidRole`].
+        pub fn role_enum(&self) -> MessageRole {
+            MessageRole::try_from(self.role).unwrap_or(MessageRole::InvalidRole)
+        }
+    }
+
+    impl CompletionMessage {
+        /// Returns the message role, mapping unknown values to [`MessageRole::InvalidRole`].
+        pub fn role_enum(&self) -> MessageRole {
+            MessageRole::try_from(self.role).unwrap_or(MessageRole::InvalidRole)
+        }
+    }
 }
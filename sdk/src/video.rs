@@ -5,9 +5,10 @@
 
 pub mod client {
     use crate::common;
+    use crate::common::channel::ConnectError;
     use crate::common::interceptor::ClientInterceptor;
     use crate::export::service::{Interceptor, interceptor::InterceptedService};
-    use crate::export::transport::{Channel, Error};
+    use crate::export::transport::Channel;
     use crate::xai_api::video_client::VideoClient as XVideoClient;
 
     pub type VideoClient = XVideoClient<InterceptedService<Channel, ClientInterceptor>>;
@@ -20,9 +21,9 @@ pub mod client {
     /// * `api_key` - Valid xAI API key for authentication
     ///
     /// # Returns
-    /// * `Result<VideoClient, Error>` - Connected client or transport error
+    /// * `Result<VideoClient, ConnectError>` - Connected client or transport error
     ///
-    pub async fn new(api_key: &str) -> Result<VideoClient, Error> {
+    pub async fn new(api_key: &str) -> Result<VideoClient, ConnectError> {
         let channel = common::channel::new().await?;
         let auth_intercept = common::interceptor::auth(api_key);
         let client = XVideoClient::with_interceptor(channel, auth_intercept);
@@ -30,6 +31,32 @@ pub mod client {
         Ok(client)
     }
 
+    /// Creates a new authenticated `VideoClient` with custom gRPC message size limits.
+    ///
+    /// See [message size limits](crate::common#message-size-limits) for the defaults.
+    ///
+    /// # Arguments
+    /// * `api_key` - Valid xAI API key for authentication
+    /// * `max_decode` - Maximum size in bytes of a decoded response message
+    /// * `max_encode` - Maximum size in bytes of an encoded request message
+    ///
+    /// # Returns
+    /// * `Result<VideoClient, ConnectError>` - Connected client or transport error
+    ///
+    pub async fn new_with_limits(
+        api_key: &str,
+        max_decode: usize,
+        max_encode: usize,
+    ) -> Result<VideoClient, ConnectError> {
+        let channel = common::channel::new().await?;
+        let auth_intercept = common::interceptor::auth(api_key);
+        let client = XVideoClient::with_interceptor(channel, auth_intercept)
+            .max_decoding_message_size(max_decode)
+            .max_encoding_message_size(max_encode);
+
+        Ok(client)
+    }
+
     /// Creates a new authenticated `VideoClient` using an existing gRPC channel.
     ///
     /// Useful for sharing connections across multiple service clients.
@@ -56,11 +83,11 @@ pub mod client {
     /// * `interceptor` - Custom request interceptor (must handle authentication)
     ///
     /// # Returns
-    /// * `Result<VideoClient, Error>` - Intercepted client or connection error
+    /// * `Result<VideoClient, ConnectError>` - Intercepted client or connection error
     ///
     pub async fn with_interceptor(
         interceptor: impl Interceptor + Send + Sync + 'static,
-    ) -> Result<VideoClient, Error> {
+    ) -> Result<VideoClient, ConnectError> {
         let channel = common::channel::new().await?;
         let client = XVideoClient::with_interceptor(channel, ClientInterceptor::new(interceptor));
         Ok(client)
@@ -83,3 +110,47 @@ pub mod client {
         XVideoClient::with_interceptor(channel, ClientInterceptor::new(interceptor))
     }
 }
+
+/// Waiting for deferred video generations.
+pub mod deferred {
+    use crate::common::deferred;
+    use crate::export::{Request, Status};
+    use crate::video::client::VideoClient;
+    use crate::xai_api::{GetDeferredVideoRequest, GetDeferredVideoResponse};
+    use std::time::Duration;
+
+    /// Polls a video generation started with `generate_video` until it finishes.
+    ///
+    /// Built on [`common::deferred::poll`](crate::common::deferred::poll): the first poll is
+    /// immediate, then the wait starts at `interval` and backs off exponentially.
+    ///
+    /// # Arguments
+    /// * `client` - Video client
+    /// * `request_id` - ID from the `StartDeferredResponse`
+    /// * `interval` - Initial wait between polls
+    /// * `timeout` - Total time allowed before giving up
+    ///
+    /// # Returns
+    /// * `Ok(GetDeferredVideoResponse)` - Response with status `Done`, `Expired` or `Failed`
+    ///   (a failed response carries the error in `response.error`)
+    /// * `Err(Status)` - The RPC's status, or `DeadlineExceeded` after `timeout`
+    pub async fn wait(
+        client: &mut VideoClient,
+        request_id: &str,
+        interval: Duration,
+        timeout: Duration,
+    ) -> Result<GetDeferredVideoResponse, Status> {
+        deferred::poll(
+            || {
+                let mut client = client.clone();
+                let request = Request::new(GetDeferredVideoRequest {
+                    request_id: request_id.to_string(),
+                });
+                async move { Ok(client.get_deferred_video(request).await?.into_inner()) }
+            },
+            interval,
+            timeout,
+        )
+        .await
+    }
+}
@@ -0,0 +1,191 @@
+use tonic::Code;
+use tonic::Status;
+use xai_sdk::api::ApiKey;
+use xai_sdk::auth::credentials::api_key_from_file;
+use xai_sdk::auth::{ApiKeyError, ApiKeyInfo, AuthError, check_usable, map_status};
+
+fn api_key() -> ApiKey {
+    ApiKey {
+        redacted_api_key: "xai-...abcd".to_string(),
+        user_id: "user-1".to_string(),
+        name: "ci".to_string(),
+        team_id: "team-1".to_string(),
+        acls: vec!["api-key:model:*".to_string()],
+        api_key_id: "key-1".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_api_key_info_from_api_key() {
+    let info = ApiKeyInfo::from(api_key());
+    assert_eq!(info.api_key_id(), "key-1");
+    assert_eq!(info.redacted_api_key(), "xai-...abcd");
+    assert_eq!(info.user_id(), "user-1");
+    assert!(!info.api_key_blocked());
+    assert!(!info.team_blocked());
+    assert!(info.is_usable());
+}
+
+#[test]
+fn test_api_key_info_accessors() {
+    let info = ApiKeyInfo::from(ApiKey {
+        create_time: Some(prost_types::Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 0,
+        }),
+        ..api_key()
+    });
+    assert_eq!(info.name(), "ci");
+    assert_eq!(info.team_id(), "team-1");
+    assert_eq!(info.acls(), ["api-key:model:*".to_string()]);
+    assert_eq!(
+        info.created_at(),
+        Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000))
+    );
+    assert_eq!(info.modified_at(), None);
+    assert!(!info.disabled());
+}
+
+#[tokio::test]
+async fn test_get_info_reports_connection_failure() {
+    let channel =
+        xai_sdk::export::transport::Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+    let mut client = xai_sdk::auth::client::with_channel(channel, "test-key");
+    let err = xai_sdk::auth::get_info(&mut client).await.unwrap_err();
+    assert_eq!(err.code(), Code::Unavailable);
+}
+
+#[test]
+fn test_check_usable_accepts_usable_key() {
+    let info = ApiKeyInfo::from(api_key());
+    assert_eq!(check_usable(info.clone()).unwrap(), info);
+}
+
+#[test]
+fn test_check_usable_rejects_disabled_or_blocked_key() {
+    let cases = [
+        (
+            ApiKey {
+                disabled: true,
+                ..api_key()
+            },
+            "API key is disabled",
+        ),
+        (
+            ApiKey {
+                api_key_blocked: true,
+                ..api_key()
+            },
+            "API key is blocked",
+        ),
+        (
+            ApiKey {
+                team_blocked: true,
+                ..api_key()
+            },
+            "Team of the API key is blocked",
+        ),
+    ];
+
+    for (key, reason) in cases {
+        let status = check_usable(key.into()).unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert!(status.message().starts_with(reason), "{}", status.message());
+        assert!(status.message().contains("xai-...abcd"));
+    }
+}
+
+#[test]
+fn test_map_status_wraps_auth_failures() {
+    let status = map_status(Status::unauthenticated("invalid api key"));
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(
+        status.message(),
+        "authentication failed: invalid api key (check that XAI_API_KEY holds a valid xAI API key)"
+    );
+
+    let error = AuthError::from_status(&status).unwrap();
+    assert_eq!(error.code, Code::Unauthenticated);
+    assert_eq!(error.detail, "invalid api key");
+
+    let denied = map_status(Status::permission_denied(""));
+    assert_eq!(denied.code(), Code::PermissionDenied);
+    assert!(
+        denied
+            .message()
+            .starts_with("access denied (check that XAI_API_KEY")
+    );
+}
+
+#[test]
+fn test_map_status_keeps_details_and_metadata() {
+    let mut metadata = tonic::metadata::MetadataMap::new();
+    metadata.insert("x-request-id", "req-1".parse().unwrap());
+    let status = map_status(Status::with_details_and_metadata(
+        Code::Unauthenticated,
+        "invalid api key",
+        vec![1, 2, 3].into(),
+        metadata,
+    ));
+    assert!(status.message().starts_with("authentication failed"));
+    assert_eq!(status.details(), [1, 2, 3]);
+    assert_eq!(status.metadata().get("x-request-id").unwrap(), "req-1");
+}
+
+#[test]
+fn test_map_status_is_idempotent_and_ignores_other_codes() {
+    let once = map_status(Status::unauthenticated("bad key"));
+    let twice = map_status(once.clone());
+    assert_eq!(twice.message(), once.message());
+    assert_eq!(AuthError::from_status(&twice).unwrap().detail, "bad key");
+
+    let unavailable = map_status(Status::unavailable("down"));
+    assert_eq!(unavailable.message(), "down");
+    assert!(AuthError::from_status(&unavailable).is_none());
+}
+
+fn temp_credentials(name: &str, contents: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "xai-sdk-credentials-{}-{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_api_key_from_file_reads_api_key_entry() {
+    let path = temp_credentials(
+        "valid",
+        "# xAI credentials\n\nregion = us\napi_key = \"xai-secret\"  \n",
+    );
+    let key = api_key_from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(key.unwrap(), "xai-secret");
+}
+
+#[test]
+fn test_api_key_from_file_errors() {
+    let path = temp_credentials("empty", "# api_key = commented-out\napi_key =\n");
+    let err = api_key_from_file(&path).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(err, ApiKeyError::MissingEntry { .. }));
+    assert!(err.to_string().contains("has no `api_key = ...` entry"));
+
+    let missing = std::env::temp_dir().join("xai-sdk-no-such-credentials");
+    let err = api_key_from_file(&missing).unwrap_err();
+    assert!(matches!(err, ApiKeyError::Unreadable { .. }));
+}
+
+#[test]
+fn test_api_key_not_found_message() {
+    let err = ApiKeyError::NotFound {
+        credentials_path: Some("/home/me/.config/xai/credentials".into()),
+    };
+    assert_eq!(
+        err.to_string(),
+        "no xAI API key found: set XAI_API_KEY or add `api_key = ...` to /home/me/.config/xai/credentials"
+    );
+}
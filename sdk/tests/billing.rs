@@ -0,0 +1,107 @@
+use xai_sdk::api::management::billing::{self as api, Cent};
+use xai_sdk::billing::{InvoiceLine, Money, SpendingLimits, UnitPrice};
+
+#[test]
+fn test_money_display() {
+    assert_eq!(Money::from_cents(0).to_string(), "$0.00");
+    assert_eq!(Money::from_cents(5).to_string(), "$0.05");
+    assert_eq!(Money::from_cents(99_999).to_string(), "$999.99");
+    assert_eq!(Money::from_cents(123_456).to_string(), "$1,234.56");
+    assert_eq!(Money::from_cents(100_000_000).to_string(), "$1,000,000.00");
+    assert_eq!(Money::from_cents(-123_456).to_string(), "-$1,234.56");
+    assert_eq!(Money::from_cents(i64::MIN).cents(), i64::MIN);
+    assert!(
+        Money::from_cents(i64::MIN)
+            .to_string()
+            .starts_with("-$92,233,720")
+    );
+}
+
+#[test]
+fn test_money_arithmetic() {
+    let a = Money::from_cents(1_050);
+    let b = Money::from_cents(250);
+    assert_eq!(a + b, Money::from_cents(1_300));
+    assert_eq!(a - b, Money::from_cents(800));
+    assert_eq!(-b, Money::from_cents(-250));
+    assert_eq!(b * 3, Money::from_cents(750));
+
+    let mut total = Money::ZERO;
+    total += a;
+    total -= b;
+    assert_eq!(total, Money::from_cents(800));
+    assert_eq!([a, b].into_iter().sum::<Money>(), Money::from_cents(1_300));
+    assert_eq!(a.as_dollars(), 10.5);
+    assert!(b < a);
+}
+
+#[test]
+fn test_money_cent_conversion() {
+    let money = Money::from(Cent { val: 4_200 });
+    assert_eq!(money.cents(), 4_200);
+    assert_eq!(Cent::from(money), Cent { val: 4_200 });
+}
+
+#[test]
+fn test_spending_limits_from_proto() {
+    let limits = SpendingLimits::from(api::SpendingLimits {
+        hard_sl_override: None,
+        hard_sl_auto: Some(Cent { val: 50_000 }),
+        effective_hard_sl: Some(Cent { val: 50_000 }),
+        soft_sl: Some(Cent { val: 0 }),
+        effective_sl: Some(Cent { val: 50_000 }),
+    });
+    assert_eq!(limits.hard, Some(Money::from_cents(50_000)));
+    assert_eq!(limits.soft, None);
+    assert_eq!(limits.effective, Some(Money::from_cents(50_000)));
+}
+
+#[test]
+fn test_unit_price_display_and_scaling() {
+    // $2 per million tokens is 0.0002 cents per token
+    let per_token = UnitPrice::from_micro_cents(200);
+    assert_eq!(per_token.to_string(), "$0.000002");
+    assert!((per_token.as_dollars() - 0.000002).abs() < 1e-12);
+
+    assert_eq!(UnitPrice::from_micro_cents(0).to_string(), "$0.00");
+    assert_eq!(
+        UnitPrice::from_micro_cents(300_000_000).to_string(),
+        "$3.00"
+    );
+    assert_eq!(UnitPrice::from_micro_cents(1).to_string(), "$0.00000001");
+    assert_eq!(
+        UnitPrice::from_micro_cents(-150_000_000).to_string(),
+        "-$1.50"
+    );
+}
+
+#[test]
+fn test_unit_price_total_rounds_to_cents() {
+    let per_token = UnitPrice::from_micro_cents(200);
+    assert_eq!(per_token.total(1_000_000), Money::from_cents(200));
+    assert_eq!(per_token.total(2_500), Money::from_cents(1)); // 0.5 cents rounds up
+    assert_eq!(per_token.total(2_499), Money::from_cents(0));
+    assert_eq!(
+        UnitPrice::from_micro_cents(-200).total(2_500),
+        Money::from_cents(-1)
+    );
+}
+
+#[test]
+fn test_invoice_line_from_proto() {
+    let line = InvoiceLine::from(api::Line {
+        cluster_name: "us-east".to_string(),
+        description: "grok-4 input tokens".to_string(),
+        unit_type: "token".to_string(),
+        unit_price: 300,
+        num_units: 1_000_000,
+        amount: 300,
+    });
+    assert_eq!(line.invoice_id, "");
+    assert_eq!(line.description, "grok-4 input tokens");
+    assert_eq!(line.quantity, 1_000_000);
+    assert_eq!(line.unit_type, "token");
+    assert_eq!(line.unit_price, UnitPrice::from_micro_cents(300));
+    assert_eq!(line.amount, Money::from_cents(300));
+    assert_eq!(line.unit_price.total(line.quantity), line.amount);
+}
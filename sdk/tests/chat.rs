@@ -2,15 +2,119 @@ use futures::StreamExt;
 use futures::channel::mpsc;
 use futures::stream::{self, Stream};
 use std::sync::{Arc, Mutex};
-use tonic::Status;
-use xai_sdk::api::tool_call;
+use std::time::Duration;
+use tonic::{Request, Status};
 use xai_sdk::api::{
-    CompletionMessage, CompletionOutput, CompletionOutputChunk, Delta, FinishReason, FunctionCall,
-    GetChatCompletionChunk, InlineCitation, MessageRole, SamplingUsage, ToolCall, ToolCallType,
+    CompletionMessage, CompletionOutput, CompletionOutputChunk, Delta, FinishReason, FormatType,
+    FunctionCall, GetChatCompletionChunk, GetChatCompletionResponse, GetCompletionsRequest,
+    ImageDetail, IncludeOption, InlineCitation, LogProb, LogProbs, Message, MessageRole,
+    ReasoningEffort, RequestSettings, SamplingUsage, ToolCall, ToolCallType, TopLogProb,
     content::Content as ApiContent,
 };
-use xai_sdk::chat::stream::{Consumer, Event, OutputContext, PhaseStatus, assemble, process};
+use xai_sdk::api::{ToolMode, tool, tool_call, tool_choice};
+use xai_sdk::billing::Money;
+use xai_sdk::chat::client::ChatClient;
+use xai_sdk::chat::diff::{self, WordChange};
+use xai_sdk::chat::message::{NameError, image_file, merge_system, merge_system_with, named};
+use xai_sdk::chat::progress::{ProgressConsumer, ProgressEvent, ProgressSink};
+use xai_sdk::chat::stream::{
+    AssembleError, ChoiceHandler, Consumer, Event, OutputContext, PhaseStatus, ResilientEvent,
+    RestartPolicy, SSE_DONE, StreamError, Utf8StreamDecoder, assemble, assemble_normalized,
+    assemble_or_err, assemble_with_settings, assemble_without_reasoning, cancellable, chunks,
+    into_channel, last_usage, process, process_collect, process_lenient, process_with_idle_timeout,
+    process_with_metrics, record, replay, resilient, sentences, to_chunks, to_sse,
+};
+use xai_sdk::chat::tools::{
+    self, CodeExecutionCall, CollectionsSearchCall, McpAuth, Timestamp, ToolBuildError,
+};
+use xai_sdk::chat::transcript;
 use xai_sdk::chat::utils::to_messages;
+use xai_sdk::chat::{
+    BuildWarning, ChatBackend, CompletionStatus, ContentStats, CostEstimate, MockChatBackend, Pool,
+    RequestBuilder, ValidationError, validate_sampling_params, validate_tool_results,
+};
+use xai_sdk::export::transport::Endpoint;
+use xai_sdk::models::Pricing;
+
+// ########################################
+// FIXTURES
+// ########################################
+
+// Builds a response output by output; `reasoning`, `tool_calls` and `citations` apply to
+// the last output added
+struct ResponseFixture(GetChatCompletionResponse);
+
+fn response() -> ResponseFixture {
+    ResponseFixture(GetChatCompletionResponse::default())
+}
+
+impl ResponseFixture {
+    fn id(mut self, id: &str) -> Self {
+        self.0.id = id.to_string();
+        self
+    }
+
+    fn output(mut self, content: &str, finish_reason: FinishReason) -> Self {
+        self.0.outputs.push(CompletionOutput {
+            index: self.0.outputs.len() as i32,
+            finish_reason: finish_reason.into(),
+            message: Some(CompletionMessage {
+                role: MessageRole::RoleAssistant.into(),
+                content: content.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        self
+    }
+
+    fn reasoning(mut self, reasoning: &str) -> Self {
+        self.last_message().reasoning_content = reasoning.to_string();
+        self
+    }
+
+    fn tool_calls(mut self, tool_calls: Vec<ToolCall>) -> Self {
+        self.last_message().tool_calls = tool_calls;
+        self
+    }
+
+    fn citations(mut self, citations: Vec<InlineCitation>) -> Self {
+        self.last_message().citations = citations;
+        self
+    }
+
+    fn usage(mut self, prompt_tokens: i32, completion_tokens: i32) -> Self {
+        self.0.usage = Some(SamplingUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            ..Default::default()
+        });
+        self
+    }
+
+    fn build(self) -> GetChatCompletionResponse {
+        self.0
+    }
+
+    fn last_message(&mut self) -> &mut CompletionMessage {
+        self.0
+            .outputs
+            .last_mut()
+            .and_then(|output| output.message.as_mut())
+            .expect("add an output first")
+    }
+}
+
+fn text_message(role: MessageRole, text: &str) -> Message {
+    Message {
+        role: role.into(),
+        content: vec![xai_sdk::api::Content {
+            content: Some(ApiContent::Text(text.to_string())),
+        }],
+        ..Default::default()
+    }
+}
 
 #[test]
 fn test_output_context_new() {
@@ -59,6 +163,7 @@ fn test_phase_status_equality() {
 fn test_consumer_new() {
     let consumer = Consumer::new();
     assert!(consumer.on_chunk.is_none());
+    assert!(consumer.on_keepalive.is_none());
     assert!(consumer.on_reasoning_token.is_none());
     assert!(consumer.on_reasoning_complete.is_none());
     assert!(consumer.on_content_token.is_none());
@@ -74,6 +179,7 @@ fn test_consumer_new() {
 fn test_consumer_default() {
     let consumer = Consumer::default();
     assert!(consumer.on_chunk.is_none());
+    assert!(consumer.on_keepalive.is_none());
     assert!(consumer.on_reasoning_token.is_none());
     assert!(consumer.on_reasoning_complete.is_none());
     assert!(consumer.on_content_token.is_none());
@@ -218,6 +324,30 @@ fn test_assemble_single_chunk_single_choice() {
     assert_eq!(message.reasoning_content, "Thinking...");
 }
 
+#[test]
+fn test_assemble_with_settings_sets_settings() {
+    let chunks = vec![make_simple_chunk(0, None, Some("Hi")), make_finish_chunk(0)];
+    let settings = RequestSettings {
+        max_tokens: Some(64),
+        temperature: Some(0.2),
+        user: "u-1".to_string(),
+        ..Default::default()
+    };
+
+    assert_eq!(assemble(chunks.clone()).unwrap().settings, None);
+
+    let response = assemble_with_settings(chunks.clone(), settings.clone()).unwrap();
+    assert_eq!(response.settings, Some(settings.clone()));
+    assert_eq!(
+        GetChatCompletionResponse {
+            settings: None,
+            ..response
+        },
+        assemble(chunks).unwrap()
+    );
+    assert!(assemble_with_settings(Vec::new(), settings).is_none());
+}
+
 #[test]
 fn test_assemble_multiple_chunks_accumulate_content() {
     // Create multiple chunks that accumulate content
@@ -357,6 +487,35 @@ fn test_assemble_accumulates_reasoning_content() {
     assert_eq!(message.content, "Answer");
 }
 
+#[test]
+fn test_assemble_without_reasoning_keeps_only_the_answer() {
+    let chunks = vec![
+        make_simple_chunk(0, Some("Step 1"), None),
+        make_simple_chunk(0, Some("Step 2"), Some("Ans")),
+        make_simple_chunk(0, None, Some("wer")),
+        make_finish_chunk(0),
+    ];
+
+    let response = assemble_without_reasoning(chunks.clone()).unwrap();
+    let message = response.outputs[0].message.as_ref().unwrap();
+    assert_eq!(message.reasoning_content, "");
+    assert_eq!(message.content, "Answer");
+    assert_eq!(
+        response.outputs[0].finish_reason,
+        FinishReason::ReasonStop as i32
+    );
+
+    let mut full = assemble(chunks).unwrap();
+    full.outputs[0]
+        .message
+        .as_mut()
+        .unwrap()
+        .reasoning_content
+        .clear();
+    assert_eq!(full, response);
+    assert!(assemble_without_reasoning(vec![]).is_none());
+}
+
 #[test]
 fn test_assemble_uses_last_chunk_for_usage() {
     let mut chunk1 = GetChatCompletionChunk::default();
@@ -577,6 +736,39 @@ fn test_assemble_with_citations_in_last_chunk() {
     assert_eq!(response.citations[1], "https://test.com");
 }
 
+#[test]
+fn test_assemble_dedups_citations_across_chunks() {
+    let chunk = |citations: &[&str]| GetChatCompletionChunk {
+        id: "test-id".to_string(),
+        citations: citations.iter().map(|c| c.to_string()).collect(),
+        ..Default::default()
+    };
+    let chunks = vec![
+        chunk(&["https://a.com", "https://b.com", "https://a.com"]),
+        chunk(&[]),
+        chunk(&["https://b.com", "https://c.com"]),
+    ];
+
+    let response = assemble(chunks).unwrap();
+    assert_eq!(
+        response.citations,
+        vec!["https://a.com", "https://b.com", "https://c.com"]
+    );
+
+    let unary = GetChatCompletionResponse {
+        citations: vec![
+            "https://x.com".to_string(),
+            "https://y.com".to_string(),
+            "https://x.com".to_string(),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        unary.citations_deduped(),
+        vec!["https://x.com", "https://y.com"]
+    );
+}
+
 // Tests for helper functions (get_reasoning_status, get_content_status)
 // These are tested indirectly through the process function by checking OutputContext values
 
@@ -591,7 +783,7 @@ fn test_reasoning_status_init() {
     // For Init: no reasoning, no content, not finished
     assert!(delta.reasoning_content.is_empty());
     assert!(delta.content.is_empty());
-    assert_eq!(output.finish_reason, FinishReason::ReasonInvalid.into());
+    assert_eq!(output.finish_reason, i32::from(FinishReason::ReasonInvalid));
 }
 
 #[test]
@@ -629,7 +821,7 @@ fn test_content_status_init() {
     // Verify: has reasoning, no content, not finished -> content should be Init
     assert!(!delta.reasoning_content.is_empty());
     assert!(delta.content.is_empty());
-    assert_eq!(output.finish_reason, FinishReason::ReasonInvalid.into());
+    assert_eq!(output.finish_reason, i32::from(FinishReason::ReasonInvalid));
 }
 
 #[test]
@@ -643,7 +835,7 @@ fn test_content_status_pending() {
     // Verify: no reasoning, has content, not finished -> content should be Pending
     assert!(delta.reasoning_content.is_empty());
     assert!(!delta.content.is_empty());
-    assert_eq!(output.finish_reason, FinishReason::ReasonInvalid.into());
+    assert_eq!(output.finish_reason, i32::from(FinishReason::ReasonInvalid));
 }
 
 #[test]
@@ -660,7 +852,7 @@ fn test_content_status_complete() {
     // Verify: no reasoning, has content, finished -> content should be Complete
     assert!(delta.reasoning_content.is_empty());
     assert!(!delta.content.is_empty());
-    assert_ne!(output.finish_reason, FinishReason::ReasonInvalid.into());
+    assert_ne!(output.finish_reason, i32::from(FinishReason::ReasonInvalid));
 }
 
 // More comprehensive tests for assemble function
@@ -782,7 +974,7 @@ fn test_assemble_updates_finish_reason_from_latest_chunk() {
     let response = result.unwrap();
     assert_eq!(
         response.outputs[0].finish_reason,
-        FinishReason::ReasonStop.into()
+        i32::from(FinishReason::ReasonStop)
     );
 }
 
@@ -946,7 +1138,7 @@ fn test_to_messages_single_completion_message() {
         message.reasoning_content,
         Some("Thinking step by step...".to_string())
     );
-    assert_eq!(message.role, MessageRole::RoleAssistant.into());
+    assert_eq!(message.role, i32::from(MessageRole::RoleAssistant));
     assert_eq!(message.tool_calls.len(), 1);
     assert_eq!(message.tool_calls[0].id, "call-123");
     assert_eq!(message.encrypted_content, "encrypted-data");
@@ -984,7 +1176,7 @@ fn test_to_messages_multiple_completion_messages() {
     assert_eq!(messages.len(), 2);
 
     // Check first message
-    assert_eq!(messages[0].role, MessageRole::RoleUser.into());
+    assert_eq!(messages[0].role, i32::from(MessageRole::RoleUser));
     match &messages[0].content[0].content {
         Some(ApiContent::Text(text)) => {
             assert_eq!(text, "First message");
@@ -993,7 +1185,7 @@ fn test_to_messages_multiple_completion_messages() {
     }
 
     // Check second message
-    assert_eq!(messages[1].role, MessageRole::RoleAssistant.into());
+    assert_eq!(messages[1].role, i32::from(MessageRole::RoleAssistant));
     match &messages[1].content[0].content {
         Some(ApiContent::Text(text)) => {
             assert_eq!(text, "Second message");
@@ -1397,6 +1589,80 @@ async fn test_process_multi_output() {
     assert!(content_fired.contains(&1));
 }
 
+#[tokio::test]
+async fn test_assemble_or_err_reports_empty_stream() {
+    let chunks = process(mock_stream(vec![]), Consumer::new()).await.unwrap();
+    let err = assemble_or_err(chunks).unwrap_err();
+    assert_eq!(err, AssembleError::EmptyStream);
+    assert_eq!(err.to_string(), "stream ended without any chunks");
+    assert_eq!(Status::from(err).code(), tonic::Code::DataLoss);
+
+    let response = assemble_or_err(vec![make_simple_chunk(0, None, Some("Hi"))]).unwrap();
+    assert_eq!(response.outputs[0].message.as_ref().unwrap().content, "Hi");
+}
+
+#[tokio::test]
+async fn test_completion_callbacks_receive_finish_reason() {
+    let mut truncated = make_finish_chunk(1);
+    truncated.outputs[0].finish_reason = FinishReason::ReasonMaxLen.into();
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("Done")),
+        make_simple_chunk(1, None, Some("Cut")),
+        make_finish_chunk(0),
+        truncated,
+    ];
+
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let tokens = Arc::new(Mutex::new(Vec::new()));
+    let mut consumer = Consumer::new();
+    let reasons_clone = reasons.clone();
+    consumer.on_content_complete = Some(Box::new(move |ctx: &OutputContext| {
+        reasons_clone
+            .lock()
+            .unwrap()
+            .push((ctx.output_index, ctx.finish_reason));
+        Box::pin(async {})
+    }));
+    let tokens_clone = tokens.clone();
+    consumer.on_content_token = Some(Box::new(move |ctx: &OutputContext, _: &str| {
+        tokens_clone.lock().unwrap().push(ctx.finish_reason);
+        Box::pin(async {})
+    }));
+
+    process(mock_stream(chunks), consumer).await.unwrap();
+
+    assert_eq!(
+        *reasons.lock().unwrap(),
+        vec![
+            (0, Some(FinishReason::ReasonStop)),
+            (1, Some(FinishReason::ReasonMaxLen))
+        ]
+    );
+    assert_eq!(*tokens.lock().unwrap(), vec![None, None]);
+}
+
+#[tokio::test]
+async fn test_reasoning_complete_precedes_finish_reason() {
+    let chunks = vec![
+        make_simple_chunk(0, Some("think"), None),
+        make_simple_chunk(0, None, Some("Hi")),
+        make_finish_chunk(0),
+    ];
+
+    let reasons = Arc::new(Mutex::new(Vec::new()));
+    let mut consumer = Consumer::new();
+    let reasons_clone = reasons.clone();
+    consumer.on_reasoning_complete = Some(Box::new(move |ctx: &OutputContext| {
+        reasons_clone.lock().unwrap().push(ctx.finish_reason);
+        Box::pin(async {})
+    }));
+
+    process(mock_stream(chunks), consumer).await.unwrap();
+
+    // Reasoning ends with the first content token, while the output is still generating
+    assert_eq!(*reasons.lock().unwrap(), vec![None]);
+}
+
 // Test case 3: Empty stream
 #[tokio::test]
 async fn test_process_empty_stream() {
@@ -1660,6 +1926,84 @@ async fn test_process_on_inline_citations_invoked() {
 }
 
 // Test case 12: Chunk with empty outputs still collected
+#[tokio::test]
+async fn test_process_on_keepalive_invoked_for_empty_chunks() {
+    let chunks = vec![
+        make_simple_chunk(0, Some("r"), None),
+        make_simple_chunk(0, None, None),
+        make_simple_chunk(0, None, None),
+        make_simple_chunk(0, None, Some("c")),
+        make_finish_chunk(0),
+    ];
+    let count = Arc::new(Mutex::new(0));
+    let count_clone = count.clone();
+    let mut consumer = Consumer::new();
+    consumer.on_keepalive(move |_chunk| {
+        *count_clone.lock().unwrap() += 1;
+        async {}
+    });
+    let result = process(mock_stream(chunks), consumer).await.unwrap();
+    assert_eq!(result.len(), 5);
+    assert_eq!(*count.lock().unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_process_with_idle_timeout_completes_normally() {
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("a")),
+        make_simple_chunk(0, None, Some("b")),
+        make_finish_chunk(0),
+    ];
+    let result =
+        process_with_idle_timeout(mock_stream(chunks), Consumer::new(), Duration::from_secs(5))
+            .await
+            .unwrap();
+    assert_eq!(result.len(), 3);
+}
+
+#[tokio::test]
+async fn test_process_with_idle_timeout_preserves_chunks_on_stall() {
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("a")),
+        make_simple_chunk(0, None, Some("b")),
+    ];
+    let stalled = mock_stream(chunks).chain(stream::pending());
+    let tokens = Arc::new(Mutex::new(String::new()));
+    let tokens_clone = tokens.clone();
+    let mut consumer = Consumer::new();
+    consumer.on_content_token(move |_ctx, token| {
+        tokens_clone.lock().unwrap().push_str(token);
+        async {}
+    });
+    let err = process_with_idle_timeout(stalled, consumer, Duration::from_millis(50))
+        .await
+        .unwrap_err();
+    match err {
+        StreamError::IdleTimeout { idle, chunks } => {
+            assert_eq!(idle, Duration::from_millis(50));
+            assert_eq!(chunks.len(), 2);
+        }
+        other => panic!("expected IdleTimeout, got {other:?}"),
+    }
+    assert_eq!(*tokens.lock().unwrap(), "ab");
+}
+
+#[tokio::test]
+async fn test_process_with_idle_timeout_surfaces_status() {
+    let items = vec![
+        Ok(make_simple_chunk(0, None, Some("a"))),
+        Err(Status::unavailable("gone")),
+    ];
+    let err =
+        process_with_idle_timeout(stream::iter(items), Consumer::new(), Duration::from_secs(5))
+            .await
+            .unwrap_err();
+    match err {
+        StreamError::Status(status) => assert_eq!(status.code(), tonic::Code::Unavailable),
+        other => panic!("expected Status, got {other:?}"),
+    }
+}
+
 #[tokio::test]
 async fn test_process_chunk_with_empty_outputs_collected() {
     let chunks = vec![GetChatCompletionChunk {
@@ -2166,3 +2510,3787 @@ async fn test_with_sink_event_order_chunk_before_phase_events() {
     assert!(reasoning_start_pos.is_some());
     assert!(first_chunk_pos.unwrap() < reasoning_start_pos.unwrap());
 }
+
+// ########################################
+// PARALLEL TESTS
+// ########################################
+
+fn unreachable_client() -> ChatClient {
+    // Lazily connected channel to a closed local port: every call fails fast without network
+    let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+    xai_sdk::chat::client::with_channel(channel, "test-key")
+}
+
+#[test]
+fn test_chat_client_is_clone() {
+    fn assert_clone<T: Clone>() {}
+    assert_clone::<ChatClient>();
+}
+
+#[tokio::test]
+async fn test_rekey_reuses_the_channel_connection() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // A plain TCP listener that counts connections and never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    tokio::spawn({
+        let accepted = accepted.clone();
+        async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let _socket = socket;
+                    std::future::pending::<()>().await
+                });
+            }
+        }
+    });
+
+    let channel = Endpoint::from_shared(format!("http://{addr}"))
+        .unwrap()
+        .connect_lazy();
+    let mut tenant_a = xai_sdk::chat::client::rekey(channel.clone(), "key-a");
+    let mut tenant_b = xai_sdk::chat::client::rekey(channel, "key-b");
+
+    let wait = Duration::from_millis(300);
+    let (a, b) = tokio::join!(
+        tokio::time::timeout(
+            wait,
+            tenant_a.get_completion(GetCompletionsRequest::default())
+        ),
+        tokio::time::timeout(
+            wait,
+            tenant_b.get_completion(GetCompletionsRequest::default())
+        ),
+    );
+    assert!(a.is_err() && b.is_err());
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_rekey_sends_the_new_api_key() {
+    let (url, headers) = header_recording_server().await;
+    let channel = Endpoint::from_shared(url).unwrap().connect_lazy();
+    let mut tenant_a = xai_sdk::chat::client::with_channel(channel.clone(), "key-a");
+    let mut tenant_b = xai_sdk::chat::client::rekey(channel, "key-b");
+
+    for client in [&mut tenant_a, &mut tenant_b] {
+        let _ = client
+            .get_completion(GetCompletionsRequest::default())
+            .await;
+    }
+
+    let headers = headers.lock().unwrap();
+    assert_eq!(headers[0]["authorization"], "Bearer key-a");
+    assert_eq!(headers[1]["authorization"], "Bearer key-b");
+}
+
+#[tokio::test]
+async fn test_parallel_complete_empty_requests() {
+    let client = unreachable_client();
+    let results = xai_sdk::chat::parallel::complete(&client, vec![], 4).await;
+    assert!(results.is_empty());
+}
+
+#[tokio::test]
+async fn test_parallel_complete_one_result_per_request() {
+    let client = unreachable_client();
+    let requests = (0..5)
+        .map(|i| GetCompletionsRequest {
+            model: format!("model-{i}"),
+            ..Default::default()
+        })
+        .collect();
+    // A concurrency of 0 is treated as 1
+    let results = xai_sdk::chat::parallel::complete(&client, requests, 0).await;
+    assert_eq!(results.len(), 5);
+    assert!(results.iter().all(|r| r.is_err()));
+}
+
+// ########################################
+// VALIDATION TESTS
+// ########################################
+
+fn assistant_with_tool_calls(ids: &[&str]) -> Message {
+    Message {
+        role: MessageRole::RoleAssistant.into(),
+        tool_calls: ids
+            .iter()
+            .map(|id| ToolCall {
+                id: id.to_string(),
+                r#type: ToolCallType::ClientSideTool.into(),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn tool_result(id: Option<&str>) -> Message {
+    Message {
+        role: MessageRole::RoleTool.into(),
+        content: vec![xai_sdk::api::Content {
+            content: Some(ApiContent::Text("result".to_string())),
+        }],
+        tool_call_id: id.map(str::to_string),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_validate_tool_results_valid_sequence() {
+    let request = GetCompletionsRequest {
+        messages: vec![
+            Message {
+                role: MessageRole::RoleUser.into(),
+                ..Default::default()
+            },
+            assistant_with_tool_calls(&["call_1", "call_2"]),
+            tool_result(Some("call_1")),
+            tool_result(Some("call_2")),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(validate_tool_results(&request), Ok(()));
+}
+
+#[test]
+fn test_validate_tool_results_dangling_id() {
+    let request = GetCompletionsRequest {
+        messages: vec![
+            assistant_with_tool_calls(&["call_1"]),
+            tool_result(Some("call_1")),
+            tool_result(Some("call_9")),
+        ],
+        ..Default::default()
+    };
+    assert_eq!(
+        validate_tool_results(&request),
+        Err(ValidationError::UnknownToolCallId {
+            message_index: 2,
+            tool_call_id: "call_9".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_validate_tool_results_result_before_call() {
+    // A tool result must follow the assistant message that issued the call
+    let request = GetCompletionsRequest {
+        messages: vec![
+            tool_result(Some("call_1")),
+            assistant_with_tool_calls(&["call_1"]),
+        ],
+        ..Default::default()
+    };
+    assert!(matches!(
+        validate_tool_results(&request),
+        Err(ValidationError::UnknownToolCallId {
+            message_index: 0,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_validate_tool_results_missing_id() {
+    let request = GetCompletionsRequest {
+        messages: vec![assistant_with_tool_calls(&["call_1"]), tool_result(None)],
+        ..Default::default()
+    };
+    assert_eq!(
+        validate_tool_results(&request),
+        Err(ValidationError::MissingToolCallId { message_index: 1 })
+    );
+}
+
+// ########################################
+// REQUEST BUILDER TESTS
+// ########################################
+
+#[test]
+fn test_request_builder_new() {
+    let request = RequestBuilder::new("grok-4").build();
+    assert_eq!(request.model, "grok-4");
+    assert!(request.messages.is_empty());
+    assert!(request.previous_response_id.is_none());
+    assert!(!request.store_messages);
+}
+
+#[test]
+fn test_request_builder_sets_fields() {
+    let mut builder = RequestBuilder::new("grok-3");
+    builder
+        .model("grok-4")
+        .message(Message {
+            role: MessageRole::RoleUser.into(),
+            ..Default::default()
+        })
+        .n(2)
+        .max_tokens(128)
+        .temperature(0.5)
+        .top_p(0.9)
+        .store_messages(true)
+        .use_encrypted_content(true);
+    let request = builder.build();
+    assert_eq!(request.model, "grok-4");
+    assert_eq!(request.messages.len(), 1);
+    assert_eq!(request.n, Some(2));
+    assert_eq!(request.max_tokens, Some(128));
+    assert_eq!(request.temperature, Some(0.5));
+    assert_eq!(request.top_p, Some(0.9));
+    assert!(request.store_messages);
+    assert!(request.use_encrypted_content);
+}
+
+#[test]
+fn test_request_builder_continue_from() {
+    let request = RequestBuilder::new("grok-4")
+        .store_messages(true)
+        .continue_from("resp_123")
+        .build();
+    assert_eq!(request.previous_response_id.as_deref(), Some("resp_123"));
+}
+
+#[test]
+fn test_request_builder_continue_from_response() {
+    let response = GetChatCompletionResponse {
+        id: "resp_456".to_string(),
+        ..Default::default()
+    };
+    let request = RequestBuilder::new("grok-4")
+        .continue_from_response(&response)
+        .build();
+    assert_eq!(request.previous_response_id.as_deref(), Some("resp_456"));
+}
+
+#[test]
+fn test_request_builder_build_is_repeatable() {
+    let mut builder = RequestBuilder::new("grok-4");
+    let first = builder.build();
+    builder.continue_from("resp_1");
+    let second = builder.build();
+    assert!(first.previous_response_id.is_none());
+    assert_eq!(second.previous_response_id.as_deref(), Some("resp_1"));
+}
+
+#[test]
+fn test_request_builder_sampling_params() {
+    let request = RequestBuilder::new("grok-3")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .seed(42)
+        .stop(["END", "STOP"])
+        .frequency_penalty(0.5)
+        .presence_penalty(-1.0)
+        .try_build()
+        .unwrap();
+    assert_eq!(request.seed, Some(42));
+    assert_eq!(request.stop, vec!["END".to_string(), "STOP".to_string()]);
+    assert_eq!(request.frequency_penalty, Some(0.5));
+    assert_eq!(request.presence_penalty, Some(-1.0));
+}
+
+#[test]
+fn test_request_builder_try_build_rejects_penalty_out_of_range() {
+    let err = RequestBuilder::new("grok-3")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .presence_penalty(2.5)
+        .try_build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ValidationError::OutOfRange {
+            field: "presence_penalty",
+            value: 2.5,
+            min: -2.0,
+            max: 2.0,
+        }
+    );
+}
+
+#[test]
+fn test_validate_sampling_params_too_many_stop_sequences() {
+    let request = GetCompletionsRequest {
+        stop: (0..9).map(|i| i.to_string()).collect(),
+        ..Default::default()
+    };
+    assert_eq!(
+        validate_sampling_params(&request),
+        Err(ValidationError::TooManyStopSequences { count: 9 })
+    );
+}
+
+// ########################################
+// RESPONSE ACCESSOR TESTS
+// ########################################
+
+fn tool_call_of(id: &str, call_type: ToolCallType) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        r#type: call_type as i32,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_response_tool_calls_by_index() {
+    let response = response()
+        .output("", FinishReason::ReasonToolCalls)
+        .tool_calls(vec![tool_call_of("a", ToolCallType::ClientSideTool)])
+        .output("", FinishReason::ReasonToolCalls)
+        .tool_calls(vec![
+            tool_call_of("b", ToolCallType::WebSearchTool),
+            tool_call_of("c", ToolCallType::ClientSideTool),
+        ])
+        .build();
+    assert_eq!(response.tool_calls(0).len(), 1);
+    assert_eq!(response.tool_calls(1)[1].id, "c");
+    assert!(response.tool_calls(2).is_empty());
+}
+
+#[test]
+fn test_response_tool_calls_missing_message() {
+    let response = GetChatCompletionResponse {
+        outputs: vec![CompletionOutput::default()],
+        ..Default::default()
+    };
+    assert!(response.tool_calls(0).is_empty());
+    assert!(response.all_tool_calls().is_empty());
+}
+
+#[test]
+fn test_response_all_and_client_side_tool_calls() {
+    let response = response()
+        .output("", FinishReason::ReasonToolCalls)
+        .tool_calls(vec![tool_call_of("a", ToolCallType::ClientSideTool)])
+        .output("", FinishReason::ReasonToolCalls)
+        .tool_calls(vec![
+            tool_call_of("b", ToolCallType::WebSearchTool),
+            tool_call_of("c", ToolCallType::ClientSideTool),
+        ])
+        .build();
+    let all: Vec<(usize, &str)> = response
+        .all_tool_calls()
+        .into_iter()
+        .map(|(index, call)| (index, call.id.as_str()))
+        .collect();
+    assert_eq!(all, vec![(0, "a"), (1, "b"), (1, "c")]);
+
+    let client: Vec<(usize, &str)> = response
+        .client_side_tool_calls()
+        .into_iter()
+        .map(|(index, call)| (index, call.id.as_str()))
+        .collect();
+    assert_eq!(client, vec![(0, "a"), (1, "c")]);
+}
+
+#[test]
+fn test_response_completion_status() {
+    let response = response()
+        .output("", FinishReason::ReasonStop)
+        .output("", FinishReason::ReasonMaxLen)
+        .output("", FinishReason::ReasonMaxContext)
+        .output("", FinishReason::ReasonToolCalls)
+        .output("", FinishReason::ReasonTimeLimit)
+        .output("", FinishReason::ReasonInvalid)
+        .build();
+    assert_eq!(response.completion_status(0), CompletionStatus::Complete);
+    assert_eq!(response.completion_status(1), CompletionStatus::Truncated);
+    assert_eq!(response.completion_status(2), CompletionStatus::Truncated);
+    assert_eq!(
+        response.completion_status(3),
+        CompletionStatus::ToolCallsPending
+    );
+    assert_eq!(response.completion_status(4), CompletionStatus::TimedOut);
+    assert_eq!(response.completion_status(5), CompletionStatus::Unknown);
+    assert_eq!(response.completion_status(6), CompletionStatus::Unknown);
+}
+
+#[test]
+fn test_response_is_truncated() {
+    let complete = response().output("", FinishReason::ReasonStop).build();
+    assert!(!complete.is_truncated());
+    let truncated = response()
+        .output("", FinishReason::ReasonStop)
+        .output("", FinishReason::ReasonMaxLen)
+        .build();
+    assert!(truncated.is_truncated());
+    assert!(CompletionStatus::Truncated.is_truncated());
+    assert!(!CompletionStatus::TimedOut.is_truncated());
+}
+
+#[test]
+fn test_response_stats_counts_chars_not_bytes() {
+    let response = response()
+        .output("Grüße, 世界!\nZweite Zeile\n", FinishReason::ReasonStop)
+        .reasoning("denke…")
+        .output("", FinishReason::ReasonStop)
+        .build();
+    assert_eq!(
+        response.stats(0),
+        ContentStats {
+            char_count: 24,
+            word_count: 4,
+            reasoning_char_count: 6,
+            line_count: 2,
+        }
+    );
+    assert_eq!(response.stats(1), ContentStats::default());
+    assert_eq!(response.stats(7), ContentStats::default());
+}
+
+#[test]
+fn test_response_total_stats_sums_outputs() {
+    let response = response()
+        .output("one two", FinishReason::ReasonStop)
+        .reasoning("why")
+        .output("three\nfour five", FinishReason::ReasonStop)
+        .build();
+    assert_eq!(
+        response.total_stats(),
+        ContentStats {
+            char_count: 22,
+            word_count: 5,
+            reasoning_char_count: 3,
+            line_count: 3,
+        }
+    );
+}
+
+#[test]
+fn test_response_refusal_detects_common_openings() {
+    for content in [
+        "I'm sorry, but I can't help with that.",
+        "  I’m sorry, I cannot assist with this request.",
+        "I cannot provide instructions for that.",
+        "Sorry, I won't write that.",
+        "I'm unable to comply with this request.",
+    ] {
+        let response = response().output(content, FinishReason::ReasonStop).build();
+        assert_eq!(response.refusal(0), Some(content.trim()), "{content}");
+        assert!(response.is_refusal());
+    }
+}
+
+#[test]
+fn test_response_refusal_ignores_normal_answers() {
+    for content in [
+        "I can't believe how simple this is: use a HashMap.",
+        "Sure! Here is the code.",
+        "I cannot stress enough how important tests are.",
+    ] {
+        let response = response().output(content, FinishReason::ReasonStop).build();
+        assert_eq!(response.refusal(0), None, "{content}");
+        assert!(!response.is_refusal());
+    }
+
+    let truncated = response()
+        .output("I can't help with", FinishReason::ReasonMaxLen)
+        .build();
+    assert_eq!(truncated.refusal(0), None);
+    assert_eq!(truncated.refusal(3), None);
+}
+
+// ########################################
+// SEARCH TOOL BUILDER TESTS
+// ########################################
+
+#[test]
+fn test_web_search_builder_sets_options() {
+    let built = tools::web_search()
+        .allowed_domains(["x.ai", "example.com"])
+        .enable_image_understanding(true)
+        .country("GB")
+        .timezone("Europe/London")
+        .build()
+        .unwrap();
+    let Some(tool::Tool::WebSearch(web)) = built.tool else {
+        panic!("expected web search tool");
+    };
+    assert_eq!(web.allowed_domains, vec!["x.ai", "example.com"]);
+    assert_eq!(web.enable_image_understanding, Some(true));
+    let location = web.user_location.unwrap();
+    assert_eq!(location.country.as_deref(), Some("GB"));
+    assert_eq!(location.timezone.as_deref(), Some("Europe/London"));
+    assert_eq!(location.city, None);
+}
+
+#[test]
+fn test_web_search_builder_rejects_conflicting_domains() {
+    let err = tools::web_search()
+        .allowed_domains(["a.com"])
+        .excluded_domains(["b.com"])
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolBuildError::ConflictingFilters {
+            allowed: "allowed_domains",
+            excluded: "excluded_domains",
+        }
+    );
+}
+
+#[test]
+fn test_web_search_builder_rejects_too_many_domains() {
+    let err = tools::web_search()
+        .excluded_domains(["a.com", "b.com", "c.com", "d.com", "e.com", "f.com"])
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ToolBuildError::TooManyDomains {
+            field: "excluded_domains",
+            count: 6,
+        }
+    );
+}
+
+#[test]
+fn test_x_search_builder_date_range() {
+    let from = Timestamp::date(2025, 1, 1).unwrap();
+    let to = Timestamp::date(2025, 1, 7).unwrap();
+    let built = tools::x_search()
+        .allowed_x_handles(["xai"])
+        .date_range(from, to)
+        .build()
+        .unwrap();
+    let Some(tool::Tool::XSearch(x)) = built.tool else {
+        panic!("expected x search tool");
+    };
+    assert_eq!(x.from_date, Some(from));
+    assert_eq!(x.to_date, Some(to));
+    assert_eq!(x.allowed_x_handles, vec!["xai"]);
+}
+
+#[test]
+fn test_x_search_builder_rejects_inverted_date_range() {
+    let from = Timestamp::date(2025, 2, 1).unwrap();
+    let to = Timestamp::date(2025, 1, 1).unwrap();
+    let err = tools::x_search().date_range(from, to).build().unwrap_err();
+    assert_eq!(err, ToolBuildError::InvalidDateRange { from, to });
+}
+
+#[test]
+fn test_x_search_builder_rejects_conflicting_handles() {
+    let err = tools::x_search()
+        .allowed_x_handles(["a"])
+        .excluded_x_handles(["b"])
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, ToolBuildError::ConflictingFilters { .. }));
+}
+
+#[test]
+fn test_mcp_tool_sets_server_and_auth() {
+    let built = tools::mcp(
+        "https://mcp.example.com/sse",
+        vec!["search".to_string()],
+        Some(McpAuth::Bearer("secret".to_string())),
+    )
+    .unwrap();
+    let Some(tool::Tool::Mcp(mcp)) = built.tool else {
+        panic!("expected MCP tool");
+    };
+    assert_eq!(mcp.server_url, "https://mcp.example.com/sse");
+    assert_eq!(mcp.allowed_tool_names, vec!["search"]);
+    assert_eq!(mcp.authorization.as_deref(), Some("Bearer secret"));
+
+    let headers = McpAuth::Headers(vec![("x-api-key".to_string(), "k".to_string())]);
+    let Some(tool::Tool::Mcp(mcp)) = tools::mcp("http://localhost:8080", vec![], Some(headers))
+        .unwrap()
+        .tool
+    else {
+        panic!("expected MCP tool");
+    };
+    assert_eq!(mcp.authorization, None);
+    assert_eq!(
+        mcp.extra_headers.get("x-api-key").map(String::as_str),
+        Some("k")
+    );
+}
+
+#[test]
+fn test_mcp_tool_rejects_invalid_urls() {
+    for url in [
+        "mcp.example.com",
+        "ftp://example.com",
+        "https://",
+        "https://:80/x",
+        "https://a b",
+    ] {
+        let err = tools::mcp(url, vec![], None).unwrap_err();
+        assert!(matches!(err, ToolBuildError::InvalidUrl { .. }), "{url}");
+        assert!(err.to_string().contains(url));
+    }
+}
+
+// ########################################
+// Consumer::collecting() TESTS
+// ########################################
+
+#[tokio::test]
+async fn test_consumer_collecting_first_output_only() {
+    let chunks = vec![
+        make_simple_chunk(0, Some("thinking"), None),
+        make_simple_chunk(0, None, Some("Hello")),
+        make_simple_chunk(1, None, Some("Other")),
+        make_simple_chunk(0, None, Some(", world")),
+        make_finish_chunk(0),
+        make_finish_chunk(1),
+    ];
+    let (consumer, text) = Consumer::collecting();
+    process(mock_stream(chunks), consumer).await.unwrap();
+    assert_eq!(*text.lock().await, "Hello, world");
+}
+
+#[tokio::test]
+async fn test_consumer_collecting_can_be_extended() {
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("a")),
+        make_simple_chunk(0, None, Some("b")),
+        make_finish_chunk(0),
+    ];
+    let seen = Arc::new(Mutex::new(0));
+    let seen_clone = seen.clone();
+    let (mut consumer, text) = Consumer::collecting();
+    consumer.on_chunk(move |_chunk| {
+        *seen_clone.lock().unwrap() += 1;
+        async {}
+    });
+    process(mock_stream(chunks), consumer).await.unwrap();
+    assert_eq!(*text.lock().await, "ab");
+    assert_eq!(*seen.lock().unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_consumer_collecting_all_keys_by_output() {
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("zero ")),
+        make_simple_chunk(1, None, Some("one ")),
+        make_simple_chunk(1, None, Some("more")),
+        make_simple_chunk(0, None, Some("done")),
+        make_finish_chunk(0),
+        make_finish_chunk(1),
+    ];
+    let (consumer, texts) = Consumer::collecting_all();
+    process(mock_stream(chunks), consumer).await.unwrap();
+    let texts = texts.lock().await;
+    assert_eq!(texts.len(), 2);
+    assert_eq!(texts[&0], "zero done");
+    assert_eq!(texts[&1], "one more");
+}
+
+// ########################################
+// LOGPROBS TESTS
+// ########################################
+
+fn log_prob(token: &str, logprob: f32, alternatives: &[(&str, f32)]) -> LogProb {
+    LogProb {
+        token: token.to_string(),
+        logprob,
+        top_logprobs: alternatives
+            .iter()
+            .map(|(token, logprob)| TopLogProb {
+                token: token.to_string(),
+                logprob: *logprob,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_logprobs_tokens() {
+    let logprobs = LogProbs {
+        content: vec![
+            log_prob("Hello", -0.1, &[("Hi", -2.5)]),
+            log_prob("!", 0.0, &[]),
+        ],
+    };
+    let tokens = logprobs.tokens();
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].token, "Hello");
+    assert_eq!(tokens[0].logprob, -0.1);
+    assert_eq!(tokens[0].top_alternatives, vec![("Hi".to_string(), -2.5)]);
+    assert!(tokens[1].top_alternatives.is_empty());
+    assert_eq!(tokens[1].probability(), 1.0);
+}
+
+#[test]
+fn test_logprobs_perplexity() {
+    assert_eq!(LogProbs::default().perplexity(), None);
+    let logprobs = LogProbs {
+        content: vec![log_prob("a", -1.0, &[]), log_prob("b", -3.0, &[])],
+    };
+    let perplexity = logprobs.perplexity().unwrap();
+    assert!((perplexity - 2.0f32.exp()).abs() < 1e-4);
+}
+
+#[test]
+fn test_response_token_logprobs_by_index() {
+    let response = GetChatCompletionResponse {
+        outputs: vec![
+            CompletionOutput {
+                index: 0,
+                logprobs: None,
+                ..Default::default()
+            },
+            CompletionOutput {
+                index: 1,
+                logprobs: Some(LogProbs {
+                    content: vec![log_prob("x", -0.5, &[])],
+                }),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    assert!(response.token_logprobs(0).is_empty());
+    assert_eq!(response.token_logprobs(1)[0].token, "x");
+    assert!(response.token_logprobs(5).is_empty());
+}
+
+#[test]
+fn test_assemble_accumulates_logprobs() {
+    let mut first = make_simple_chunk(0, None, Some("Hel"));
+    first.outputs[0].logprobs = Some(LogProbs {
+        content: vec![log_prob("Hel", -0.2, &[])],
+    });
+    let mut second = make_simple_chunk(0, None, Some("lo"));
+    second.outputs[0].logprobs = Some(LogProbs {
+        content: vec![log_prob("lo", -0.3, &[])],
+    });
+    let response = assemble(vec![first, second, make_finish_chunk(0)]).unwrap();
+    let tokens: Vec<String> = response
+        .token_logprobs(0)
+        .into_iter()
+        .map(|t| t.token)
+        .collect();
+    assert_eq!(tokens, vec!["Hel", "lo"]);
+}
+
+// ########################################
+// COMPRESSION TESTS
+// ########################################
+
+/// Starts an HTTP/2 server on a local port that records the headers of every request and
+/// answers it with `Unimplemented`.
+async fn header_recording_server() -> (String, Arc<Mutex<Vec<http::HeaderMap>>>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let headers = Arc::new(Mutex::new(Vec::new()));
+    tokio::spawn({
+        let headers = headers.clone();
+        async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let headers = headers.clone();
+                tokio::spawn(async move {
+                    let mut connection = h2::server::handshake(socket).await.unwrap();
+                    while let Some(Ok((request, mut respond))) = connection.accept().await {
+                        headers.lock().unwrap().push(request.headers().clone());
+                        let response = http::Response::builder()
+                            .header("content-type", "application/grpc")
+                            .header("grpc-status", "12")
+                            .body(())
+                            .unwrap();
+                        let _ = respond.send_response(response, true);
+                    }
+                });
+            }
+        }
+    });
+    (url, headers)
+}
+
+#[tokio::test]
+async fn test_chat_client_compression_off_by_default() {
+    let (url, headers) = header_recording_server().await;
+    let channel = Endpoint::from_shared(url).unwrap().connect_lazy();
+    let mut client = xai_sdk::chat::client::with_channel(channel, "test-key");
+
+    let status = client
+        .get_completion(GetCompletionsRequest::default())
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), tonic::Code::Unimplemented);
+    let headers = headers.lock().unwrap();
+    assert!(headers[0].get("grpc-encoding").is_none());
+    assert!(headers[0].get("grpc-accept-encoding").is_none());
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn test_chat_client_with_gzip_compression() {
+    use xai_sdk::export::codec::CompressionEncoding;
+
+    let (url, headers) = header_recording_server().await;
+    let channel = Endpoint::from_shared(url).unwrap().connect_lazy();
+    let mut client = xai_sdk::chat::client::with_channel_and_compression(
+        channel,
+        "test-key",
+        CompressionEncoding::Gzip,
+    );
+
+    let status = client
+        .get_completion(GetCompletionsRequest::default())
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), tonic::Code::Unimplemented);
+    let headers = headers.lock().unwrap();
+    assert_eq!(headers[0]["grpc-encoding"], "gzip");
+    assert!(
+        headers[0]["grpc-accept-encoding"]
+            .to_str()
+            .unwrap()
+            .contains("gzip")
+    );
+}
+
+// ########################################
+// CONTINUATION TESTS
+// ########################################
+
+#[tokio::test]
+async fn test_continue_until_complete_propagates_errors() {
+    let client = unreachable_client();
+    let request = RequestBuilder::new("grok-4").build();
+    let result = xai_sdk::chat::continue_until_complete(&client, request, 3).await;
+    assert!(result.is_err());
+}
+
+// ########################################
+// MOCK BACKEND TESTS
+// ########################################
+
+#[tokio::test]
+async fn test_mock_backend_multi_turn_conversation() {
+    let mock = MockChatBackend::new();
+    mock.push_response(
+        response()
+            .id("r1")
+            .output("Hi! How can I help?", FinishReason::ReasonStop)
+            .build(),
+    )
+    .push_response(
+        response()
+            .id("r2")
+            .output("Paris.", FinishReason::ReasonStop)
+            .build(),
+    );
+
+    // Code under test only sees the ChatBackend trait
+    async fn converse<B: ChatBackend>(backend: &mut B, turns: &[&str]) -> Vec<String> {
+        let mut builder = RequestBuilder::new("grok-4");
+        let mut replies = Vec::new();
+        for turn in turns {
+            builder.message(text_message(MessageRole::RoleUser, turn));
+            let response = backend.get_completion(builder.build()).await.unwrap();
+            builder.messages(to_messages(&response.outputs));
+            replies.push(
+                response.outputs[0]
+                    .message
+                    .as_ref()
+                    .unwrap()
+                    .content
+                    .clone(),
+            );
+        }
+        replies
+    }
+
+    let replies = converse(&mut mock.clone(), &["Hello", "Capital of France?"]).await;
+    assert_eq!(replies, vec!["Hi! How can I help?", "Paris."]);
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].messages.len(), 1);
+    // Second turn carries the first exchange plus the new question
+    assert_eq!(requests[1].messages.len(), 3);
+    assert_eq!(
+        requests[1].messages[1].role,
+        MessageRole::RoleAssistant as i32
+    );
+}
+
+#[tokio::test]
+async fn test_mock_backend_empty_queue_fails() {
+    let mut mock = MockChatBackend::new();
+    let err = mock
+        .get_completion(GetCompletionsRequest::default())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+}
+
+#[tokio::test]
+async fn test_mock_backend_stream_drives_process() {
+    let mut mock = MockChatBackend::new();
+    mock.push_stream(vec![
+        make_simple_chunk(0, None, Some("Hello")),
+        make_simple_chunk(0, None, Some(" world")),
+        make_finish_chunk(0),
+    ]);
+    let stream = mock
+        .get_completion_chunk(GetCompletionsRequest::default())
+        .await
+        .unwrap();
+    let (consumer, text) = Consumer::collecting();
+    process(stream, consumer).await.unwrap();
+    assert_eq!(*text.lock().await, "Hello world");
+}
+
+#[tokio::test]
+async fn test_mock_backend_stream_with_error() {
+    let mut mock = MockChatBackend::new();
+    mock.push_stream_with_error(
+        vec![make_simple_chunk(0, None, Some("partial"))],
+        Status::unavailable("connection reset"),
+    );
+    let stream = mock
+        .get_completion_chunk(GetCompletionsRequest::default())
+        .await
+        .unwrap();
+    let err = process(stream, Consumer::new()).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Unavailable);
+}
+
+#[tokio::test]
+async fn test_parallel_complete_with_mock_backend() {
+    let mock = MockChatBackend::new();
+    mock.push_response(
+        response()
+            .id("a")
+            .output("one", FinishReason::ReasonStop)
+            .build(),
+    )
+    .push_error(Status::internal("boom"));
+    let results = xai_sdk::chat::parallel::complete(
+        &mock,
+        vec![
+            GetCompletionsRequest::default(),
+            GetCompletionsRequest::default(),
+        ],
+        1,
+    )
+    .await;
+    assert_eq!(results[0].as_ref().unwrap().id, "a");
+    assert!(results[1].is_err());
+}
+
+#[tokio::test]
+async fn test_continue_until_complete_merges_rounds() {
+    let mock = MockChatBackend::new();
+    mock.push_response(
+        response()
+            .id("r1")
+            .output("Once upon ", FinishReason::ReasonMaxLen)
+            .usage(5, 10)
+            .build(),
+    )
+    .push_response(
+        response()
+            .id("r2")
+            .output("a time", FinishReason::ReasonMaxLen)
+            .usage(5, 10)
+            .build(),
+    )
+    .push_response(
+        response()
+            .id("r3")
+            .output(". The end.", FinishReason::ReasonStop)
+            .usage(5, 10)
+            .build(),
+    );
+
+    let request = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Tell a story"))
+        .build();
+    let response = xai_sdk::chat::continue_until_complete(&mock, request, 5)
+        .await
+        .unwrap();
+
+    assert_eq!(response.id, "r3");
+    assert_eq!(
+        response.outputs[0].message.as_ref().unwrap().content,
+        "Once upon a time. The end."
+    );
+    assert_eq!(response.completion_status(0), CompletionStatus::Complete);
+    assert_eq!(response.usage.unwrap().completion_tokens, 30);
+
+    // Each continuation resends the partial content of the previous round
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].messages.len(), 3);
+}
+
+#[tokio::test]
+async fn test_continue_until_complete_respects_round_limit() {
+    let mock = MockChatBackend::new();
+    mock.push_response(
+        response()
+            .id("r1")
+            .output("a", FinishReason::ReasonMaxLen)
+            .usage(5, 10)
+            .build(),
+    )
+    .push_response(
+        response()
+            .id("r2")
+            .output("b", FinishReason::ReasonMaxLen)
+            .usage(5, 10)
+            .build(),
+    )
+    .push_response(
+        response()
+            .id("r3")
+            .output("c", FinishReason::ReasonStop)
+            .usage(5, 10)
+            .build(),
+    );
+
+    let response =
+        xai_sdk::chat::continue_until_complete(&mock, GetCompletionsRequest::default(), 2)
+            .await
+            .unwrap();
+    assert!(response.is_truncated());
+    assert_eq!(response.outputs[0].message.as_ref().unwrap().content, "ab");
+    assert_eq!(mock.requests().len(), 2);
+}
+
+// ########################################
+// RECORD / REPLAY TESTS
+// ########################################
+
+fn temp_recording(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("xai-sdk-{}-{}.bin", name, std::process::id()))
+}
+
+#[tokio::test]
+async fn test_record_and_replay_round_trip() {
+    let path = temp_recording("round-trip");
+    let chunks = vec![
+        make_simple_chunk(0, Some("thinking"), None),
+        make_simple_chunk(0, None, Some("Hello")),
+        make_finish_chunk(0),
+    ];
+
+    let recording = record(mock_stream(chunks.clone()), &path).unwrap();
+    let processed = process(recording, Consumer::new()).await.unwrap();
+    assert_eq!(processed, chunks);
+
+    let (consumer, text) = Consumer::collecting();
+    let replayed = process(replay(&path).unwrap(), consumer).await.unwrap();
+    assert_eq!(replayed, chunks);
+    assert_eq!(*text.lock().await, "Hello");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_replay_rejects_invalid_file() {
+    let path = temp_recording("invalid");
+    std::fs::write(&path, [0xff, 0xff, 0xff]).unwrap();
+    assert!(replay(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+// ########################################
+// INLINE CITATION TESTS
+// ########################################
+
+fn inline_citation(id: &str, start_index: i32, end_index: i32) -> InlineCitation {
+    InlineCitation {
+        id: id.to_string(),
+        start_index,
+        end_index,
+        citation: None,
+    }
+}
+
+#[test]
+fn test_assemble_orders_inline_citations_by_position() {
+    let chunk = |citations: Vec<InlineCitation>| GetChatCompletionChunk {
+        outputs: vec![CompletionOutputChunk {
+            index: 0,
+            delta: Some(Delta {
+                citations,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let chunks = vec![
+        chunk(vec![inline_citation("2", 20, 25)]),
+        chunk(vec![
+            inline_citation("1", 5, 10),
+            inline_citation("3", 5, 8),
+        ]),
+    ];
+
+    let response = assemble(chunks).unwrap();
+    let ids: Vec<&str> = response
+        .inline_citations(0)
+        .iter()
+        .map(|c| c.id.as_str())
+        .collect();
+    assert_eq!(ids, ["3", "1", "2"]);
+    assert!(response.inline_citations(1).is_empty());
+}
+
+#[test]
+fn test_render_with_citations_replaces_spans() {
+    let response = response()
+        .id("cited")
+        .output(
+            "Rust is fast[[1]](a). It is safe[[2]](b).",
+            FinishReason::ReasonStop,
+        )
+        .citations(vec![
+            inline_citation("2", 32, 40),
+            inline_citation("1", 12, 20),
+        ])
+        .build();
+    assert_eq!(
+        response.render_with_citations(0),
+        "Rust is fast[1]. It is safe[2]."
+    );
+}
+
+#[test]
+fn test_render_with_citations_inserts_empty_spans() {
+    let response = response()
+        .id("cited")
+        .output("Café is open.", FinishReason::ReasonStop)
+        .citations(vec![inline_citation("1", 4, 4)])
+        .build();
+    assert_eq!(response.render_with_citations(0), "Café[1] is open.");
+}
+
+#[test]
+fn test_render_with_citations_edge_cases() {
+    // Overlapping spans are placed after the previous marker; out-of-range offsets are clamped
+    let response = response()
+        .id("cited")
+        .output("abcdef", FinishReason::ReasonStop)
+        .citations(vec![
+            inline_citation("1", 1, 4),
+            inline_citation("2", 2, 5),
+            inline_citation("3", 50, 60),
+            inline_citation("4", -3, 0),
+        ])
+        .build();
+    assert_eq!(response.render_with_citations(0), "[4]a[1][2]f[3]");
+    assert_eq!(response.render_with_citations(3), "");
+}
+
+// ########################################
+// RESPONSE TO CHUNKS TESTS
+// ########################################
+
+fn full_response() -> GetChatCompletionResponse {
+    let mut response = response()
+        .id("resp-1")
+        .output(
+            "Weather in Zürich: ☀️ 21°C[[1]](u)",
+            FinishReason::ReasonToolCalls,
+        )
+        .reasoning("Let me look that up…")
+        .tool_calls(vec![ToolCall {
+            id: "call-1".to_string(),
+            ..Default::default()
+        }])
+        .citations(vec![inline_citation("1", 25, 33)])
+        .output("Sunny.", FinishReason::ReasonStop)
+        .output("", FinishReason::ReasonMaxLen)
+        .usage(8, 12)
+        .build();
+    response.outputs[0]
+        .message
+        .as_mut()
+        .unwrap()
+        .encrypted_content = "opaque".to_string();
+    response.outputs[0].logprobs = Some(LogProbs {
+        content: vec![log_prob("Weather", -0.1, &[]), log_prob(" in", -0.2, &[])],
+    });
+    response.created = Some(Timestamp {
+        seconds: 1_700_000_000,
+        nanos: 0,
+    });
+    response.model = "grok-4".to_string();
+    response.system_fingerprint = "fp".to_string();
+    response.citations = vec!["https://example.com".to_string()];
+    response
+}
+
+#[test]
+fn test_to_chunks_round_trip() {
+    let response = full_response();
+    for chunk_size in 0..=40 {
+        let chunks = to_chunks(&response, chunk_size);
+        assert_eq!(
+            assemble(chunks),
+            Some(response.clone()),
+            "chunk_size = {chunk_size}"
+        );
+    }
+}
+
+#[test]
+fn test_to_chunks_round_trip_simple_responses() {
+    let responses = [
+        GetChatCompletionResponse::default(),
+        response()
+            .id("a")
+            .output("", FinishReason::ReasonStop)
+            .build(),
+        response()
+            .id("b")
+            .output("Hello, world!", FinishReason::ReasonStop)
+            .build(),
+        response()
+            .id("c")
+            .output("日本語のテキスト", FinishReason::ReasonMaxLen)
+            .build(),
+    ];
+    for response in responses {
+        for chunk_size in [1, 2, 3, 7, 100] {
+            assert_eq!(
+                assemble(to_chunks(&response, chunk_size)),
+                Some(response.clone())
+            );
+        }
+    }
+}
+
+#[test]
+fn test_to_chunks_splits_by_characters() {
+    let response = response()
+        .id("id")
+        .output("héllo", FinishReason::ReasonStop)
+        .usage(5, 10)
+        .build();
+    let chunks = to_chunks(&response, 2);
+    let deltas: Vec<&str> = chunks
+        .iter()
+        .map(|c| c.outputs[0].delta.as_ref().unwrap().content.as_str())
+        .collect();
+    assert_eq!(deltas, ["hé", "ll", "o"]);
+
+    // Only the final chunk finishes the output and carries usage
+    assert!(chunks[..2].iter().all(|c| c.outputs[0].finish_reason == 0));
+    assert!(chunks[..2].iter().all(|c| c.usage.is_none()));
+    assert_eq!(
+        chunks[2].outputs[0].finish_reason,
+        FinishReason::ReasonStop as i32
+    );
+    assert!(chunks[2].usage.is_some());
+}
+
+// ########################################
+// ASSEMBLY HARDENING TESTS
+// ########################################
+
+#[test]
+fn test_assemble_keeps_arrival_order() {
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("b")),
+        make_simple_chunk(0, None, Some("a")),
+    ];
+    let response = assemble(chunks).unwrap();
+    assert_eq!(response.outputs[0].message.as_ref().unwrap().content, "ba");
+}
+
+#[test]
+fn test_assemble_skips_duplicate_tool_calls_and_citations() {
+    let tool_call = ToolCall {
+        id: "call-1".to_string(),
+        ..Default::default()
+    };
+    let mut chunk = make_simple_chunk(0, None, None);
+    let delta = chunk.outputs[0].delta.as_mut().unwrap();
+    delta.tool_calls = vec![tool_call.clone()];
+    delta.citations = vec![inline_citation("1", 0, 3)];
+
+    let response = assemble(vec![chunk.clone(), chunk, make_finish_chunk(0)]).unwrap();
+    let message = response.outputs[0].message.as_ref().unwrap();
+    assert_eq!(message.tool_calls, vec![tool_call]);
+    assert_eq!(message.citations.len(), 1);
+}
+
+#[test]
+fn test_assemble_keeps_finish_reason_from_earlier_chunk() {
+    // The finish chunk arrives before a trailing delta that carries no finish reason
+    let mut trailing = make_simple_chunk(0, None, Some("!"));
+    trailing.outputs[0].finish_reason = 0;
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("Hi")),
+        make_finish_chunk(0),
+        trailing,
+    ];
+    let response = assemble(chunks).unwrap();
+    assert_eq!(
+        response.outputs[0].finish_reason,
+        FinishReason::ReasonStop as i32
+    );
+}
+
+// ########################################
+// PER-CHOICE CONSUMER TESTS
+// ########################################
+
+#[derive(Debug, Clone, PartialEq)]
+enum ChoiceEvent {
+    Reasoning(usize, String),
+    Content(usize, String),
+    ToolCalls(usize, usize),
+    Complete(usize, FinishReason),
+}
+
+struct RecordingHandler {
+    index: usize,
+    events: Arc<Mutex<Vec<ChoiceEvent>>>,
+}
+
+impl ChoiceHandler for RecordingHandler {
+    fn on_reasoning_token(&mut self, token: &str) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(ChoiceEvent::Reasoning(self.index, token.to_string()));
+    }
+
+    fn on_content_token(&mut self, token: &str) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(ChoiceEvent::Content(self.index, token.to_string()));
+    }
+
+    fn on_tool_calls(&mut self, tool_calls: &[ToolCall]) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(ChoiceEvent::ToolCalls(self.index, tool_calls.len()));
+    }
+
+    fn on_complete(&mut self, finish_reason: FinishReason) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(ChoiceEvent::Complete(self.index, finish_reason));
+    }
+}
+
+fn recording_consumer() -> (Consumer<'static>, Arc<Mutex<Vec<ChoiceEvent>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let consumer = Consumer::per_choice({
+        let events = events.clone();
+        move |index| RecordingHandler {
+            index,
+            events: events.clone(),
+        }
+    });
+    (consumer, events)
+}
+
+#[tokio::test]
+async fn test_per_choice_routes_tokens_and_completion() {
+    let mut finish_with_token = make_simple_chunk(1, None, Some("B2"));
+    finish_with_token.outputs[0].finish_reason = FinishReason::ReasonMaxLen.into();
+
+    let chunks = vec![
+        make_simple_chunk(0, Some("r0"), None),
+        make_simple_chunk(1, None, Some("B1")),
+        make_simple_chunk(0, None, Some("A1")),
+        finish_with_token,
+        make_simple_chunk(0, None, Some("A2")),
+        make_finish_chunk(0),
+    ];
+
+    let (consumer, events) = recording_consumer();
+    process(mock_stream(chunks), consumer).await.unwrap();
+
+    let events = events.lock().unwrap().clone();
+    let of = |index: usize| -> Vec<ChoiceEvent> {
+        events
+            .iter()
+            .filter(|e| match e {
+                ChoiceEvent::Reasoning(i, _)
+                | ChoiceEvent::Content(i, _)
+                | ChoiceEvent::ToolCalls(i, _)
+                | ChoiceEvent::Complete(i, _) => *i == index,
+            })
+            .cloned()
+            .collect()
+    };
+    assert_eq!(
+        of(0),
+        vec![
+            ChoiceEvent::Reasoning(0, "r0".to_string()),
+            ChoiceEvent::Content(0, "A1".to_string()),
+            ChoiceEvent::Content(0, "A2".to_string()),
+            ChoiceEvent::Complete(0, FinishReason::ReasonStop),
+        ]
+    );
+    assert_eq!(
+        of(1),
+        vec![
+            ChoiceEvent::Content(1, "B1".to_string()),
+            ChoiceEvent::Content(1, "B2".to_string()),
+            ChoiceEvent::Complete(1, FinishReason::ReasonMaxLen),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_per_choice_ignores_deltas_after_completion() {
+    let mut tool_chunk = make_simple_chunk(0, None, None);
+    tool_chunk.outputs[0].delta.as_mut().unwrap().tool_calls = vec![ToolCall::default()];
+
+    let chunks = vec![
+        tool_chunk,
+        make_finish_chunk(0),
+        make_simple_chunk(0, None, Some("late")),
+        make_finish_chunk(0),
+    ];
+
+    let (consumer, events) = recording_consumer();
+    process(mock_stream(chunks), consumer).await.unwrap();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            ChoiceEvent::ToolCalls(0, 1),
+            ChoiceEvent::Complete(0, FinishReason::ReasonStop),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_per_choice_without_finish_reason_does_not_complete() {
+    let (consumer, events) = recording_consumer();
+    process(
+        mock_stream(vec![make_simple_chunk(0, None, Some("cut"))]),
+        consumer,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![ChoiceEvent::Content(0, "cut".to_string())]
+    );
+}
+
+// ########################################
+// INTO CHANNEL TESTS
+// ########################################
+
+#[tokio::test]
+async fn test_into_channel_forwards_events_in_order() {
+    let chunks = vec![
+        make_simple_chunk(0, Some("think"), None),
+        make_simple_chunk(0, None, Some("Hello")),
+        make_finish_chunk(0),
+    ];
+    let (mut rx, handle) = into_channel(mock_stream(chunks.clone()), 4);
+
+    let mut tokens = Vec::new();
+    let mut completed = false;
+    while let Some(event) = rx.recv().await {
+        match event {
+            Event::ReasoningToken(_, token) | Event::ContentToken(_, token) => tokens.push(token),
+            Event::Complete => completed = true,
+            Event::Error(e) => panic!("unexpected error: {e}"),
+            _ => {}
+        }
+    }
+
+    assert_eq!(tokens, ["think", "Hello"]);
+    assert!(completed);
+    assert_eq!(handle.await.unwrap().unwrap(), chunks);
+}
+
+#[tokio::test]
+async fn test_into_channel_reports_stream_error() {
+    let stream = stream::iter(vec![
+        Ok(make_simple_chunk(0, None, Some("partial"))),
+        Err(Status::unavailable("connection lost")),
+    ]);
+    let (mut rx, handle) = into_channel(stream, 8);
+
+    let mut last = None;
+    while let Some(event) = rx.recv().await {
+        last = Some(event);
+    }
+
+    assert!(matches!(last, Some(Event::Error(_))));
+    let status = handle.await.unwrap().unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+}
+
+#[tokio::test]
+async fn test_into_channel_accepts_zero_buffer() {
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("Hello")),
+        make_finish_chunk(0),
+    ];
+    let (mut rx, handle) = into_channel(mock_stream(chunks.clone()), 0);
+
+    let mut completed = false;
+    while let Some(event) = rx.recv().await {
+        completed |= matches!(event, Event::Complete);
+    }
+
+    assert!(completed);
+    assert_eq!(handle.await.unwrap().unwrap(), chunks);
+}
+
+#[tokio::test]
+async fn test_into_channel_cancels_when_receiver_dropped() {
+    // A stream that never ends: processing only stops because the receiver goes away
+    let endless = stream::repeat_with(|| Ok(make_simple_chunk(0, None, Some("tok"))));
+    let (mut rx, handle) = into_channel(endless, 1);
+
+    assert!(rx.recv().await.is_some());
+    drop(rx);
+
+    let status = tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("processing was not cancelled")
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Cancelled);
+}
+
+// ########################################
+// REQUEST VALIDATION TESTS
+// ########################################
+
+#[test]
+fn test_validate_accepts_minimal_request() {
+    let request = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .n(1)
+        .temperature(0.7)
+        .build();
+    assert_eq!(request.validate(), Ok(()));
+}
+
+#[test]
+fn test_validate_rejects_missing_model_and_messages() {
+    let no_model = RequestBuilder::new("")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .build();
+    assert_eq!(no_model.validate(), Err(ValidationError::MissingModel));
+
+    let err = RequestBuilder::new("grok-4").try_build().unwrap_err();
+    assert_eq!(err, ValidationError::NoMessages);
+}
+
+#[test]
+fn test_validate_rejects_invalid_roles() {
+    let mut invalid = text_message(MessageRole::RoleUser, "Hi");
+    invalid.role = MessageRole::InvalidRole.into();
+    let mut unknown = text_message(MessageRole::RoleUser, "Hi");
+    unknown.role = 42;
+
+    for (message, role) in [(invalid, 0), (unknown, 42)] {
+        let err = RequestBuilder::new("grok-4")
+            .message(text_message(MessageRole::RoleUser, "First"))
+            .message(message)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::InvalidRole {
+                message_index: 1,
+                role
+            }
+        );
+    }
+}
+
+#[test]
+fn test_validate_rejects_names_on_non_user_messages() {
+    let mut assistant = text_message(MessageRole::RoleAssistant, "Hello");
+    assistant.name = "bot".to_string();
+    let err = RequestBuilder::new("grok-4")
+        .message(named(MessageRole::RoleUser, "alice", "Hi").unwrap())
+        .message(assistant)
+        .try_build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ValidationError::UnexpectedName {
+            message_index: 1,
+            role: MessageRole::RoleAssistant
+        }
+    );
+}
+
+#[test]
+fn test_validate_rejects_invalid_n_and_temperature() {
+    let err = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .n(0)
+        .try_build()
+        .unwrap_err();
+    assert_eq!(err, ValidationError::InvalidN { n: 0 });
+    assert_eq!(err.to_string(), "`n` is 0, expected at least 1");
+
+    let err = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .temperature(3.0)
+        .try_build()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::OutOfRange {
+            field: "temperature",
+            ..
+        }
+    ));
+}
+
+// ########################################
+// CHUNK STREAM ADAPTER
+// ########################################
+
+#[tokio::test]
+async fn test_chunks_passes_chunks_through() {
+    let input = vec![
+        make_simple_chunk(0, None, Some("a")),
+        make_simple_chunk(0, None, Some("b")),
+        make_simple_chunk(0, None, Some("c")),
+    ];
+    let output: Vec<_> = chunks(mock_stream(input.clone()))
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+    assert_eq!(output, input);
+}
+
+#[tokio::test]
+async fn test_chunks_ends_after_first_error() {
+    let items = vec![
+        Ok(make_simple_chunk(0, None, Some("a"))),
+        Err(Status::unavailable("connection reset")),
+        Ok(make_simple_chunk(0, None, Some("b"))),
+        Err(Status::internal("unreachable")),
+    ];
+    let output: Vec<_> = chunks(stream::iter(items)).collect().await;
+
+    assert_eq!(output.len(), 2);
+    assert!(output[0].is_ok());
+    assert_eq!(
+        output[1].as_ref().unwrap_err().code(),
+        tonic::Code::Unavailable
+    );
+}
+
+#[tokio::test]
+async fn test_chunks_composes_with_combinators() {
+    let input = vec![
+        make_simple_chunk(0, None, Some("a")),
+        make_simple_chunk(0, None, None),
+        make_simple_chunk(0, None, Some("b")),
+        make_simple_chunk(0, None, Some("c")),
+    ];
+    let content: Vec<String> = chunks(mock_stream(input))
+        .filter_map(|chunk| async move {
+            let delta = chunk.ok()?.outputs.into_iter().next()?.delta?;
+            (!delta.content.is_empty()).then_some(delta.content)
+        })
+        .take(2)
+        .collect()
+        .await;
+    assert_eq!(content, vec!["a", "b"]);
+}
+
+// ########################################
+// MERGE SYSTEM MESSAGES
+// ########################################
+
+#[test]
+fn test_named_message() {
+    let message = named(MessageRole::RoleUser, "agent-1", "Status?").unwrap();
+    assert_eq!(message.name(), Some("agent-1"));
+    assert_eq!(message.role_enum(), MessageRole::RoleUser);
+    assert_eq!(message_text(&message), "Status?");
+    assert_eq!(text_message(MessageRole::RoleUser, "Hi").name(), None);
+
+    assert_eq!(
+        named(MessageRole::RoleAssistant, "bot", "Hi").unwrap_err(),
+        NameError::RoleNotUser {
+            role: MessageRole::RoleAssistant
+        }
+    );
+    for name in ["", "  ", "bad\nname"] {
+        assert_eq!(
+            named(MessageRole::RoleUser, name, "Hi").unwrap_err(),
+            NameError::InvalidName {
+                name: name.to_string()
+            }
+        );
+    }
+}
+
+fn message_text(message: &Message) -> String {
+    message
+        .content
+        .iter()
+        .filter_map(|part| match &part.content {
+            Some(ApiContent::Text(text)) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_merge_system_joins_consecutive_system_messages() {
+    let merged = merge_system(vec![
+        text_message(MessageRole::RoleSystem, "You are helpful."),
+        text_message(MessageRole::RoleSystem, "Answer briefly."),
+        text_message(MessageRole::RoleUser, "Hi"),
+        text_message(MessageRole::RoleAssistant, "Hello"),
+        text_message(MessageRole::RoleSystem, "Be polite."),
+        text_message(MessageRole::RoleSystem, "No emojis."),
+        text_message(MessageRole::RoleUser, "Bye"),
+    ]);
+
+    let summary: Vec<(i32, String)> = merged.iter().map(|m| (m.role, message_text(m))).collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                MessageRole::RoleSystem.into(),
+                "You are helpful.\nAnswer briefly.".to_string()
+            ),
+            (MessageRole::RoleUser.into(), "Hi".to_string()),
+            (MessageRole::RoleAssistant.into(), "Hello".to_string()),
+            (
+                MessageRole::RoleSystem.into(),
+                "Be polite.\nNo emojis.".to_string()
+            ),
+            (MessageRole::RoleUser.into(), "Bye".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_merge_system_with_custom_separator() {
+    let merged = merge_system_with(
+        vec![
+            text_message(MessageRole::RoleSystem, "A"),
+            text_message(MessageRole::RoleSystem, "B"),
+            text_message(MessageRole::RoleSystem, "C"),
+        ],
+        "\n\n",
+    );
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].content.len(), 1);
+    assert_eq!(message_text(&merged[0]), "A\n\nB\n\nC");
+}
+
+#[test]
+fn test_merge_system_leaves_single_messages_untouched() {
+    let messages = vec![
+        text_message(MessageRole::RoleSystem, "Only one"),
+        text_message(MessageRole::RoleUser, "Hi"),
+        text_message(MessageRole::RoleUser, "Again"),
+    ];
+    assert_eq!(merge_system(messages.clone()), messages);
+    assert!(merge_system(Vec::new()).is_empty());
+}
+
+// ########################################
+// IMAGE FILES
+// ########################################
+
+fn write_temp_file(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("xai_sdk_{}_{name}", std::process::id()));
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn image_url(content: &xai_sdk::api::Content) -> (&str, i32) {
+    match &content.content {
+        Some(ApiContent::ImageUrl(image)) => (image.image_url.as_str(), image.detail),
+        other => panic!("expected image content, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_image_file_detects_mime_from_magic_bytes() {
+    // PNG signature, saved with a misleading extension
+    let png = write_temp_file("magic.jpg", b"\x89PNG\r\n\x1a\n\0\0");
+    let content = image_file(&png, ImageDetail::DetailHigh).unwrap();
+    let (url, detail) = image_url(&content);
+    assert_eq!(url, "data:image/png;base64,iVBORw0KGgoAAA==");
+    assert_eq!(detail, ImageDetail::DetailHigh as i32);
+
+    let webp = write_temp_file("magic.bin", b"RIFF\x04\0\0\0WEBPVP8 ");
+    let content = image_file(&webp, ImageDetail::DetailAuto).unwrap();
+    assert!(image_url(&content).0.starts_with("data:image/webp;base64,"));
+
+    std::fs::remove_file(png).unwrap();
+    std::fs::remove_file(webp).unwrap();
+}
+
+#[test]
+fn test_image_file_falls_back_to_extension() {
+    let jpeg = write_temp_file("photo.JPEG", b"not a real header");
+    let content = image_file(&jpeg, ImageDetail::DetailLow).unwrap();
+    assert!(image_url(&content).0.starts_with("data:image/jpeg;base64,"));
+    std::fs::remove_file(jpeg).unwrap();
+}
+
+#[test]
+fn test_image_file_rejects_unsupported_formats() {
+    let gif = write_temp_file("anim.gif", b"GIF89a\x01\0");
+    let err = image_file(&gif, ImageDetail::DetailAuto).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("expected PNG, JPEG or WebP"));
+    std::fs::remove_file(gif).unwrap();
+
+    let missing = image_file("/nonexistent/image.png", ImageDetail::DetailAuto).unwrap_err();
+    assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+}
+
+// ########################################
+// REASONING EFFORT
+// ########################################
+
+#[test]
+fn test_reasoning_effort_is_set_for_reasoning_models() {
+    let request = RequestBuilder::new("grok-3-mini-fast-latest")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .reasoning_effort(ReasoningEffort::EffortHigh)
+        .try_build()
+        .unwrap();
+    assert_eq!(
+        request.reasoning_effort,
+        Some(ReasoningEffort::EffortHigh as i32)
+    );
+
+    // Unknown models are passed through unchecked
+    let request = RequestBuilder::new("grok-42-preview")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .reasoning_effort(ReasoningEffort::EffortLow)
+        .try_build();
+    assert!(request.is_ok());
+}
+
+#[test]
+fn test_reasoning_effort_rejected_for_unsupported_models() {
+    for model in ["grok-3", "grok-2-1212", "grok-4-latest", "grok-code-fast-1"] {
+        let err = RequestBuilder::new(model)
+            .message(text_message(MessageRole::RoleUser, "Hi"))
+            .reasoning_effort(ReasoningEffort::EffortLow)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::UnsupportedReasoningEffort {
+                model: model.to_string()
+            }
+        );
+    }
+
+    // Without an effort the same models validate
+    assert!(
+        RequestBuilder::new("grok-3")
+            .message(text_message(MessageRole::RoleUser, "Hi"))
+            .try_build()
+            .is_ok()
+    );
+}
+
+// ########################################
+// CONSUMER TEE
+// ########################################
+
+#[tokio::test]
+async fn test_tee_feeds_both_consumers() {
+    let (first, first_text) = Consumer::collecting();
+    let (second, second_texts) = Consumer::collecting_all();
+
+    let chunks = vec![
+        make_simple_chunk(0, Some("think"), None),
+        make_simple_chunk(0, None, Some("Hello")),
+        make_simple_chunk(1, None, Some("Other")),
+        make_simple_chunk(0, None, Some(" world")),
+    ];
+    process(mock_stream(chunks), first.tee(second))
+        .await
+        .unwrap();
+
+    assert_eq!(*first_text.lock().await, "Hello world");
+    let texts = second_texts.lock().await;
+    assert_eq!(texts[&0], "Hello world");
+    assert_eq!(texts[&1], "Other");
+}
+
+#[tokio::test]
+async fn test_tee_invokes_callbacks_in_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let logging = |name: &'static str| {
+        let mut consumer = Consumer::new_static();
+        consumer
+            .on_chunk({
+                let log = log.clone();
+                move |_| {
+                    log.lock().unwrap().push(format!("{name}:chunk"));
+                    async {}
+                }
+            })
+            .on_content_token({
+                let log = log.clone();
+                move |_, token| {
+                    log.lock().unwrap().push(format!("{name}:{token}"));
+                    async {}
+                }
+            });
+        consumer
+    };
+
+    // Only the second consumer sets a reasoning callback; it is kept as is
+    let mut second = logging("b");
+    second.on_reasoning_token({
+        let log = log.clone();
+        move |_, token| {
+            log.lock().unwrap().push(format!("b:r:{token}"));
+            async {}
+        }
+    });
+
+    let chunks = vec![
+        make_simple_chunk(0, Some("x"), None),
+        make_simple_chunk(0, None, Some("y")),
+    ];
+    process(mock_stream(chunks), logging("a").tee(second))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "a:chunk", "b:chunk", "b:r:x", "a:chunk", "b:chunk", "a:y", "b:y"
+        ]
+    );
+}
+
+// ########################################
+// PROCESS AND COLLECT
+// ########################################
+
+#[tokio::test]
+async fn test_process_collect_returns_response_and_chunks() {
+    let (consumer, text) = Consumer::collecting();
+    let chunks = vec![
+        make_simple_chunk(0, Some("hmm"), None),
+        make_simple_chunk(0, None, Some("Hello")),
+        make_simple_chunk(0, None, Some(" world")),
+    ];
+
+    let (response, collected) = process_collect(mock_stream(chunks.clone()), consumer)
+        .await
+        .unwrap();
+
+    assert_eq!(collected, chunks);
+    assert_eq!(Some(&response), assemble(chunks).as_ref());
+    let message = response.outputs[0].message.as_ref().unwrap();
+    assert_eq!(message.content, "Hello world");
+    assert_eq!(message.reasoning_content, "hmm");
+    assert_eq!(*text.lock().await, "Hello world");
+}
+
+#[tokio::test]
+async fn test_process_collect_errors() {
+    let err = process_collect(mock_stream(Vec::new()), Consumer::new())
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::DataLoss);
+
+    let failing = stream::iter(vec![
+        Ok(make_simple_chunk(0, None, Some("a"))),
+        Err(Status::unavailable("gone")),
+    ]);
+    let err = process_collect(failing, Consumer::new()).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Unavailable);
+}
+
+// ########################################
+// UTF-8 STREAM DECODER
+// ########################################
+
+#[tokio::test]
+async fn test_utf8_decoder_joins_split_emoji() {
+    let tokens = Arc::new(Mutex::new(Vec::new()));
+    let mut consumer = Consumer::new();
+    consumer.on_content_token({
+        let tokens = tokens.clone();
+        move |_, token| {
+            tokens.lock().unwrap().push(token.to_string());
+            async {}
+        }
+    });
+    let on_content_token = consumer.on_content_token.as_mut().unwrap();
+    let ctx = OutputContext::new(1, 0, PhaseStatus::Init, PhaseStatus::Pending);
+
+    // "Hi 🦀!" with the 4-byte crab split across two deltas
+    let bytes = "Hi 🦀!".as_bytes();
+    let deltas = [&bytes[..5], &bytes[5..]];
+
+    let mut decoder = Utf8StreamDecoder::new();
+    for delta in deltas {
+        let text = decoder.decode(delta);
+        if !text.is_empty() {
+            on_content_token(&ctx, &text).await;
+        }
+    }
+    assert!(!decoder.has_pending());
+    assert_eq!(decoder.finish(), "");
+
+    let tokens = tokens.lock().unwrap();
+    assert_eq!(*tokens, vec!["Hi ", "🦀!"]);
+    assert_eq!(tokens.iter().filter(|t| t.contains('🦀')).count(), 1);
+}
+
+#[test]
+fn test_utf8_decoder_byte_by_byte_and_invalid_bytes() {
+    let text = "añ€🦀";
+    let mut decoder = Utf8StreamDecoder::new();
+    let decoded: String = text
+        .as_bytes()
+        .iter()
+        .map(|byte| decoder.decode(std::slice::from_ref(byte)))
+        .collect();
+    assert_eq!(decoded, text);
+
+    // A stray continuation byte is replaced, decoding resumes afterwards
+    assert_eq!(decoder.decode(b"a\x80b"), "a\u{FFFD}b");
+
+    // A truncated character left at the end is flushed as a replacement character
+    assert_eq!(decoder.decode(&"🦀".as_bytes()[..2]), "");
+    assert!(decoder.has_pending());
+    assert_eq!(decoder.finish(), "\u{FFFD}");
+    assert!(!decoder.has_pending());
+}
+
+// ########################################
+// CITATION ACCESSORS
+// ########################################
+
+#[test]
+fn test_inline_citation_accessors() {
+    use xai_sdk::api::inline_citation::Citation;
+    use xai_sdk::api::{CollectionsCitation, WebCitation, XCitation};
+
+    let web = InlineCitation {
+        citation: Some(Citation::WebCitation(WebCitation {
+            url: "https://example.com".to_string(),
+        })),
+        ..inline_citation("1", 0, 0)
+    };
+    assert_eq!(web.url(), Some("https://example.com"));
+    assert_eq!(web.as_web().unwrap().url, "https://example.com");
+    assert!(web.as_x_post().is_none());
+    assert_eq!(web.snippet(), None);
+
+    let x = InlineCitation {
+        citation: Some(Citation::XCitation(XCitation {
+            url: "https://x.com/xai/status/1".to_string(),
+        })),
+        ..inline_citation("2", 0, 0)
+    };
+    assert_eq!(x.url(), Some("https://x.com/xai/status/1"));
+    assert!(x.as_x_post().is_some());
+    assert!(x.citation.as_ref().unwrap().as_web().is_none());
+
+    let collections = InlineCitation {
+        citation: Some(Citation::CollectionsCitation(CollectionsCitation {
+            file_id: "file-1".to_string(),
+            chunk_content: "Rust is a systems language.".to_string(),
+            ..Default::default()
+        })),
+        ..inline_citation("3", 0, 0)
+    };
+    assert_eq!(collections.url(), None);
+    assert_eq!(collections.snippet(), Some("Rust is a systems language."));
+    assert_eq!(collections.as_collections().unwrap().file_id, "file-1");
+
+    let empty = inline_citation("4", 0, 0);
+    assert_eq!(empty.url(), None);
+    assert_eq!(empty.snippet(), None);
+    assert!(empty.as_web().is_none());
+}
+
+// ########################################
+// PROGRESS REPORTING
+// ########################################
+
+#[derive(Default)]
+struct RecordingProgress(Mutex<Vec<ProgressEvent>>);
+
+impl ProgressSink for RecordingProgress {
+    fn on_progress(&self, event: ProgressEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+#[tokio::test]
+async fn test_progress_consumer_reports_phases() {
+    let sink = Arc::new(RecordingProgress::default());
+    let (collector, text) = Consumer::collecting();
+    let consumer = ProgressConsumer::from_arc(sink.clone()).wrap(collector);
+
+    let mut search = make_simple_chunk(0, None, None);
+    search.outputs[0].delta.as_mut().unwrap().tool_calls = vec![ToolCall {
+        id: "call-1".to_string(),
+        r#type: ToolCallType::WebSearchTool.into(),
+        tool: Some(tool_call::Tool::Function(FunctionCall {
+            name: "web_search".to_string(),
+            arguments: "{}".to_string(),
+        })),
+        ..Default::default()
+    }];
+    let mut last = make_simple_chunk(0, None, Some("!"));
+    last.outputs[0].finish_reason = FinishReason::ReasonStop.into();
+
+    let chunks = vec![
+        make_simple_chunk(0, Some("Let me"), None),
+        make_simple_chunk(0, Some(" think"), None),
+        search,
+        make_simple_chunk(0, None, Some("Answer")),
+        last,
+    ];
+    process(mock_stream(chunks), consumer).await.unwrap();
+
+    assert_eq!(*text.lock().await, "Answer!");
+    assert_eq!(
+        *sink.0.lock().unwrap(),
+        vec![
+            ProgressEvent::ReasoningStarted { output_index: 0 },
+            ProgressEvent::ToolCallStarted {
+                output_index: 0,
+                name: "web_search".to_string()
+            },
+            ProgressEvent::ReasoningDone { output_index: 0 },
+            ProgressEvent::ContentStarted { output_index: 0 },
+            ProgressEvent::ContentDone { output_index: 0 },
+        ]
+    );
+}
+
+// ########################################
+// TOOL CHOICE
+// ########################################
+
+fn function_tool(name: &str) -> xai_sdk::api::Tool {
+    xai_sdk::api::Tool {
+        tool: Some(tool::Tool::Function(xai_sdk::api::Function {
+            name: name.to_string(),
+            ..Default::default()
+        })),
+    }
+}
+
+#[test]
+fn test_builder_tool_controls() {
+    let request = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .tool(function_tool("get_weather"))
+        .tools([function_tool("get_time")])
+        .parallel_tool_calls(false)
+        .tool_choice(ToolMode::Required)
+        .try_build()
+        .unwrap();
+
+    assert_eq!(request.tools.len(), 2);
+    assert_eq!(request.parallel_tool_calls, Some(false));
+    assert_eq!(
+        request.tool_choice.unwrap().tool_choice,
+        Some(tool_choice::ToolChoice::Mode(ToolMode::Required as i32))
+    );
+}
+
+#[test]
+fn test_builder_force_tool() {
+    let request = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .tool(function_tool("get_weather"))
+        .force_tool("get_weather")
+        .try_build()
+        .unwrap();
+    assert_eq!(
+        request.tool_choice.unwrap().tool_choice,
+        Some(tool_choice::ToolChoice::FunctionName(
+            "get_weather".to_string()
+        ))
+    );
+
+    let err = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .tool(function_tool("get_weather"))
+        .tool(tools::web_search().build().unwrap())
+        .force_tool("get_time")
+        .try_build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ValidationError::UnknownForcedTool {
+            name: "get_time".to_string()
+        }
+    );
+
+    // A later mode replaces the forced tool
+    let request = RequestBuilder::new("grok-4")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .force_tool("get_time")
+        .tool_choice(ToolMode::Auto)
+        .try_build();
+    assert!(request.is_ok());
+}
+
+// ########################################
+// DELTA CALLBACK
+// ########################################
+
+#[tokio::test]
+async fn test_on_delta_fires_before_token_callbacks() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut consumer = Consumer::new();
+    consumer
+        .on_delta({
+            let log = log.clone();
+            move |ctx, delta| {
+                log.lock().unwrap().push(format!(
+                    "delta:{}:{}:{}:{}",
+                    ctx.output_index,
+                    delta.role,
+                    delta.content,
+                    delta.tool_calls.len()
+                ));
+                async {}
+            }
+        })
+        .on_content_token({
+            let log = log.clone();
+            move |_, token| {
+                log.lock().unwrap().push(format!("token:{token}"));
+                async {}
+            }
+        });
+
+    let mut first = make_simple_chunk(0, None, Some("Hi"));
+    first.outputs[0].delta.as_mut().unwrap().role = MessageRole::RoleAssistant.into();
+    let mut tool_chunk = make_simple_chunk(1, None, None);
+    tool_chunk.outputs[0].delta.as_mut().unwrap().tool_calls = vec![ToolCall::default()];
+    let mut no_delta = make_simple_chunk(0, None, None);
+    no_delta.outputs[0].delta = None;
+
+    process(mock_stream(vec![first, tool_chunk, no_delta]), consumer)
+        .await
+        .unwrap();
+
+    let assistant = MessageRole::RoleAssistant as i32;
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            format!("delta:0:{assistant}:Hi:0"),
+            "token:Hi".to_string(),
+            "delta:1:0::1".to_string(),
+        ]
+    );
+}
+
+// ########################################
+// IDEMPOTENCY KEY
+// ########################################
+
+#[test]
+fn test_build_request_reuses_idempotency_key() {
+    let mut builder = RequestBuilder::new("grok-4");
+    builder.message(Message {
+        role: MessageRole::RoleUser.into(),
+        ..Default::default()
+    });
+
+    let plain = builder.build_request().unwrap();
+    assert!(plain.metadata().get("x-idempotency-key").is_none());
+
+    builder.idempotency_key("order-7");
+    let first = builder.build_request().unwrap();
+    let retry = builder.build_request().unwrap();
+    assert_eq!(
+        first.metadata().get("x-idempotency-key").unwrap(),
+        "order-7"
+    );
+    assert_eq!(
+        retry.metadata().get("x-idempotency-key").unwrap(),
+        "order-7"
+    );
+    assert_eq!(retry.get_ref(), &builder.build());
+
+    builder.idempotency_key("bad\nkey");
+    assert_eq!(
+        builder.build_request().unwrap_err().code(),
+        tonic::Code::InvalidArgument
+    );
+}
+
+// ########################################
+// PARTIAL RESULTS
+// ########################################
+
+#[tokio::test]
+async fn test_process_lenient_keeps_chunks_before_error() {
+    let (consumer, text) = Consumer::collecting();
+    let failing = stream::iter(vec![
+        Ok(make_simple_chunk(0, None, Some("Hello"))),
+        Ok(make_simple_chunk(0, None, Some(" wor"))),
+        Err(Status::unavailable("connection reset")),
+        Ok(make_simple_chunk(0, None, Some("ld"))),
+    ]);
+
+    let result = process_lenient(failing, consumer).await;
+
+    assert!(result.is_partial());
+    assert_eq!(result.chunks.len(), 2);
+    assert_eq!(
+        result.error.as_ref().unwrap().code(),
+        tonic::Code::Unavailable
+    );
+    let response = result.assemble().unwrap();
+    assert_eq!(
+        response.outputs[0].message.as_ref().unwrap().content,
+        "Hello wor"
+    );
+    assert_eq!(*text.lock().await, "Hello wor");
+    assert!(result.into_result().is_err());
+}
+
+#[tokio::test]
+async fn test_process_lenient_complete_stream() {
+    let chunks = vec![make_simple_chunk(0, None, Some("done"))];
+    let result = process_lenient(mock_stream(chunks.clone()), Consumer::new()).await;
+
+    assert!(!result.is_partial());
+    assert_eq!(result.into_result().unwrap(), chunks);
+
+    let empty = process_lenient(mock_stream(Vec::new()), Consumer::new()).await;
+    assert!(empty.assemble().is_none());
+}
+
+// ########################################
+// CODE EXECUTION CALLS
+// ########################################
+
+fn code_execution_call(arguments: &str) -> ToolCall {
+    ToolCall {
+        id: "call_1".to_string(),
+        r#type: ToolCallType::CodeExecutionTool.into(),
+        tool: Some(tool_call::Tool::Function(FunctionCall {
+            name: "code_execution".to_string(),
+            arguments: arguments.to_string(),
+        })),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_as_code_execution_reads_code() {
+    let arguments = r#"{"code":"print(1)","language":"python"}"#;
+    let call = code_execution_call(arguments).as_code_execution().unwrap();
+    assert_eq!(
+        call,
+        CodeExecutionCall {
+            code: "print(1)".to_string(),
+            arguments: arguments.to_string(),
+        }
+    );
+
+    let escaped =
+        CodeExecutionCall::from_arguments(r#"{ "code": "print(\"caf\u00e9 \ud83d\ude00\")\n" }"#);
+    assert_eq!(escaped.code, "print(\"café 😀\")\n");
+
+    let raw = CodeExecutionCall::from_arguments("x = 1");
+    assert_eq!(raw.code, "x = 1");
+    assert_eq!(CodeExecutionCall::from_arguments("{}").code, "");
+}
+
+#[test]
+fn test_as_code_execution_ignores_other_calls() {
+    let mut call = code_execution_call(r#"{"code":"1"}"#);
+    call.r#type = ToolCallType::ClientSideTool.into();
+    assert!(call.as_code_execution().is_none());
+
+    call.r#type = ToolCallType::CodeExecutionTool.into();
+    call.tool = None;
+    assert!(call.as_code_execution().is_none());
+}
+
+// ########################################
+// COLLECTIONS SEARCH
+// ########################################
+
+#[test]
+fn test_collections_search_tool_sets_ids_and_limit() {
+    let built =
+        tools::collections_search(vec!["col_1".to_string(), "col_2".to_string()], 5).unwrap();
+    let Some(tool::Tool::CollectionsSearch(search)) = built.tool else {
+        panic!("expected collections search tool");
+    };
+    assert_eq!(search.collection_ids, vec!["col_1", "col_2"]);
+    assert_eq!(search.limit, Some(5));
+}
+
+#[test]
+fn test_collections_search_tool_validates_inputs() {
+    assert_eq!(
+        tools::collections_search(vec![], 5).unwrap_err(),
+        ToolBuildError::InvalidCollections { count: 0 }
+    );
+    let too_many = (0..11).map(|i| format!("col_{i}")).collect();
+    assert_eq!(
+        tools::collections_search(too_many, 5).unwrap_err(),
+        ToolBuildError::InvalidCollections { count: 11 }
+    );
+    for top_k in [0, u32::MAX] {
+        assert_eq!(
+            tools::collections_search(vec!["c".to_string()], top_k).unwrap_err(),
+            ToolBuildError::InvalidTopK { top_k }
+        );
+    }
+}
+
+#[test]
+fn test_as_collections_search_reads_query() {
+    let arguments = r#"{"query":"refund policy","limit":3}"#;
+    let mut call = code_execution_call(arguments);
+    assert!(call.as_collections_search().is_none());
+
+    call.r#type = ToolCallType::CollectionsSearchTool.into();
+    let search = call.as_collections_search().unwrap();
+    assert_eq!(search.query, "refund policy");
+    assert_eq!(search.arguments, arguments);
+
+    let query_only = CollectionsSearchCall::from_arguments("refunds");
+    assert_eq!(query_only.query, "refunds");
+    assert_eq!(query_only.arguments, "refunds");
+}
+
+// ########################################
+// INCLUDE OPTIONS
+// ########################################
+
+#[test]
+fn test_include_dedupes_options() {
+    let mut builder = RequestBuilder::new("grok-4");
+    builder
+        .include(IncludeOption::InlineCitations)
+        .include_all(IncludeOption::all_outputs())
+        .include(IncludeOption::InlineCitations)
+        .include(IncludeOption::WebSearchCallOutput);
+
+    let request = builder.build();
+    assert_eq!(request.include.len(), 7);
+    assert_eq!(request.include[0], IncludeOption::InlineCitations as i32);
+    assert!(
+        IncludeOption::all_outputs()
+            .iter()
+            .all(|option| request.include.contains(&(*option as i32)))
+    );
+    assert!(IncludeOption::McpCallOutput.is_call_output());
+    assert!(!IncludeOption::VerboseStreaming.is_call_output());
+}
+
+#[test]
+fn test_validate_rejects_invalid_include_option() {
+    let mut builder = RequestBuilder::new("grok-4");
+    builder
+        .message(Message {
+            role: MessageRole::RoleUser.into(),
+            ..Default::default()
+        })
+        .include(IncludeOption::VerboseStreaming);
+    assert!(builder.try_build().is_ok());
+
+    builder.include(IncludeOption::Invalid);
+    assert_eq!(
+        builder.try_build().unwrap_err(),
+        ValidationError::InvalidIncludeOption { value: 0 }
+    );
+
+    let mut request = RequestBuilder::new("grok-4").build();
+    request.messages.push(Message {
+        role: MessageRole::RoleUser.into(),
+        ..Default::default()
+    });
+    request.include.push(99);
+    assert_eq!(
+        request.validate(),
+        Err(ValidationError::InvalidIncludeOption { value: 99 })
+    );
+}
+
+// ########################################
+// TEXT CONVERSIONS
+// ########################################
+
+#[test]
+fn test_content_and_message_from_text() {
+    let content: Vec<xai_sdk::api::Content> = vec!["hi".into(), String::from("there").into()];
+    assert_eq!(content[0].content, Some(ApiContent::Text("hi".to_string())));
+    assert_eq!(
+        content[1].content,
+        Some(ApiContent::Text("there".to_string()))
+    );
+
+    let message = Message::from((MessageRole::RoleUser, "hi"));
+    assert_eq!(message.role, MessageRole::RoleUser as i32);
+    assert_eq!(message.content, vec!["hi".into()]);
+    assert_eq!(
+        Message::from((MessageRole::RoleSystem, "be brief".to_string())),
+        Message {
+            role: MessageRole::RoleSystem.into(),
+            content: vec!["be brief".into()],
+            ..Default::default()
+        }
+    );
+}
+
+// ########################################
+// ASYNC WRITER
+// ########################################
+
+#[tokio::test]
+async fn test_with_async_writer_writes_selected_output() {
+    use tokio::io::AsyncReadExt;
+
+    let (writer, mut reader) = tokio::io::duplex(1024);
+    let chunks = vec![
+        make_simple_chunk(0, Some("thinking"), None),
+        make_simple_chunk(0, None, Some("Hello")),
+        make_simple_chunk(1, None, Some("other")),
+        make_simple_chunk(0, None, Some(", café")),
+    ];
+
+    process(mock_stream(chunks), Consumer::with_async_writer(writer, 0))
+        .await
+        .unwrap();
+
+    let mut written = String::new();
+    reader.read_to_string(&mut written).await.unwrap();
+    assert_eq!(written, "Hello, café");
+}
+
+#[tokio::test]
+async fn test_with_async_writer_stops_after_error() {
+    let (writer, reader) = tokio::io::duplex(64);
+    drop(reader);
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("a")),
+        make_simple_chunk(0, None, Some("b")),
+    ];
+
+    let collected = process(mock_stream(chunks), Consumer::with_async_writer(writer, 0))
+        .await
+        .unwrap();
+    assert_eq!(collected.len(), 2);
+}
+
+// ########################################
+// SENTENCES
+// ########################################
+
+async fn collect_sentences(tokens: &[&str]) -> Vec<Result<String, Status>> {
+    let chunks = tokens
+        .iter()
+        .map(|token| make_simple_chunk(0, None, Some(token)))
+        .collect();
+    sentences(mock_stream(chunks)).collect().await
+}
+
+#[tokio::test]
+async fn test_sentences_splits_on_boundaries_across_tokens() {
+    let items = collect_sentences(&[
+        "Hello there",
+        ". How",
+        " are you?",
+        " Pi is 3.",
+        "14! Dr. Smith said \"hi.\"",
+        " List:\n- one",
+    ])
+    .await;
+    let sentences: Vec<String> = items.into_iter().map(Result::unwrap).collect();
+    assert_eq!(
+        sentences,
+        vec![
+            "Hello there.",
+            "How are you?",
+            "Pi is 3.14!",
+            "Dr. Smith said \"hi.\"",
+            "List:",
+            "- one",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_sentences_ignores_other_outputs_and_reasoning() {
+    let chunks = vec![
+        make_simple_chunk(0, Some("Thinking. Hard."), None),
+        make_simple_chunk(1, None, Some("Other. ")),
+        make_simple_chunk(0, None, Some("Only this.")),
+    ];
+    let items: Vec<_> = sentences(mock_stream(chunks)).collect().await;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].as_ref().unwrap(), "Only this.");
+}
+
+#[tokio::test]
+async fn test_sentences_passes_error_and_drops_partial_sentence() {
+    let items: Vec<_> = sentences(stream::iter(vec![
+        Ok(make_simple_chunk(0, None, Some("Done. Partial"))),
+        Err(Status::unavailable("reset")),
+        Ok(make_simple_chunk(0, None, Some("never"))),
+    ]))
+    .collect()
+    .await;
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), "Done.");
+    assert_eq!(
+        items[1].as_ref().unwrap_err().code(),
+        tonic::Code::Unavailable
+    );
+}
+
+// ########################################
+// POOL
+// ########################################
+
+#[tokio::test]
+async fn test_pool_limits_concurrent_streams() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let backend = MockChatBackend::new();
+    for _ in 0..4 {
+        backend.push_stream(vec![
+            make_simple_chunk(0, None, Some("a")),
+            make_simple_chunk(0, None, Some("b")),
+        ]);
+    }
+    let pool = Pool::new(backend.clone(), 2);
+    assert_eq!(pool.max_in_flight(), 2);
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let runs = (0..4).map(|_| {
+        let pool = pool.clone();
+        let observer = pool.clone();
+        let peak = peak.clone();
+        async move {
+            let mut consumer = Consumer::new();
+            consumer.on_chunk(move |_chunk| {
+                peak.fetch_max(observer.in_flight(), Ordering::SeqCst);
+                tokio::task::yield_now()
+            });
+            pool.acquire_and_stream(GetCompletionsRequest::default(), consumer)
+                .await
+        }
+    });
+    let results = futures::future::join_all(runs).await;
+
+    assert!(
+        results
+            .iter()
+            .all(|result| result.as_ref().unwrap().len() == 2)
+    );
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    assert_eq!(pool.in_flight(), 0);
+    assert_eq!(backend.requests().len(), 4);
+}
+
+#[test]
+fn test_pool_clamps_max_in_flight() {
+    let pool = Pool::new(MockChatBackend::new(), usize::MAX);
+    assert_eq!(pool.max_in_flight(), tokio::sync::Semaphore::MAX_PERMITS);
+    assert_eq!(pool.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_pool_releases_slot_on_error() {
+    let backend = MockChatBackend::new();
+    backend.push_stream_error(Status::unavailable("down"));
+    let pool = Pool::new(backend, 0);
+    assert_eq!(pool.max_in_flight(), 1);
+
+    let result = pool
+        .acquire_and_stream(GetCompletionsRequest::default(), Consumer::new())
+        .await;
+    assert_eq!(result.unwrap_err().code(), tonic::Code::Unavailable);
+    assert_eq!(pool.in_flight(), 0);
+}
+
+// ########################################
+// TRANSCRIPT
+// ########################################
+
+fn transcript_messages() -> Vec<Message> {
+    let mut user = Message::from((MessageRole::RoleUser, "What is in this picture?"));
+    user.name = "alice".to_string();
+    user.content.push(xai_sdk::api::Content {
+        content: Some(ApiContent::ImageUrl(xai_sdk::api::ImageUrlContent {
+            image_url: "https://example.com/cat.png".to_string(),
+            ..Default::default()
+        })),
+    });
+
+    let mut assistant = Message::from((MessageRole::RoleAssistant, "Let me check."));
+    assistant.reasoning_content = Some("hidden".to_string());
+    assistant.tool_calls = vec![ToolCall {
+        id: "call_1".to_string(),
+        tool: Some(tool_call::Tool::Function(FunctionCall {
+            name: "classify".to_string(),
+            arguments: r#"{"image":0}"#.to_string(),
+        })),
+        ..Default::default()
+    }];
+
+    let mut tool = Message::from((MessageRole::RoleTool, "[\"cat\"]"));
+    tool.tool_call_id = Some("call_1".to_string());
+
+    vec![
+        Message::from((MessageRole::RoleSystem, "Be brief.")),
+        user,
+        assistant,
+        tool,
+    ]
+}
+
+#[test]
+fn test_transcript_render() {
+    assert_eq!(
+        transcript::render(&transcript_messages()),
+        "SYSTEM: Be brief.\n\
+         USER (alice): What is in this picture? [image]\n\
+         ASSISTANT: Let me check. [tool call classify({\"image\":0})]\n\
+         TOOL (call_1): [\"cat\"]"
+    );
+    assert_eq!(transcript::render(&[]), "");
+}
+
+#[test]
+fn test_transcript_render_markdown() {
+    let markdown = transcript::render_markdown(&transcript_messages());
+    assert_eq!(
+        markdown,
+        "### System\n\nBe brief.\n\n\
+         ### User (alice)\n\nWhat is in this picture?\n\n`[image]`\n\n\
+         ### Assistant\n\nLet me check.\n\n`[tool call classify({\"image\":0})]`\n\n\
+         ### Tool (call_1)\n\n[\"cat\"]"
+    );
+}
+
+// ########################################
+// NDJSON LOGGER
+// ########################################
+
+#[tokio::test]
+async fn test_ndjson_logger_writes_one_line_per_event() {
+    let path = std::env::temp_dir().join(format!("xai-sdk-ndjson-{}.ndjson", std::process::id()));
+    let mut finish = make_finish_chunk(0);
+    finish.usage = Some(SamplingUsage {
+        prompt_tokens: 3,
+        completion_tokens: 2,
+        total_tokens: 5,
+        ..Default::default()
+    });
+    let chunks = vec![
+        make_simple_chunk(0, Some("hmm"), None),
+        make_simple_chunk(0, None, Some("Hi")),
+        finish,
+    ];
+
+    let consumer = Consumer::ndjson_logger(&path).unwrap();
+    process(mock_stream(chunks), consumer).await.unwrap();
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert!(lines.iter().all(|line| line.starts_with("{\"ts_ms\":")));
+
+    let types: Vec<&str> = lines
+        .iter()
+        .map(|line| {
+            line.split("\"type\":\"")
+                .nth(1)
+                .unwrap()
+                .split('"')
+                .next()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(types.iter().filter(|t| **t == "chunk").count(), 3);
+    for expected in ["reasoning", "content", "content_complete", "usage"] {
+        assert!(types.contains(&expected), "missing {expected}: {types:?}");
+    }
+    assert!(log.contains("\"type\":\"content\",\"index\":0,\"text\":\"Hi\""));
+    assert!(log.contains("\"prompt_tokens\":3,\"completion_tokens\":2"));
+}
+
+#[test]
+fn test_ndjson_logger_reports_create_error() {
+    let path = std::env::temp_dir()
+        .join("xai-sdk-missing-dir")
+        .join("log.ndjson");
+    assert!(Consumer::ndjson_logger(path).is_err());
+}
+
+// ########################################
+// SERVER-SENT EVENTS
+// ########################################
+
+#[tokio::test]
+async fn test_to_sse_formats_events() {
+    let mut tool_chunk = make_simple_chunk(0, None, None);
+    tool_chunk.outputs[0].delta.as_mut().unwrap().tool_calls = vec![ToolCall {
+        id: "call_1".to_string(),
+        r#type: ToolCallType::ClientSideTool.into(),
+        tool: Some(tool_call::Tool::Function(FunctionCall {
+            name: "lookup".to_string(),
+            arguments: r#"{"q":"x"}"#.to_string(),
+        })),
+        ..Default::default()
+    }];
+    let mut finish = make_finish_chunk(0);
+    finish.usage = Some(SamplingUsage {
+        prompt_tokens: 3,
+        completion_tokens: 2,
+        total_tokens: 5,
+        ..Default::default()
+    });
+    let chunks = vec![
+        make_simple_chunk(0, Some("hm"), None),
+        make_simple_chunk(0, None, Some("say \"hi\"\n")),
+        tool_chunk,
+        finish,
+    ];
+
+    let frames: Vec<String> = to_sse(mock_stream(chunks))
+        .map(Result::unwrap)
+        .collect()
+        .await;
+
+    assert_eq!(
+        frames,
+        vec![
+            "data: {\"type\":\"reasoning\",\"index\":0,\"text\":\"hm\"}\n\n",
+            "data: {\"type\":\"content\",\"index\":0,\"text\":\"say \\\"hi\\\"\\n\"}\n\n",
+            "data: {\"type\":\"tool_calls\",\"index\":0,\"tool_calls\":[{\"id\":\"call_1\",\"type\":\"client_side_tool\",\"name\":\"lookup\",\"arguments\":\"{\\\"q\\\":\\\"x\\\"}\"}]}\n\n",
+            "data: {\"type\":\"finish\",\"index\":0,\"reason\":\"stop\"}\n\n",
+            "data: {\"type\":\"usage\",\"prompt_tokens\":3,\"completion_tokens\":2,\"reasoning_tokens\":0,\"total_tokens\":5}\n\n",
+            SSE_DONE,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_to_sse_passes_error_without_done() {
+    let failing = stream::iter(vec![
+        Ok(make_simple_chunk(0, None, Some("a"))),
+        Err(Status::unavailable("gone")),
+    ]);
+
+    let frames: Vec<Result<String, Status>> = to_sse(failing).collect().await;
+
+    assert_eq!(frames.len(), 2);
+    assert!(frames[0].as_ref().unwrap().contains("\"text\":\"a\""));
+    assert_eq!(
+        frames[1].as_ref().unwrap_err().code(),
+        tonic::Code::Unavailable
+    );
+}
+
+// ########################################
+// RESPONSE DIFF
+// ########################################
+
+#[test]
+fn test_diff_words() {
+    assert_eq!(
+        diff::diff_words("the quick fox", "the slow  fox jumps"),
+        vec![
+            WordChange::Same("the".to_string()),
+            WordChange::Removed("quick".to_string()),
+            WordChange::Added("slow".to_string()),
+            WordChange::Same("fox".to_string()),
+            WordChange::Added("jumps".to_string()),
+        ]
+    );
+    assert!(diff::diff_words("", "").is_empty());
+}
+
+#[test]
+fn test_diff_words_finds_longest_common_subsequence() {
+    fn lcs_len(a: &[&str], b: &[&str]) -> usize {
+        let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                table[i + 1][j + 1] = if a[i] == b[j] {
+                    table[i][j] + 1
+                } else {
+                    table[i][j + 1].max(table[i + 1][j])
+                };
+            }
+        }
+        table[a.len()][b.len()]
+    }
+
+    // Pseudo-random texts over a small vocabulary, so they share many words
+    let mut seed = 7u32;
+    let mut text = |len: usize| {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                ["a", "b", "c", "d"][(seed >> 16) as usize % 4]
+            })
+            .collect::<Vec<_>>()
+    };
+    for (len_a, len_b) in [(0, 5), (5, 0), (1, 6), (9, 4), (17, 23), (40, 40)] {
+        let (a, b) = (text(len_a), text(len_b));
+        let changes = diff::diff_words(&a.join(" "), &b.join(" "));
+
+        let kept = |keep: fn(&WordChange) -> Option<&String>| -> Vec<&str> {
+            changes
+                .iter()
+                .filter_map(keep)
+                .map(String::as_str)
+                .collect()
+        };
+        let old = kept(|c| match c {
+            WordChange::Same(w) | WordChange::Removed(w) => Some(w),
+            WordChange::Added(_) => None,
+        });
+        let new = kept(|c| match c {
+            WordChange::Same(w) | WordChange::Added(w) => Some(w),
+            WordChange::Removed(_) => None,
+        });
+        assert_eq!((old, new), (a.clone(), b.clone()));
+        let same = changes
+            .iter()
+            .filter(|c| matches!(c, WordChange::Same(_)))
+            .count();
+        assert_eq!(same, lcs_len(&a, &b));
+        // Removals come before additions within each run of changes
+        assert!(
+            changes
+                .windows(2)
+                .all(|w| !matches!(w, [WordChange::Added(_), WordChange::Removed(_)]))
+        );
+    }
+}
+
+#[test]
+fn test_diff_words_handles_long_texts() {
+    let a: Vec<String> = (0..10_000).map(|i| format!("w{i}")).collect();
+    let mut b = a.clone();
+    b[5_000] = "changed".to_string();
+    let changes = diff::diff_words(&a.join(" "), &b.join(" "));
+    assert_eq!(changes.len(), 10_001);
+    assert_eq!(changes[5_000], WordChange::Removed("w5000".to_string()));
+    assert_eq!(changes[5_001], WordChange::Added("changed".to_string()));
+}
+
+#[test]
+fn test_compare_reports_content_finish_reason_and_usage() {
+    let a = response()
+        .output("Paris is the capital", FinishReason::ReasonStop)
+        .output("same", FinishReason::ReasonStop)
+        .usage(10, 4)
+        .build();
+    let b = response()
+        .output("Paris is the capital of France", FinishReason::ReasonMaxLen)
+        .output("same", FinishReason::ReasonStop)
+        .output("extra", FinishReason::ReasonStop)
+        .usage(10, 7)
+        .build();
+
+    let result = diff::compare(&a, &b);
+    assert!(!result.is_unchanged());
+    assert_eq!(result.choices.len(), 3);
+
+    let first = &result.choices[0];
+    assert!(first.content_changed());
+    assert!(first.finish_reason_changed());
+    assert_eq!(first.to_string(), "Paris is the capital {+of+} {+France+}");
+
+    assert!(!result.choices[1].content_changed());
+    assert_eq!(
+        result.choices[2].finish_reasons,
+        (FinishReason::ReasonInvalid, FinishReason::ReasonStop)
+    );
+    assert_eq!(
+        result
+            .changed_choices()
+            .map(|c| c.index)
+            .collect::<Vec<_>>(),
+        vec![0, 2]
+    );
+
+    assert_eq!(result.usage.completion_tokens, 3);
+    assert_eq!(result.usage.total_tokens, 3);
+    assert_eq!(result.usage.prompt_tokens, 0);
+}
+
+#[test]
+fn test_compare_identical_responses_is_unchanged() {
+    let a = response()
+        .output("hello world", FinishReason::ReasonStop)
+        .usage(10, 2)
+        .build();
+    let result = diff::compare(&a, &a);
+    assert!(result.is_unchanged());
+    assert_eq!(result.usage, diff::UsageDelta::default());
+}
+
+// ########################################
+// RESPONSE FORMAT
+// ########################################
+
+#[test]
+fn test_json_object_and_json_schema_str_set_response_format() {
+    let mut builder = RequestBuilder::new("grok-4");
+    builder
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .json_object();
+    let format = builder.try_build().unwrap().response_format.unwrap();
+    assert_eq!(format.format_type, FormatType::JsonObject as i32);
+    assert_eq!(format.schema, None);
+
+    let schema = r#"{"type":"object","properties":{"city":{"type":"string"}}}"#;
+    builder.json_schema_str(schema);
+    let format = builder.try_build().unwrap().response_format.unwrap();
+    assert_eq!(format.format_type, FormatType::JsonSchema as i32);
+    assert_eq!(format.schema.as_deref(), Some(schema));
+}
+
+#[test]
+fn test_json_schema_rejects_invalid_schema_and_unsupported_models() {
+    for schema in ["not json", "[1, 2]", r#"{"type": "object""#] {
+        let err = RequestBuilder::new("grok-4")
+            .message(text_message(MessageRole::RoleUser, "Hi"))
+            .json_schema_str(schema)
+            .try_build()
+            .unwrap_err();
+        assert_eq!(err, ValidationError::InvalidJsonSchema);
+    }
+
+    let err = RequestBuilder::new("grok-2-vision-1212")
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .json_object()
+        .try_build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ValidationError::UnsupportedStructuredOutput {
+            model: "grok-2-vision-1212".to_string()
+        }
+    );
+
+    // Unknown models are passed through unchecked
+    assert!(
+        RequestBuilder::new("grok-42-preview")
+            .message(text_message(MessageRole::RoleUser, "Hi"))
+            .json_schema_str(r#"{"type":"object"}"#)
+            .try_build()
+            .is_ok()
+    );
+}
+
+// ########################################
+// USAGE COLLECTION
+// ########################################
+
+fn usage(completion_tokens: i32) -> SamplingUsage {
+    SamplingUsage {
+        prompt_tokens: 5,
+        completion_tokens,
+        total_tokens: 5 + completion_tokens,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_last_usage_skips_trailing_chunks_without_usage() {
+    let mut with_usage = make_simple_chunk(0, None, Some("done"));
+    with_usage.usage = Some(usage(3));
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("hi")),
+        with_usage,
+        make_simple_chunk(0, None, None),
+    ];
+
+    assert_eq!(last_usage(&chunks), Some(usage(3)));
+    assert_eq!(assemble(chunks.clone()).unwrap().usage, Some(usage(3)));
+    assert_eq!(last_usage(&chunks[..1]), None);
+    assert_eq!(chunks[0].usage_or_empty(), SamplingUsage::default());
+    assert_eq!(chunks[1].usage_or_empty(), usage(3));
+}
+
+#[tokio::test]
+async fn test_stream_usage_collected_without_consumer_callback() {
+    let mut last = make_simple_chunk(0, None, Some("done"));
+    last.usage = Some(usage(7));
+    let chunks = vec![last, make_simple_chunk(0, None, None)];
+
+    let result = process_lenient(mock_stream(chunks.clone()), Consumer::new()).await;
+    assert_eq!(result.usage, Some(usage(7)));
+
+    let seen = Arc::new(Mutex::new(None));
+    let mut consumer = Consumer::new();
+    let sink = seen.clone();
+    consumer.on_usage(move |usage| {
+        *sink.lock().unwrap() = Some(usage.clone());
+        async {}
+    });
+    process(mock_stream(chunks), consumer).await.unwrap();
+    assert_eq!(*seen.lock().unwrap(), Some(usage(7)));
+}
+
+// ########################################
+// RESILIENT STREAMS
+// ########################################
+
+fn no_backoff(max_restarts: u32) -> RestartPolicy {
+    RestartPolicy {
+        max_restarts,
+        backoff: Duration::ZERO,
+        max_retry_after: Duration::from_secs(60),
+    }
+}
+
+fn resilient_request() -> impl FnMut() -> Result<Request<GetCompletionsRequest>, Status> + Send {
+    || {
+        Ok(Request::new(
+            RequestBuilder::new("grok-4")
+                .message(text_message(MessageRole::RoleUser, "Hi"))
+                .build(),
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_resilient_restarts_after_dropped_stream() {
+    let backend = MockChatBackend::new();
+    backend
+        .push_stream_error(Status::unavailable("connect failed"))
+        .push_stream_with_error(
+            vec![make_simple_chunk(0, None, Some("Hel"))],
+            Status::unavailable("connection reset"),
+        )
+        .push_stream(vec![make_simple_chunk(0, None, Some("Hello"))]);
+
+    let events: Vec<_> = resilient(backend.clone(), resilient_request(), no_backoff(3))
+        .collect()
+        .await;
+
+    assert_eq!(events.len(), 3);
+    assert!(matches!(&events[0], Ok(ResilientEvent::Chunk(_))));
+    // The failure before the first chunk restarted silently
+    match &events[1] {
+        Ok(ResilientEvent::Restarted { attempt, status }) => {
+            assert_eq!(*attempt, 2);
+            assert_eq!(status.code(), tonic::Code::Unavailable);
+        }
+        other => panic!("expected a restart, got {other:?}"),
+    }
+    match &events[2] {
+        Ok(ResilientEvent::Chunk(chunk)) => {
+            assert_eq!(chunk.outputs[0].delta.as_ref().unwrap().content, "Hello")
+        }
+        other => panic!("expected a chunk, got {other:?}"),
+    }
+    assert_eq!(backend.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_resilient_sends_request_metadata_on_every_attempt() {
+    let backend = MockChatBackend::new();
+    backend
+        .push_stream_error(Status::unavailable("connect failed"))
+        .push_stream(vec![make_simple_chunk(0, None, Some("Hello"))]);
+
+    let mut builder = RequestBuilder::new("grok-4");
+    builder
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .idempotency_key("key-1");
+    let events: Vec<_> = resilient(
+        backend.clone(),
+        move || builder.build_request(),
+        no_backoff(3),
+    )
+    .collect()
+    .await;
+
+    assert_eq!(events.len(), 1);
+    let metadata = backend.metadata();
+    assert_eq!(metadata.len(), 2);
+    assert!(
+        metadata
+            .iter()
+            .all(|m| m.get("x-idempotency-key").unwrap() == "key-1")
+    );
+
+    // An error building the request ends the stream without sending anything
+    let backend = MockChatBackend::new();
+    let events: Vec<_> = resilient(
+        backend.clone(),
+        || Err(Status::invalid_argument("bad key")),
+        no_backoff(3),
+    )
+    .collect()
+    .await;
+    assert_eq!(events.len(), 1);
+    assert!(backend.requests().is_empty());
+}
+
+#[tokio::test]
+async fn test_resilient_gives_up_on_exhausted_or_fatal_errors() {
+    let backend = MockChatBackend::new();
+    backend
+        .push_stream_error(Status::unavailable("down"))
+        .push_stream_error(Status::unavailable("still down"));
+    let events: Vec<_> = resilient(backend.clone(), resilient_request(), no_backoff(1))
+        .collect()
+        .await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].as_ref().unwrap_err().message(), "still down");
+
+    let backend = MockChatBackend::new();
+    backend.push_stream_with_error(
+        vec![make_simple_chunk(0, None, Some("Hi"))],
+        Status::invalid_argument("bad request"),
+    );
+    let events: Vec<_> = resilient(backend.clone(), resilient_request(), no_backoff(3))
+        .collect()
+        .await;
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events[1].as_ref().unwrap_err().code(),
+        tonic::Code::InvalidArgument
+    );
+    assert_eq!(backend.requests().len(), 1);
+}
+
+fn rate_limited(retry_after: &str) -> Status {
+    let mut metadata = tonic::metadata::MetadataMap::new();
+    metadata.insert("retry-after", retry_after.parse().unwrap());
+    Status::with_metadata(tonic::Code::ResourceExhausted, "rate limited", metadata)
+}
+
+#[test]
+fn test_restart_policy_retry_after() {
+    assert_eq!(
+        RestartPolicy::retry_after(&rate_limited("2")),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(
+        RestartPolicy::retry_after(&rate_limited("0.25")),
+        Some(Duration::from_millis(250))
+    );
+    assert_eq!(
+        RestartPolicy::retry_after(&rate_limited("Wed, 21 Oct 2015 07:28:00 GMT")),
+        None
+    );
+    assert_eq!(
+        RestartPolicy::retry_after(&Status::unavailable("down")),
+        None
+    );
+    assert!(RestartPolicy::is_retryable_before_first_chunk(
+        &rate_limited("1")
+    ));
+    assert!(!RestartPolicy::is_retryable(&rate_limited("1")));
+}
+
+#[tokio::test]
+async fn test_resilient_retries_rate_limit_only_before_first_chunk() {
+    let backend = MockChatBackend::new();
+    backend
+        .push_stream_error(rate_limited("0.05"))
+        .push_stream_with_error(
+            vec![make_simple_chunk(0, None, Some("Hel"))],
+            rate_limited("0"),
+        );
+
+    let start = std::time::Instant::now();
+    let events: Vec<_> = resilient(backend.clone(), resilient_request(), no_backoff(3))
+        .collect()
+        .await;
+
+    // The 429 before any chunk waited for retry-after and restarted silently
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert_eq!(backend.requests().len(), 2);
+    // The 429 after a chunk ended the stream instead of duplicating output
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], Ok(ResilientEvent::Chunk(_))));
+    assert_eq!(
+        events[1].as_ref().unwrap_err().code(),
+        tonic::Code::ResourceExhausted
+    );
+}
+
+#[tokio::test]
+async fn test_resilient_clamps_retry_after() {
+    let backend = MockChatBackend::new();
+    backend
+        .push_stream_error(rate_limited("3600"))
+        .push_stream(vec![make_simple_chunk(0, None, Some("Hi"))]);
+    let policy = RestartPolicy {
+        max_retry_after: Duration::from_millis(10),
+        ..no_backoff(1)
+    };
+
+    let events: Vec<_> = tokio::time::timeout(
+        Duration::from_secs(5),
+        resilient(backend.clone(), resilient_request(), policy).collect::<Vec<_>>(),
+    )
+    .await
+    .expect("an hour-long retry-after was not clamped");
+
+    assert_eq!(backend.requests().len(), 2);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(&events[0], Ok(ResilientEvent::Chunk(_))));
+}
+
+// ########################################
+// COST ESTIMATES
+// ########################################
+
+#[test]
+fn test_cost_estimate_bounds() {
+    // $2 per million prompt tokens, $10 per million completion tokens
+    let pricing = Pricing {
+        prompt_text_token_price: 20_000,
+        completion_text_token_price: 100_000,
+    };
+    let mut builder = RequestBuilder::new("grok-4");
+    builder.message(text_message(MessageRole::RoleUser, "Hi"));
+
+    // Without max_tokens there is no upper bound
+    let estimate = CostEstimate::new(1_000_000, &builder.build(), &pricing);
+    assert_eq!(estimate.prompt_tokens, 1_000_000);
+    assert_eq!(estimate.min, Money::from_cents(200));
+    assert_eq!(estimate.max, None);
+
+    // 1M prompt tokens ($2) + 2 x 50k completion tokens ($1)
+    builder.max_tokens(50_000).n(2);
+    let estimate = CostEstimate::new(1_000_000, &builder.build(), &pricing);
+    assert_eq!(estimate.max, Some(Money::from_cents(300)));
+
+    // Fractions of a cent round down for the minimum and up for the maximum
+    builder.max_tokens(1).n(1);
+    let estimate = CostEstimate::new(10, &builder.build(), &pricing);
+    assert_eq!(estimate.min, Money::ZERO);
+    assert_eq!(estimate.max, Some(Money::from_cents(1)));
+}
+
+// ########################################
+// BUILD WARNINGS
+// ########################################
+
+#[test]
+fn test_build_checked_reports_warnings() {
+    let mut builder = RequestBuilder::new("grok-4");
+    builder
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .temperature(1.8)
+        .top_p(0.2)
+        .logprobs(true)
+        .stop(["END"]);
+
+    // `build()` stays lenient
+    assert_eq!(builder.build().stop, vec!["END".to_string()]);
+
+    let (request, warnings) = builder.build_checked().unwrap();
+    assert!(request.logprobs);
+    assert_eq!(
+        warnings,
+        vec![
+            BuildWarning::TemperatureAndTopP {
+                temperature: 1.8,
+                top_p: 0.2
+            },
+            BuildWarning::LogprobsUnsupported {
+                model: "grok-4".to_string()
+            },
+            BuildWarning::UnsupportedByReasoningModel {
+                field: "stop",
+                model: "grok-4".to_string()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_build_checked_clean_request_and_errors() {
+    let mut builder = RequestBuilder::new("grok-3");
+    builder
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .temperature(0.7)
+        .top_p(1.0)
+        .logprobs(true)
+        .top_logprobs(3)
+        .frequency_penalty(0.5);
+    let (_, warnings) = builder.build_checked().unwrap();
+    assert!(warnings.is_empty());
+
+    builder.logprobs(false);
+    let (_, warnings) = builder.build_checked().unwrap();
+    assert_eq!(warnings, vec![BuildWarning::TopLogprobsWithoutLogprobs]);
+
+    // Too many alternatives is only a warning, since the limit is not enforced locally
+    builder.logprobs(true);
+    let (_, warnings) = builder.top_logprobs(9).build_checked().unwrap();
+    assert_eq!(
+        warnings,
+        vec![BuildWarning::TopLogprobsOutOfRange { top_logprobs: 9 }]
+    );
+
+    // Invalid settings are still errors
+    let err = builder
+        .top_logprobs(1)
+        .stop(["a", "b", "c", "d", "e", "f", "g", "h", "i"])
+        .build_checked()
+        .unwrap_err();
+    assert_eq!(err, ValidationError::TooManyStopSequences { count: 9 });
+}
+
+// ########################################
+// END-USER IDENTIFIERS
+// ########################################
+
+#[test]
+fn test_user_and_user_hashed() {
+    let mut builder = RequestBuilder::new("grok-4");
+    builder
+        .message(text_message(MessageRole::RoleUser, "Hi"))
+        .user("team-7");
+    assert_eq!(builder.build().user, "team-7");
+
+    builder.user(format!("team-{}", 8));
+    assert_eq!(builder.build().user, "team-8");
+
+    builder.user_hashed("abc");
+    assert_eq!(
+        builder.build().user,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+// ########################################
+// CONTENT HASH
+// ########################################
+
+#[test]
+fn test_content_hash_ignores_volatile_fields() {
+    let request = GetCompletionsRequest {
+        model: "grok-4".to_string(),
+        messages: vec![Message::from((MessageRole::RoleUser, "Hello"))],
+        temperature: Some(0.5),
+        ..Default::default()
+    };
+    let hash = request.content_hash();
+    assert_eq!(hash.len(), 64);
+    assert_eq!(hash, request.clone().content_hash());
+
+    let mut attributed = request.clone();
+    attributed.user = "user-42".to_string();
+    attributed.store_messages = true;
+    assert_eq!(attributed.content_hash(), hash);
+
+    let mut warmer = request.clone();
+    warmer.temperature = Some(0.7);
+    assert_ne!(warmer.content_hash(), hash);
+
+    let mut other_prompt = request.clone();
+    other_prompt.messages[0] = Message::from((MessageRole::RoleUser, "Hi"));
+    assert_ne!(other_prompt.content_hash(), hash);
+}
+
+#[test]
+fn test_content_hash_sorts_mcp_headers() {
+    let with_headers = |headers: Vec<(&str, &str)>| {
+        let auth = McpAuth::Headers(
+            headers
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        );
+        GetCompletionsRequest {
+            model: "grok-4".to_string(),
+            tools: vec![tools::mcp("https://mcp.example.com", vec![], Some(auth)).unwrap()],
+            ..Default::default()
+        }
+    };
+
+    let many: Vec<(String, String)> = (0..16)
+        .map(|i| (format!("x-h{i}"), i.to_string()))
+        .collect();
+    let forward: Vec<(&str, &str)> = many.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect();
+    let backward: Vec<(&str, &str)> = forward.iter().rev().copied().collect();
+    assert_eq!(
+        with_headers(forward.clone()).content_hash(),
+        with_headers(backward).content_hash()
+    );
+    assert_ne!(
+        with_headers(forward).content_hash(),
+        with_headers(vec![("x-h0", "other")]).content_hash()
+    );
+}
+
+// ########################################
+// STREAM METRICS
+// ########################################
+
+#[tokio::test]
+async fn test_process_with_metrics_measures_first_token() {
+    let mut last = make_finish_chunk(0);
+    last.usage = Some(SamplingUsage {
+        completion_tokens: 8,
+        reasoning_tokens: 2,
+        ..Default::default()
+    });
+    let chunks = vec![
+        GetChatCompletionChunk::default(),
+        make_simple_chunk(0, None, Some("Hello")),
+        make_simple_chunk(0, None, Some(" world")),
+        last,
+    ];
+    let delayed = stream::iter(chunks.clone())
+        .then(|chunk| async move {
+            tokio::time::sleep(Duration::from_millis(15)).await;
+            Ok::<_, Status>(chunk)
+        })
+        .boxed();
+
+    let (collected, metrics) = process_with_metrics(delayed, Consumer::new())
+        .await
+        .unwrap();
+
+    assert_eq!(collected, chunks);
+    let ttft = metrics.ttft.unwrap();
+    assert!(ttft >= Duration::from_millis(30));
+    assert!(metrics.total_duration >= ttft + Duration::from_millis(30));
+    assert_eq!(metrics.tokens, 10);
+    let generation = (metrics.total_duration - ttft).as_secs_f64();
+    assert!((metrics.tps.unwrap() - 10.0 / generation).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_process_with_metrics_without_usage_or_tokens() {
+    let chunks = vec![
+        make_simple_chunk(0, Some("hmm"), None),
+        make_simple_chunk(0, None, Some("Hi")),
+    ];
+    let (_, metrics) = process_with_metrics(mock_stream(chunks), Consumer::new())
+        .await
+        .unwrap();
+    assert_eq!(metrics.tokens, 2);
+    assert!(metrics.ttft.is_some());
+
+    let (_, metrics) =
+        process_with_metrics(mock_stream(vec![make_finish_chunk(0)]), Consumer::new())
+            .await
+            .unwrap();
+    assert_eq!(metrics.ttft, None);
+    assert_eq!(metrics.tps, None);
+    assert_eq!(metrics.tokens, 0);
+}
+
+// ########################################
+// WHITESPACE NORMALIZATION
+// ########################################
+
+#[test]
+fn test_normalize_whitespace_rules() {
+    use xai_sdk::chat::response::normalize_whitespace;
+
+    assert_eq!(normalize_whitespace("  \n\nHello  \n\t"), "Hello");
+    assert_eq!(normalize_whitespace("a\n\nb"), "a\n\nb");
+    assert_eq!(normalize_whitespace("a\n\n\n\nb"), "a\n\nb");
+    assert_eq!(normalize_whitespace("a\n  \n \n\nb"), "a\n  \nb");
+    assert_eq!(
+        normalize_whitespace("    indented\nline  end"),
+        "indented\nline  end"
+    );
+    assert_eq!(
+        normalize_whitespace("```\ncode\n\n\n\nmore\n```\n\n\n\nafter"),
+        "```\ncode\n\n\n\nmore\n```\n\nafter"
+    );
+    assert_eq!(normalize_whitespace(""), "");
+    assert_eq!(normalize_whitespace(" \n \n "), "");
+}
+
+#[test]
+fn test_trimmed_and_assemble_normalized() {
+    let mut chunk = make_simple_chunk(0, Some("\n\nthinking\n"), Some("\n\nSee [1]\n\n\n\nDone. "));
+    chunk.outputs[0].delta.as_mut().unwrap().citations = vec![InlineCitation {
+        id: "1".to_string(),
+        start_index: 6,
+        end_index: 9,
+        ..Default::default()
+    }];
+    let chunks = vec![chunk, make_finish_chunk(0)];
+
+    let raw = assemble(chunks.clone()).unwrap();
+    assert_eq!(raw.trimmed(0), "See [1]\n\nDone.");
+    assert_eq!(raw.trimmed(5), "");
+    let raw_message = raw.outputs[0].message.as_ref().unwrap();
+    assert_eq!(raw_message.content, "\n\nSee [1]\n\n\n\nDone. ");
+
+    let normalized = assemble_normalized(chunks).unwrap();
+    let message = normalized.outputs[0].message.as_ref().unwrap();
+    assert_eq!(message.content, "See [1]\n\nDone.");
+    assert_eq!(message.reasoning_content, "thinking");
+    assert_eq!(
+        (
+            message.citations[0].start_index,
+            message.citations[0].end_index
+        ),
+        (4, 7)
+    );
+    assert_eq!(normalized.render_with_citations(0), "See [1]\n\nDone.");
+}
+
+// ########################################
+// CANCELLATION
+// ########################################
+
+/// Stream that yields its chunks, then stays pending like a stalled generation, and records
+/// when it is dropped.
+struct DropTracked {
+    chunks: std::vec::IntoIter<GetChatCompletionChunk>,
+    dropped: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl Stream for DropTracked {
+    type Item = Result<GetChatCompletionChunk, Status>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        match self.chunks.next() {
+            Some(chunk) => std::task::Poll::Ready(Some(Ok(chunk))),
+            None => std::task::Poll::Pending,
+        }
+    }
+}
+
+impl Drop for DropTracked {
+    fn drop(&mut self) {
+        self.dropped
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_cancellable_drops_inner_stream_on_cancel() {
+    let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let inner = DropTracked {
+        chunks: vec![make_simple_chunk(0, None, Some("Hello"))].into_iter(),
+        dropped: dropped.clone(),
+    };
+    let (stream, cancel) = cancellable(inner);
+
+    let (started_tx, started_rx) = futures::channel::oneshot::channel();
+    let mut started_tx = Some(started_tx);
+    let mut consumer = Consumer::new();
+    consumer.on_content_token(move |_ctx, _token| {
+        if let Some(tx) = started_tx.take() {
+            let _ = tx.send(());
+        }
+        async {}
+    });
+    let processing = tokio::spawn(process(stream, consumer));
+
+    started_rx.await.unwrap();
+    assert!(!dropped.load(std::sync::atomic::Ordering::SeqCst));
+    cancel.cancel();
+    assert!(cancel.is_cancelled());
+
+    let err = tokio::time::timeout(Duration::from_secs(1), processing)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Cancelled);
+    assert!(dropped.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_cancellable_passes_through_and_ignores_late_cancel() {
+    let chunks = vec![
+        make_simple_chunk(0, None, Some("a")),
+        make_simple_chunk(0, None, Some("b")),
+    ];
+    let (mut stream, cancel) = cancellable(mock_stream(chunks.clone()).boxed());
+    let mut received = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        received.push(chunk.unwrap());
+    }
+    assert_eq!(received, chunks);
+
+    cancel.cancel();
+    assert!(stream.next().await.is_none());
+}
+
+// ########################################
+// REASONING TRACE
+// ########################################
+
+#[test]
+fn test_reasoning_and_steps() {
+    let response = assemble(vec![
+        make_simple_chunk(0, Some("First, read the question.\n\n"), None),
+        make_simple_chunk(0, Some("Then compute\n2 + 2 = 4.\n\n\n\nFinally, "), None),
+        make_simple_chunk(0, Some("answer.  "), Some("4")),
+        make_simple_chunk(1, None, Some("four")),
+    ])
+    .unwrap();
+
+    assert_eq!(
+        response.reasoning(0),
+        Some("First, read the question.\n\nThen compute\n2 + 2 = 4.\n\n\n\nFinally, answer.  ")
+    );
+    assert_eq!(
+        response.reasoning_steps(0),
+        vec![
+            "First, read the question.",
+            "Then compute\n2 + 2 = 4.",
+            "Finally, answer."
+        ]
+    );
+
+    assert_eq!(response.reasoning(1), None);
+    assert!(response.reasoning_steps(1).is_empty());
+    assert_eq!(response.reasoning(7), None);
+
+    let mut windows = response.clone();
+    windows.outputs[0]
+        .message
+        .as_mut()
+        .unwrap()
+        .reasoning_content = "a\r\n\r\nb".to_string();
+    assert_eq!(windows.reasoning_steps(0), vec!["a", "b"]);
+}
@@ -1,7 +1,16 @@
+use std::time::Duration;
+use xai_sdk::api::{DeferredStatus, GetDeferredCompletionResponse};
 use xai_sdk::common;
-use xai_sdk::common::interceptor::{ClientInterceptor, auth, compose};
+use xai_sdk::common::deferred;
+use xai_sdk::common::interceptor::{
+    ClientInterceptor, REQUEST_ID_HEADER, auth, compose, request_id, request_id_from,
+};
+use xai_sdk::common::request::{with_header, with_headers};
+use xai_sdk::common::timed;
+use xai_sdk::common::timeout;
+use xai_sdk::common::transform;
 use xai_sdk::export::service::Interceptor;
-use xai_sdk::{Request, Status};
+use xai_sdk::{Request, Response, Status};
 
 #[test]
 fn test_client_interceptor_new() {
@@ -334,6 +343,37 @@ async fn test_channel_new() {
     );
 }
 
+#[test]
+fn test_check_native_roots_reports_count_or_hint() {
+    match common::channel::check_native_roots() {
+        Ok(count) => assert!(count > 0),
+        Err(err) => assert!(err.to_string().contains("webpki-roots")),
+    }
+}
+
+#[test]
+fn test_native_roots_error_display() {
+    let err = common::channel::NativeRootsError {
+        errors: vec!["could not read /etc/ssl/certs".to_string()],
+    };
+    let message = err.to_string();
+    assert!(
+        message.starts_with("no native root certificates found (could not read /etc/ssl/certs)")
+    );
+    assert!(message.contains("ca-certificates"));
+    assert!(message.contains("common::channel::new_webpki()"));
+}
+
+#[test]
+fn test_connect_error_explains_missing_native_roots() {
+    let err = common::channel::ConnectError::NativeRoots(common::channel::NativeRootsError {
+        errors: Vec::new(),
+    });
+    // The hint is the error's own message, not buried in a source chain
+    assert!(err.to_string().contains("webpki-roots"));
+    assert!(std::error::Error::source(&err).is_none());
+}
+
 #[tokio::test]
 async fn test_client_interceptor_send_sync() {
     // Verify that ClientInterceptor is Send + Sync, allowing it to be used in tokio::spawn
@@ -364,3 +404,539 @@ async fn test_client_interceptor_send_sync() {
         "value"
     );
 }
+
+#[test]
+fn test_client_interceptor_clone_shares_inner() {
+    // Clones share the same underlying interceptor, so state is visible across clones
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls_clone = calls.clone();
+    let interceptor =
+        ClientInterceptor::new(move |req: Request<()>| -> Result<Request<()>, Status> {
+            calls_clone.fetch_add(1, Ordering::SeqCst);
+            Ok(req)
+        });
+
+    let mut first = interceptor.clone();
+    let mut second = interceptor;
+    assert!(first.call(Request::new(())).is_ok());
+    assert!(second.call(Request::new(())).is_ok());
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_channel_with_tls_config_rejects_invalid_identity() {
+    // A malformed client identity is rejected while building the TLS config, before connecting
+    use xai_sdk::export::transport::{ClientTlsConfig, Identity};
+
+    let tls = ClientTlsConfig::new().identity(Identity::from_pem("not a cert", "not a key"));
+    let result = common::channel::with_tls_config(tls).await;
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_request_id_generates_uuid() {
+    let (mut interceptor, last_id) = request_id();
+    assert!(last_id.lock().unwrap().is_none());
+
+    let request = interceptor.call(Request::new(())).unwrap();
+    let id = request
+        .metadata()
+        .get(REQUEST_ID_HEADER)
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // Hyphenated v4 UUID: 8-4-4-4-12 hex digits, version nibble 4
+    let groups: Vec<&str> = id.split('-').collect();
+    assert_eq!(
+        groups.iter().map(|g| g.len()).collect::<Vec<_>>(),
+        vec![8, 4, 4, 4, 12]
+    );
+    assert!(groups[2].starts_with('4'));
+    assert_eq!(last_id.lock().unwrap().as_deref(), Some(id.as_str()));
+
+    // Every request gets a fresh id
+    let next = interceptor.call(Request::new(())).unwrap();
+    assert_ne!(next.metadata().get(REQUEST_ID_HEADER).unwrap(), id.as_str());
+}
+
+#[test]
+fn test_request_id_keeps_existing_header() {
+    let (mut interceptor, last_id) = request_id();
+    let mut request = Request::new(());
+    request
+        .metadata_mut()
+        .insert(REQUEST_ID_HEADER, "parent-123".parse().unwrap());
+
+    let request = interceptor.call(request).unwrap();
+    assert_eq!(
+        request.metadata().get(REQUEST_ID_HEADER).unwrap(),
+        "parent-123"
+    );
+    assert_eq!(last_id.lock().unwrap().as_deref(), Some("parent-123"));
+}
+
+#[test]
+fn test_request_id_from() {
+    let mut interceptor = request_id_from("trace-42");
+    let request = interceptor.call(Request::new(())).unwrap();
+    assert_eq!(
+        request.metadata().get(REQUEST_ID_HEADER).unwrap(),
+        "trace-42"
+    );
+}
+
+#[test]
+fn test_with_header_sets_metadata() {
+    let request = with_header(Request::new(()), "idempotency-key", "abc-123").unwrap();
+    assert_eq!(
+        request.metadata().get("idempotency-key").unwrap(),
+        "abc-123"
+    );
+
+    let err = with_header(Request::new(()), "Bad Header", "x").unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(err.message().contains("Bad Header"));
+
+    let err = with_header(Request::new(()), "x-ok", "line\nbreak").unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+}
+
+#[test]
+fn test_with_headers_applies_all_and_survives_interceptor() {
+    let request = with_headers(
+        Request::new(()),
+        [("x-tenant", "acme"), ("x-trace", "1"), ("x-trace", "2")],
+    )
+    .unwrap();
+    let request = auth("key").call(request).unwrap();
+
+    let metadata = request.metadata();
+    assert_eq!(metadata.get("x-tenant").unwrap(), "acme");
+    assert_eq!(metadata.get("x-trace").unwrap(), "2");
+    assert_eq!(metadata.get("authorization").unwrap(), "Bearer key");
+
+    let headers = std::collections::HashMap::from([("x tenant".to_string(), "v".to_string())]);
+    assert!(with_headers(Request::new(()), headers).is_err());
+}
+
+#[tokio::test]
+async fn test_connect_endpoint_with_retry_backs_off_between_attempts() {
+    use xai_sdk::common::channel::connect_endpoint_with_retry;
+    use xai_sdk::export::transport::Endpoint;
+
+    // Reserve a port, then close it so every connection attempt is refused
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let endpoint = Endpoint::from_shared(format!("http://{addr}")).unwrap();
+
+    let started = std::time::Instant::now();
+    let result = connect_endpoint_with_retry(endpoint.clone(), 3, Duration::from_millis(20)).await;
+    assert!(result.is_err());
+    // Waits 20ms and 40ms before the second and third attempts
+    assert!(started.elapsed() >= Duration::from_millis(60));
+
+    let started = std::time::Instant::now();
+    let result = connect_endpoint_with_retry(endpoint, 0, Duration::from_secs(10)).await;
+    assert!(result.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+// A plain TCP listener that never answers, counting connections and reporting when the
+// client closes one
+async fn silent_listener() -> (
+    String,
+    std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    tokio::sync::mpsc::UnboundedReceiver<()>,
+) {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncReadExt;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let (closed_tx, closed_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn({
+        let accepted = accepted.clone();
+        async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let closed_tx = closed_tx.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while socket.read(&mut buf).await.is_ok_and(|n| n > 0) {}
+                    let _ = closed_tx.send(());
+                });
+            }
+        }
+    });
+    (format!("http://{addr}"), accepted, closed_rx)
+}
+
+#[tokio::test]
+async fn test_shared_channel_reuses_one_connection() {
+    use std::sync::atomic::Ordering;
+    use xai_sdk::common::channel::Shared;
+    use xai_sdk::export::transport::Endpoint;
+    use xai_sdk::models;
+
+    let (url, accepted, mut closed_rx) = silent_listener().await;
+    let shared = Shared::new(Endpoint::from_shared(url).unwrap());
+    let mut first =
+        models::client::with_channel_and_interceptor(shared.channel(), shared.auth("key"));
+    let mut second =
+        models::client::with_channel_and_interceptor(shared.channel(), shared.auth("key"));
+
+    // The listener never answers, so both requests time out after reaching it
+    let wait = Duration::from_millis(300);
+    let (a, b) = tokio::join!(
+        tokio::time::timeout(wait, first.list_language_models(())),
+        tokio::time::timeout(wait, second.list_language_models(())),
+    );
+    assert!(a.is_err() && b.is_err());
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+    // Shutdown closes the connection although both clients are still alive
+    tokio::time::timeout(Duration::from_secs(5), shared.shutdown())
+        .await
+        .expect("shutdown without calls in flight did not finish");
+    tokio::time::timeout(Duration::from_secs(5), closed_rx.recv())
+        .await
+        .expect("connection was not closed by shutdown");
+
+    // Requests afterwards fail fast instead of reconnecting
+    let err = tokio::time::timeout(wait, first.list_language_models(()))
+        .await
+        .expect("request after shutdown did not fail fast")
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Unavailable);
+    assert_eq!(err.message(), "channel shut down");
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_shared_shutdown_waits_for_calls_in_flight() {
+    use xai_sdk::common::channel::Shared;
+    use xai_sdk::export::transport::Endpoint;
+    use xai_sdk::models;
+
+    let (url, _, mut closed_rx) = silent_listener().await;
+    let shared = Shared::new(Endpoint::from_shared(url).unwrap());
+    let mut client =
+        models::client::with_channel_and_interceptor(shared.channel(), shared.auth("key"));
+    // Opens the connection
+    let wait = Duration::from_millis(300);
+    assert!(
+        tokio::time::timeout(wait, client.list_language_models(()))
+            .await
+            .is_err()
+    );
+
+    let (finish_tx, finish_rx) = tokio::sync::oneshot::channel::<()>();
+    let in_flight = tokio::spawn({
+        let shared = shared.clone();
+        async move {
+            shared
+                .run(async {
+                    let _ = finish_rx.await;
+                    Ok(42)
+                })
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let mut shutdown = tokio::spawn(shared.clone().shutdown());
+
+    // New calls are rejected while shutdown waits for the call in flight
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let err = shared.run(async { Ok(()) }).await.unwrap_err();
+    assert_eq!(err.code(), tonic::Code::Unavailable);
+    assert!(!shutdown.is_finished());
+    assert!(closed_rx.try_recv().is_err());
+
+    finish_tx.send(()).unwrap();
+    assert_eq!(in_flight.await.unwrap().unwrap(), 42);
+    tokio::time::timeout(Duration::from_secs(5), &mut shutdown)
+        .await
+        .expect("shutdown did not finish after the call completed")
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(5), closed_rx.recv())
+        .await
+        .expect("connection was not closed by shutdown");
+}
+
+#[tokio::test]
+async fn test_deferred_poll_until_done_with_backoff() {
+    let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let start = tokio::time::Instant::now();
+    let status = deferred::poll(
+        || {
+            let calls = calls.clone();
+            async move {
+                let mut calls = calls.lock().unwrap();
+                calls.push(start.elapsed());
+                Ok(if calls.len() < 4 {
+                    DeferredStatus::Pending
+                } else {
+                    DeferredStatus::Done
+                })
+            }
+        },
+        Duration::from_millis(100),
+        Duration::from_secs(5),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(status, DeferredStatus::Done);
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 4);
+    // Waits of 100, 200 and 400 ms between the polls
+    assert!(calls[0] < Duration::from_millis(100));
+    assert!(calls[3] >= Duration::from_millis(700));
+    assert!(calls[3] - calls[2] >= Duration::from_millis(400));
+}
+
+#[tokio::test]
+async fn test_deferred_poll_clamps_interval_and_accepts_unbounded_timeout() {
+    let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let start = tokio::time::Instant::now();
+    let status = deferred::poll(
+        || {
+            let calls = calls.clone();
+            async move {
+                let mut calls = calls.lock().unwrap();
+                calls.push(start.elapsed());
+                Ok(if calls.len() < 2 {
+                    DeferredStatus::Pending
+                } else {
+                    DeferredStatus::Done
+                })
+            }
+        },
+        Duration::ZERO,
+        Duration::MAX,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(status, DeferredStatus::Done);
+    let calls = calls.lock().unwrap();
+    assert!(calls[1] - calls[0] >= deferred::MIN_INTERVAL);
+}
+
+#[tokio::test]
+async fn test_deferred_poll_returns_final_statuses_and_errors() {
+    for final_status in [DeferredStatus::Expired, DeferredStatus::Failed] {
+        let response = deferred::poll(
+            || async move {
+                Ok(GetDeferredCompletionResponse {
+                    status: final_status.into(),
+                    response: None,
+                })
+            },
+            Duration::from_millis(1),
+            Duration::from_secs(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), final_status);
+    }
+
+    let err = deferred::poll(
+        || async { Err::<DeferredStatus, _>(Status::not_found("unknown request")) },
+        Duration::from_millis(1),
+        Duration::from_secs(1),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_deferred_poll_times_out() {
+    let err = deferred::poll(
+        || async { Ok(DeferredStatus::Pending) },
+        Duration::from_millis(100),
+        Duration::from_millis(150),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn test_unary_timeout_sets_deadline_and_returns_response() {
+    let response = timeout::unary(
+        Request::new("ping"),
+        Duration::from_secs(5),
+        |request: Request<&str>| async move {
+            assert!(request.metadata().get("grpc-timeout").is_some());
+            Ok(Response::new(request.into_inner().len()))
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.into_inner(), 4);
+}
+
+#[tokio::test]
+async fn test_unary_timeout_expires_with_deadline_exceeded() {
+    let err = timeout::unary(Request::new(()), Duration::from_millis(20), |_| {
+        std::future::pending::<Result<Response<()>, Status>>()
+    })
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::DeadlineExceeded);
+}
+
+#[tokio::test]
+async fn test_timed_call_measures_elapsed() {
+    let (result, elapsed) = timed::call(async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        Ok::<_, Status>(7)
+    })
+    .await;
+    assert_eq!(result.unwrap(), 7);
+    assert!(elapsed >= Duration::from_millis(20));
+}
+
+#[tokio::test]
+async fn test_timed_unary_reports_deadline_use() {
+    let (result, timing) = timed::unary(Request::new(()), Duration::from_millis(30), |_| {
+        std::future::pending::<Result<Response<()>, Status>>()
+    })
+    .await;
+    assert_eq!(result.unwrap_err().code(), tonic::Code::DeadlineExceeded);
+    assert_eq!(timing.deadline, Some(Duration::from_millis(30)));
+    assert!(timing.near_deadline(0.9));
+
+    let quick = timed::Timing {
+        elapsed: Duration::from_secs(2),
+        deadline: Some(Duration::from_secs(10)),
+    };
+    assert_eq!(quick.deadline_used(), Some(0.2));
+    assert!(!quick.near_deadline(0.8));
+    let unbounded = timed::Timing {
+        deadline: None,
+        ..quick
+    };
+    assert_eq!(unbounded.deadline_used(), None);
+    assert!(!unbounded.near_deadline(0.0));
+}
+
+#[test]
+fn test_prompt_bytes_matches_encoded_messages() {
+    use prost::Message as _;
+    use xai_sdk::api::{GetCompletionsRequest, Message, MessageRole};
+
+    let messages = vec![
+        Message::from((MessageRole::RoleSystem, "Be brief.")),
+        Message::from((MessageRole::RoleUser, "x".repeat(300))),
+    ];
+    let request = GetCompletionsRequest {
+        model: "grok-4".to_string(),
+        messages: messages.clone(),
+        ..Default::default()
+    };
+    let messages_only = GetCompletionsRequest {
+        messages,
+        ..Default::default()
+    };
+    assert_eq!(
+        transform::prompt_bytes(&request),
+        messages_only.encoded_len()
+    );
+    assert_eq!(
+        transform::prompt_bytes(&GetCompletionsRequest::default()),
+        0
+    );
+}
+
+#[test]
+fn test_max_prompt_bytes_rejects_oversized_prompts() {
+    use xai_sdk::api::{GetCompletionsRequest, Message, MessageRole};
+
+    let request = |text: &str| {
+        Request::new(GetCompletionsRequest {
+            messages: vec![Message::from((MessageRole::RoleUser, text))],
+            ..Default::default()
+        })
+    };
+    let guard = transform::max_prompt_bytes(100);
+
+    let small = guard(request("hello")).unwrap();
+    assert_eq!(small.get_ref().messages.len(), 1);
+
+    let err = guard(request(&"x".repeat(200))).unwrap_err();
+    assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    assert!(err.message().contains("limit of 100 bytes"));
+}
+
+#[test]
+fn test_user_hash_id() {
+    use xai_sdk::common::user::hash_id;
+
+    assert_eq!(
+        hash_id("abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(hash_id("user-42"), hash_id("user-42"));
+    assert_ne!(hash_id("user-42"), hash_id("user-43"));
+}
+
+#[tokio::test]
+async fn test_paginate_items_follows_tokens() {
+    use futures::TryStreamExt;
+    use std::sync::{Arc, Mutex};
+    use xai_sdk::common::paginate;
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let fetch_seen = seen.clone();
+    let items = paginate::items(move |token: Option<String>| {
+        fetch_seen.lock().unwrap().push(token.clone());
+        async move {
+            Ok(match token.as_deref() {
+                None => (vec![1, 2], Some("a".to_string())),
+                Some("a") => (vec![], Some("b".to_string())),
+                Some("b") => (vec![3], Some(String::new())),
+                Some(other) => panic!("unexpected token {other}"),
+            })
+        }
+    });
+
+    let items: Vec<i32> = items.try_collect().await.unwrap();
+    assert_eq!(items, vec![1, 2, 3]);
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![None, Some("a".to_string()), Some("b".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_paginate_items_stops_after_error() {
+    use futures::StreamExt;
+    use xai_sdk::common::paginate;
+
+    let items = paginate::items(|token: Option<String>| async move {
+        match token {
+            None => Ok((vec![1], Some("next".to_string()))),
+            Some(_) => Err(Status::unavailable("down")),
+        }
+    });
+
+    let results: Vec<Result<i32, Status>> = items.collect().await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(*results[0].as_ref().unwrap(), 1);
+    assert_eq!(
+        results[1].as_ref().unwrap_err().code(),
+        tonic::Code::Unavailable
+    );
+}
@@ -0,0 +1,159 @@
+use xai_sdk::api::{
+    EmbedEncodingFormat, EmbedResponse, Embedding, FeatureVector, ImageDetail, embed_input::Input,
+};
+use xai_sdk::embed::{EmbedBuildError, RequestBuilder};
+
+#[test]
+fn test_embed_builder_mixes_inputs_in_order() {
+    let request = RequestBuilder::new("embed-model")
+        .text("first")
+        .image_url("https://example.com/cat.png")
+        .texts(vec!["second".to_string(), "third".to_string()])
+        .user("user-1")
+        .try_build()
+        .unwrap();
+
+    assert_eq!(request.model, "embed-model");
+    assert_eq!(request.user, "user-1");
+    assert_eq!(
+        request.encoding_format,
+        EmbedEncodingFormat::FormatFloat as i32
+    );
+
+    let inputs: Vec<&Input> = request
+        .input
+        .iter()
+        .map(|input| input.input.as_ref().unwrap())
+        .collect();
+    assert_eq!(inputs.len(), 4);
+    assert_eq!(inputs[0], &Input::String("first".to_string()));
+    match inputs[1] {
+        Input::ImageUrl(image) => {
+            assert_eq!(image.image_url, "https://example.com/cat.png");
+            assert_eq!(image.detail, ImageDetail::DetailAuto as i32);
+        }
+        other => panic!("expected image input, got {other:?}"),
+    }
+    assert_eq!(inputs[3], &Input::String("third".to_string()));
+}
+
+#[test]
+fn test_embed_builder_encoding_and_model_override() {
+    let request = RequestBuilder::new("a")
+        .model("b")
+        .encoding_format(EmbedEncodingFormat::FormatBase64)
+        .text("x")
+        .build();
+    assert_eq!(request.model, "b");
+    assert_eq!(
+        request.encoding_format,
+        EmbedEncodingFormat::FormatBase64 as i32
+    );
+}
+
+#[test]
+fn test_embed_builder_validation() {
+    assert_eq!(
+        RequestBuilder::new("embed-model").try_build(),
+        Err(EmbedBuildError::NoInputs)
+    );
+    assert_eq!(
+        RequestBuilder::new("").text("x").try_build(),
+        Err(EmbedBuildError::MissingModel)
+    );
+
+    let err = RequestBuilder::new("embed-model")
+        .texts(vec!["x".to_string(); 129])
+        .try_build()
+        .unwrap_err();
+    assert_eq!(err, EmbedBuildError::TooManyInputs { count: 129 });
+    assert_eq!(
+        err.to_string(),
+        "`input` has 129 entries, at most 128 are allowed"
+    );
+}
+
+fn floats(values: &[f32]) -> FeatureVector {
+    FeatureVector {
+        float_array: values.to_vec(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_embed_response_vectors_follow_input_order() {
+    use base64::Engine;
+
+    let encoded: Vec<u8> = [5.0f32, 6.0].iter().flat_map(|f| f.to_le_bytes()).collect();
+    let response = EmbedResponse {
+        embeddings: vec![
+            Embedding {
+                index: 1,
+                embeddings: vec![floats(&[3.0, 4.0])],
+            },
+            Embedding {
+                index: 0,
+                embeddings: vec![floats(&[1.0, 2.0])],
+            },
+            Embedding {
+                index: 2,
+                embeddings: vec![FeatureVector {
+                    base64_array: base64::engine::general_purpose::STANDARD.encode(encoded),
+                    ..Default::default()
+                }],
+            },
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        response.vectors(),
+        vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]]
+    );
+    assert_eq!(response.dimensions(), Some(2));
+
+    assert_eq!(EmbedResponse::default().dimensions(), None);
+    let ragged = EmbedResponse {
+        embeddings: vec![Embedding {
+            index: 0,
+            embeddings: vec![floats(&[1.0]), floats(&[1.0, 2.0])],
+        }],
+        ..Default::default()
+    };
+    assert_eq!(ragged.vectors().len(), 2);
+    assert_eq!(ragged.dimensions(), None);
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn test_embed_response_as_array2() {
+    let response = EmbedResponse {
+        embeddings: vec![Embedding {
+            index: 0,
+            embeddings: vec![floats(&[1.0, 2.0]), floats(&[3.0, 4.0])],
+        }],
+        ..Default::default()
+    };
+    let matrix = response.as_array2().unwrap();
+    assert_eq!(matrix.shape(), &[2, 2]);
+    assert_eq!(matrix[[1, 0]], 3.0);
+}
+
+#[cfg(feature = "ndarray")]
+#[test]
+fn test_embed_response_as_array2_rejects_ragged_vectors() {
+    // 2 + 1 + 3 floats would also fill a 3x2 matrix
+    let response = EmbedResponse {
+        embeddings: vec![Embedding {
+            index: 0,
+            embeddings: vec![
+                floats(&[1.0, 2.0]),
+                floats(&[3.0]),
+                floats(&[4.0, 5.0, 6.0]),
+            ],
+        }],
+        ..Default::default()
+    };
+    assert_eq!(response.dimensions(), None);
+    assert!(response.as_array2().is_none());
+}
@@ -0,0 +1,92 @@
+use xai_sdk::api::{ImageAspectRatio, ImageDetail, ImageFormat, ImageResolution};
+use xai_sdk::image::{ImageBuildError, RequestBuilder};
+
+#[test]
+fn test_image_builder_sets_options() {
+    let request = RequestBuilder::new("grok-imagine-image")
+        .prompt("A lighthouse at dawn")
+        .n(2)
+        .aspect_ratio(ImageAspectRatio::ImgAspectRatio169)
+        .resolution(ImageResolution::ImgResolution2k)
+        .format(ImageFormat::ImgFormatBase64)
+        .image_url("https://example.com/reference.png")
+        .detail(ImageDetail::DetailHigh)
+        .user("user-1")
+        .try_build()
+        .unwrap();
+
+    assert_eq!(request.model, "grok-imagine-image");
+    assert_eq!(request.prompt, "A lighthouse at dawn");
+    assert_eq!(request.n, Some(2));
+    assert_eq!(
+        request.aspect_ratio,
+        Some(ImageAspectRatio::ImgAspectRatio169 as i32)
+    );
+    assert_eq!(
+        request.resolution,
+        Some(ImageResolution::ImgResolution2k as i32)
+    );
+    assert_eq!(request.format, ImageFormat::ImgFormatBase64 as i32);
+    assert_eq!(request.images.len(), 1);
+    assert_eq!(request.images[0].detail, ImageDetail::DetailHigh as i32);
+    assert_eq!(request.user, "user-1");
+}
+
+#[test]
+fn test_image_builder_defaults() {
+    let request = RequestBuilder::new("grok-imagine-image")
+        .prompt("A cat")
+        .try_build()
+        .unwrap();
+    assert_eq!(request.n, None);
+    assert_eq!(request.format, ImageFormat::ImgFormatUrl as i32);
+    assert_eq!(request.aspect_ratio, None);
+    assert!(request.images.is_empty());
+}
+
+#[test]
+fn test_image_builder_validation() {
+    let mut builder = RequestBuilder::new("");
+    assert_eq!(builder.try_build(), Err(ImageBuildError::MissingModel));
+    builder.model("grok-imagine-image");
+    assert_eq!(builder.try_build(), Err(ImageBuildError::MissingPrompt));
+    builder.prompt("A cat");
+
+    for n in [0, 11] {
+        assert_eq!(
+            builder.clone().n(n).try_build(),
+            Err(ImageBuildError::InvalidN { n })
+        );
+    }
+
+    assert_eq!(
+        builder
+            .clone()
+            .aspect_ratio(ImageAspectRatio::ImgAspectRatioInvalid)
+            .try_build(),
+        Err(ImageBuildError::InvalidOption {
+            field: "aspect_ratio"
+        })
+    );
+    assert_eq!(
+        builder
+            .clone()
+            .detail(ImageDetail::DetailInvalid)
+            .try_build(),
+        Err(ImageBuildError::InvalidOption { field: "detail" })
+    );
+    assert!(builder.n(10).try_build().is_ok());
+}
+
+#[test]
+fn test_image_builder_user_hashed() {
+    let request = RequestBuilder::new("grok-imagine-image")
+        .prompt("A cat")
+        .user_hashed("abc")
+        .try_build()
+        .unwrap();
+    assert_eq!(
+        request.user,
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
@@ -0,0 +1,195 @@
+use xai_sdk::api::{EmbeddingModel, ImageGenerationModel, LanguageModel};
+use xai_sdk::models::{AllModels, ModelError, ModelFamily, ModelId, ModelRef, ModelSize, Pricing};
+
+#[test]
+fn test_model_id_parses_parts() {
+    assert_eq!(
+        ModelId::parse("grok-3-mini"),
+        ModelId::Known {
+            family: ModelFamily::Grok3,
+            size: Some(ModelSize::Mini),
+            variant: None,
+            is_latest: false,
+        }
+    );
+    assert_eq!(
+        ModelId::parse("grok-4-latest"),
+        ModelId::Known {
+            family: ModelFamily::Grok4,
+            size: None,
+            variant: None,
+            is_latest: true,
+        }
+    );
+    assert_eq!(
+        ModelId::parse("grok-code-fast-1"),
+        ModelId::Known {
+            family: ModelFamily::GrokCode,
+            size: None,
+            variant: Some("fast-1".to_string()),
+            is_latest: false,
+        }
+    );
+    assert_eq!(
+        ModelId::parse("grok-3-mini-fast-latest"),
+        ModelId::Known {
+            family: ModelFamily::Grok3,
+            size: Some(ModelSize::Mini),
+            variant: Some("fast".to_string()),
+            is_latest: true,
+        }
+    );
+}
+
+#[test]
+fn test_model_id_unknown_and_round_trip() {
+    for raw in [
+        "grok-2-vision-1212",
+        "grok-3",
+        "grok-3-mini",
+        "grok-4-0709",
+        "grok-4-fast-non-reasoning",
+        "grok-code-fast-1",
+        "grok-imagine-video",
+        "grok-4-",
+        "grok-9",
+        "gpt-4o",
+        "",
+    ] {
+        let id: ModelId = raw.parse().unwrap();
+        assert_eq!(id.to_string(), raw);
+    }
+
+    assert_eq!(
+        ModelId::from("grok-9"),
+        ModelId::Unknown("grok-9".to_string())
+    );
+    assert_eq!(
+        ModelId::from("grok-4-"),
+        ModelId::Unknown("grok-4-".to_string())
+    );
+    assert_eq!(ModelId::from("gpt-4o").family(), None);
+}
+
+#[test]
+fn test_model_id_capabilities() {
+    let reasoning = |raw: &str| ModelId::parse(raw).is_reasoning_model();
+    assert!(reasoning("grok-3-mini"));
+    assert!(reasoning("grok-4-latest"));
+    assert!(reasoning("grok-4-fast-reasoning"));
+    assert!(reasoning("grok-code-fast-1"));
+    assert!(!reasoning("grok-3"));
+    assert!(!reasoning("grok-4-fast-non-reasoning"));
+    assert!(!reasoning("grok-2-1212"));
+    assert!(!reasoning("some-future-model"));
+
+    let vision = |raw: &str| ModelId::parse(raw).supports_vision();
+    assert!(vision("grok-4"));
+    assert!(vision("grok-2-vision-1212"));
+    assert!(!vision("grok-3-mini"));
+    assert!(!vision("grok-code-fast-1"));
+    assert!(!vision("some-future-model"));
+}
+
+#[test]
+fn test_model_id_reasoning_effort_support() {
+    assert!(ModelId::parse("grok-3-mini").supports_reasoning_effort());
+    assert!(ModelId::parse("grok-3-mini-fast-latest").supports_reasoning_effort());
+    assert!(!ModelId::parse("grok-3").supports_reasoning_effort());
+    assert!(!ModelId::parse("grok-4").supports_reasoning_effort());
+    assert!(!ModelId::parse("grok-future").supports_reasoning_effort());
+}
+
+#[test]
+fn test_model_id_structured_output_support() {
+    let structured = |raw: &str| ModelId::parse(raw).supports_structured_output();
+    assert!(structured("grok-4"));
+    assert!(structured("grok-3-mini"));
+    assert!(structured("grok-2-1212"));
+    assert!(structured("grok-code-fast-1"));
+    assert!(!structured("grok-2-vision-1212"));
+    assert!(!structured("grok-imagine-image"));
+    assert!(!structured("some-future-model"));
+}
+
+#[test]
+fn test_all_models_find_across_categories() {
+    let all = AllModels {
+        language: vec![LanguageModel {
+            name: "grok-4-0709".to_string(),
+            aliases: vec!["grok-4".to_string(), "grok-4-latest".to_string()],
+            ..Default::default()
+        }],
+        embedding: vec![EmbeddingModel {
+            name: "v1".to_string(),
+            ..Default::default()
+        }],
+        image_generation: vec![ImageGenerationModel {
+            name: "grok-2-image-1212".to_string(),
+            aliases: vec!["grok-2-image".to_string()],
+            ..Default::default()
+        }],
+    };
+
+    let found = all.find("grok-4-latest").unwrap();
+    assert!(matches!(found, ModelRef::Language(_)));
+    assert_eq!(found.name(), "grok-4-0709");
+
+    assert!(matches!(all.find("v1"), Some(ModelRef::Embedding(_))));
+    assert!(matches!(
+        all.find("grok-2-image"),
+        Some(ModelRef::ImageGeneration(model)) if model.name == "grok-2-image-1212"
+    ));
+    assert_eq!(all.find("grok-9"), None);
+
+    let names: Vec<&str> = all.iter().map(|model| model.name()).collect();
+    assert_eq!(names, vec!["grok-4-0709", "v1", "grok-2-image-1212"]);
+}
+
+#[test]
+fn test_all_models_resolve() {
+    let all = AllModels {
+        language: vec![
+            LanguageModel {
+                name: "grok-4-0709".to_string(),
+                aliases: vec!["grok-4".to_string()],
+                ..Default::default()
+            },
+            LanguageModel {
+                name: "grok-3-mini".to_string(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    assert_eq!(all.resolve("grok-4").unwrap(), "grok-4-0709");
+    assert_eq!(all.resolve("Grok-4-LATEST").unwrap(), "grok-4-0709");
+    assert_eq!(all.resolve(" grok-3-mini-latest ").unwrap(), "grok-3-mini");
+
+    match all.resolve("grok-3-mni") {
+        Err(ModelError::Suggestion { name, suggestions }) => {
+            assert_eq!(name, "grok-3-mni");
+            assert_eq!(suggestions[0], "grok-3-mini");
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let err = all.resolve("llama").unwrap_err();
+    assert!(matches!(&err, ModelError::Suggestion { suggestions, .. } if suggestions.is_empty()));
+    assert_eq!(err.to_string(), "unknown model `llama`");
+    let err = all.resolve("grok4").unwrap_err();
+    assert!(err.to_string().contains("did you mean `grok-4`"));
+}
+
+#[test]
+fn test_pricing_from_language_model() {
+    let model = LanguageModel {
+        prompt_text_token_price: 30_000,
+        completion_text_token_price: 150_000,
+        ..Default::default()
+    };
+    let pricing = Pricing::from(&model);
+    assert_eq!(pricing.prompt_text_token_price, 30_000);
+    assert_eq!(pricing.cost_units(2, 1), 210_000);
+}
@@ -0,0 +1,349 @@
+#![cfg(feature = "openai")]
+
+use serde_json::json;
+use xai_sdk::api::{
+    CompletionMessage, CompletionOutput, CompletionOutputChunk, Content, Delta, FinishReason,
+    FormatType, FunctionCall, GetChatCompletionChunk, GetChatCompletionResponse, ImageDetail,
+    ImageUrlContent, Message, MessageRole, ReasoningEffort, SamplingUsage, ToolCall, ToolCallType,
+    ToolMode, content, tool, tool_call, tool_choice,
+};
+use xai_sdk::interop::openai::{
+    ChunkConverter, ConvertError, from_openai_request, to_openai_chat_completion, to_openai_chunk,
+};
+
+fn function_call(id: &str, call_type: ToolCallType, name: &str) -> ToolCall {
+    ToolCall {
+        id: id.to_string(),
+        r#type: call_type.into(),
+        tool: Some(tool_call::Tool::Function(FunctionCall {
+            name: name.to_string(),
+            arguments: "{}".to_string(),
+        })),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_to_openai_chat_completion() {
+    let response = GetChatCompletionResponse {
+        id: "resp_1".to_string(),
+        model: "grok-4".to_string(),
+        created: Some(prost_types::Timestamp {
+            seconds: 1_700_000_000,
+            nanos: 0,
+        }),
+        outputs: vec![CompletionOutput {
+            index: 0,
+            finish_reason: FinishReason::ReasonToolCalls.into(),
+            message: Some(CompletionMessage {
+                role: MessageRole::RoleAssistant.into(),
+                reasoning_content: "thinking".to_string(),
+                encrypted_content: "opaque".to_string(),
+                tool_calls: vec![
+                    function_call("call_1", ToolCallType::ClientSideTool, "lookup"),
+                    function_call("call_2", ToolCallType::WebSearchTool, "web_search"),
+                ],
+                ..Default::default()
+            }),
+            logprobs: None,
+        }],
+        usage: Some(SamplingUsage {
+            prompt_tokens: 10,
+            completion_tokens: 4,
+            reasoning_tokens: 2,
+            total_tokens: 14,
+            ..Default::default()
+        }),
+        citations: vec!["https://x.ai".to_string()],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        to_openai_chat_completion(&response),
+        json!({
+            "id": "resp_1",
+            "object": "chat.completion",
+            "created": 1_700_000_000,
+            "model": "grok-4",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "reasoning_content": "thinking",
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "lookup", "arguments": "{}"},
+                    }],
+                },
+                "finish_reason": "tool_calls",
+                "logprobs": null,
+            }],
+            "usage": {
+                "prompt_tokens": 10,
+                "completion_tokens": 4,
+                "total_tokens": 14,
+                "prompt_tokens_details": {"cached_tokens": 0},
+                "completion_tokens_details": {"reasoning_tokens": 2},
+            },
+            "citations": ["https://x.ai"],
+        })
+    );
+}
+
+#[test]
+fn test_to_openai_chunk() {
+    let chunk = GetChatCompletionChunk {
+        id: "resp_1".to_string(),
+        model: "grok-4".to_string(),
+        outputs: vec![
+            CompletionOutputChunk {
+                index: 0,
+                delta: Some(Delta {
+                    role: MessageRole::RoleAssistant.into(),
+                    content: "Hi".to_string(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            CompletionOutputChunk {
+                index: 1,
+                delta: Some(Delta {
+                    tool_calls: vec![function_call("call_9", ToolCallType::ClientSideTool, "f")],
+                    ..Default::default()
+                }),
+                finish_reason: FinishReason::ReasonMaxLen.into(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let value = to_openai_chunk(&chunk);
+    assert_eq!(value["object"], "chat.completion.chunk");
+    assert_eq!(
+        value["choices"][0],
+        json!({
+            "index": 0,
+            "delta": {"role": "assistant", "content": "Hi"},
+            "finish_reason": null,
+            "logprobs": null,
+        })
+    );
+    assert_eq!(value["choices"][1]["finish_reason"], "length");
+    assert_eq!(value["choices"][1]["delta"]["tool_calls"][0]["index"], 0);
+    assert_eq!(
+        value["choices"][1]["delta"]["tool_calls"][0]["id"],
+        "call_9"
+    );
+    assert!(value.get("usage").is_none());
+}
+
+#[test]
+fn test_chunk_converter_keeps_tool_call_indices_across_chunks() {
+    let tool_chunk = |id: &str| GetChatCompletionChunk {
+        outputs: vec![CompletionOutputChunk {
+            index: 0,
+            delta: Some(Delta {
+                tool_calls: vec![function_call(id, ToolCallType::ClientSideTool, "f")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let index =
+        |value: &serde_json::Value| value["choices"][0]["delta"]["tool_calls"][0]["index"].clone();
+
+    let mut converter = ChunkConverter::new();
+    assert_eq!(index(&converter.convert(&tool_chunk("call_1"))), 0);
+    assert_eq!(index(&converter.convert(&tool_chunk("call_2"))), 1);
+    assert_eq!(index(&converter.convert(&tool_chunk("call_1"))), 0);
+
+    // A fresh stream starts numbering again
+    assert_eq!(
+        index(&ChunkConverter::new().convert(&tool_chunk("call_2"))),
+        0
+    );
+}
+
+#[test]
+fn test_from_openai_request_maps_fields() {
+    let request = from_openai_request(&json!({
+        "model": "grok-4",
+        "stream": true,
+        "messages": [
+            {"role": "developer", "content": "Be brief."},
+            {"role": "user", "content": [
+                {"type": "text", "text": "What is this?"},
+                {"type": "image_url", "image_url": {"url": "https://x.ai/a.png", "detail": "low"}},
+            ]},
+            {"role": "assistant", "content": null, "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "lookup", "arguments": "{\"q\":1}"},
+            }]},
+            {"role": "tool", "tool_call_id": "call_1", "content": "42"},
+        ],
+        "temperature": 0.5,
+        "max_tokens": 100,
+        "n": 2,
+        "stop": "END",
+        "reasoning_effort": "low",
+        "tools": [{
+            "type": "function",
+            "function": {"name": "lookup", "description": "Look up", "parameters": {"type": "object"}},
+        }],
+        "tool_choice": {"type": "function", "function": {"name": "lookup"}},
+        "response_format": {"type": "json_schema", "json_schema": {"name": "x", "schema": {"type": "object"}}},
+    }))
+    .unwrap();
+
+    assert_eq!(request.model, "grok-4");
+    assert_eq!(request.temperature, Some(0.5));
+    assert_eq!(request.max_tokens, Some(100));
+    assert_eq!(request.n, Some(2));
+    assert_eq!(request.stop, vec!["END".to_string()]);
+    assert_eq!(
+        request.reasoning_effort,
+        Some(ReasoningEffort::EffortLow as i32)
+    );
+
+    assert_eq!(request.messages.len(), 4);
+    assert_eq!(
+        request.messages[0],
+        Message::from((MessageRole::RoleSystem, "Be brief."))
+    );
+    assert_eq!(
+        request.messages[1].content[1],
+        Content {
+            content: Some(content::Content::ImageUrl(ImageUrlContent {
+                image_url: "https://x.ai/a.png".to_string(),
+                detail: ImageDetail::DetailLow.into(),
+            })),
+        }
+    );
+    assert_eq!(request.messages[2].tool_calls[0].id, "call_1");
+    assert!(request.messages[2].content.is_empty());
+    assert_eq!(request.messages[3].tool_call_id.as_deref(), Some("call_1"));
+
+    let Some(tool::Tool::Function(function)) = &request.tools[0].tool else {
+        panic!("expected a function tool");
+    };
+    assert_eq!(function.name, "lookup");
+    assert_eq!(function.parameters, r#"{"type":"object"}"#);
+    assert_eq!(
+        request.tool_choice.unwrap().tool_choice,
+        Some(tool_choice::ToolChoice::FunctionName("lookup".to_string()))
+    );
+    let format = request.response_format.unwrap();
+    assert_eq!(format.format_type, i32::from(FormatType::JsonSchema));
+    assert_eq!(format.schema.as_deref(), Some(r#"{"type":"object"}"#));
+
+    let auto = from_openai_request(&json!({
+        "model": "grok-4",
+        "messages": [{"role": "user", "content": "hi"}],
+        "tool_choice": "required",
+    }))
+    .unwrap();
+    assert_eq!(
+        auto.tool_choice.unwrap().tool_choice,
+        Some(tool_choice::ToolChoice::Mode(ToolMode::Required.into()))
+    );
+}
+
+#[test]
+fn test_from_openai_request_reasoning_effort_round_trip() {
+    let efforts = [
+        ReasoningEffort::EffortLow,
+        ReasoningEffort::EffortMedium,
+        ReasoningEffort::EffortHigh,
+    ];
+    for effort in efforts {
+        let request = from_openai_request(&json!({
+            "model": "grok-3-mini",
+            "messages": [{"role": "user", "content": "hi"}],
+            "reasoning_effort": effort.to_string(),
+        }))
+        .unwrap();
+        let converted = ReasoningEffort::try_from(request.reasoning_effort.unwrap()).unwrap();
+        assert_eq!(converted, effort);
+        assert_eq!(converted.to_string(), effort.to_string());
+    }
+}
+
+#[test]
+fn test_from_openai_request_errors() {
+    let user = json!([{"role": "user", "content": "hi"}]);
+    let convert = |body: serde_json::Value| from_openai_request(&body).unwrap_err();
+
+    assert_eq!(
+        convert(json!({"messages": user})),
+        ConvertError::MissingField {
+            field: "model".to_string()
+        }
+    );
+    assert_eq!(
+        convert(json!({"model": "grok-4", "messages": user, "logit_bias": {"1": 5}})),
+        ConvertError::UnsupportedField {
+            field: "logit_bias".to_string()
+        }
+    );
+    assert_eq!(
+        convert(json!({"model": "grok-4", "messages": user, "temperature": "hot"})),
+        ConvertError::InvalidField {
+            field: "temperature".to_string(),
+            expected: "a number"
+        }
+    );
+    assert_eq!(
+        convert(
+            json!({"model": "grok-4", "messages": [{"role": "user", "content": [{"type": "input_audio"}]}]})
+        ),
+        ConvertError::UnsupportedField {
+            field: "messages[0].content[0].type".to_string()
+        }
+    );
+    assert_eq!(
+        convert(json!({"model": "grok-4", "messages": [{"content": "hi"}]})).to_string(),
+        "missing field `messages[0].role`"
+    );
+}
+
+#[test]
+fn test_openai_round_trip_of_assistant_message() {
+    let original = CompletionMessage {
+        role: MessageRole::RoleAssistant.into(),
+        content: "Let me check.".to_string(),
+        reasoning_content: "need a lookup".to_string(),
+        tool_calls: vec![function_call(
+            "call_1",
+            ToolCallType::ClientSideTool,
+            "lookup",
+        )],
+        ..Default::default()
+    };
+    let response = GetChatCompletionResponse {
+        id: "resp_1".to_string(),
+        model: "grok-4".to_string(),
+        outputs: vec![CompletionOutput {
+            message: Some(original.clone()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let openai = to_openai_chat_completion(&response);
+    let request = from_openai_request(&json!({
+        "model": openai["model"],
+        "messages": [openai["choices"][0]["message"]],
+    }))
+    .unwrap();
+
+    assert_eq!(request.model, "grok-4");
+    assert_eq!(
+        request.messages,
+        xai_sdk::chat::utils::to_messages(&response.outputs)
+    );
+}
@@ -0,0 +1,47 @@
+use xai_sdk::api::{
+    FinishReason, GetChatCompletionResponse, MessageRole, SampleChoice, SampleTextResponse,
+    SamplingUsage,
+};
+
+#[test]
+fn test_sample_response_into_chat_response() {
+    let response = SampleTextResponse {
+        id: "sample-1".to_string(),
+        choices: vec![
+            SampleChoice {
+                finish_reason: FinishReason::ReasonStop.into(),
+                index: 0,
+                text: "Once upon a time".to_string(),
+            },
+            SampleChoice {
+                finish_reason: FinishReason::ReasonMaxLen.into(),
+                index: 1,
+                text: "In a galaxy".to_string(),
+            },
+        ],
+        model: "grok-3".to_string(),
+        usage: Some(SamplingUsage {
+            completion_tokens: 7,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let chat = response.clone().into_chat_response();
+    assert_eq!(chat.id, "sample-1");
+    assert_eq!(chat.model, "grok-3");
+    assert_eq!(chat.usage, response.usage);
+    assert_eq!(chat.outputs.len(), 2);
+
+    let second = &chat.outputs[1];
+    assert_eq!(second.index, 1);
+    assert_eq!(second.finish_reason_enum(), FinishReason::ReasonMaxLen);
+    let message = second.message.as_ref().unwrap();
+    assert_eq!(message.content, "In a galaxy");
+    assert_eq!(message.role_enum(), MessageRole::RoleAssistant);
+    assert!(message.reasoning_content.is_empty());
+    assert!(message.tool_calls.is_empty());
+    assert!(chat.is_truncated());
+
+    assert_eq!(GetChatCompletionResponse::from(response), chat);
+}
@@ -0,0 +1,99 @@
+use xai_sdk::api::Token;
+use xai_sdk::export::transport::Endpoint;
+use xai_sdk::tokenize::{TokenSegment, count_many, render_colored, to_segments};
+
+fn token(id: u32, text: &str) -> Token {
+    Token {
+        token_id: id,
+        string_token: text.to_string(),
+        token_bytes: text.as_bytes().to_vec(),
+    }
+}
+
+#[test]
+fn test_to_segments_tracks_byte_ranges() {
+    let tokens = vec![
+        token(1, "Hello"),
+        token(2, ", wörld"),
+        Token {
+            token_id: 3,
+            string_token: "!".to_string(),
+            token_bytes: Vec::new(),
+        },
+    ];
+
+    let segments = to_segments(&tokens);
+    assert_eq!(
+        segments,
+        vec![
+            TokenSegment {
+                id: 1,
+                text: "Hello".to_string(),
+                byte_range: 0..5
+            },
+            TokenSegment {
+                id: 2,
+                text: ", wörld".to_string(),
+                byte_range: 5..13
+            },
+            TokenSegment {
+                id: 3,
+                text: "!".to_string(),
+                byte_range: 13..14
+            },
+        ]
+    );
+    let text = "Hello, wörld!";
+    assert_eq!(&text[segments[1].byte_range.clone()], ", wörld");
+}
+
+#[test]
+fn test_to_segments_split_multibyte_character() {
+    let tokens = vec![
+        Token {
+            token_id: 1,
+            string_token: String::new(),
+            token_bytes: vec![0xC3],
+        },
+        Token {
+            token_id: 2,
+            string_token: String::new(),
+            token_bytes: vec![0xB6],
+        },
+    ];
+    let segments = to_segments(&tokens);
+    assert_eq!(segments[0].text, "\u{FFFD}");
+    assert_eq!(segments[1].byte_range, 1..2);
+}
+
+#[test]
+fn test_render_colored_alternates_colors() {
+    let segments = to_segments(&[token(1, "a"), token(2, "b"), token(3, "c")]);
+    assert_eq!(
+        render_colored(&segments),
+        "\x1b[30;46ma\x1b[0m\x1b[30;43mb\x1b[0m\x1b[30;46mc\x1b[0m"
+    );
+    assert_eq!(render_colored(&[]), "");
+}
+
+#[tokio::test]
+async fn test_count_many_reports_per_text_results_in_order() {
+    // Lazily connected channel to a closed local port: every call fails fast without network
+    let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+    let client = xai_sdk::tokenize::client::with_channel(channel, "test-key");
+
+    let texts: Vec<String> = (0..5).map(|i| format!("text {i}")).collect();
+    let results = count_many(&client, "grok-4", texts, 2).await;
+    assert_eq!(results.len(), 5);
+    assert!(
+        results
+            .iter()
+            .all(|result| result.as_ref().unwrap_err().code() == tonic::Code::Unavailable)
+    );
+
+    assert!(
+        count_many(&client, "grok-4", Vec::new(), 0)
+            .await
+            .is_empty()
+    );
+}
@@ -612,3 +612,156 @@ fn test_display_from_str_roundtrip() {
     let s = modality.to_string();
     assert_eq!(Modality::from_str(&s).unwrap(), modality);
 }
+
+// Tests for typed accessors
+
+#[test]
+fn test_tool_call_typed_accessors() {
+    let call = xai_sdk::api::ToolCall {
+        r#type: ToolCallType::XSearchTool as i32,
+        status: ToolCallStatus::Failed as i32,
+        ..Default::default()
+    };
+    assert_eq!(call.call_type(), ToolCallType::XSearchTool);
+    assert_eq!(call.call_status(), Some(ToolCallStatus::Failed));
+
+    let invalid = xai_sdk::api::ToolCall {
+        r#type: 999,
+        status: 999,
+        ..Default::default()
+    };
+    assert_eq!(invalid.call_type(), ToolCallType::Invalid);
+    assert_eq!(invalid.call_status(), None);
+}
+
+#[test]
+fn test_finish_reason_enum_accessors() {
+    let output = xai_sdk::api::CompletionOutput {
+        finish_reason: FinishReason::ReasonToolCalls as i32,
+        ..Default::default()
+    };
+    assert_eq!(output.finish_reason_enum(), FinishReason::ReasonToolCalls);
+
+    let chunk = xai_sdk::api::CompletionOutputChunk {
+        finish_reason: 42,
+        ..Default::default()
+    };
+    assert_eq!(chunk.finish_reason_enum(), FinishReason::ReasonInvalid);
+
+    let choice = xai_sdk::api::SampleChoice {
+        finish_reason: FinishReason::ReasonStop as i32,
+        ..Default::default()
+    };
+    assert_eq!(choice.finish_reason_enum(), FinishReason::ReasonStop);
+}
+
+#[test]
+fn test_role_enum_accessors() {
+    let message = xai_sdk::api::Message {
+        role: MessageRole::RoleTool as i32,
+        ..Default::default()
+    };
+    assert_eq!(message.role_enum(), MessageRole::RoleTool);
+
+    let completion = xai_sdk::api::CompletionMessage {
+        role: -1,
+        ..Default::default()
+    };
+    assert_eq!(completion.role_enum(), MessageRole::InvalidRole);
+}
+
+// Tests for use as collection keys
+
+/// Inserts every value into a `HashSet` and a `BTreeSet`, which must both keep all of them.
+fn assert_collection_keys<T>(values: Vec<T>)
+where
+    T: std::hash::Hash + Ord + Clone + std::fmt::Debug,
+{
+    assert!(!values.is_empty());
+    let hashed: std::collections::HashSet<T> = values.iter().cloned().collect();
+    let sorted: std::collections::BTreeSet<T> = values.iter().cloned().collect();
+    assert_eq!(hashed.len(), values.len(), "{values:?}");
+    assert_eq!(sorted.len(), values.len(), "{values:?}");
+}
+
+/// Returns every variant of a generated enum, found by probing raw values.
+fn variants<T: TryFrom<i32>>() -> Vec<T> {
+    (0..64)
+        .filter_map(|value| T::try_from(value).ok())
+        .collect()
+}
+
+#[test]
+fn test_enums_usable_as_collection_keys() {
+    assert_collection_keys(variants::<DeferredStatus>());
+    assert_collection_keys(variants::<EmbedEncodingFormat>());
+    assert_collection_keys(variants::<FinishReason>());
+    assert_collection_keys(variants::<FormatType>());
+    assert_collection_keys(variants::<ImageAspectRatio>());
+    assert_collection_keys(variants::<ImageDetail>());
+    assert_collection_keys(variants::<ImageFormat>());
+    assert_collection_keys(variants::<ImageQuality>());
+    assert_collection_keys(variants::<ImageResolution>());
+    assert_collection_keys(variants::<IncludeOption>());
+    assert_collection_keys(variants::<MessageRole>());
+    assert_collection_keys(variants::<Modality>());
+    assert_collection_keys(variants::<RankingMetric>());
+    assert_collection_keys(variants::<ReasoningEffort>());
+    assert_collection_keys(variants::<SearchMode>());
+    assert_collection_keys(variants::<ServerSideTool>());
+    assert_collection_keys(variants::<ToolCallStatus>());
+    assert_collection_keys(variants::<ToolCallType>());
+    assert_collection_keys(variants::<ToolMode>());
+    assert_collection_keys(variants::<VideoAspectRatio>());
+    assert_collection_keys(variants::<VideoResolution>());
+}
+
+#[test]
+fn test_sdk_enums_usable_as_collection_keys() {
+    use xai_sdk::chat::CompletionStatus;
+    use xai_sdk::chat::diff::WordChange;
+    use xai_sdk::chat::stream::PhaseStatus;
+    use xai_sdk::models::{ModelFamily, ModelId, ModelSize};
+
+    assert_collection_keys(vec![
+        ModelId::parse("grok-4"),
+        ModelId::parse("grok-3-mini-fast"),
+        ModelId::parse("grok-4-latest"),
+        ModelId::parse("custom-model"),
+    ]);
+    assert_collection_keys(vec![
+        ModelFamily::Grok2,
+        ModelFamily::Grok3,
+        ModelFamily::Grok4,
+        ModelFamily::GrokCode,
+        ModelFamily::GrokImagine,
+    ]);
+    assert_collection_keys(vec![ModelSize::Mini]);
+    assert_collection_keys(vec![
+        PhaseStatus::Init,
+        PhaseStatus::Start,
+        PhaseStatus::Pending,
+        PhaseStatus::Complete,
+    ]);
+    assert!(PhaseStatus::Init < PhaseStatus::Complete);
+
+    let statuses: std::collections::HashSet<_> = [
+        CompletionStatus::Complete,
+        CompletionStatus::Truncated,
+        CompletionStatus::ToolCallsPending,
+        CompletionStatus::TimedOut,
+        CompletionStatus::Unknown,
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(statuses.len(), 5);
+
+    let changes: std::collections::HashSet<_> = [
+        WordChange::Same("a".to_string()),
+        WordChange::Added("a".to_string()),
+        WordChange::Removed("a".to_string()),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(changes.len(), 3);
+}